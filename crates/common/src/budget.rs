@@ -0,0 +1,37 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const TICK: Duration = Duration::from_millis(50);
+
+/// A number of bytes that may be sent per tick, shared by everything that draws from it.
+#[derive(Debug)]
+pub struct SendBudget {
+    bytes_per_tick: usize,
+    tick: Mutex<(Instant, usize)>,
+}
+
+impl SendBudget {
+    pub fn new(bytes_per_tick: usize) -> Self {
+        Self {
+            bytes_per_tick,
+            tick: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    /// Takes bytes from the current tick's budget if there are enough left. The first take of a
+    /// tick always succeeds so a packet bigger than the whole budget still gets sent.
+    pub fn try_take(&self, bytes: usize) -> bool {
+        let mut tick = self.tick.lock().unwrap();
+        let now = Instant::now();
+        if now.duration_since(tick.0) >= TICK {
+            *tick = (now, 0);
+        }
+
+        let (_, used) = &mut *tick;
+        if *used > 0 && *used + bytes > self.bytes_per_tick {
+            return false;
+        }
+        *used += bytes;
+        true
+    }
+}
@@ -9,12 +9,18 @@ pub enum ConfigError {
     InvalidSimulationDistance(u8),
     #[error("Invalid max players: {0}. Must be a positive number.")]
     InvalidMaxPlayers(i32),
+    #[error("Invalid chunk bytes per tick: {0}. Must be a positive number.")]
+    InvalidChunkBytesPerTick(usize),
+    #[error("Invalid spawn chunk radius: {0}. Must be between 0 and 32.")]
+    InvalidSpawnChunkRadius(u8),
 }
 
 #[derive(Deserialize)]
 pub struct Config {
     pub server: Server,
     pub players: Players,
+    #[serde(default)]
+    pub world: World,
 }
 
 impl Config {
@@ -30,6 +36,15 @@ impl Config {
         if self.players.max_players <= 0 {
             return Err(ConfigError::InvalidMaxPlayers(self.players.max_players));
         }
+        if self.server.chunk_bytes_per_tick == 0 {
+            return Err(ConfigError::InvalidChunkBytesPerTick(self.server.chunk_bytes_per_tick));
+        }
+        if self.players.chunk_bytes_per_tick == 0 {
+            return Err(ConfigError::InvalidChunkBytesPerTick(self.players.chunk_bytes_per_tick));
+        }
+        if self.world.spawn_chunk_radius > 32 {
+            return Err(ConfigError::InvalidSpawnChunkRadius(self.world.spawn_chunk_radius));
+        }
         Ok(())
     }
 }
@@ -41,9 +56,119 @@ pub struct Server {
     pub view_distance: u8,
     pub simulation_distance: u8,
     pub enable_packet_logging: bool,
+    /// The number of bytes of chunk data sent per tick across all players.
+    #[serde(default = "default_server_chunk_bytes_per_tick")]
+    pub chunk_bytes_per_tick: usize,
+}
+
+fn default_server_chunk_bytes_per_tick() -> usize {
+    1024 * 1024
 }
 
 #[derive(Deserialize)]
 pub struct Players {
     pub max_players: i32,
+    /// The number of bytes of chunk data sent per tick to a single player.
+    #[serde(default = "default_player_chunk_bytes_per_tick")]
+    pub chunk_bytes_per_tick: usize,
+}
+
+fn default_player_chunk_bytes_per_tick() -> usize {
+    256 * 1024
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    Survival,
+    #[default]
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+impl GameMode {
+    pub fn id(&self) -> u8 {
+        match self {
+            GameMode::Survival => 0,
+            GameMode::Creative => 1,
+            GameMode::Adventure => 2,
+            GameMode::Spectator => 3,
+        }
+    }
+}
+
+/// How chunks that were never saved are generated.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Generator {
+    /// The layers of `flat_preset` everywhere.
+    #[default]
+    Flat,
+    /// Empty chunks.
+    Void,
+    /// Terrain with biomes, oceans and caves from noise seeded by `seed`.
+    Noise,
+}
+
+/// The world the players spawn in. The server runs this one world only, so its generator is the
+/// only one; choosing a generator per world needs support for more than one world first.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct World {
+    /// The dimension type from the `minecraft:dimension_type` registry.
+    pub dimension_type: String,
+    /// The name of the dimension the players spawn in.
+    pub dimension_name: String,
+    pub seed: i64,
+    pub game_mode: GameMode,
+    pub spawn_x: i32,
+    pub spawn_z: i32,
+    /// The radius of the square of chunks around the spawn that is always loaded. 0 doesn't keep
+    /// any chunk loaded.
+    pub spawn_chunk_radius: u8,
+    /// The directory of a world saved by vanilla, whose region files are read before generating
+    /// chunks. Modified chunks are saved back to it.
+    pub directory: String,
+    /// How often modified chunks are saved, in seconds. 0 only saves on shutdown.
+    pub autosave_interval_secs: u64,
+    /// A compact world file to load whole instead of the region files. Such worlds aren't saved.
+    pub compact_file: Option<String>,
+    pub generator: Generator,
+    /// The superflat preset of the flat generator, like
+    /// `minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains`.
+    pub flat_preset: String,
+    /// The number of threads loading and generating chunks. 0 uses one per CPU.
+    pub worker_threads: usize,
+    /// How long chunks no player or ticket needs stay loaded, in seconds.
+    pub unload_delay_secs: u64,
+    /// The number of loaded chunks above which unneeded chunks are unloaded right away. 0 means
+    /// no limit.
+    pub max_loaded_chunks: usize,
+    /// How many blocks of each chunk section within the simulation distance of a player tick at
+    /// random every tick, making crops grow and leaves decay. 0 turns random ticks off.
+    pub random_tick_speed: u32,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self {
+            dimension_type: "minecraft:overworld".to_string(),
+            dimension_name: "minecraft:overworld".to_string(),
+            seed: 0,
+            game_mode: GameMode::default(),
+            spawn_x: 0,
+            spawn_z: 0,
+            spawn_chunk_radius: 2,
+            directory: "world".to_string(),
+            autosave_interval_secs: 300,
+            compact_file: None,
+            generator: Generator::default(),
+            flat_preset: "minecraft:stone,3*minecraft:dirt,minecraft:grass_block;minecraft:plains".to_string(),
+            worker_threads: 0,
+            unload_delay_secs: 10,
+            max_loaded_chunks: 0,
+            random_tick_speed: 3,
+        }
+    }
 }
@@ -24,6 +24,8 @@ pub struct Connection {
     pub state: ConnectionState,
     pub config: Arc<Config>,
     pub protocol_version: i32,
+    /// The view distance the client asked for in its client information, 0 until it is received.
+    pub client_view_distance: u8,
 }
 
 impl Connection {
@@ -34,6 +36,7 @@ impl Connection {
             state: ConnectionState::Handshaking,
             config,
             protocol_version: 0,
+            client_view_distance: 0,
         }
     }
 
@@ -75,6 +78,26 @@ impl Connection {
         }
     }
 
+    /// Waits until a whole packet is buffered and returns its ID without consuming it.
+    pub async fn peek_packet_id(&mut self) -> Result<Option<i32>> {
+        loop {
+            if let Some(packet_id) = self.parse_packet_id()? {
+                return Ok(Some(packet_id));
+            }
+
+            if self.stream.read_buf(&mut self.buffer).await? == 0 {
+                return if self.buffer.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(Error::Io(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "Connection closed by peer",
+                    )))
+                };
+            }
+        }
+    }
+
     pub async fn read_packet_raw(&mut self) -> Result<Option<BytesMut>> {
         loop {
             if let Some(packet) = self.parse_packet_raw()? {
@@ -116,7 +139,7 @@ impl Connection {
         let total_packet_len = packet_len_len + packet_len as usize;
 
         let packet_data = &self.buffer[packet_len_len..total_packet_len];
-        let mut packet_data_slice = &packet_data[..];
+        let mut packet_data_slice = packet_data;
         let packet_id = read_varint(&mut packet_data_slice)?;
 
         let packet = T::read(&mut packet_data_slice)?;
@@ -133,6 +156,26 @@ impl Connection {
         Ok(Some(packet))
     }
 
+    fn parse_packet_id(&self) -> Result<Option<i32>> {
+        let mut buf = &self.buffer[..];
+
+        if buf.is_empty() {
+            return Ok(None);
+        }
+
+        let packet_len = match read_varint(&mut buf) {
+            Ok(len) => len,
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        if buf.len() < packet_len as usize {
+            return Ok(None);
+        }
+
+        read_varint(&mut buf).map(Some)
+    }
+
     fn parse_packet_raw(&mut self) -> Result<Option<BytesMut>> {
         let mut buf = &self.buffer[..];
         let initial_len = buf.len();
@@ -1,2 +1,3 @@
+pub mod budget;
 pub mod config;
 pub mod connection;
\ No newline at end of file
@@ -0,0 +1,27 @@
+use std::thread::sleep;
+use std::time::Duration;
+use iron_oxide_common::budget::SendBudget;
+
+#[test]
+fn test_budget_limits_bytes_per_tick() {
+    let budget = SendBudget::new(100);
+    assert!(budget.try_take(60));
+    assert!(budget.try_take(40));
+    assert!(!budget.try_take(1));
+}
+
+#[test]
+fn test_first_take_of_tick_always_succeeds() {
+    let budget = SendBudget::new(100);
+    assert!(budget.try_take(500));
+    assert!(!budget.try_take(1));
+}
+
+#[test]
+fn test_budget_refills_next_tick() {
+    let budget = SendBudget::new(100);
+    assert!(budget.try_take(100));
+    assert!(!budget.try_take(10));
+    sleep(Duration::from_millis(60));
+    assert!(budget.try_take(10));
+}
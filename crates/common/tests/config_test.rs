@@ -1,16 +1,31 @@
 
-use iron_oxide_common::config::{Config, ConfigError, Players, Server};
+use iron_oxide_common::config::{Config, ConfigError, Players, Server, World};
 
 fn create_test_config(view_distance: u8, simulation_distance: u8, max_players: i32) -> Config {
+    create_test_config_with_budgets(view_distance, simulation_distance, max_players, 1024 * 1024, 256 * 1024)
+}
+
+fn create_test_config_with_budgets(
+    view_distance: u8,
+    simulation_distance: u8,
+    max_players: i32,
+    server_chunk_bytes_per_tick: usize,
+    player_chunk_bytes_per_tick: usize,
+) -> Config {
     Config {
         server: Server {
             address: "127.0.0.1:25565".to_string(),
             motd: "A Minecraft Server".to_string(),
             view_distance,
             simulation_distance,
-            enable_packet_logging: false
+            enable_packet_logging: false,
+            chunk_bytes_per_tick: server_chunk_bytes_per_tick,
+        },
+        players: Players {
+            max_players,
+            chunk_bytes_per_tick: player_chunk_bytes_per_tick,
         },
-        players: Players { max_players },
+        world: World::default(),
     }
 }
 
@@ -85,3 +100,21 @@ fn test_invalid_max_players_negative() {
         _ => panic!("Expected InvalidMaxPlayers error"),
     }
 }
+
+#[test]
+fn test_invalid_server_chunk_bytes_per_tick() {
+    let config = create_test_config_with_budgets(10, 10, 20, 0, 256 * 1024);
+    match config.validate().unwrap_err() {
+        ConfigError::InvalidChunkBytesPerTick(v) => assert_eq!(v, 0),
+        _ => panic!("Expected InvalidChunkBytesPerTick error"),
+    }
+}
+
+#[test]
+fn test_invalid_player_chunk_bytes_per_tick() {
+    let config = create_test_config_with_budgets(10, 10, 20, 1024 * 1024, 0);
+    match config.validate().unwrap_err() {
+        ConfigError::InvalidChunkBytesPerTick(v) => assert_eq!(v, 0),
+        _ => panic!("Expected InvalidChunkBytesPerTick error"),
+    }
+}
@@ -27,6 +27,16 @@ impl PacketData for u16 {
     }
 }
 
+impl PacketData for i16 {
+    fn read(buffer: &mut &[u8]) -> Result<Self> {
+        super::raw_data::read_short(buffer)
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        super::raw_data::write_short(buffer, *self)
+    }
+}
+
 impl PacketData for i32 {
     fn read(buffer: &mut &[u8]) -> Result<Self> {
         super::raw_data::read_varint(buffer)
@@ -47,6 +57,26 @@ impl PacketData for i64 {
     }
 }
 
+impl PacketData for f32 {
+    fn read(buffer: &mut &[u8]) -> Result<Self> {
+        super::raw_data::read_float(buffer)
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        super::raw_data::write_float(buffer, *self)
+    }
+}
+
+impl PacketData for f64 {
+    fn read(buffer: &mut &[u8]) -> Result<Self> {
+        super::raw_data::read_double(buffer)
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        super::raw_data::write_double(buffer, *self)
+    }
+}
+
 impl PacketData for Uuid {
     fn read(buffer: &mut &[u8]) -> Result<Self> {
         super::raw_data::read_uuid(buffer)
@@ -69,3 +99,38 @@ impl PacketData for bool {
         Ok(())
     }
 }
+
+impl<T: PacketData> PacketData for Vec<T> {
+    fn read(buffer: &mut &[u8]) -> Result<Self> {
+        let len = super::raw_data::read_varint(buffer)?;
+        let mut items = Vec::with_capacity(len.max(0) as usize);
+        for _ in 0..len {
+            items.push(T::read(buffer)?);
+        }
+        Ok(items)
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        super::raw_data::write_varint_prefixed_array(buffer, self, |buffer, item| item.write(buffer))
+    }
+}
+
+impl<T: PacketData> PacketData for Option<T> {
+    fn read(buffer: &mut &[u8]) -> Result<Self> {
+        if bool::read(buffer)? {
+            Ok(Some(T::read(buffer)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Some(value) => {
+                true.write(buffer)?;
+                value.write(buffer)
+            }
+            None => false.write(buffer),
+        }
+    }
+}
@@ -3,8 +3,43 @@ pub mod data;
 pub mod types;
 
 use crate::error::Result;
+use std::sync::Arc;
 
 pub trait Packet: Sized {
     fn read(buffer: &mut &[u8]) -> Result<Self> where Self: Sized;
     fn write(&self, buffer: &mut Vec<u8>) -> Result<()>;
 }
+
+/// A packet that has already been serialized, starting with its ID. Useful to serialize a packet
+/// once and send it to many connections, or to know its size before sending it.
+#[derive(Debug, Clone)]
+pub struct EncodedPacket(pub Arc<[u8]>);
+
+impl EncodedPacket {
+    pub fn encode<T: Packet>(packet: &T) -> Result<Self> {
+        let mut buffer = Vec::new();
+        packet.write(&mut buffer)?;
+        Ok(Self(buffer.into()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Packet for EncodedPacket {
+    fn read(buffer: &mut &[u8]) -> Result<Self> {
+        let data = buffer.to_vec();
+        *buffer = &buffer[buffer.len()..];
+        Ok(Self(data.into()))
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        buffer.extend_from_slice(&self.0);
+        Ok(())
+    }
+}
@@ -76,6 +76,50 @@ pub fn write_long(buffer: &mut Vec<u8>, value: i64) -> Result<()> {
     Ok(())
 }
 
+pub fn read_short(buffer: &mut &[u8]) -> Result<i16> {
+    let mut buf = [0; 2];
+    buffer.read_exact(&mut buf)?;
+    Ok(i16::from_be_bytes(buf))
+}
+
+pub fn write_short(buffer: &mut Vec<u8>, value: i16) -> Result<()> {
+    buffer.extend_from_slice(&value.to_be_bytes());
+    Ok(())
+}
+
+pub fn read_int(buffer: &mut &[u8]) -> Result<i32> {
+    let mut buf = [0; 4];
+    buffer.read_exact(&mut buf)?;
+    Ok(i32::from_be_bytes(buf))
+}
+
+pub fn write_int(buffer: &mut Vec<u8>, value: i32) -> Result<()> {
+    buffer.extend_from_slice(&value.to_be_bytes());
+    Ok(())
+}
+
+pub fn read_float(buffer: &mut &[u8]) -> Result<f32> {
+    let mut buf = [0; 4];
+    buffer.read_exact(&mut buf)?;
+    Ok(f32::from_be_bytes(buf))
+}
+
+pub fn write_float(buffer: &mut Vec<u8>, value: f32) -> Result<()> {
+    buffer.extend_from_slice(&value.to_be_bytes());
+    Ok(())
+}
+
+pub fn read_double(buffer: &mut &[u8]) -> Result<f64> {
+    let mut buf = [0; 8];
+    buffer.read_exact(&mut buf)?;
+    Ok(f64::from_be_bytes(buf))
+}
+
+pub fn write_double(buffer: &mut Vec<u8>, value: f64) -> Result<()> {
+    buffer.extend_from_slice(&value.to_be_bytes());
+    Ok(())
+}
+
 pub fn read_uuid(buffer: &mut &[u8]) -> Result<Uuid> {
     let mut buf = [0; 16];
     buffer.read_exact(&mut buf)?;
@@ -1,4 +1,4 @@
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::packet::data::PacketData;
 use std::io::Read;
 
@@ -18,7 +18,27 @@ impl PacketData for PacketBytes {
     }
 }
 
-#[derive(Debug)]
+/// A byte array prefixed with its length as a VarInt.
+#[derive(Debug, Clone)]
+pub struct PrefixedBytes(pub Vec<u8>);
+
+impl PacketData for PrefixedBytes {
+    fn read(buffer: &mut &[u8]) -> Result<Self> {
+        let len = super::raw_data::read_varint(buffer)?;
+        if len < 0 {
+            return Err(Error::PacketDeserialization(format!("Negative byte array length: {}", len)));
+        }
+        Ok(PrefixedBytes(super::raw_data::read_bytes(buffer, len as usize)?.to_vec()))
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        super::raw_data::write_varint(buffer, self.0.len() as i32)?;
+        buffer.extend_from_slice(&self.0);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct PacketByte(pub u8);
 
 impl PacketData for PacketByte {
@@ -32,4 +52,43 @@ impl PacketData for PacketByte {
         buffer.push(self.0);
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PacketInt(pub i32);
+
+impl PacketData for PacketInt {
+    fn read(buffer: &mut &[u8]) -> Result<Self> {
+        Ok(PacketInt(super::raw_data::read_int(buffer)?))
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        super::raw_data::write_int(buffer, self.0)
+    }
+}
+
+/// A block position packed into a single long: 26 bits of X, 26 bits of Z and 12 bits of Y.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl PacketData for Position {
+    fn read(buffer: &mut &[u8]) -> Result<Self> {
+        let value = super::raw_data::read_long(buffer)?;
+        Ok(Position {
+            x: (value >> 38) as i32,
+            y: (value << 52 >> 52) as i32,
+            z: (value << 26 >> 38) as i32,
+        })
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        let value = ((self.x as i64 & 0x3FFFFFF) << 38)
+            | ((self.z as i64 & 0x3FFFFFF) << 12)
+            | (self.y as i64 & 0xFFF);
+        super::raw_data::write_long(buffer, value)
+    }
+}
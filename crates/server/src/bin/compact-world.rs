@@ -0,0 +1,31 @@
+//! Converts a world saved in region files to a compact world file.
+//!
+//! Usage: `compact-world <world directory> <output file> [dimension type]`
+
+use anyhow::{bail, Context};
+use iron_oxide_versions::v1_21_5::registry;
+use iron_oxide_world::biome::install_biome_registry;
+use iron_oxide_world::block::install_block_registry;
+use iron_oxide_world::compact::CompactWorld;
+use std::path::Path;
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (directory, output, dimension_type) = match args.as_slice() {
+        [directory, output] => (directory, output, "minecraft:overworld"),
+        [directory, output, dimension_type] => (directory, output, dimension_type.as_str()),
+        _ => bail!("Usage: compact-world <world directory> <output file> [dimension type]"),
+    };
+
+    let registries = registry::load_registry_data()?;
+    let height = registry::world_height(&registries, dimension_type)?;
+    install_block_registry(registry::load_block_registry()?);
+    install_biome_registry(registry::biome_registry(&registries)?);
+
+    let region_directory = Path::new(directory).join("region");
+    let world = CompactWorld::from_regions(&region_directory, height)
+        .with_context(|| format!("Failed to read {}", region_directory.display()))?;
+    world.save(output).with_context(|| format!("Failed to write {}", output))?;
+    println!("Converted {} chunks to {}", world.len(), output);
+    Ok(())
+}
@@ -1,9 +1,16 @@
+use std::sync::Arc;
 use tracing::info;
+use iron_oxide_common::budget::SendBudget;
 use iron_oxide_common::connection::{Connection, ConnectionState};
 use iron_oxide_protocol::error::{Error, Result};
+use iron_oxide_world::world::World;
 use crate::handlers;
 
-pub async fn handle_connection(mut conn: Connection) -> Result<()> {
+pub async fn handle_connection(
+    mut conn: Connection,
+    world: Arc<World>,
+    chunk_budget: Arc<SendBudget>,
+) -> Result<()> {
     loop {
         match conn.state {
             ConnectionState::Handshaking => {
@@ -33,8 +40,8 @@ pub async fn handle_connection(mut conn: Connection) -> Result<()> {
                 conn.state = new_state;
             }
             ConnectionState::Play => {
-                info!("Play not implemented");
-                return Ok(());
+                let config = conn.config.clone();
+                return handlers::play::handle_play(&mut conn, config, world, chunk_budget).await;
             }
         }
     }
@@ -2,3 +2,4 @@ pub mod handshake;
 pub mod status;
 pub mod login;
 pub mod configuration;
+pub mod play;
@@ -0,0 +1,20 @@
+use iron_oxide_common::budget::SendBudget;
+use iron_oxide_common::config::Config;
+use iron_oxide_common::connection::Connection;
+use iron_oxide_protocol::error::Result;
+use iron_oxide_versions::VersionManager;
+use iron_oxide_world::world::World;
+use std::sync::Arc;
+
+pub async fn handle_play(
+    conn: &mut Connection,
+    config: Arc<Config>,
+    world: Arc<World>,
+    chunk_budget: Arc<SendBudget>,
+) -> Result<()> {
+    let version = VersionManager::get_version(conn.protocol_version)?;
+    match version.protocol_version() {
+        770 => iron_oxide_versions::v1_21_5::handlers::play::handle_play(conn, config, world, chunk_budget).await,
+        _ => unreachable!(),
+    }
+}
@@ -1,13 +1,31 @@
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
-use tracing::{error, info};
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, error, info};
+use iron_oxide_common::budget::SendBudget;
 use iron_oxide_common::connection::Connection;
-use iron_oxide_common::config::Config;
+use iron_oxide_common::config::{Config, Generator, World as WorldConfig};
 use iron_oxide_protocol::error::Result;
+use iron_oxide_versions::v1_21_5::registry;
+use iron_oxide_world::behavior::BlockBehaviors;
+use iron_oxide_world::biome::install_biome_registry;
+use iron_oxide_world::block::install_block_registry;
+use iron_oxide_world::compact::CompactWorld;
+use iron_oxide_world::generator::{ChunkGenerator, FlatGenerator, NoiseGenerator, VoidGenerator};
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::ticket::Ticket;
+use iron_oxide_world::view::ChunkPos;
+use iron_oxide_world::world::World;
 
 mod handlers;
 mod connection_handler;
 
+const TICK_INTERVAL: Duration = Duration::from_millis(50);
+const TICKS_PER_SECOND: u64 = 20;
+/// How often the chunk counts are logged, in ticks.
+const METRICS_INTERVAL: u64 = 60 * TICKS_PER_SECOND;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
@@ -19,19 +37,126 @@ async fn main() -> Result<()> {
         return Ok(());
     }
     let config = Arc::new(config);
+    let registries = registry::load_registry_data()?;
+    let world_height = registry::world_height(&registries, &config.world.dimension_type)?;
+    let blocks = registry::load_block_registry()?;
+    info!("Loaded {} blocks with {} states", blocks.block_count(), blocks.state_count());
+    install_block_registry(blocks);
+    install_biome_registry(registry::biome_registry(&registries)?);
+    let world = open_world(&config.world, world_height)
+        .map_err(|e| iron_oxide_protocol::error::Error::Protocol(e.to_string()))?;
+    info!("Loading chunks on {} worker threads", world.worker_threads());
+    if config.world.spawn_chunk_radius > 0 {
+        let spawn = ChunkPos::from_block(config.world.spawn_x, config.world.spawn_z);
+        world.add_ticket(spawn, Ticket::spawn(config.world.spawn_chunk_radius as i32));
+    }
+    let world = Arc::new(world);
+    tokio::spawn(tick_world(Arc::clone(&world)));
+    let chunk_budget = Arc::new(SendBudget::new(config.server.chunk_bytes_per_tick));
+
+    if config.world.autosave_interval_secs > 0 {
+        tokio::spawn(autosave(Arc::clone(&world), Duration::from_secs(config.world.autosave_interval_secs)));
+    }
 
     let listener = TcpListener::bind(config.server.address.clone()).await?;
     info!("Server listening on {}", config.server.address);
 
     loop {
-        let (socket, addr) = listener.accept().await?;
+        let (socket, addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = tokio::signal::ctrl_c() => break,
+        };
         info!("Accepted connection from: {}", addr);
         let config = Arc::clone(&config);
+        let world = Arc::clone(&world);
+        let chunk_budget = Arc::clone(&chunk_budget);
         tokio::spawn(async move {
             let connection = Connection::new(socket, config);
-            if let Err(e) = connection_handler::handle_connection(connection).await {
+            if let Err(e) = connection_handler::handle_connection(connection, world, chunk_budget).await {
                 error!("Error handling connection: {}", e);
             }
         });
     }
+
+    info!("Shutting down, saving the world");
+    save(world).await;
+    Ok(())
+}
+
+fn open_world(config: &WorldConfig, height: WorldHeight) -> iron_oxide_world::error::Result<World> {
+    let generator: Box<dyn ChunkGenerator> = match config.generator {
+        Generator::Flat => Box::new(FlatGenerator::from_preset(&config.flat_preset)?),
+        Generator::Void => Box::new(VoidGenerator::new()),
+        Generator::Noise => Box::new(NoiseGenerator::new(config.seed)?),
+    };
+    let world = match &config.compact_file {
+        Some(file) => {
+            let compact = CompactWorld::open(file)?;
+            info!("Loaded {} chunks from {}", compact.len(), file);
+            World::from_compact(height, compact, generator)?
+        }
+        None => World::open(height, &config.directory, generator),
+    };
+    let world = world
+        .with_update_handler(Box::new(BlockBehaviors::vanilla()))
+        .with_random_tick_speed(config.random_tick_speed)
+        .with_random_seed(config.seed as u64)
+        .with_unload_delay(config.unload_delay_secs * TICKS_PER_SECOND)
+        .with_max_loaded_chunks(config.max_loaded_chunks);
+    Ok(match config.worker_threads {
+        0 => world,
+        threads => world.with_worker_threads(threads),
+    })
+}
+
+/// Ticks the world's chunk tickets, loading and unloading chunks, and logs the chunk counts now
+/// and then.
+async fn tick_world(world: Arc<World>) {
+    let mut interval = tokio::time::interval(TICK_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut ticks = 0u64;
+    loop {
+        interval.tick().await;
+        let ticked = Arc::clone(&world);
+        match tokio::task::spawn_blocking(move || ticked.tick()).await {
+            Ok(Ok(0)) => {}
+            Ok(Ok(count)) => debug!("Unloaded {} chunks", count),
+            Ok(Err(e)) => error!("Failed to tick the world: {}", e),
+            Err(e) => error!("Failed to tick the world: {}", e),
+        }
+
+        ticks += 1;
+        if ticks.is_multiple_of(METRICS_INTERVAL) {
+            let metrics = world.chunk_metrics();
+            info!(
+                "Chunks: {} loaded, {} loading, {} ticketed by {} tickets, {} awaiting unload, {} unloaded",
+                metrics.loaded,
+                metrics.pending,
+                metrics.ticketed,
+                metrics.tickets,
+                metrics.awaiting_unload,
+                metrics.unloaded
+            );
+        }
+    }
+}
+
+/// Saves the modified chunks of the world every interval.
+async fn autosave(world: Arc<World>, interval: Duration) {
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    loop {
+        interval.tick().await;
+        save(Arc::clone(&world)).await;
+    }
+}
+
+/// Saves the modified chunks off the async runtime, since writing region files blocks.
+async fn save(world: Arc<World>) {
+    let saved = tokio::task::spawn_blocking(move || world.save_dirty()).await;
+    match saved {
+        Ok(Ok(0)) => {}
+        Ok(Ok(count)) => info!("Saved {} chunks", count),
+        Ok(Err(e)) => error!("Failed to save the world: {}", e),
+        Err(e) => error!("Failed to save the world: {}", e),
+    }
 }
\ No newline at end of file
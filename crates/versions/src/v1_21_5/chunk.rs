@@ -0,0 +1,174 @@
+use fastnbt::Value;
+use iron_oxide_protocol::error::Result;
+use iron_oxide_protocol::packet::data::PacketData;
+use iron_oxide_protocol::packet::raw_data::write_varint;
+use iron_oxide_protocol::packet::{EncodedPacket, Packet};
+use iron_oxide_protocol::packet::types::{PacketByte, PacketInt, Position, PrefixedBytes};
+use iron_oxide_world::block_entity::BlockEntity;
+use iron_oxide_world::chunk::{ChunkColumn, ChunkSection, SECTION_VOLUME};
+use iron_oxide_world::heightmap::HeightmapKind;
+use iron_oxide_world::light::{LightArray, LightKind};
+use iron_oxide_world::palette::{Palette, PaletteType, PalettedContainer};
+use iron_oxide_world::pos::BlockPos;
+use crate::v1_21_5::registry::block_entity_type_id;
+use crate::v1_21_5::PROTOCOL_VERSION;
+use crate::v1_21_5::packets::play::{
+    BlockEntityData, ChunkBlockEntity, ChunkDataAndUpdateLight, Heightmap, LightData, NetworkNbt, UpdateLight,
+    HEIGHTMAP_MOTION_BLOCKING, HEIGHTMAP_WORLD_SURFACE,
+};
+
+/// Block entity data only the server needs: the items in containers and the loot they will be
+/// filled with.
+const PRIVATE_BLOCK_ENTITY_KEYS: [&str; 3] = ["Items", "LootTable", "LootTableSeed"];
+
+/// Returns the Chunk Data and Update Light packet for the column, reusing the column's cached
+/// encoding when it hasn't changed.
+pub fn encoded_chunk_data_packet(chunk_x: i32, chunk_z: i32, column: &ChunkColumn) -> Result<EncodedPacket> {
+    column
+        .encoded(PROTOCOL_VERSION, |column| {
+            let mut buffer = Vec::new();
+            chunk_data_packet(chunk_x, chunk_z, column)?.write(&mut buffer)?;
+            Ok(buffer)
+        })
+        .map(EncodedPacket)
+}
+
+pub fn chunk_data_packet(chunk_x: i32, chunk_z: i32, column: &ChunkColumn) -> Result<ChunkDataAndUpdateLight> {
+    let mut data = Vec::new();
+    for section in column.sections() {
+        write_section(section, &mut data)?;
+    }
+
+    Ok(ChunkDataAndUpdateLight {
+        chunk_x: PacketInt(chunk_x),
+        chunk_z: PacketInt(chunk_z),
+        heightmaps: heightmaps(column),
+        data: PrefixedBytes(data),
+        block_entities: block_entities(column),
+        light: light_data(column),
+    })
+}
+
+/// Returns the Update Light packet resending the whole light of the column.
+pub fn update_light_packet(chunk_x: i32, chunk_z: i32, column: &ChunkColumn) -> UpdateLight {
+    UpdateLight { chunk_x, chunk_z, light: light_data(column) }
+}
+
+fn write_section(section: &ChunkSection, buffer: &mut Vec<u8>) -> Result<()> {
+    PacketData::write(&(section.block_count() as i16), buffer)?;
+    if section.is_empty() {
+        // Whatever kinds of air an empty section holds, the client only needs to know it is air.
+        write_paletted_container(&PalettedContainer::new(PaletteType::Block, SECTION_VOLUME), buffer)?;
+    } else {
+        write_paletted_container(section.block_states(), buffer)?;
+    }
+    write_paletted_container(section.biomes(), buffer)
+}
+
+/// Since 1.21.5 the length of the data array is no longer sent, the client derives it from the
+/// bits per entry.
+pub fn write_paletted_container(container: &PalettedContainer, buffer: &mut Vec<u8>) -> Result<()> {
+    buffer.push(container.bits_per_entry());
+    match container.palette() {
+        Palette::Single(value) => write_varint(buffer, *value as i32)?,
+        Palette::Indirect(palette) => {
+            write_varint(buffer, palette.len() as i32)?;
+            for value in palette {
+                write_varint(buffer, *value as i32)?;
+            }
+        }
+        Palette::Direct => {}
+    }
+    for long in container.storage() {
+        PacketData::write(&(*long as i64), buffer)?;
+    }
+    Ok(())
+}
+
+/// The heightmaps the client needs, which every column keeps up to date.
+fn heightmaps(column: &ChunkColumn) -> Vec<Heightmap> {
+    let world_height = column.world_height().height();
+    [
+        (HeightmapKind::WorldSurface, HEIGHTMAP_WORLD_SURFACE),
+        (HeightmapKind::MotionBlocking, HEIGHTMAP_MOTION_BLOCKING),
+    ]
+    .into_iter()
+    .filter_map(|(kind, id)| {
+        let heightmap = column.heightmap(kind)?;
+        Some(Heightmap { kind: id, data: heightmap.pack(world_height) })
+    })
+    .collect()
+}
+
+/// Returns the block entities of the column the client knows the type of.
+fn block_entities(column: &ChunkColumn) -> Vec<ChunkBlockEntity> {
+    column
+        .block_entities()
+        .filter_map(|(pos, entity)| {
+            Some(ChunkBlockEntity {
+                packed_xz: PacketByte(((pos.x << 4) | pos.z) as u8),
+                y: pos.y as i16,
+                kind: block_entity_type_id(&entity.kind)?,
+                data: network_data(entity),
+            })
+        })
+        .collect()
+}
+
+/// Returns the data of the block entity that is sent to clients. What containers hold is left
+/// out, since the client only learns it when the container is opened.
+pub fn network_data(entity: &BlockEntity) -> Value {
+    let mut data = entity.data.clone();
+    for key in PRIVATE_BLOCK_ENTITY_KEYS {
+        data.remove(key);
+    }
+    Value::Compound(data)
+}
+
+/// Returns the Block Entity Data packet for the block entity at the world position, or `None` if
+/// the client doesn't know its type.
+pub fn block_entity_data_packet(pos: BlockPos, entity: &BlockEntity) -> Option<BlockEntityData> {
+    Some(BlockEntityData {
+        location: Position { x: pos.x, y: pos.y, z: pos.z },
+        kind: block_entity_type_id(&entity.kind)?,
+        data: NetworkNbt(network_data(entity)),
+    })
+}
+
+/// Sends the levels of every section that isn't dark and marks the dark ones as empty. Columns
+/// that were never lit are sent fully sky lit, so their terrain is still visible.
+fn light_data(column: &ChunkColumn) -> LightData {
+    let light = column.light();
+    let mut data = LightData::default();
+    for kind in LightKind::ALL {
+        let arrays = light.arrays(kind);
+        let mut mask = vec![0i64; arrays.len().div_ceil(64)];
+        let mut empty_mask = mask.clone();
+        let mut sent = Vec::new();
+        for (index, array) in arrays.iter().enumerate() {
+            let array = match (column.is_lit(), kind) {
+                (false, LightKind::Sky) => &LightArray::Uniform(15),
+                _ => array,
+            };
+            if array.is_dark() {
+                empty_mask[index / 64] |= 1 << (index % 64);
+            } else {
+                mask[index / 64] |= 1 << (index % 64);
+                sent.push(PrefixedBytes(array.to_bytes()));
+            }
+        }
+        match kind {
+            LightKind::Sky => {
+                data.sky_light_mask = mask;
+                data.empty_sky_light_mask = empty_mask;
+                data.sky_light_arrays = sent;
+            }
+            LightKind::Block => {
+                data.block_light_mask = mask;
+                data.empty_block_light_mask = empty_mask;
+                data.block_light_arrays = sent;
+            }
+        }
+    }
+    data
+}
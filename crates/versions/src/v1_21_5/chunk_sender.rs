@@ -0,0 +1,255 @@
+use std::collections::HashSet;
+use iron_oxide_common::budget::SendBudget;
+use iron_oxide_common::connection::Connection;
+use iron_oxide_protocol::error::Result;
+use iron_oxide_protocol::packet::types::{PacketInt, Position};
+use iron_oxide_world::changes::ChangeKind;
+use iron_oxide_world::pos::BlockPos;
+use iron_oxide_world::view::{ChunkPos, ChunkView};
+use iron_oxide_world::world::{split, World};
+use crate::v1_21_5::chunk::{block_entity_data_packet, encoded_chunk_data_packet};
+use crate::v1_21_5::packets::play::{BlockUpdate, ChunkBatchFinished, ChunkBatchStart, SetCenterChunk, UnloadChunk};
+
+const INITIAL_CHUNKS_PER_TICK: f32 = 9.0;
+const MIN_CHUNKS_PER_TICK: f32 = 0.01;
+const MAX_CHUNKS_PER_TICK: f32 = 64.0;
+const MAX_UNACKNOWLEDGED_BATCHES: u32 = 10;
+/// How far ahead a player's movement is extrapolated when picking the chunks it needs first.
+const LOOKAHEAD_TICKS: f64 = 20.0;
+/// Chunks this close to the player are sent first regardless of where it is looking.
+const NEARBY_DISTANCE: f64 = 24.0;
+/// How much further away a chunk directly behind the player is treated than one straight ahead.
+const BEHIND_PENALTY: f64 = 1.0;
+
+/// Where a player is and where it is heading, used to decide which chunks it needs first.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Viewer {
+    pub x: f64,
+    pub z: f64,
+    pub yaw: f32,
+    /// Blocks moved along each axis during the last tick.
+    pub velocity_x: f64,
+    pub velocity_z: f64,
+}
+
+impl Viewer {
+    /// Returns the order in which the chunk should be sent, lower first. Chunks are ranked by their
+    /// distance to where the player will be shortly, with chunks behind it pushed back.
+    pub fn chunk_priority(&self, pos: ChunkPos) -> f64 {
+        let x = self.x + self.velocity_x * LOOKAHEAD_TICKS;
+        let z = self.z + self.velocity_z * LOOKAHEAD_TICKS;
+        let dx = pos.x as f64 * 16.0 + 8.0 - x;
+        let dz = pos.z as f64 * 16.0 + 8.0 - z;
+        let distance = (dx * dx + dz * dz).sqrt();
+        if distance < NEARBY_DISTANCE {
+            return distance;
+        }
+
+        // A yaw of 0 faces south (+Z) and 90 faces west (-X).
+        let yaw = (self.yaw as f64).to_radians();
+        let alignment = (dx * -yaw.sin() + dz * yaw.cos()) / distance;
+        distance * (1.0 + BEHIND_PENALTY * (1.0 - alignment) / 2.0)
+    }
+}
+
+/// Streams the chunks in a player's view, paced by how many chunks per tick the client reports it
+/// can process and limited by a per-player and a server-wide byte budget.
+///
+/// Until the client acknowledges the first batch only one batch is in flight, afterwards up to
+/// [`MAX_UNACKNOWLEDGED_BATCHES`].
+#[derive(Debug)]
+pub struct ChunkSender {
+    view: ChunkView,
+    sent: HashSet<ChunkPos>,
+    pending: Vec<ChunkPos>,
+    bytes_per_tick: usize,
+    desired_chunks_per_tick: f32,
+    batch_quota: f32,
+    unacknowledged_batches: u32,
+    max_unacknowledged_batches: u32,
+    /// The number of the next change to the world's blocks to send.
+    change_cursor: u64,
+}
+
+impl ChunkSender {
+    /// Changes to the blocks from `change_cursor` on are sent for the chunks sent by then, earlier
+    /// ones are part of the chunks.
+    pub fn new(view: ChunkView, bytes_per_tick: usize, change_cursor: u64) -> Self {
+        Self {
+            view,
+            sent: HashSet::new(),
+            pending: view.spiral().collect(),
+            bytes_per_tick,
+            desired_chunks_per_tick: INITIAL_CHUNKS_PER_TICK,
+            batch_quota: 0.0,
+            unacknowledged_batches: 0,
+            max_unacknowledged_batches: 1,
+            change_cursor,
+        }
+    }
+
+    pub fn view(&self) -> ChunkView {
+        self.view
+    }
+
+    /// Whether the chunk has been sent to the client and not unloaded since.
+    pub fn is_sent(&self, pos: ChunkPos) -> bool {
+        self.sent.contains(&pos)
+    }
+
+    /// Moves the view, unloading the chunks that fell out of it and queueing the ones that came
+    /// into it.
+    pub async fn update_view(&mut self, conn: &mut Connection, view: ChunkView) -> Result<()> {
+        if view == self.view {
+            return Ok(());
+        }
+
+        if view.center != self.view.center {
+            conn.write_packet(SetCenterChunk {
+                chunk_x: view.center.x,
+                chunk_z: view.center.z,
+            })
+            .await?;
+        }
+
+        let out_of_view: Vec<ChunkPos> = self.sent.iter().copied().filter(|pos| !view.contains(*pos)).collect();
+        for pos in out_of_view {
+            self.sent.remove(&pos);
+            conn.write_packet(UnloadChunk {
+                chunk_z: PacketInt(pos.z),
+                chunk_x: PacketInt(pos.x),
+            })
+            .await?;
+        }
+
+        self.view = view;
+        self.pending = view.spiral().filter(|pos| !self.sent.contains(pos)).collect();
+        Ok(())
+    }
+
+    pub fn on_batch_received(&mut self, chunks_per_tick: f32) {
+        self.unacknowledged_batches = self.unacknowledged_batches.saturating_sub(1);
+        self.desired_chunks_per_tick = if chunks_per_tick.is_nan() {
+            MIN_CHUNKS_PER_TICK
+        } else {
+            chunks_per_tick.clamp(MIN_CHUNKS_PER_TICK, MAX_CHUNKS_PER_TICK)
+        };
+        if self.unacknowledged_batches == 0 {
+            self.batch_quota = 1.0;
+        }
+        self.max_unacknowledged_batches = MAX_UNACKNOWLEDGED_BATCHES;
+    }
+
+    /// Sends the changes made to the blocks of the chunks the client has. If changes were dropped
+    /// before they could be sent, every chunk is sent again instead.
+    pub async fn send_changes(&mut self, conn: &mut Connection, world: &World) -> Result<()> {
+        let changes = world.changes_since(self.change_cursor);
+        self.change_cursor = changes.next;
+        if changes.missed {
+            self.sent.clear();
+            self.pending = self.view.spiral().collect();
+            return Ok(());
+        }
+
+        let mut sent_changes = HashSet::new();
+        for change in changes.changes {
+            let chunk_pos = ChunkPos::from_block(change.pos.x, change.pos.z);
+            if !self.sent.contains(&chunk_pos) || !sent_changes.insert(change) {
+                continue;
+            }
+            let Some(chunk) = world.loaded_chunk(chunk_pos.x, chunk_pos.z) else {
+                continue;
+            };
+            let (_, local_x, _, local_z) = split(change.pos.x, change.pos.z);
+            let local = BlockPos::new(local_x, change.pos.y, local_z);
+            match change.kind {
+                ChangeKind::Block => {
+                    let Ok(block) = chunk.read().unwrap().get_block(local.x, local.y, local.z) else {
+                        continue;
+                    };
+                    let location = Position { x: change.pos.x, y: change.pos.y, z: change.pos.z };
+                    conn.write_packet(BlockUpdate { location, block_id: block.id() as i32 }).await?;
+                }
+                ChangeKind::BlockEntity => {
+                    let packet = chunk
+                        .read()
+                        .unwrap()
+                        .block_entity(local)
+                        .and_then(|entity| block_entity_data_packet(change.pos, entity));
+                    if let Some(packet) = packet {
+                        conn.write_packet(packet).await?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends the chunks the player needs most if the client is ready for another batch and the
+    /// budgets allow it.
+    pub async fn tick(
+        &mut self,
+        conn: &mut Connection,
+        world: &World,
+        viewer: Viewer,
+        budget: &SendBudget,
+    ) -> Result<()> {
+        if self.pending.is_empty() || self.unacknowledged_batches >= self.max_unacknowledged_batches {
+            return Ok(());
+        }
+
+        self.batch_quota = (self.batch_quota + self.desired_chunks_per_tick).min(MAX_CHUNKS_PER_TICK);
+        let count = (self.batch_quota as usize).min(self.pending.len());
+        if count == 0 {
+            return Ok(());
+        }
+
+        self.pending
+            .sort_by(|a, b| viewer.chunk_priority(*a).total_cmp(&viewer.chunk_priority(*b)));
+
+        // Chunks that aren't loaded yet are requested and sent once they and their neighbors
+        // are, so their light is complete and a slow load doesn't hold up the chunks behind it.
+        let mut packets = Vec::with_capacity(count);
+        let mut batch = HashSet::with_capacity(count);
+        let mut bytes = 0;
+        for pos in &self.pending {
+            if packets.len() == count {
+                break;
+            }
+            let Some(chunk) = world.loaded_chunk(pos.x, pos.z) else {
+                world.load_chunk(pos.x, pos.z);
+                continue;
+            };
+            if !world.is_ready(pos.x, pos.z) {
+                continue;
+            }
+            let packet = encoded_chunk_data_packet(pos.x, pos.z, &chunk.read().unwrap())?;
+            if !packets.is_empty() && bytes + packet.len() > self.bytes_per_tick {
+                break;
+            }
+            if !budget.try_take(packet.len()) {
+                break;
+            }
+            bytes += packet.len();
+            packets.push(packet);
+            batch.insert(*pos);
+        }
+
+        if packets.is_empty() {
+            return Ok(());
+        }
+        let batch_size = packets.len();
+        self.pending.retain(|pos| !batch.contains(pos));
+        self.sent.extend(batch);
+
+        conn.write_packet(ChunkBatchStart {}).await?;
+        for packet in packets {
+            conn.write_packet(packet).await?;
+        }
+        conn.write_packet(ChunkBatchFinished { batch_size: batch_size as i32 }).await?;
+
+        self.batch_quota -= batch_size as f32;
+        self.unacknowledged_batches += 1;
+        Ok(())
+    }
+}
@@ -1,16 +1,16 @@
 use std::sync::Arc;
 use tracing::info;
 use iron_oxide_common::config::Config;
-use iron_oxide_common::connection::{Connection, ConnectionState};
+use iron_oxide_common::connection::Connection;
 use crate::v1_21_5::packets::configuration::{
     ClientboundPluginMessage, ClientInformation, FinishConfiguration, AcknowledgeFinishConfiguration,
     ClientboundKnownPacks, KnownPack, ServerboundKnownPacks, RegistryData, ServerboundPluginMessage,
+    TagRegistry,
 };
 use std::fs;
 use fastnbt::Value;
 use serde_json::Value as JsonValue;
 use iron_oxide_protocol::error::{Error, Result};
-use iron_oxide_protocol::packet::raw_data::read_varint;
 use iron_oxide_protocol::packet::types::PacketBytes;
 
 fn json_to_nbt(json: &JsonValue) -> Result<Value> {
@@ -46,14 +46,15 @@ pub async fn handle_configuration(conn: &mut Connection, _config: Arc<Config>) -
     info!("Client entered configuration state");
 
     loop {
-        let packet_id = read_varint(&mut conn.peek_packet().await?)?;
+        let packet_id = conn.peek_packet_id().await?.ok_or_else(|| Error::Protocol("Connection closed during configuration".to_string()))?;
         match packet_id {
-            0x0f => { // Client Information
-                let _client_info: ClientInformation = conn.read_packet().await?.ok_or_else(|| Error::Protocol("ClientInformation packet not received".to_string()))?;
-                info!("Received client information: {:?}", _client_info);
+            0x00 => { // Client Information
+                let client_info: ClientInformation = conn.read_packet().await?.ok_or_else(|| Error::Protocol("ClientInformation packet not received".to_string()))?;
+                info!("Received client information: {:?}", client_info);
+                conn.client_view_distance = client_info.view_distance.0;
                 send_initial_server_configuration(conn).await?
             }
-            0x19 => { // Plugin Message
+            0x02 => { // Plugin Message
                 let plugin_message: ServerboundPluginMessage = conn.read_packet().await?.ok_or_else(|| Error::Protocol("ServerboundPluginMessage packet not received".to_string()))?;
                 info!("Received plugin message: {:?}", plugin_message);
             }
@@ -62,7 +63,7 @@ pub async fn handle_configuration(conn: &mut Connection, _config: Arc<Config>) -
                 info!("Received Known Packs: {:?}", serverbound_known_packs);
                 send_final_server_configuration(conn).await?;
             }
-            0x18 => {
+            0x03 => { // Acknowledge Finish Configuration
                 let _ack: AcknowledgeFinishConfiguration = conn.read_packet().await?.ok_or_else(|| Error::Protocol("AcknowledgeFinishConfiguration packet not received".to_string()))?;
                 info!("Received Acknowledge Finish Configuration");
                 return Ok(())
@@ -107,8 +108,7 @@ async fn send_initial_server_configuration(conn: &mut Connection) -> Result<()>
 
 async fn send_final_server_configuration(conn: &mut Connection) -> Result<()> {
     // Send Registry Data
-    let registry_data_str = fs::read_to_string("config/v1_20_6/registry-data.json")?;
-    let registry_data_json: JsonValue = serde_json::from_str(&registry_data_str).map_err(|e| Error::Protocol(format!("Failed to parse registry data: {}", e)))?;
+    let registry_data_json = crate::v1_21_5::registry::load_registry_data()?;
 
     if let JsonValue::Object(registries) = registry_data_json {
         for (registry_id, registry) in registries {
@@ -145,7 +145,7 @@ async fn send_final_server_configuration(conn: &mut Connection) -> Result<()> {
     Ok(())
 }
 
-fn load_tags() -> Result<Vec<(String, Vec<(String, Vec<i32>)>)>> {
+fn load_tags() -> Result<Vec<TagRegistry>> {
     let tags_str = fs::read_to_string("config/v1_20_6/tags.json")?;
     let tags_json: JsonValue = serde_json::from_str(&tags_str).map_err(|e| Error::Protocol(format!("Failed to parse tags: {}", e)))?;
     let mut tags = Vec::new();
@@ -1,3 +1,4 @@
 pub mod status;
 pub mod login;
-pub mod configuration;
\ No newline at end of file
+pub mod configuration;
+pub mod play;
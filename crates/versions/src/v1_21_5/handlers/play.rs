@@ -0,0 +1,375 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info};
+use iron_oxide_common::budget::SendBudget;
+use iron_oxide_common::config::{Config, Generator};
+use iron_oxide_common::connection::Connection;
+use iron_oxide_protocol::error::{Error, Result};
+use iron_oxide_protocol::packet::Packet;
+use iron_oxide_protocol::packet::types::{PacketByte, PacketInt, Position};
+use iron_oxide_world::heightmap::HeightmapKind;
+use iron_oxide_world::ticket::Ticket;
+use iron_oxide_world::view::{ChunkPos, ChunkView};
+use iron_oxide_world::world::{split, World};
+use sha2::{Digest, Sha256};
+use crate::v1_21_5::chunk_sender::{ChunkSender, Viewer};
+use crate::v1_21_5::packets::configuration::ClientInformation;
+use crate::v1_21_5::packets::play::{
+    ChunkBatchReceived, ClientboundKeepAlive, ConfirmTeleportation, GameEvent, LoginPlay,
+    ServerboundKeepAlive, SetCenterChunk, SetDefaultSpawnPosition, SetPlayerPosition,
+    SetPlayerPositionAndRotation, SetPlayerRotation, SynchronizePlayerPosition,
+    GAME_EVENT_START_WAITING_FOR_CHUNKS,
+};
+use crate::v1_21_5::registry;
+
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+const TICK_INTERVAL: Duration = Duration::from_millis(50);
+
+static NEXT_ENTITY_ID: AtomicI32 = AtomicI32::new(1);
+
+/// The state of a player for the lifetime of its play connection.
+#[derive(Debug)]
+pub struct Player {
+    pub entity_id: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+    /// The view distance the client asked for, 0 if it didn't say.
+    pub client_view_distance: u8,
+    last_tick_x: f64,
+    last_tick_z: f64,
+    velocity_x: f64,
+    velocity_z: f64,
+    next_teleport_id: i32,
+    pending_teleport: Option<i32>,
+    pending_keep_alive: Option<i64>,
+}
+
+impl Player {
+    fn new(x: f64, y: f64, z: f64, client_view_distance: u8) -> Self {
+        Self {
+            entity_id: NEXT_ENTITY_ID.fetch_add(1, Ordering::Relaxed),
+            x,
+            y,
+            z,
+            yaw: 0.0,
+            pitch: 0.0,
+            on_ground: false,
+            client_view_distance,
+            last_tick_x: x,
+            last_tick_z: z,
+            velocity_x: 0.0,
+            velocity_z: 0.0,
+            next_teleport_id: 0,
+            pending_teleport: None,
+            pending_keep_alive: None,
+        }
+    }
+
+    /// Whether the client has confirmed the last position the server teleported it to.
+    /// Movement sent before the confirmation refers to the old position and is ignored.
+    pub fn is_teleport_confirmed(&self) -> bool {
+        self.pending_teleport.is_none()
+    }
+
+    pub fn chunk_pos(&self) -> ChunkPos {
+        ChunkPos::from_block(self.x.floor() as i32, self.z.floor() as i32)
+    }
+
+    /// The client's view distance, limited to the server's.
+    pub fn view_distance(&self, config: &Config) -> i32 {
+        let server_view_distance = config.server.view_distance;
+        match self.client_view_distance {
+            0 => server_view_distance as i32,
+            client => client.clamp(2, server_view_distance) as i32,
+        }
+    }
+
+    pub fn chunk_view(&self, config: &Config) -> ChunkView {
+        ChunkView::new(self.chunk_pos(), self.view_distance(config))
+    }
+
+    /// Measures how far the player moved horizontally since the last tick.
+    fn update_velocity(&mut self) {
+        self.velocity_x = self.x - self.last_tick_x;
+        self.velocity_z = self.z - self.last_tick_z;
+        self.last_tick_x = self.x;
+        self.last_tick_z = self.z;
+    }
+
+    pub fn viewer(&self) -> Viewer {
+        Viewer {
+            x: self.x,
+            z: self.z,
+            yaw: self.yaw,
+            velocity_x: self.velocity_x,
+            velocity_z: self.velocity_z,
+        }
+    }
+}
+
+/// Keeps the chunks a player can see loaded, and the ring around them their light depends on,
+/// and ticks the blocks within the simulation distance, following the player until it leaves.
+struct ViewTicket<'a> {
+    world: &'a World,
+    pos: ChunkPos,
+    ticket: Ticket,
+    simulation: Ticket,
+}
+
+impl<'a> ViewTicket<'a> {
+    fn new(world: &'a World, view: ChunkView, simulation_distance: u8) -> Self {
+        let ticket = Ticket::player(view.view_distance + 1);
+        let simulation = Ticket::simulation(simulation_distance as i32);
+        world.add_ticket(view.center, ticket);
+        world.add_ticket(view.center, simulation);
+        Self { world, pos: view.center, ticket, simulation }
+    }
+
+    fn update(&mut self, view: ChunkView) {
+        let ticket = Ticket::player(view.view_distance + 1);
+        if view.center != self.pos || ticket != self.ticket {
+            self.world.add_ticket(view.center, ticket);
+            self.world.remove_ticket(self.pos, self.ticket);
+            if view.center != self.pos {
+                self.world.add_ticket(view.center, self.simulation);
+                self.world.remove_ticket(self.pos, self.simulation);
+            }
+            self.pos = view.center;
+            self.ticket = ticket;
+        }
+    }
+}
+
+impl Drop for ViewTicket<'_> {
+    fn drop(&mut self) {
+        self.world.remove_ticket(self.pos, self.ticket);
+        self.world.remove_ticket(self.pos, self.simulation);
+    }
+}
+
+pub async fn handle_play(
+    conn: &mut Connection,
+    config: Arc<Config>,
+    world: Arc<World>,
+    chunk_budget: Arc<SendBudget>,
+) -> Result<()> {
+    info!("Client entered play state");
+
+    let mut player = join_game(conn, &config, &world).await?;
+
+    let view = player.chunk_view(&config);
+    conn.write_packet(SetCenterChunk {
+        chunk_x: view.center.x,
+        chunk_z: view.center.z,
+    })
+    .await?;
+    let mut chunks = ChunkSender::new(view, config.players.chunk_bytes_per_tick, world.change_cursor());
+    let mut ticket = ViewTicket::new(&world, view, config.server.simulation_distance);
+
+    let mut keep_alive = tokio::time::interval(KEEP_ALIVE_INTERVAL);
+    keep_alive.tick().await;
+    let mut tick = tokio::time::interval(TICK_INTERVAL);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        tokio::select! {
+            packet_id = conn.peek_packet_id() => {
+                let Some(packet_id) = packet_id? else {
+                    info!("Client disconnected");
+                    return Ok(());
+                };
+                handle_packet(conn, &mut player, &mut chunks, packet_id).await?;
+                let view = player.chunk_view(&config);
+                chunks.update_view(conn, view).await?;
+                ticket.update(view);
+            }
+            _ = tick.tick() => {
+                player.update_velocity();
+                chunks.send_changes(conn, &world).await?;
+                chunks.tick(conn, &world, player.viewer(), &chunk_budget).await?;
+            }
+            _ = keep_alive.tick() => {
+                send_keep_alive(conn, &mut player).await?;
+            }
+        }
+    }
+}
+
+async fn join_game(conn: &mut Connection, config: &Config, world: &World) -> Result<Player> {
+    let registries = registry::load_registry_data()?;
+    let dimension_type_id = registry::entry_id(&registries, "minecraft:dimension_type", &config.world.dimension_type)?;
+
+    let spawn_x = config.world.spawn_x;
+    let spawn_z = config.world.spawn_z;
+    let spawn_y = surface_y(world, spawn_x, spawn_z).await?;
+
+    let mut player = Player::new(spawn_x as f64 + 0.5, spawn_y as f64, spawn_z as f64 + 0.5, conn.client_view_distance);
+
+    conn.write_packet(LoginPlay {
+        entity_id: PacketInt(player.entity_id),
+        is_hardcore: false,
+        dimension_names: vec![config.world.dimension_name.clone()],
+        max_players: config.players.max_players,
+        view_distance: config.server.view_distance as i32,
+        simulation_distance: config.server.simulation_distance as i32,
+        reduced_debug_info: false,
+        enable_respawn_screen: true,
+        do_limited_crafting: false,
+        dimension_type: dimension_type_id,
+        dimension_name: config.world.dimension_name.clone(),
+        hashed_seed: hash_seed(config.world.seed),
+        game_mode: PacketByte(config.world.game_mode.id()),
+        previous_game_mode: PacketByte(0xFF),
+        is_debug: false,
+        is_flat: config.world.generator == Generator::Flat,
+        death_location: None,
+        portal_cooldown: 0,
+        sea_level: 63,
+        enforces_secure_chat: false,
+    })
+    .await?;
+    info!("Sent Login (play)");
+
+    conn.write_packet(SetDefaultSpawnPosition {
+        location: Position { x: spawn_x, y: spawn_y, z: spawn_z },
+        angle: 0.0,
+    })
+    .await?;
+
+    conn.write_packet(GameEvent {
+        event: PacketByte(GAME_EVENT_START_WAITING_FOR_CHUNKS),
+        value: 0.0,
+    })
+    .await?;
+
+    teleport(conn, &mut player).await?;
+    info!("Player {} spawned at {}, {}, {}", player.entity_id, player.x, player.y, player.z);
+
+    Ok(player)
+}
+
+/// Returns the y above the highest block that stops movement or holds a fluid, so players don't
+/// spawn in plants or under water.
+async fn surface_y(world: &World, x: i32, z: i32) -> Result<i32> {
+    let (chunk_x, local_x, chunk_z, local_z) = split(x, z);
+    let chunk = world.load_chunk(chunk_x, chunk_z).await.map_err(|e| Error::Protocol(e.to_string()))?;
+    let chunk = chunk.read().unwrap();
+    let height = chunk
+        .heightmap(HeightmapKind::MotionBlocking)
+        .map_or(0, |heightmap| heightmap.get(local_x as usize, local_z as usize));
+    Ok(world.height().min_y() + height as i32)
+}
+
+/// The client only receives the first 8 bytes of the SHA-256 of the seed, which it uses for biome
+/// noise. Vanilla hashes the seed and reads the hash back little endian, as Guava does.
+fn hash_seed(seed: i64) -> i64 {
+    let digest = Sha256::digest(seed.to_le_bytes());
+    i64::from_le_bytes(digest[..8].try_into().unwrap())
+}
+
+async fn teleport(conn: &mut Connection, player: &mut Player) -> Result<()> {
+    let teleport_id = player.next_teleport_id;
+    player.next_teleport_id = player.next_teleport_id.wrapping_add(1);
+    player.pending_teleport = Some(teleport_id);
+
+    conn.write_packet(SynchronizePlayerPosition {
+        teleport_id,
+        x: player.x,
+        y: player.y,
+        z: player.z,
+        velocity_x: 0.0,
+        velocity_y: 0.0,
+        velocity_z: 0.0,
+        yaw: player.yaw,
+        pitch: player.pitch,
+        flags: PacketInt(0),
+    })
+    .await
+}
+
+async fn send_keep_alive(conn: &mut Connection, player: &mut Player) -> Result<()> {
+    if player.pending_keep_alive.is_some() {
+        return Err(Error::Protocol("Keep alive timed out".to_string()));
+    }
+
+    let keep_alive_id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    player.pending_keep_alive = Some(keep_alive_id);
+    conn.write_packet(ClientboundKeepAlive { keep_alive_id }).await
+}
+
+async fn handle_packet(conn: &mut Connection, player: &mut Player, chunks: &mut ChunkSender, packet_id: i32) -> Result<()> {
+    match packet_id {
+        0x00 => {
+            let confirm: ConfirmTeleportation = read_packet(conn).await?;
+            if player.pending_teleport == Some(confirm.teleport_id) {
+                player.pending_teleport = None;
+                debug!("Teleport {} confirmed", confirm.teleport_id);
+            }
+        }
+        0x0A => {
+            let batch_received: ChunkBatchReceived = read_packet(conn).await?;
+            chunks.on_batch_received(batch_received.chunks_per_tick);
+        }
+        0x0D => {
+            // Same layout as in the configuration state.
+            let client_info: ClientInformation = read_packet(conn).await?;
+            player.client_view_distance = client_info.view_distance.0;
+        }
+        0x1B => {
+            let keep_alive: ServerboundKeepAlive = read_packet(conn).await?;
+            if player.pending_keep_alive == Some(keep_alive.keep_alive_id) {
+                player.pending_keep_alive = None;
+            }
+        }
+        0x1D => {
+            let movement: SetPlayerPosition = read_packet(conn).await?;
+            if player.is_teleport_confirmed() {
+                player.x = movement.x;
+                player.y = movement.y;
+                player.z = movement.z;
+                player.on_ground = movement.flags.0 & 0x01 != 0;
+            }
+        }
+        0x1E => {
+            let movement: SetPlayerPositionAndRotation = read_packet(conn).await?;
+            if player.is_teleport_confirmed() {
+                player.x = movement.x;
+                player.y = movement.y;
+                player.z = movement.z;
+                player.yaw = movement.yaw;
+                player.pitch = movement.pitch;
+                player.on_ground = movement.flags.0 & 0x01 != 0;
+            }
+        }
+        0x1F => {
+            let rotation: SetPlayerRotation = read_packet(conn).await?;
+            if player.is_teleport_confirmed() {
+                player.yaw = rotation.yaw;
+                player.pitch = rotation.pitch;
+                player.on_ground = rotation.flags.0 & 0x01 != 0;
+            }
+        }
+        _ => {
+            // For now, we'll ignore other packets.
+            debug!("Unhandled play packet 0x{:X}", packet_id);
+            let _ = conn.read_packet_raw().await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn read_packet<T: Packet + Send>(conn: &mut Connection) -> Result<T> {
+    conn.read_packet()
+        .await?
+        .ok_or_else(|| Error::Protocol(format!("{} packet not received", std::any::type_name::<T>())))
+}
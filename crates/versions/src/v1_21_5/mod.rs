@@ -2,13 +2,17 @@ use crate::Version;
 
 pub mod packets;
 pub mod handlers;
+pub mod registry;
+pub mod chunk;
+pub mod chunk_sender;
 
 pub const V1_21_5: VersionImpl = VersionImpl;
+pub const PROTOCOL_VERSION: i32 = 770;
 
 pub struct VersionImpl;
 
 impl Version for VersionImpl {
     fn protocol_version(&self) -> i32 {
-        770
+        PROTOCOL_VERSION
     }
 }
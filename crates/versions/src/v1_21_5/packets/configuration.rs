@@ -152,8 +152,8 @@ packet! {
     }
 }
 
-type Tag = (String, Vec<i32>);
-type TagRegistry = (String, Vec<Tag>);
+pub type Tag = (String, Vec<i32>);
+pub type TagRegistry = (String, Vec<Tag>);
 
 fn read_tags(buffer: &mut &[u8]) -> Result<Vec<TagRegistry>> {
     let len: i32 = packet::data::PacketData::read(buffer)?;
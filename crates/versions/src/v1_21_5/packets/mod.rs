@@ -2,8 +2,10 @@ pub mod handshake;
 pub mod status;
 pub mod login;
 pub mod configuration;
+pub mod play;
 
 pub use handshake::*;
 pub use status::*;
 pub use login::*;
 pub use configuration::*;
+pub use play::*;
@@ -0,0 +1,312 @@
+use fastnbt::Value;
+use iron_oxide_protocol::error::{Error, Result};
+use iron_oxide_protocol::packet;
+use iron_oxide_protocol::packet::types::{PacketByte, PacketInt, Position, PrefixedBytes};
+
+#[derive(Debug)]
+pub struct DeathLocation {
+    pub dimension_name: String,
+    pub location: Position,
+}
+
+impl packet::data::PacketData for DeathLocation {
+    fn read(buffer: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            dimension_name: packet::data::PacketData::read(buffer)?,
+            location: packet::data::PacketData::read(buffer)?,
+        })
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        packet::data::PacketData::write(&self.dimension_name, buffer)?;
+        packet::data::PacketData::write(&self.location, buffer)?;
+        Ok(())
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct LoginPlay(0x2B) {
+        entity_id: PacketInt,
+        is_hardcore: bool,
+        dimension_names: Vec<String>,
+        max_players: i32,
+        view_distance: i32,
+        simulation_distance: i32,
+        reduced_debug_info: bool,
+        enable_respawn_screen: bool,
+        do_limited_crafting: bool,
+        dimension_type: i32,
+        dimension_name: String,
+        hashed_seed: i64,
+        game_mode: PacketByte,
+        previous_game_mode: PacketByte,
+        is_debug: bool,
+        is_flat: bool,
+        death_location: Option<DeathLocation>,
+        portal_cooldown: i32,
+        sea_level: i32,
+        enforces_secure_chat: bool,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct SetDefaultSpawnPosition(0x5A) {
+        location: Position,
+        angle: f32,
+    }
+}
+
+pub const GAME_EVENT_START_WAITING_FOR_CHUNKS: u8 = 13;
+
+packet! {
+    #[derive(Debug)]
+    pub struct GameEvent(0x22) {
+        event: PacketByte,
+        value: f32,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct SynchronizePlayerPosition(0x41) {
+        teleport_id: i32,
+        x: f64,
+        y: f64,
+        z: f64,
+        velocity_x: f64,
+        velocity_y: f64,
+        velocity_z: f64,
+        yaw: f32,
+        pitch: f32,
+        flags: PacketInt,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct ClientboundKeepAlive(0x26) {
+        keep_alive_id: i64,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct ConfirmTeleportation(0x00) {
+        teleport_id: i32,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct ServerboundKeepAlive(0x1B) {
+        keep_alive_id: i64,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct SetPlayerPosition(0x1D) {
+        x: f64,
+        y: f64,
+        z: f64,
+        flags: PacketByte,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct SetPlayerPositionAndRotation(0x1E) {
+        x: f64,
+        y: f64,
+        z: f64,
+        yaw: f32,
+        pitch: f32,
+        flags: PacketByte,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct SetPlayerRotation(0x1F) {
+        yaw: f32,
+        pitch: f32,
+        flags: PacketByte,
+    }
+}
+
+pub const HEIGHTMAP_WORLD_SURFACE: i32 = 1;
+pub const HEIGHTMAP_MOTION_BLOCKING: i32 = 4;
+
+#[derive(Debug, Clone)]
+pub struct Heightmap {
+    pub kind: i32,
+    pub data: Vec<i64>,
+}
+
+impl packet::data::PacketData for Heightmap {
+    fn read(buffer: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            kind: packet::data::PacketData::read(buffer)?,
+            data: packet::data::PacketData::read(buffer)?,
+        })
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        packet::data::PacketData::write(&self.kind, buffer)?;
+        packet::data::PacketData::write(&self.data, buffer)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChunkBlockEntity {
+    /// The local X coordinate in the high nibble and the local Z coordinate in the low nibble.
+    pub packed_xz: PacketByte,
+    pub y: i16,
+    pub kind: i32,
+    pub data: Value,
+}
+
+impl packet::data::PacketData for ChunkBlockEntity {
+    fn read(_: &mut &[u8]) -> Result<Self> {
+        Err(Error::Protocol("Client cannot send chunk block entities".to_string()))
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        packet::data::PacketData::write(&self.packed_xz, buffer)?;
+        packet::data::PacketData::write(&self.y, buffer)?;
+        packet::data::PacketData::write(&self.kind, buffer)?;
+        write_network_nbt(&self.data, buffer)
+    }
+}
+
+/// Writes NBT the way it is sent over the network since 1.20.2, with no name for the root tag.
+pub fn write_network_nbt(value: &Value, buffer: &mut Vec<u8>) -> Result<()> {
+    let nbt = fastnbt::to_bytes(value).map_err(|e| Error::PacketSerialization(e.to_string()))?;
+    // Skip the empty root name that follows the tag ID.
+    buffer.push(nbt[0]);
+    buffer.extend_from_slice(&nbt[3..]);
+    Ok(())
+}
+
+/// An NBT compound sent as a packet field, in the network format of [`write_network_nbt`].
+#[derive(Debug, Clone)]
+pub struct NetworkNbt(pub Value);
+
+impl packet::data::PacketData for NetworkNbt {
+    fn read(_: &mut &[u8]) -> Result<Self> {
+        Err(Error::Protocol("Reading network NBT is not supported".to_string()))
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        write_network_nbt(&self.0, buffer)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LightData {
+    pub sky_light_mask: Vec<i64>,
+    pub block_light_mask: Vec<i64>,
+    pub empty_sky_light_mask: Vec<i64>,
+    pub empty_block_light_mask: Vec<i64>,
+    pub sky_light_arrays: Vec<PrefixedBytes>,
+    pub block_light_arrays: Vec<PrefixedBytes>,
+}
+
+impl packet::data::PacketData for LightData {
+    fn read(buffer: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            sky_light_mask: packet::data::PacketData::read(buffer)?,
+            block_light_mask: packet::data::PacketData::read(buffer)?,
+            empty_sky_light_mask: packet::data::PacketData::read(buffer)?,
+            empty_block_light_mask: packet::data::PacketData::read(buffer)?,
+            sky_light_arrays: packet::data::PacketData::read(buffer)?,
+            block_light_arrays: packet::data::PacketData::read(buffer)?,
+        })
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        packet::data::PacketData::write(&self.sky_light_mask, buffer)?;
+        packet::data::PacketData::write(&self.block_light_mask, buffer)?;
+        packet::data::PacketData::write(&self.empty_sky_light_mask, buffer)?;
+        packet::data::PacketData::write(&self.empty_block_light_mask, buffer)?;
+        packet::data::PacketData::write(&self.sky_light_arrays, buffer)?;
+        packet::data::PacketData::write(&self.block_light_arrays, buffer)?;
+        Ok(())
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct ChunkDataAndUpdateLight(0x27) {
+        chunk_x: PacketInt,
+        chunk_z: PacketInt,
+        heightmaps: Vec<Heightmap>,
+        data: PrefixedBytes,
+        block_entities: Vec<ChunkBlockEntity>,
+        light: LightData,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct BlockUpdate(0x08) {
+        location: Position,
+        block_id: i32,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct BlockEntityData(0x06) {
+        location: Position,
+        kind: i32,
+        data: NetworkNbt,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct UpdateLight(0x2A) {
+        chunk_x: i32,
+        chunk_z: i32,
+        light: LightData,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct SetCenterChunk(0x57) {
+        chunk_x: i32,
+        chunk_z: i32,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct UnloadChunk(0x21) {
+        chunk_z: PacketInt,
+        chunk_x: PacketInt,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct ChunkBatchStart(0x0C) {}
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct ChunkBatchFinished(0x0B) {
+        batch_size: i32,
+    }
+}
+
+packet! {
+    #[derive(Debug)]
+    pub struct ChunkBatchReceived(0x0A) {
+        chunks_per_tick: f32,
+    }
+}
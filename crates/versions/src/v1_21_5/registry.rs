@@ -0,0 +1,122 @@
+use iron_oxide_protocol::error::{Error, Result};
+use iron_oxide_world::biome::BiomeRegistry;
+use iron_oxide_world::block::BlockRegistry;
+use iron_oxide_world::height::WorldHeight;
+use serde_json::Value as JsonValue;
+use std::fs;
+
+const REGISTRY_DATA_PATH: &str = "config/v1_20_6/registry-data.json";
+/// The `blocks.json` report of the 1.21.5 data generator, which `scripts/block-report.sh` writes.
+/// The copy in the repository only lists the blocks up to `minecraft:oak_planks` and those the
+/// noise generator places, so the server refuses to start until the script was run.
+const BLOCK_REPORT_PATH: &str = "config/v1_21_5/blocks.json";
+
+/// The block entity types in the order of their network IDs. They are built into the client, so
+/// unlike the registries above they aren't sent during configuration.
+const BLOCK_ENTITY_TYPES: [&str; 47] = [
+    "minecraft:furnace",
+    "minecraft:chest",
+    "minecraft:trapped_chest",
+    "minecraft:ender_chest",
+    "minecraft:jukebox",
+    "minecraft:dispenser",
+    "minecraft:dropper",
+    "minecraft:sign",
+    "minecraft:hanging_sign",
+    "minecraft:mob_spawner",
+    "minecraft:creaking_heart",
+    "minecraft:piston",
+    "minecraft:brewing_stand",
+    "minecraft:enchanting_table",
+    "minecraft:end_portal",
+    "minecraft:beacon",
+    "minecraft:skull",
+    "minecraft:daylight_detector",
+    "minecraft:hopper",
+    "minecraft:comparator",
+    "minecraft:banner",
+    "minecraft:structure_block",
+    "minecraft:end_gateway",
+    "minecraft:command_block",
+    "minecraft:shulker_box",
+    "minecraft:bed",
+    "minecraft:conduit",
+    "minecraft:barrel",
+    "minecraft:smoker",
+    "minecraft:blast_furnace",
+    "minecraft:lectern",
+    "minecraft:bell",
+    "minecraft:jigsaw",
+    "minecraft:campfire",
+    "minecraft:beehive",
+    "minecraft:sculk_sensor",
+    "minecraft:calibrated_sculk_sensor",
+    "minecraft:sculk_catalyst",
+    "minecraft:sculk_shrieker",
+    "minecraft:chiseled_bookshelf",
+    "minecraft:brushable_block",
+    "minecraft:decorated_pot",
+    "minecraft:crafter",
+    "minecraft:trial_spawner",
+    "minecraft:vault",
+    "minecraft:test_block",
+    "minecraft:test_instance_block",
+];
+
+pub fn load_registry_data() -> Result<JsonValue> {
+    let registry_data_str = fs::read_to_string(REGISTRY_DATA_PATH)?;
+    serde_json::from_str(&registry_data_str).map_err(|e| Error::Protocol(format!("Failed to parse registry data: {}", e)))
+}
+
+pub fn load_block_registry() -> Result<BlockRegistry> {
+    let report = fs::read_to_string(BLOCK_REPORT_PATH)?;
+    BlockRegistry::from_report(&report).map_err(|e| {
+        Error::Protocol(format!("{} in {}, run scripts/block-report.sh to write it", e, BLOCK_REPORT_PATH))
+    })
+}
+
+/// Returns the biomes in the order of their network IDs.
+pub fn biome_registry(registries: &JsonValue) -> Result<BiomeRegistry> {
+    let names = registry_entries(registries, "minecraft:worldgen/biome")?.keys().cloned().collect();
+    Ok(BiomeRegistry::new(names))
+}
+
+/// Returns the network ID of a registry entry, which is its index in the order the entries are
+/// sent during configuration.
+pub fn entry_id(registries: &JsonValue, registry_id: &str, entry_id: &str) -> Result<i32> {
+    registry_entries(registries, registry_id)?
+        .keys()
+        .position(|key| key == entry_id)
+        .map(|id| id as i32)
+        .ok_or_else(|| Error::Protocol(format!("Unknown {} entry: {}", registry_id, entry_id)))
+}
+
+pub fn entry<'a>(registries: &'a JsonValue, registry_id: &str, entry_id: &str) -> Result<&'a JsonValue> {
+    registry_entries(registries, registry_id)?
+        .get(entry_id)
+        .ok_or_else(|| Error::Protocol(format!("Unknown {} entry: {}", registry_id, entry_id)))
+}
+
+/// Returns the vertical extent of worlds of the dimension type.
+pub fn world_height(registries: &JsonValue, dimension_type: &str) -> Result<WorldHeight> {
+    let dimension_type = entry(registries, "minecraft:dimension_type", dimension_type)?;
+    let field = |name: &str| {
+        dimension_type
+            .get(name)
+            .and_then(JsonValue::as_i64)
+            .ok_or_else(|| Error::Protocol(format!("Dimension type has no {}", name)))
+    };
+    WorldHeight::new(field("min_y")? as i32, field("height")? as i32).map_err(|e| Error::Protocol(e.to_string()))
+}
+
+fn registry_entries<'a>(registries: &'a JsonValue, registry_id: &str) -> Result<&'a serde_json::Map<String, JsonValue>> {
+    registries
+        .get(registry_id)
+        .and_then(JsonValue::as_object)
+        .ok_or_else(|| Error::Protocol(format!("Unknown registry: {}", registry_id)))
+}
+
+/// Returns the network ID of a block entity type, or `None` if the client doesn't know it.
+pub fn block_entity_type_id(kind: &str) -> Option<i32> {
+    BLOCK_ENTITY_TYPES.iter().position(|known| *known == kind).map(|id| id as i32)
+}
@@ -0,0 +1,130 @@
+use fastnbt::Value;
+use iron_oxide_protocol::packet::Packet;
+use iron_oxide_protocol::packet::raw_data::{read_int, read_varint};
+use iron_oxide_versions::v1_21_5::chunk::{
+    block_entity_data_packet, chunk_data_packet, update_light_packet, write_paletted_container,
+};
+use iron_oxide_versions::v1_21_5::registry::block_entity_type_id;
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::block_entity::BlockEntity;
+use iron_oxide_world::chunk::{ChunkColumn, SECTION_VOLUME};
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::light::light_column;
+use iron_oxide_world::palette::{PaletteType, PalettedContainer};
+use iron_oxide_world::pos::BlockPos;
+
+#[test]
+fn test_single_valued_container() {
+    let container = PalettedContainer::new(PaletteType::Block, SECTION_VOLUME);
+    let mut buffer = Vec::new();
+    write_paletted_container(&container, &mut buffer).unwrap();
+    assert_eq!(buffer, vec![0, 0]);
+}
+
+#[test]
+fn test_indirect_container_has_no_length_prefix() {
+    let mut container = PalettedContainer::new(PaletteType::Block, SECTION_VOLUME);
+    container.set(0, 1);
+    let mut buffer = Vec::new();
+    write_paletted_container(&container, &mut buffer).unwrap();
+
+    // 4 bits per entry, a palette of air and the block, then 16 entries per long.
+    assert_eq!(&buffer[..4], &[4, 2, 0, 1]);
+    assert_eq!(buffer.len(), 4 + SECTION_VOLUME / 16 * 8);
+    assert_eq!(&buffer[4..12], &1i64.to_be_bytes());
+}
+
+#[test]
+fn test_direct_container_has_no_palette() {
+    let mut container = PalettedContainer::new(PaletteType::Block, SECTION_VOLUME);
+    for value in 0..300 {
+        container.set(value as usize, value);
+    }
+    let mut buffer = Vec::new();
+    write_paletted_container(&container, &mut buffer).unwrap();
+
+    assert_eq!(buffer[0], 15);
+    // 4 entries of 15 bits fit in a long.
+    assert_eq!(buffer.len(), 1 + SECTION_VOLUME / 4 * 8);
+    assert_eq!(&buffer[1..9], &((1i64 << 15) | (2 << 30) | (3 << 45)).to_be_bytes());
+}
+
+#[test]
+fn test_chunk_data_packet() {
+    let mut column = ChunkColumn::new(WorldHeight::OVERWORLD);
+    column.set_block(0, -64, 0, BlockState::from_id(1)).unwrap();
+    column.set_block(15, -24, 15, BlockState::from_id(1)).unwrap();
+
+    let packet = chunk_data_packet(3, -2, &column).unwrap();
+    let mut buffer = Vec::new();
+    packet.write(&mut buffer).unwrap();
+
+    let mut data = &buffer[..];
+    assert_eq!(read_varint(&mut data).unwrap(), 0x27);
+    assert_eq!(read_int(&mut data).unwrap(), 3);
+    assert_eq!(read_int(&mut data).unwrap(), -2);
+
+    assert_eq!(packet.heightmaps.len(), 2);
+    let heights = &packet.heightmaps[0].data;
+    // 384 blocks need 9 bits per height, so 7 heights fit in a long.
+    assert_eq!(heights.len(), 256usize.div_ceil(7));
+    assert_eq!(heights[0] & 0x1FF, 1);
+    assert_eq!(heights[255 / 7] >> (255 % 7 * 9) & 0x1FF, 41);
+
+    let mut sections = &packet.data.0[..];
+    assert_eq!(i16::from_be_bytes([sections[0], sections[1]]), 1);
+    sections = &sections[2..];
+    assert_eq!(sections[0], 4);
+    // The second section is empty, so it is sent as single valued air.
+    let second = &packet.data.0[2 + 4 + 256 * 8 + 2..];
+    assert_eq!(&second[..4], &[0, 0, 0, 0]);
+
+    assert_eq!(packet.light.sky_light_arrays.len(), column.sections().len() + 2);
+    assert!(packet.block_entities.is_empty());
+}
+
+#[test]
+fn test_lit_chunk_light_data() {
+    let mut column = ChunkColumn::new(WorldHeight::OVERWORLD);
+    column.fill_layer(-64, BlockState::from_id(1)).unwrap();
+    light_column(&mut column);
+
+    let light = chunk_data_packet(0, 0, &column).unwrap().light;
+    let sections = column.sections().len();
+    // Below the stone floor there is no sky light, and no blocks give off light at all.
+    assert_eq!(light.sky_light_mask, vec![((1i64 << (sections + 2)) - 1) & !1]);
+    assert_eq!(light.empty_sky_light_mask, vec![1]);
+    assert_eq!(light.sky_light_arrays.len(), sections + 1);
+    assert_eq!(light.block_light_mask, vec![0]);
+    assert_eq!(light.empty_block_light_mask, vec![(1i64 << (sections + 2)) - 1]);
+    assert!(light.block_light_arrays.is_empty());
+
+    let update = update_light_packet(0, 0, &column);
+    assert_eq!(update.light.sky_light_arrays.len(), sections + 1);
+}
+
+#[test]
+fn test_block_entities_are_sent() {
+    let mut column = ChunkColumn::new(WorldHeight::OVERWORLD);
+    let chest = BlockEntity::new("minecraft:chest")
+        .with("CustomName", Value::String("\"Loot\"".to_string()))
+        .with("Items", Value::List(Vec::new()));
+    column.set_block_entity(BlockPos::new(3, -50, 12), chest.clone()).unwrap();
+    column.set_block_entity(BlockPos::new(0, 0, 0), BlockEntity::new("example:unknown")).unwrap();
+
+    let packet = chunk_data_packet(0, 0, &column).unwrap();
+    assert_eq!(packet.block_entities.len(), 1);
+    let entity = &packet.block_entities[0];
+    assert_eq!(entity.packed_xz.0, 0x3C);
+    assert_eq!(entity.y, -50);
+    assert_eq!(entity.kind, block_entity_type_id("minecraft:chest").unwrap());
+
+    // What the chest holds stays on the server.
+    let update = block_entity_data_packet(BlockPos::new(35, -50, 12), &chest).unwrap();
+    let Value::Compound(data) = &update.data.0 else {
+        panic!("block entity data isn't a compound");
+    };
+    assert!(data.contains_key("CustomName"));
+    assert!(!data.contains_key("Items"));
+    assert_eq!(update.location.x, 35);
+}
@@ -0,0 +1,30 @@
+use iron_oxide_versions::v1_21_5::chunk_sender::Viewer;
+use iron_oxide_world::view::ChunkPos;
+
+#[test]
+fn test_nearby_chunks_come_first() {
+    let viewer = Viewer { x: 8.0, z: 8.0, yaw: 180.0, ..Default::default() };
+    let behind_nearby = viewer.chunk_priority(ChunkPos::new(0, 1));
+    let ahead_far = viewer.chunk_priority(ChunkPos::new(0, -3));
+    assert!(behind_nearby < ahead_far);
+}
+
+#[test]
+fn test_chunks_ahead_come_before_chunks_behind() {
+    // Facing south, towards +Z.
+    let viewer = Viewer { x: 8.0, z: 8.0, yaw: 0.0, ..Default::default() };
+    let ahead = viewer.chunk_priority(ChunkPos::new(0, 5));
+    let side = viewer.chunk_priority(ChunkPos::new(5, 0));
+    let behind = viewer.chunk_priority(ChunkPos::new(0, -5));
+    assert!(ahead < side);
+    assert!(side < behind);
+}
+
+#[test]
+fn test_movement_prioritises_chunks_walked_into() {
+    // Facing east but sprinting north.
+    let viewer = Viewer { x: 8.0, z: 8.0, yaw: -90.0, velocity_x: 0.0, velocity_z: -0.8 };
+    let north = viewer.chunk_priority(ChunkPos::new(0, -2));
+    let south = viewer.chunk_priority(ChunkPos::new(0, 2));
+    assert!(north < south);
+}
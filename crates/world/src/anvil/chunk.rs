@@ -0,0 +1,418 @@
+use crate::biome::biome_registry;
+use crate::block::BlockState;
+use crate::block_entity::BlockEntity;
+use crate::chunk::{ChunkColumn, ChunkSection, BIOME_VOLUME, SECTION_HEIGHT, SECTION_VOLUME, SECTION_WIDTH};
+use crate::error::{Result, WorldError};
+use crate::height::WorldHeight;
+use crate::heightmap::HeightmapKind;
+use crate::palette::{Palette, PaletteType, PalettedContainer};
+use crate::pos::BlockPos;
+use fastnbt::{LongArray, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// The first data version with the 1.18 chunk layout, which this reader expects.
+pub const MIN_DATA_VERSION: i32 = 2860;
+/// The data version of 1.21.5, which chunks are saved with.
+pub const DATA_VERSION: i32 = 4325;
+const FULL_STATUS: &str = "minecraft:full";
+/// The top-level tags of [`ChunkNbt`]. Any others are kept on the column.
+const CHUNK_TAGS: [&str; 10] = [
+    "DataVersion",
+    "xPos",
+    "zPos",
+    "yPos",
+    "Status",
+    "LastUpdate",
+    "isLightOn",
+    "sections",
+    "Heightmaps",
+    "block_entities",
+];
+/// The saved block states the registry doesn't know that were logged already.
+static UNKNOWN_STATES: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+#[derive(Serialize, Deserialize)]
+struct ChunkNbt {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    #[serde(rename = "xPos", default)]
+    x_pos: i32,
+    #[serde(rename = "zPos", default)]
+    z_pos: i32,
+    #[serde(rename = "yPos", default)]
+    y_pos: i32,
+    #[serde(rename = "Status")]
+    status: String,
+    #[serde(rename = "LastUpdate", default)]
+    last_update: i64,
+    /// Whether the saved light is valid. It never is, so vanilla lights the chunk again. A byte
+    /// since fastnbt can't serialize bools.
+    #[serde(rename = "isLightOn", default)]
+    is_light_on: i8,
+    #[serde(default)]
+    sections: Vec<SectionNbt>,
+    #[serde(rename = "Heightmaps", default)]
+    heightmaps: HashMap<String, LongArray>,
+    #[serde(default)]
+    block_entities: Vec<HashMap<String, Value>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SectionNbt {
+    #[serde(rename = "Y")]
+    y: i8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_states: Option<PaletteNbt<BlockStateNbt>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    biomes: Option<PaletteNbt<String>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PaletteNbt<T> {
+    palette: Vec<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<LongArray>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BlockStateNbt {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Properties", default, skip_serializing_if = "HashMap::is_empty")]
+    properties: HashMap<String, String>,
+}
+
+/// Decodes the NBT of a chunk as vanilla saves it, returning `None` for chunks whose generation
+/// didn't finish so they are generated again.
+pub fn read_chunk_nbt(chunk_x: i32, chunk_z: i32, nbt: &[u8], height: WorldHeight) -> Result<Option<ChunkColumn>> {
+    let invalid = |message: String| WorldError::InvalidChunk { x: chunk_x, z: chunk_z, message };
+    let mut extra_tags: HashMap<String, Value> = fastnbt::from_bytes(nbt).map_err(|e| invalid(e.to_string()))?;
+    let tags = CHUNK_TAGS.iter().filter_map(|&tag| extra_tags.remove_entry(tag)).collect();
+    let chunk: ChunkNbt = fastnbt::from_value(&Value::Compound(tags)).map_err(|e| invalid(e.to_string()))?;
+    if chunk.data_version < MIN_DATA_VERSION {
+        return Err(invalid(format!("data version {} is older than 1.18", chunk.data_version)));
+    }
+    if chunk.status != FULL_STATUS && chunk.status != "full" {
+        return Ok(None);
+    }
+
+    let mut unknown = false;
+    let sections = read_sections(chunk.sections, height, &mut unknown, &invalid)?;
+    // The heightmaps are built from the blocks rather than read, so they can't disagree.
+    let mut column = ChunkColumn::with_sections(height, sections);
+    read_block_entities(&mut column, chunk.block_entities, &invalid)?;
+    column.set_extra_tags(extra_tags);
+    column.mark_saved();
+    if unknown {
+        warn!("Chunk {}, {} has block states this version doesn't know, so it won't be saved", chunk_x, chunk_z);
+        column.mark_read_only();
+    }
+    Ok(Some(column))
+}
+
+/// Decodes saved sections into a full column of sections, leaving out those outside the world.
+/// Sets `unknown` if a block state the registry doesn't know was read as another.
+pub(crate) fn read_sections(
+    saved: Vec<SectionNbt>,
+    height: WorldHeight,
+    unknown: &mut bool,
+    invalid: &impl Fn(String) -> WorldError,
+) -> Result<Vec<ChunkSection>> {
+    let min_section = height.min_y().div_euclid(SECTION_HEIGHT as i32);
+    let mut sections: Vec<ChunkSection> = (0..height.section_count()).map(|_| missing_section()).collect();
+    for section in saved {
+        let index = section.y as i32 - min_section;
+        if index < 0 || index as usize >= sections.len() {
+            // Vanilla keeps light-only sections above and below the world.
+            continue;
+        }
+        sections[index as usize] = read_section(section, unknown, invalid)?;
+    }
+    Ok(sections)
+}
+
+/// The section vanilla reads in place of one that wasn't saved: air in plains.
+fn missing_section() -> ChunkSection {
+    let mut biomes = PalettedContainer::new(PaletteType::Biome, BIOME_VOLUME);
+    biomes.fill(plains());
+    ChunkSection::from_containers(PalettedContainer::new(PaletteType::Block, SECTION_VOLUME), biomes)
+}
+
+/// The ID of plains, or 0 without a biome registry.
+fn plains() -> u32 {
+    biome_registry().and_then(|registry| registry.id("minecraft:plains")).unwrap_or(0)
+}
+
+/// Adds saved block entities, whose positions are world coordinates, to the column.
+pub(crate) fn read_block_entities(
+    column: &mut ChunkColumn,
+    saved: Vec<HashMap<String, Value>>,
+    invalid: &impl Fn(String) -> WorldError,
+) -> Result<()> {
+    for mut data in saved {
+        let (Some(Value::String(kind)), Some(x), Some(y), Some(z)) =
+            (data.remove("id"), int(&data, "x"), int(&data, "y"), int(&data, "z"))
+        else {
+            return Err(invalid("block entity without type or position".to_string()));
+        };
+        for key in ["x", "y", "z", "keepPacked"] {
+            data.remove(key);
+        }
+        let pos = BlockPos::new(x.rem_euclid(SECTION_WIDTH as i32), y, z.rem_euclid(SECTION_WIDTH as i32));
+        column.set_block_entity(pos, BlockEntity { kind, data })?;
+    }
+    Ok(())
+}
+
+/// Encodes the column as vanilla saves it, with the tags it was read with that aren't known.
+/// Fails for read-only columns, whose unknown blocks would be saved as what they loaded as.
+pub fn write_chunk_nbt(chunk_x: i32, chunk_z: i32, column: &ChunkColumn) -> Result<Vec<u8>> {
+    let invalid = |message: String| WorldError::InvalidChunk { x: chunk_x, z: chunk_z, message };
+    if column.is_read_only() {
+        return Err(invalid("has block states this version doesn't know".to_string()));
+    }
+    let height = column.world_height();
+    let min_section = height.min_y().div_euclid(SECTION_HEIGHT as i32);
+    let sections = write_sections(column)?;
+
+    let mut heightmaps = HashMap::new();
+    for kind in HeightmapKind::MAINTAINED {
+        if let Some(heightmap) = column.heightmap(kind) {
+            heightmaps.insert(kind.nbt_name().to_string(), LongArray::new(heightmap.pack(height.height())));
+        }
+    }
+
+    let chunk = ChunkNbt {
+        data_version: DATA_VERSION,
+        x_pos: chunk_x,
+        z_pos: chunk_z,
+        y_pos: min_section,
+        status: FULL_STATUS.to_string(),
+        last_update: 0,
+        is_light_on: 0,
+        sections,
+        heightmaps,
+        block_entities: write_block_entities(chunk_x, chunk_z, column),
+    };
+    let Value::Compound(mut tags) = fastnbt::to_value(&chunk).map_err(|e| invalid(e.to_string()))? else {
+        unreachable!("chunks serialize to compounds");
+    };
+    for (tag, value) in column.extra_tags() {
+        tags.entry(tag.clone()).or_insert_with(|| value.clone());
+    }
+    fastnbt::to_bytes(&tags).map_err(|e| invalid(e.to_string()))
+}
+
+/// Encodes the sections of the column. Empty sections that are all plains are left out, since
+/// they read back the same.
+pub(crate) fn write_sections(column: &ChunkColumn) -> Result<Vec<SectionNbt>> {
+    let min_section = column.world_height().min_y().div_euclid(SECTION_HEIGHT as i32);
+    let plains = plains();
+    let mut sections = Vec::new();
+    for (index, section) in column.sections().iter().enumerate() {
+        if section.is_empty() && matches!(section.biomes().palette(), Palette::Single(biome) if *biome == plains) {
+            continue;
+        }
+        sections.push(write_section((min_section + index as i32) as i8, section)?);
+    }
+    Ok(sections)
+}
+
+/// Encodes the block entities of the column with world coordinates, as vanilla saves them.
+pub(crate) fn write_block_entities(chunk_x: i32, chunk_z: i32, column: &ChunkColumn) -> Vec<HashMap<String, Value>> {
+    column
+        .block_entities()
+        .map(|(pos, entity)| {
+            let mut data = entity.data.clone();
+            data.insert("id".to_string(), Value::String(entity.kind.clone()));
+            data.insert("x".to_string(), Value::Int(chunk_x * SECTION_WIDTH as i32 + pos.x));
+            data.insert("y".to_string(), Value::Int(pos.y));
+            data.insert("z".to_string(), Value::Int(chunk_z * SECTION_WIDTH as i32 + pos.z));
+            data.insert("keepPacked".to_string(), Value::Byte(0));
+            data
+        })
+        .collect()
+}
+
+fn write_section(y: i8, section: &ChunkSection) -> Result<SectionNbt> {
+    let (palette, data) = pack_palette(section.block_states(), PaletteType::Block);
+    let palette = palette
+        .into_iter()
+        .map(|id| {
+            let state = BlockState::from_id(id);
+            let name = state.name().ok_or(WorldError::UnknownBlockState(id))?;
+            let properties = state
+                .properties()
+                .map(|(property, value)| (property.to_string(), value.to_string()))
+                .collect();
+            Ok(BlockStateNbt { name: name.to_string(), properties })
+        })
+        .collect::<Result<_>>()?;
+
+    let (biome_palette, biome_data) = pack_palette(section.biomes(), PaletteType::Biome);
+    let registry = biome_registry();
+    let biome_palette = biome_palette
+        .into_iter()
+        .map(|id| {
+            registry
+                .and_then(|registry| registry.name(id))
+                .map(str::to_string)
+                .ok_or_else(|| WorldError::UnknownBiome(format!("#{}", id)))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(SectionNbt {
+        y,
+        block_states: Some(PaletteNbt { palette, data }),
+        biomes: Some(PaletteNbt { palette: biome_palette, data: biome_data }),
+    })
+}
+
+/// Returns the distinct values of the container and their packed indices, with as many bits per
+/// index as vanilla uses on disk.
+fn pack_palette(container: &PalettedContainer, palette_type: PaletteType) -> (Vec<u32>, Option<LongArray>) {
+    let mut palette = Vec::new();
+    let mut indices = Vec::with_capacity(container.len());
+    let mut positions = HashMap::new();
+    for index in 0..container.len() {
+        let value = container.get(index);
+        let position = *positions.entry(value).or_insert_with(|| {
+            palette.push(value);
+            palette.len() - 1
+        });
+        indices.push(position as u64);
+    }
+    if palette.len() == 1 {
+        return (palette, None);
+    }
+
+    let bits = palette_bits(palette.len(), palette_type);
+    let per_long = 64 / bits;
+    let data = indices
+        .chunks(per_long)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u64, |long, (i, index)| long | (index << (i * bits))) as i64
+        })
+        .collect();
+    (palette, Some(LongArray::new(data)))
+}
+
+fn read_section(
+    section: SectionNbt,
+    unknown: &mut bool,
+    invalid: &impl Fn(String) -> WorldError,
+) -> Result<ChunkSection> {
+    let block_states = match section.block_states {
+        Some(states) => {
+            let palette: Vec<u32> = states.palette.iter().map(|state| block_state(state, unknown)).collect();
+            let values = unpack_palette(&palette, states.data.as_deref(), SECTION_VOLUME, PaletteType::Block)
+                .map_err(|message| invalid(format!("block states: {}", message)))?;
+            PalettedContainer::from_values(PaletteType::Block, &values)
+        }
+        None => PalettedContainer::new(PaletteType::Block, SECTION_VOLUME),
+    };
+
+    let biomes = match section.biomes {
+        Some(biomes) => {
+            let registry = biome_registry();
+            let palette = biomes
+                .palette
+                .iter()
+                .map(|name| {
+                    registry
+                        .and_then(|registry| registry.id(name))
+                        .ok_or_else(|| WorldError::UnknownBiome(name.clone()))
+                })
+                .collect::<Result<Vec<u32>>>()?;
+            let values = unpack_palette(&palette, biomes.data.as_deref(), BIOME_VOLUME, PaletteType::Biome)
+                .map_err(|message| invalid(format!("biomes: {}", message)))?;
+            PalettedContainer::from_values(PaletteType::Biome, &values)
+        }
+        None => PalettedContainer::new(PaletteType::Biome, BIOME_VOLUME),
+    };
+
+    Ok(ChunkSection::from_containers(block_states, biomes))
+}
+
+/// Resolves a saved block state. Blocks missing from the registry, such as modded blocks or blocks
+/// of newer versions, load as air and properties it doesn't know keep their default, so that one
+/// such block doesn't keep the whole chunk from loading. Sets `unknown` for either.
+fn block_state(nbt: &BlockStateNbt, unknown: &mut bool) -> u32 {
+    let Ok(mut state) = BlockState::default_of(&nbt.name) else {
+        warn_unknown(&nbt.name, "loading it as air");
+        *unknown = true;
+        return BlockState::AIR.id();
+    };
+    for (property, value) in &nbt.properties {
+        match state.with(property, value) {
+            Ok(with) => state = with,
+            Err(_) => {
+                warn_unknown(&format!("{}[{}={}]", nbt.name, property, value), "keeping the default");
+                *unknown = true;
+            }
+        }
+    }
+    state.id()
+}
+
+/// Logs a saved block state the registry doesn't know, once for each.
+fn warn_unknown(state: &str, fallback: &str) {
+    if UNKNOWN_STATES.lock().unwrap().insert(state.to_string()) {
+        warn!("Unknown block state {} in a saved chunk, {}", state, fallback);
+    }
+}
+
+/// Resolves the packed palette indices of a saved container. Vanilla saves block palettes with at
+/// least 4 bits per entry, and any palette with as many bits as its length needs once it is too
+/// large for the network's indirect palettes.
+fn unpack_palette(
+    palette: &[u32],
+    data: Option<&[i64]>,
+    size: usize,
+    palette_type: PaletteType,
+) -> std::result::Result<Vec<u32>, &'static str> {
+    match (palette, data) {
+        ([], _) => Err("empty palette"),
+        ([value], _) => Ok(vec![*value; size]),
+        (_, None) => Err("palette without data"),
+        (_, Some(data)) => {
+            let bits = palette_bits(palette.len(), palette_type);
+            let per_long = 64 / bits;
+            if data.len() != size.div_ceil(per_long) {
+                return Err("palette data has the wrong length");
+            }
+            let mask = (1u64 << bits) - 1;
+            (0..size)
+                .map(|index| {
+                    let entry = (data[index / per_long] as u64 >> (index % per_long * bits)) & mask;
+                    palette
+                        .get(entry as usize)
+                        .copied()
+                        .ok_or("palette index out of range")
+                })
+                .collect()
+        }
+    }
+}
+
+fn palette_bits(len: usize, palette_type: PaletteType) -> usize {
+    let bits = (usize::BITS - (len - 1).leading_zeros()) as usize;
+    if bits <= palette_type.max_bits() as usize {
+        bits.max(palette_type.min_bits() as usize)
+    } else {
+        bits
+    }
+}
+
+fn int(data: &HashMap<String, Value>, key: &str) -> Option<i32> {
+    match data.get(key) {
+        Some(Value::Int(value)) => Some(*value),
+        _ => None,
+    }
+}
@@ -0,0 +1,120 @@
+//! Reading and writing worlds in vanilla's Anvil format, a `region` directory of region files.
+
+mod chunk;
+mod region;
+
+pub use chunk::{read_chunk_nbt, write_chunk_nbt, DATA_VERSION, MIN_DATA_VERSION};
+pub(crate) use chunk::{read_block_entities, read_sections, write_block_entities, write_sections, SectionNbt};
+pub use region::{
+    region_coords, region_file_name, write_region, Compression, RawChunk, RegionFile, CHUNKS_PER_REGION,
+    REGION_WIDTH, SECTOR_SIZE,
+};
+
+use crate::chunk::ChunkColumn;
+use crate::error::Result;
+use crate::height::WorldHeight;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The x and z of a chunk with its uncompressed NBT.
+type EncodedChunk = (i32, i32, Vec<u8>);
+/// A region file behind its own lock. `None` until it is first needed, then `Some(None)` if the
+/// file doesn't exist.
+type Region = Arc<Mutex<Option<Option<RegionFile>>>>;
+
+/// The region files of one dimension, opened as chunks in them are needed. Each region is locked
+/// on its own, and only while its file is read or written, so chunks of other regions can be
+/// read and written meanwhile and decompressing and parsing chunks happens outside of the lock.
+pub struct RegionStorage {
+    directory: PathBuf,
+    regions: Mutex<HashMap<(i32, i32), Region>>,
+}
+
+impl RegionStorage {
+    /// Takes the `region` directory of the dimension, which doesn't have to exist.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            regions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the saved chunk, or `None` if it was never saved or its generation didn't finish.
+    pub fn read_chunk(&self, chunk_x: i32, chunk_z: i32, height: WorldHeight) -> Result<Option<ChunkColumn>> {
+        let (region_x, region_z) = (chunk_x.div_euclid(REGION_WIDTH), chunk_z.div_euclid(REGION_WIDTH));
+        let raw = {
+            let region = self.region(region_x, region_z);
+            let mut region = region.lock().unwrap();
+            match self.open(&mut region, region_x, region_z)? {
+                Some(file) => file.read_raw(chunk_x.rem_euclid(REGION_WIDTH), chunk_z.rem_euclid(REGION_WIDTH))?,
+                None => None,
+            }
+        };
+        match raw {
+            Some(raw) => read_chunk_nbt(chunk_x, chunk_z, &raw.compression.decompress(&raw.payload)?, height),
+            None => Ok(None),
+        }
+    }
+
+    /// Saves the chunks, given as their coordinates and NBT, into the free sectors of their regions.
+    pub fn write_chunks(&self, chunks: Vec<EncodedChunk>) -> Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as u32);
+        // Compressed before any region is locked.
+        let mut by_region: HashMap<_, Vec<_>> = HashMap::new();
+        for (chunk_x, chunk_z, nbt) in chunks {
+            let region = (chunk_x.div_euclid(REGION_WIDTH), chunk_z.div_euclid(REGION_WIDTH));
+            let raw = RawChunk {
+                compression: Compression::Zlib,
+                payload: Compression::Zlib.compress(&nbt)?,
+                timestamp,
+            };
+            let (x, z) = (chunk_x.rem_euclid(REGION_WIDTH), chunk_z.rem_euclid(REGION_WIDTH));
+            by_region.entry(region).or_default().push((x, z, raw));
+        }
+
+        std::fs::create_dir_all(&self.directory)?;
+        for ((region_x, region_z), chunks) in by_region {
+            let region = self.region(region_x, region_z);
+            let mut region = region.lock().unwrap();
+            if !matches!(&*region, Some(Some(file)) if file.is_writable()) {
+                let path = self.directory.join(region_file_name(region_x, region_z));
+                *region = Some(Some(RegionFile::open_writable(path)?));
+            }
+            let Some(Some(file)) = region.as_mut() else {
+                unreachable!("the region was opened");
+            };
+            if let Err(e) = file.write_chunks(&chunks) {
+                // Read the file again next time, as it may not be what was written.
+                *region = None;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn region(&self, region_x: i32, region_z: i32) -> Region {
+        Arc::clone(self.regions.lock().unwrap().entry((region_x, region_z)).or_default())
+    }
+
+    /// Opens the region's file the first time it is needed.
+    fn open<'a>(
+        &self,
+        region: &'a mut Option<Option<RegionFile>>,
+        region_x: i32,
+        region_z: i32,
+    ) -> Result<Option<&'a mut RegionFile>> {
+        if region.is_none() {
+            let path = self.directory.join(region_file_name(region_x, region_z));
+            *region = Some(if path.exists() { Some(RegionFile::open(path)?) } else { None });
+        }
+        Ok(region.as_mut().unwrap().as_mut())
+    }
+}
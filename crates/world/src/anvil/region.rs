@@ -0,0 +1,441 @@
+use crate::error::{Result, WorldError};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+pub const SECTOR_SIZE: usize = 4096;
+/// Chunks per region along each axis.
+pub const REGION_WIDTH: i32 = 32;
+pub const CHUNKS_PER_REGION: usize = (REGION_WIDTH * REGION_WIDTH) as usize;
+/// The most sectors a chunk can span, as the count is stored in one byte.
+const MAX_CHUNK_SECTORS: usize = 255;
+/// Set on the compression type when the chunk is stored in its own `.mcc` file because it didn't
+/// fit in 255 sectors.
+const EXTERNAL_FLAG: u8 = 0x80;
+
+/// How a chunk payload is compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zlib,
+    None,
+    Lz4,
+}
+
+impl Compression {
+    pub fn id(&self) -> u8 {
+        match self {
+            Compression::Gzip => 1,
+            Compression::Zlib => 2,
+            Compression::None => 3,
+            Compression::Lz4 => 4,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Compression::Gzip),
+            2 => Some(Compression::Zlib),
+            3 => Some(Compression::None),
+            4 => Some(Compression::Lz4),
+            _ => None,
+        }
+    }
+
+    /// LZ4 isn't supported, vanilla saves with zlib by default.
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Compression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Compression::None => Ok(data.to_vec()),
+            _ => Err(WorldError::InvalidRegion(format!("can't compress chunks with {:?}", self))),
+        }
+    }
+
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut decompressed = Vec::new();
+        match self {
+            Compression::Gzip => {
+                GzDecoder::new(data).read_to_end(&mut decompressed)?;
+            }
+            Compression::Zlib => {
+                ZlibDecoder::new(data).read_to_end(&mut decompressed)?;
+            }
+            Compression::None => decompressed.extend_from_slice(data),
+            Compression::Lz4 => decompressed = decompress_lz4_blocks(data)?,
+        }
+        Ok(decompressed)
+    }
+}
+
+/// One `r.<x>.<z>.mca` file holding up to 32 by 32 chunks.
+///
+/// The file starts with a sector of chunk locations, each the offset of the chunk's first sector
+/// in 3 bytes and its sector count in 1 byte, followed by a sector of last modification times.
+/// Each chunk is stored as its length in 4 bytes, the compression type in 1 byte and the payload.
+pub struct RegionFile {
+    path: PathBuf,
+    file: File,
+    writable: bool,
+    locations: [u32; CHUNKS_PER_REGION],
+    timestamps: [u32; CHUNKS_PER_REGION],
+    /// Which sectors of the file are the header or hold a chunk. Those past the end are free.
+    used_sectors: Vec<bool>,
+}
+
+impl RegionFile {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = File::open(&path)?;
+        Self::from_file(path, file, false)
+    }
+
+    /// Opens the region for [`write_chunks`](Self::write_chunks) too, creating it if it doesn't
+    /// exist.
+    pub fn open_writable(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)?;
+        Self::from_file(path, file, true)
+    }
+
+    fn from_file(path: PathBuf, mut file: File, writable: bool) -> Result<Self> {
+        let mut header = vec![0; SECTOR_SIZE * 2];
+        let read = read_up_to(&mut file, &mut header)?;
+        if read != 0 && read < header.len() {
+            return Err(WorldError::InvalidRegion(format!("{} has a truncated header", path.display())));
+        }
+
+        let mut locations = [0; CHUNKS_PER_REGION];
+        let mut timestamps = [0; CHUNKS_PER_REGION];
+        let mut used_sectors = vec![true; 2];
+        for index in 0..CHUNKS_PER_REGION {
+            locations[index] = u32::from_be_bytes(header[index * 4..index * 4 + 4].try_into().unwrap());
+            let offset = SECTOR_SIZE + index * 4;
+            timestamps[index] = u32::from_be_bytes(header[offset..offset + 4].try_into().unwrap());
+            mark_sectors(&mut used_sectors, locations[index], true);
+        }
+
+        Ok(Self { path, file, writable, locations, timestamps, used_sectors })
+    }
+
+    pub(crate) fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the region holds the chunk. Coordinates are relative to the region.
+    pub fn has_chunk(&self, x: i32, z: i32) -> bool {
+        self.locations[chunk_index(x, z)] != 0
+    }
+
+    /// The time the chunk was last saved, in seconds since the Unix epoch.
+    pub fn timestamp(&self, x: i32, z: i32) -> u32 {
+        self.timestamps[chunk_index(x, z)]
+    }
+
+    /// Returns the decompressed NBT of the chunk, or `None` if the region doesn't hold it.
+    /// Coordinates are relative to the region.
+    pub fn read_chunk(&mut self, x: i32, z: i32) -> Result<Option<Vec<u8>>> {
+        match self.read_raw(x, z)? {
+            Some(raw) => raw.compression.decompress(&raw.payload).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the chunk as stored, without decompressing it.
+    pub fn read_raw(&mut self, x: i32, z: i32) -> Result<Option<RawChunk>> {
+        let location = self.locations[chunk_index(x, z)];
+        if location == 0 {
+            return Ok(None);
+        }
+        let sector = (location >> 8) as u64;
+        let sector_count = (location & 0xFF) as usize;
+        let invalid = |message: &str| {
+            WorldError::InvalidRegion(format!("{} chunk {}, {}: {}", self.path.display(), x, z, message))
+        };
+        if sector < 2 {
+            return Err(invalid("chunk overlaps the header"));
+        }
+
+        self.file.seek(SeekFrom::Start(sector * SECTOR_SIZE as u64))?;
+        let mut header = [0; 5];
+        self.file.read_exact(&mut header)?;
+        let length = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+        if length == 0 {
+            return Err(invalid("chunk is empty"));
+        }
+        let compression_id = header[4];
+        let compression = Compression::from_id(compression_id & !EXTERNAL_FLAG)
+            .ok_or_else(|| invalid(&format!("unknown compression {}", compression_id)))?;
+
+        let payload = if compression_id & EXTERNAL_FLAG != 0 {
+            std::fs::read(self.external_path(x, z))?
+        } else {
+            if length + 4 > sector_count * SECTOR_SIZE {
+                return Err(invalid("chunk is longer than its sectors"));
+            }
+            let mut payload = vec![0; length - 1];
+            self.file.read_exact(&mut payload)?;
+            payload
+        };
+        Ok(Some(RawChunk {
+            compression,
+            payload,
+            timestamp: self.timestamps[chunk_index(x, z)],
+        }))
+    }
+
+    /// Saves the chunks, given with their coordinates relative to the region, in the free sectors
+    /// of the file, which grows if there are too few. The header only points to them once they
+    /// are written and the sectors they had are only reused after that, so a crash leaves each
+    /// chunk either as it was or as it was saved.
+    pub fn write_chunks(&mut self, chunks: &[(i32, i32, RawChunk)]) -> Result<()> {
+        if !self.writable {
+            return Err(WorldError::InvalidRegion(format!("{} was opened read-only", self.path.display())));
+        }
+
+        let mut freed = Vec::new();
+        let mut stale_external = Vec::new();
+        for (x, z, chunk) in chunks {
+            let external = 5 + chunk.payload.len() > MAX_CHUNK_SECTORS * SECTOR_SIZE;
+            let mut data = Vec::with_capacity(5 + chunk.payload.len());
+            if external {
+                write_atomically(&self.external_path(*x, *z), &chunk.payload)?;
+                data.extend_from_slice(&1u32.to_be_bytes());
+                data.push(chunk.compression.id() | EXTERNAL_FLAG);
+            } else {
+                // The chunk may have been too large when it was last saved.
+                stale_external.push(self.external_path(*x, *z));
+                data.extend_from_slice(&(chunk.payload.len() as u32 + 1).to_be_bytes());
+                data.push(chunk.compression.id());
+                data.extend_from_slice(&chunk.payload);
+            }
+            data.resize(data.len().div_ceil(SECTOR_SIZE) * SECTOR_SIZE, 0);
+
+            let sector_count = data.len() / SECTOR_SIZE;
+            let sector = self.allocate(sector_count);
+            self.file.seek(SeekFrom::Start((sector * SECTOR_SIZE) as u64))?;
+            self.file.write_all(&data)?;
+
+            let index = chunk_index(*x, *z);
+            freed.push(self.locations[index]);
+            self.locations[index] = (sector as u32) << 8 | sector_count as u32;
+            self.timestamps[index] = chunk.timestamp;
+        }
+        self.file.sync_data()?;
+
+        let mut header = Vec::with_capacity(SECTOR_SIZE * 2);
+        for location in self.locations {
+            header.extend_from_slice(&location.to_be_bytes());
+        }
+        for timestamp in self.timestamps {
+            header.extend_from_slice(&timestamp.to_be_bytes());
+        }
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)?;
+        self.file.sync_data()?;
+
+        for location in freed {
+            mark_sectors(&mut self.used_sectors, location, false);
+        }
+        for path in stale_external {
+            match std::fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes the first run of free sectors long enough, at the end of the file if there is none.
+    fn allocate(&mut self, sector_count: usize) -> usize {
+        let mut start = 0;
+        for (sector, &used) in self.used_sectors.iter().enumerate() {
+            if used {
+                start = sector + 1;
+            } else if sector + 1 - start == sector_count {
+                break;
+            }
+        }
+        let location = (start as u32) << 8 | sector_count as u32;
+        mark_sectors(&mut self.used_sectors, location, true);
+        start
+    }
+
+    fn external_path(&self, x: i32, z: i32) -> PathBuf {
+        external_path(&self.path, x, z)
+    }
+}
+
+/// Marks the sectors of a chunk location used or free.
+fn mark_sectors(used_sectors: &mut Vec<bool>, location: u32, used: bool) {
+    let (sector, sector_count) = ((location >> 8) as usize, (location & 0xFF) as usize);
+    if sector < 2 || sector_count == 0 {
+        return;
+    }
+    if used_sectors.len() < sector + sector_count {
+        used_sectors.resize(sector + sector_count, false);
+    }
+    used_sectors[sector..sector + sector_count].fill(used);
+}
+
+/// A chunk as stored in a region file.
+#[derive(Debug, Clone)]
+pub struct RawChunk {
+    pub compression: Compression,
+    /// The compressed NBT.
+    pub payload: Vec<u8>,
+    /// When the chunk was saved, in seconds since the Unix epoch.
+    pub timestamp: u32,
+}
+
+/// Writes a whole region file, one entry per chunk in the order of the header. The file is
+/// written next to its destination and renamed over it once complete, so a crash leaves either
+/// the old or the new region behind.
+pub fn write_region(path: &Path, chunks: &[Option<RawChunk>]) -> Result<()> {
+    let mut header = vec![0u8; SECTOR_SIZE * 2];
+    let mut body = Vec::new();
+    for (index, chunk) in chunks.iter().enumerate().take(CHUNKS_PER_REGION) {
+        let Some(chunk) = chunk else {
+            continue;
+        };
+        let (x, z) = ((index % REGION_WIDTH as usize) as i32, (index / REGION_WIDTH as usize) as i32);
+        let sector = 2 + body.len() / SECTOR_SIZE;
+        let external = 5 + chunk.payload.len() > MAX_CHUNK_SECTORS * SECTOR_SIZE;
+        if external {
+            write_atomically(&external_path(path, x, z), &chunk.payload)?;
+            body.extend_from_slice(&1u32.to_be_bytes());
+            body.push(chunk.compression.id() | EXTERNAL_FLAG);
+        } else {
+            // The chunk may have been too large when it was last saved.
+            match std::fs::remove_file(external_path(path, x, z)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+            body.extend_from_slice(&(chunk.payload.len() as u32 + 1).to_be_bytes());
+            body.push(chunk.compression.id());
+            body.extend_from_slice(&chunk.payload);
+        }
+        body.resize(body.len().div_ceil(SECTOR_SIZE) * SECTOR_SIZE, 0);
+        let sector_count = 2 + body.len() / SECTOR_SIZE - sector;
+
+        let location = (sector as u32) << 8 | sector_count as u32;
+        header[index * 4..index * 4 + 4].copy_from_slice(&location.to_be_bytes());
+        let offset = SECTOR_SIZE + index * 4;
+        header[offset..offset + 4].copy_from_slice(&chunk.timestamp.to_be_bytes());
+    }
+    header.extend_from_slice(&body);
+    write_atomically(path, &header)
+}
+
+fn write_atomically(path: &Path, data: &[u8]) -> Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut file = File::create(&temp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// The `c.<x>.<z>.mcc` file next to the region holding a chunk too large for it.
+fn external_path(region_path: &Path, x: i32, z: i32) -> PathBuf {
+    let (region_x, region_z) = region_coords(region_path).unwrap_or_default();
+    let chunk_x = region_x * REGION_WIDTH + x;
+    let chunk_z = region_z * REGION_WIDTH + z;
+    region_path.with_file_name(format!("c.{}.{}.mcc", chunk_x, chunk_z))
+}
+
+pub fn region_file_name(region_x: i32, region_z: i32) -> String {
+    format!("r.{}.{}.mca", region_x, region_z)
+}
+
+/// Parses the region coordinates out of a `r.<x>.<z>.mca` path.
+pub fn region_coords(path: &Path) -> Option<(i32, i32)> {
+    let name = path.file_name()?.to_str()?;
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    parts.next().is_none().then_some((x, z))
+}
+
+pub(crate) fn chunk_index(x: i32, z: i32) -> usize {
+    (x.rem_euclid(REGION_WIDTH) + z.rem_euclid(REGION_WIDTH) * REGION_WIDTH) as usize
+}
+
+fn read_up_to(file: &mut File, buffer: &mut [u8]) -> Result<usize> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match file.read(&mut buffer[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
+
+const LZ4_MAGIC: &[u8] = b"LZ4Block";
+const LZ4_METHOD_RAW: u8 = 0x10;
+const LZ4_METHOD_LZ4: u8 = 0x20;
+const LZ4_HEADER_LEN: usize = LZ4_MAGIC.len() + 13;
+/// The largest block lz4-java writes.
+const LZ4_MAX_BLOCK_LEN: usize = 1 << 25;
+/// How many times larger than its compressed data LZ4 data can decompress to, at most.
+const LZ4_MAX_RATIO: usize = 255;
+
+/// Vanilla compresses with lz4-java's block stream: blocks of a magic, a token holding the method,
+/// the little endian compressed and decompressed lengths and a checksum, ended by an empty block.
+/// The checksums aren't verified.
+fn decompress_lz4_blocks(mut data: &[u8]) -> Result<Vec<u8>> {
+    let invalid = |message: &str| WorldError::InvalidRegion(format!("LZ4 stream {}", message));
+    let mut decompressed = Vec::new();
+    loop {
+        if data.len() < LZ4_HEADER_LEN || !data.starts_with(LZ4_MAGIC) {
+            return Err(invalid("has an invalid block header"));
+        }
+        let header = &data[LZ4_MAGIC.len()..LZ4_HEADER_LEN];
+        let method = header[0] & 0xF0;
+        let compressed_len = u32::from_le_bytes(header[1..5].try_into().unwrap()) as usize;
+        let decompressed_len = u32::from_le_bytes(header[5..9].try_into().unwrap()) as usize;
+        data = &data[LZ4_HEADER_LEN..];
+        if decompressed_len == 0 {
+            return Ok(decompressed);
+        }
+        if data.len() < compressed_len {
+            return Err(invalid("is truncated"));
+        }
+        // Checked before allocating, since the lengths come from the file.
+        if decompressed_len > LZ4_MAX_BLOCK_LEN || decompressed_len > compressed_len * LZ4_MAX_RATIO {
+            return Err(invalid(&format!("has a block of {} bytes decompressing to {}", compressed_len, decompressed_len)));
+        }
+
+        let block = &data[..compressed_len];
+        match method {
+            LZ4_METHOD_RAW => decompressed.extend_from_slice(block),
+            LZ4_METHOD_LZ4 => {
+                let start = decompressed.len();
+                decompressed.resize(start + decompressed_len, 0);
+                lz4_flex::block::decompress_into(block, &mut decompressed[start..])
+                    .map_err(|e| invalid(&e.to_string()))?;
+            }
+            _ => return Err(invalid("uses an unknown method")),
+        }
+        data = &data[compressed_len..];
+    }
+}
@@ -0,0 +1,153 @@
+use super::{block_at, brightness, with_value, BlockBehavior};
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::fluid::{Fluid, FluidState};
+use crate::pos::{BlockPos, Direction};
+use crate::update::UpdateFlags;
+use crate::world::World;
+
+/// How bright a crop must be to grow.
+const MIN_GROWTH_BRIGHTNESS: u8 = 9;
+/// How far farmland looks for water around it.
+const HYDRATION_RANGE: i32 = 4;
+const MAX_MOISTURE: u8 = 7;
+
+/// Blocks that keep dry farmland under them from turning back into dirt.
+const MAINTAINS_FARMLAND: [&str; 10] = [
+    "minecraft:wheat",
+    "minecraft:carrots",
+    "minecraft:potatoes",
+    "minecraft:beetroots",
+    "minecraft:melon_stem",
+    "minecraft:pumpkin_stem",
+    "minecraft:attached_melon_stem",
+    "minecraft:attached_pumpkin_stem",
+    "minecraft:torchflower_crop",
+    "minecraft:pitcher_crop",
+];
+
+/// Wheat, carrots, potatoes and beetroots. They grow a stage now and then when they tick at
+/// random in the light, faster on wet farmland with other crops around in rows, and pop off when
+/// the farmland under them goes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CropBehavior;
+
+/// Farmland. It gets wet near water and dries out slowly away from it, turning back into dirt
+/// once it's dry with nothing planted on it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FarmlandBehavior;
+
+impl BlockBehavior for CropBehavior {
+    fn update_shape(
+        &self,
+        _world: &World,
+        _pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        neighbor: BlockState,
+    ) -> BlockState {
+        if direction == Direction::Down && !neighbor.is("farmland") {
+            return BlockState::AIR;
+        }
+        state
+    }
+
+    fn on_random_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        // Beetroots skip a third of their ticks.
+        if state.is("beetroots") && world.random_below(3) == 0 {
+            return Ok(());
+        }
+        if brightness(world, pos)? < MIN_GROWTH_BRIGHTNESS {
+            return Ok(());
+        }
+        let age = property_number(state, "age");
+        if age >= max_age(state) {
+            return Ok(());
+        }
+        let speed = growth_speed(world, pos, state);
+        if world.random_below((25.0 / speed) as u32 + 1) == 0 {
+            let grown = with_value(state, "age", &(age + 1).to_string());
+            world.set_block_with(pos.x, pos.y, pos.z, grown, UpdateFlags::SEND_TO_CLIENTS)?;
+        }
+        Ok(())
+    }
+}
+
+impl BlockBehavior for FarmlandBehavior {
+    fn on_random_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        let moisture = property_number(state, "moisture");
+        if is_near_water(world, pos) {
+            if moisture < MAX_MOISTURE {
+                let wet = with_value(state, "moisture", &MAX_MOISTURE.to_string());
+                world.set_block_with(pos.x, pos.y, pos.z, wet, UpdateFlags::SEND_TO_CLIENTS)?;
+            }
+        } else if moisture > 0 {
+            let drier = with_value(state, "moisture", &(moisture - 1).to_string());
+            world.set_block_with(pos.x, pos.y, pos.z, drier, UpdateFlags::SEND_TO_CLIENTS)?;
+        } else if !block_at(world, pos.relative(Direction::Up))
+            .and_then(BlockState::name)
+            .is_some_and(|above| MAINTAINS_FARMLAND.contains(&above))
+        {
+            world.set_block(pos.x, pos.y, pos.z, BlockState::default_of("dirt")?)?;
+        }
+        Ok(())
+    }
+}
+
+fn max_age(state: BlockState) -> u8 {
+    if state.is("beetroots") {
+        3
+    } else {
+        7
+    }
+}
+
+/// Reads a number property, or 0 if the block doesn't have it.
+fn property_number(state: BlockState, property: &str) -> u8 {
+    state.get(property).and_then(|value| value.parse().ok()).unwrap_or(0)
+}
+
+/// How fast the crop grows: faster on farmland, more so when it's wet, and half as fast with the
+/// same crop on two sides or diagonally, so that crops planted in rows grow best.
+fn growth_speed(world: &World, pos: BlockPos, state: BlockState) -> f32 {
+    let mut speed = 1.0;
+    for dx in -1..=1 {
+        for dz in -1..=1 {
+            let soil = block_at(world, BlockPos::new(pos.x + dx, pos.y - 1, pos.z + dz));
+            let mut soil_speed = match soil {
+                Some(soil) if soil.is("farmland") && property_number(soil, "moisture") > 0 => 3.0,
+                Some(soil) if soil.is("farmland") => 1.0,
+                _ => 0.0,
+            };
+            if dx != 0 || dz != 0 {
+                soil_speed /= 4.0;
+            }
+            speed += soil_speed;
+        }
+    }
+
+    let same = |dx: i32, dz: i32| {
+        block_at(world, BlockPos::new(pos.x + dx, pos.y, pos.z + dz))
+            .is_some_and(|block| block.default_state() == state.default_state())
+    };
+    let along_x = same(-1, 0) || same(1, 0);
+    let along_z = same(0, -1) || same(0, 1);
+    let diagonal = same(-1, -1) || same(1, -1) || same(1, 1) || same(-1, 1);
+    if along_x && along_z || diagonal {
+        speed /= 2.0;
+    }
+    speed
+}
+
+/// Whether there is water within four blocks around the farmland, on its level or the one above.
+fn is_near_water(world: &World, pos: BlockPos) -> bool {
+    (-HYDRATION_RANGE..=HYDRATION_RANGE).any(|dx| {
+        (0..=1).any(|dy| {
+            (-HYDRATION_RANGE..=HYDRATION_RANGE).any(|dz| {
+                block_at(world, BlockPos::new(pos.x + dx, pos.y + dy, pos.z + dz))
+                    .and_then(FluidState::of)
+                    .is_some_and(|fluid| fluid.fluid == Fluid::Water)
+            })
+        })
+    })
+}
@@ -0,0 +1,420 @@
+use super::{block_at, facing, is_sturdy, set_own_state, with_value, BlockBehavior, BlockHit, PlaceContext, UseResult};
+use crate::block::BlockState;
+use crate::block_entity::BlockEntity;
+use crate::error::Result;
+use crate::pos::{BlockPos, Direction};
+use crate::update::TickPriority;
+use crate::world::World;
+use fastnbt::Value;
+
+/// The block entity key of the power a comparator gives.
+const OUTPUT_SIGNAL_KEY: &str = "OutputSignal";
+/// How many ticks a comparator takes to pass a change on.
+const COMPARATOR_DELAY: u64 = 2;
+
+/// Repeaters. They take power from the block behind them and give full power to the block in
+/// front of them `delay` redstone ticks later, two game ticks each. A powered repeater or
+/// comparator pointing into their side locks them, holding their output until it stops.
+///
+/// Their `facing` is the side their input is on.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RepeaterBehavior;
+
+/// Comparators. They pass on the power of the block behind them, or what a comparator reads from
+/// it, unless the power from their sides is stronger, and subtract that power from it in
+/// subtract mode. The power they give is kept in their block entity.
+///
+/// Their `facing` is the side their input is on.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ComparatorBehavior;
+
+impl BlockBehavior for RepeaterBehavior {
+    fn state_for_placement(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        let Some(state) = diode_placement(world, pos, context, state) else {
+            return Ok(None);
+        };
+        Ok(Some(with_value(state, "locked", bool_value(is_locked(world, pos, state)))))
+    }
+
+    fn on_use(&self, world: &World, pos: BlockPos, state: BlockState, _hit: &BlockHit) -> Result<UseResult> {
+        let delay = repeater_delay(state) % 4 + 1;
+        world.set_block(pos.x, pos.y, pos.z, with_value(state, "delay", &delay.to_string()))?;
+        Ok(UseResult::Consumed)
+    }
+
+    fn on_set(&self, world: &World, pos: BlockPos, state: BlockState, previous: BlockState) -> Result<()> {
+        if previous.default_state() == state.default_state() {
+            return Ok(());
+        }
+        notify_front(world, pos, state)?;
+        if is_powered(state) != should_repeater_turn_on(world, pos, state) {
+            world.schedule_tick(pos, state, 1);
+        }
+        Ok(())
+    }
+
+    fn on_replaced(&self, world: &World, pos: BlockPos, state: BlockState, _new: BlockState) -> Result<()> {
+        notify_front(world, pos, state)
+    }
+
+    fn on_neighbor_update(&self, world: &World, pos: BlockPos, state: BlockState, _from: BlockPos) -> Result<()> {
+        if is_locked(world, pos, state) {
+            return Ok(());
+        }
+        let powered = is_powered(state);
+        if powered != should_repeater_turn_on(world, pos, state) && !world.is_tick_scheduled(pos, state) {
+            let priority = if points_into_diode(world, pos, state) {
+                TickPriority::ExtremelyHigh
+            } else if powered {
+                TickPriority::VeryHigh
+            } else {
+                TickPriority::High
+            };
+            world.schedule_tick_with_priority(pos, state, repeater_ticks(state), priority);
+        }
+        Ok(())
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        _neighbor: BlockState,
+    ) -> BlockState {
+        if direction == Direction::Down {
+            return diode_shape(world, pos, state);
+        }
+        let input = facing(state);
+        if input == Some(direction) || input == Some(direction.opposite()) {
+            return state;
+        }
+        with_value(state, "locked", bool_value(is_locked(world, pos, state)))
+    }
+
+    fn on_scheduled_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        if is_locked(world, pos, state) {
+            return Ok(());
+        }
+        let should_turn_on = should_repeater_turn_on(world, pos, state);
+        if is_powered(state) && !should_turn_on {
+            set_powered(world, pos, state, false)
+        } else if !is_powered(state) {
+            set_powered(world, pos, state, true)?;
+            if !should_turn_on {
+                // Even a shorter pulse comes out as long as the delay.
+                world.schedule_tick_with_priority(pos, state, repeater_ticks(state), TickPriority::VeryHigh);
+            }
+            Ok(())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn is_signal_source(&self, _state: BlockState) -> bool {
+        true
+    }
+
+    fn get_signal(&self, _world: &World, _pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        if is_powered(state) && facing(state) == Some(direction) {
+            15
+        } else {
+            0
+        }
+    }
+
+    fn get_direct_signal(&self, world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        self.get_signal(world, pos, state, direction)
+    }
+}
+
+impl BlockBehavior for ComparatorBehavior {
+    fn state_for_placement(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        Ok(diode_placement(world, pos, context, state))
+    }
+
+    fn on_use(&self, world: &World, pos: BlockPos, state: BlockState, _hit: &BlockHit) -> Result<UseResult> {
+        let mode = if state.get("mode") == Some("subtract") { "compare" } else { "subtract" };
+        let state = with_value(state, "mode", mode);
+        set_own_state(world, pos, state)?;
+        refresh_comparator(world, pos, state)?;
+        Ok(UseResult::Consumed)
+    }
+
+    fn on_set(&self, world: &World, pos: BlockPos, state: BlockState, previous: BlockState) -> Result<()> {
+        if previous.default_state() == state.default_state() {
+            return Ok(());
+        }
+        notify_front(world, pos, state)?;
+        check_comparator(world, pos, state, 1)
+    }
+
+    fn on_replaced(&self, world: &World, pos: BlockPos, state: BlockState, _new: BlockState) -> Result<()> {
+        notify_front(world, pos, state)
+    }
+
+    fn on_neighbor_update(&self, world: &World, pos: BlockPos, state: BlockState, _from: BlockPos) -> Result<()> {
+        check_comparator(world, pos, state, COMPARATOR_DELAY)
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        _neighbor: BlockState,
+    ) -> BlockState {
+        if direction == Direction::Down {
+            diode_shape(world, pos, state)
+        } else {
+            state
+        }
+    }
+
+    fn on_scheduled_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        refresh_comparator(world, pos, state)
+    }
+
+    fn is_signal_source(&self, _state: BlockState) -> bool {
+        true
+    }
+
+    fn get_signal(&self, world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        if is_powered(state) && facing(state) == Some(direction) {
+            comparator_output(world, pos)
+        } else {
+            0
+        }
+    }
+
+    fn get_direct_signal(&self, world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        self.get_signal(world, pos, state, direction)
+    }
+}
+
+fn bool_value(value: bool) -> &'static str {
+    if value {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+fn is_powered(state: BlockState) -> bool {
+    state.get("powered") == Some("true")
+}
+
+fn is_diode(state: BlockState) -> bool {
+    state.is("repeater") || state.is("comparator")
+}
+
+fn repeater_delay(state: BlockState) -> u64 {
+    state.get("delay").and_then(|delay| delay.parse().ok()).unwrap_or(1)
+}
+
+/// The game ticks the repeater takes to pass a change on.
+fn repeater_ticks(state: BlockState) -> u64 {
+    repeater_delay(state) * 2
+}
+
+/// Turns the diode to take its input from behind the player, pointing the way they look. It
+/// needs a block to stand on.
+fn diode_placement(world: &World, pos: BlockPos, context: &PlaceContext, state: BlockState) -> Option<BlockState> {
+    if !is_sturdy(world, pos.relative(Direction::Down), Direction::Up) {
+        return None;
+    }
+    Some(with_value(state, "facing", context.horizontal_facing().opposite().name()))
+}
+
+/// Breaks the diode when the block it stands on goes.
+fn diode_shape(world: &World, pos: BlockPos, state: BlockState) -> BlockState {
+    if is_sturdy(world, pos.relative(Direction::Down), Direction::Up) {
+        state
+    } else {
+        BlockState::AIR
+    }
+}
+
+/// Returns the position of the block the diode powers.
+fn front(pos: BlockPos, state: BlockState) -> Option<BlockPos> {
+    facing(state).map(|input| pos.relative(input.opposite()))
+}
+
+/// Tells the block the diode powers, and the blocks around it, that the diode changed.
+fn notify_front(world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+    let Some(front) = front(pos, state) else {
+        return Ok(());
+    };
+    world.notify_neighbor(front, pos)?;
+    world.notify_neighbors(front)
+}
+
+/// Whether the diode points into the side or the front of another diode, whose tick then runs
+/// first.
+fn points_into_diode(world: &World, pos: BlockPos, state: BlockState) -> bool {
+    let Some(front) = front(pos, state).and_then(|front| block_at(world, front)) else {
+        return false;
+    };
+    is_diode(front) && facing(front) != facing(state)
+}
+
+/// Returns the power the diode takes from the block behind it, counting the power of wire there
+/// even when it doesn't point at the diode.
+fn input_signal(world: &World, pos: BlockPos, state: BlockState) -> u8 {
+    let Some(input) = facing(state) else {
+        return 0;
+    };
+    let behind = pos.relative(input);
+    let signal = world.signal(behind, input);
+    if signal >= 15 {
+        return signal;
+    }
+    let wire = block_at(world, behind).filter(|state| state.is("redstone_wire"));
+    signal.max(wire.and_then(|wire| wire.get("power")?.parse().ok()).unwrap_or(0))
+}
+
+/// Returns the strongest power the diode takes from its sides, from diodes only if
+/// `diodes_only`, and from anything that gives off power otherwise.
+fn side_signal(world: &World, pos: BlockPos, state: BlockState, diodes_only: bool) -> u8 {
+    let Some(input) = facing(state) else {
+        return 0;
+    };
+    let mut signal = 0;
+    for side in [input.clockwise(), input.counter_clockwise()] {
+        let side_pos = pos.relative(side);
+        let Some(side_state) = block_at(world, side_pos) else {
+            continue;
+        };
+        let counts = if diodes_only { is_diode(side_state) } else { world.is_signal_source(side_state) };
+        if !counts {
+            continue;
+        }
+        let side_signal = if side_state.is("redstone_block") {
+            15
+        } else if side_state.is("redstone_wire") {
+            side_state.get("power").and_then(|power| power.parse().ok()).unwrap_or(0)
+        } else {
+            world.direct_signal(side_pos, side)
+        };
+        signal = signal.max(side_signal);
+    }
+    signal
+}
+
+fn is_locked(world: &World, pos: BlockPos, state: BlockState) -> bool {
+    side_signal(world, pos, state, true) > 0
+}
+
+fn should_repeater_turn_on(world: &World, pos: BlockPos, state: BlockState) -> bool {
+    input_signal(world, pos, state) > 0
+}
+
+/// Sets the diode on or off and tells the block it powers.
+fn set_powered(world: &World, pos: BlockPos, state: BlockState, powered: bool) -> Result<()> {
+    let state = with_value(state, "powered", bool_value(powered));
+    set_own_state(world, pos, state)?;
+    notify_front(world, pos, state)
+}
+
+/// Returns the power a comparator reads: what a comparator reads from the block behind it, or
+/// from the block behind that through a conductor, or else the power the block behind gives it.
+fn comparator_input(world: &World, pos: BlockPos, state: BlockState) -> u8 {
+    let signal = input_signal(world, pos, state);
+    let Some(input) = facing(state) else {
+        return signal;
+    };
+    let behind = pos.relative(input);
+    if let Some(output) = world.analog_output(behind) {
+        return output;
+    }
+    let through_conductor = block_at(world, behind).is_some_and(|state| world.is_redstone_conductor(state));
+    if signal < 15 && through_conductor {
+        if let Some(output) = world.analog_output(behind.relative(input)) {
+            return output;
+        }
+    }
+    signal
+}
+
+/// Returns the power the comparator should give: its input, less its side input in subtract
+/// mode, and none when the side input is stronger.
+fn comparator_target(world: &World, pos: BlockPos, state: BlockState) -> u8 {
+    let input = comparator_input(world, pos, state);
+    if input == 0 {
+        return 0;
+    }
+    let side = side_signal(world, pos, state, false);
+    if side > input {
+        0
+    } else if state.get("mode") == Some("subtract") {
+        input - side
+    } else {
+        input
+    }
+}
+
+fn should_comparator_turn_on(world: &World, pos: BlockPos, state: BlockState) -> bool {
+    let input = comparator_input(world, pos, state);
+    if input == 0 {
+        return false;
+    }
+    let side = side_signal(world, pos, state, false);
+    input > side || (input == side && state.get("mode") != Some("subtract"))
+}
+
+/// Returns the power the comparator gives, as kept in its block entity.
+fn comparator_output(world: &World, pos: BlockPos) -> u8 {
+    match world.block_entity(pos.x, pos.y, pos.z) {
+        Ok(Some(entity)) => entity.get_int(OUTPUT_SIGNAL_KEY).unwrap_or(0).clamp(0, 15) as u8,
+        _ => 0,
+    }
+}
+
+fn set_comparator_output(world: &World, pos: BlockPos, output: u8) -> Result<()> {
+    let entity = world.block_entity(pos.x, pos.y, pos.z)?.unwrap_or_else(|| BlockEntity::new("minecraft:comparator"));
+    world.set_block_entity(pos.x, pos.y, pos.z, entity.with(OUTPUT_SIGNAL_KEY, Value::Int(output as i32)))
+}
+
+/// Schedules a tick for the comparator if its power or its state is out of date.
+fn check_comparator(world: &World, pos: BlockPos, state: BlockState, delay: u64) -> Result<()> {
+    if world.is_tick_scheduled(pos, state) {
+        return Ok(());
+    }
+    let target = comparator_target(world, pos, state);
+    if target != comparator_output(world, pos) || is_powered(state) != should_comparator_turn_on(world, pos, state) {
+        let priority = if points_into_diode(world, pos, state) { TickPriority::High } else { TickPriority::Normal };
+        world.schedule_tick_with_priority(pos, state, delay, priority);
+    }
+    Ok(())
+}
+
+/// Brings the power of the comparator up to date and tells the block it powers.
+fn refresh_comparator(world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+    let target = comparator_target(world, pos, state);
+    let output = comparator_output(world, pos);
+    if target != output {
+        set_comparator_output(world, pos, target)?;
+    }
+    if target == output && state.get("mode") == Some("subtract") {
+        return Ok(());
+    }
+    let should_turn_on = should_comparator_turn_on(world, pos, state);
+    if is_powered(state) != should_turn_on {
+        set_own_state(world, pos, with_value(state, "powered", bool_value(should_turn_on)))?;
+    }
+    notify_front(world, pos, state)
+}
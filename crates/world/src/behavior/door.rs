@@ -0,0 +1,133 @@
+use super::{
+    facing, is_replaceable, set_own_state, toggled, with_value, BlockBehavior, BlockHit, PlaceContext, UseResult,
+};
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::pos::{BlockPos, Direction};
+use crate::shape::{Aabb, Shape};
+use crate::world::World;
+
+/// How thick doors and trapdoors are.
+pub(crate) const PANEL_THICKNESS: f64 = 3.0 / 16.0;
+
+/// Doors are two blocks high, the upper half following the lower one. Placing a door picks the
+/// hinge from the blocks and doors beside it, and opening one half opens the other. Iron doors
+/// don't open by hand.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DoorBehavior;
+
+impl BlockBehavior for DoorBehavior {
+    fn state_for_placement(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        let above = pos.relative(Direction::Up);
+        if !world.height().contains(above.y) || !is_replaceable(world.get_block(above.x, above.y, above.z)?) {
+            return Ok(None);
+        }
+        if !world.is_face_sturdy(pos.relative(Direction::Down), Direction::Up)? {
+            return Ok(None);
+        }
+        let facing = context.horizontal_facing();
+        let state = with_value(state, "facing", facing.name());
+        let state = with_value(state, "half", "lower");
+        Ok(Some(with_value(state, "hinge", hinge(world, pos, context, state, facing)?)))
+    }
+
+    fn on_place(&self, world: &World, pos: BlockPos, state: BlockState, _previous: BlockState) -> Result<()> {
+        world.set_block(pos.x, pos.y + 1, pos.z, with_value(state, "half", "upper"))
+    }
+
+    fn on_use(&self, world: &World, pos: BlockPos, state: BlockState, _hit: &BlockHit) -> Result<UseResult> {
+        if state.is("iron_door") {
+            return Ok(UseResult::Pass);
+        }
+        set_own_state(world, pos, toggled(state, "open"))?;
+        Ok(UseResult::Consumed)
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        neighbor: BlockState,
+    ) -> BlockState {
+        let half = state.get("half");
+        let lower = half == Some("lower");
+        let other_half = if lower { Direction::Up } else { Direction::Down };
+        if direction == other_half {
+            // The half follows the other one, or goes with it.
+            let same_door = neighbor.default_state() == state.default_state() && neighbor.get("half") != half;
+            return match (same_door, half) {
+                (true, Some(half)) => with_value(neighbor, "half", half),
+                _ => BlockState::AIR,
+            };
+        }
+        if lower
+            && direction == Direction::Down
+            && !world.is_face_sturdy(pos.relative(direction), Direction::Up).unwrap_or(true)
+        {
+            return BlockState::AIR;
+        }
+        state
+    }
+
+    fn get_collision_shape(&self, state: BlockState) -> Shape {
+        let Some(facing) = facing(state) else {
+            return Shape::full();
+        };
+        // Closed doors sit on the side the player placed them from, open ones along the side of
+        // their hinge.
+        let side = match (state.get("open"), state.get("hinge")) {
+            (Some("true"), Some("right")) => facing.clockwise(),
+            (Some("true"), _) => facing.counter_clockwise(),
+            _ => facing.opposite(),
+        };
+        Shape::from_box(Aabb::side(side, PANEL_THICKNESS))
+    }
+}
+
+/// Picks the hinge of a door being placed: on the side with more solid blocks beside it, away
+/// from a door it makes a double door with, and otherwise on the side of the block the player
+/// clicked.
+fn hinge(
+    world: &World,
+    pos: BlockPos,
+    context: &PlaceContext,
+    state: BlockState,
+    facing: Direction,
+) -> Result<&'static str> {
+    let full = |pos: BlockPos| -> Result<bool> {
+        let block = world.get_block(pos.x, pos.y, pos.z)?;
+        Ok(world.collision_shape(block).is_full())
+    };
+    let lower_door = |pos: BlockPos| -> Result<bool> {
+        let block = world.get_block(pos.x, pos.y, pos.z)?;
+        Ok(block.default_state() == state.default_state() && block.get("half") == Some("lower"))
+    };
+    let above = pos.relative(Direction::Up);
+    let (left, right) = (facing.counter_clockwise(), facing.clockwise());
+    let score = full(pos.relative(right))? as i32 + full(above.relative(right))? as i32
+        - full(pos.relative(left))? as i32
+        - full(above.relative(left))? as i32;
+    let door_left = lower_door(pos.relative(left))?;
+    let door_right = lower_door(pos.relative(right))?;
+
+    if door_left && !door_right || score > 0 {
+        return Ok("right");
+    }
+    if door_right && !door_left || score < 0 {
+        return Ok("left");
+    }
+    // The hinge goes on the half of the block to the player's left or right that was clicked.
+    let (x, _, z) = context.click_relative_to(pos);
+    let (step_x, _, step_z) = facing.offset();
+    let clicked_right =
+        (step_x < 0 && z < 0.5) || (step_x > 0 && z > 0.5) || (step_z < 0 && x > 0.5) || (step_z > 0 && x < 0.5);
+    Ok(if clicked_right { "right" } else { "left" })
+}
@@ -0,0 +1,344 @@
+use super::{block_at, with_value, BlockBehavior};
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::fluid::{Fluid, FluidState};
+use crate::pos::{BlockPos, Direction};
+use crate::world::World;
+
+/// Blocks fluids don't flow into, though they aren't solid.
+const FLUID_BLOCKING_BLOCKS: [&str; 7] = [
+    "minecraft:ladder",
+    "minecraft:sugar_cane",
+    "minecraft:bubble_column",
+    "minecraft:nether_portal",
+    "minecraft:end_portal",
+    "minecraft:end_gateway",
+    "minecraft:structure_void",
+];
+
+/// How far a fluid is from where it flows down when there is nowhere to flow down.
+const NO_SLOPE: u32 = 1000;
+
+/// Water and lava blocks. They flow when their scheduled fluid tick comes, which placing them or
+/// changing the blocks around them schedules. Lava touching water turns into obsidian if it's a
+/// source and cobblestone otherwise.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FluidBehavior;
+
+impl BlockBehavior for FluidBehavior {
+    fn on_place(&self, world: &World, pos: BlockPos, state: BlockState, _previous: BlockState) -> Result<()> {
+        if should_spread(world, pos, state)? {
+            schedule(world, pos, state);
+        }
+        Ok(())
+    }
+
+    fn on_neighbor_update(&self, world: &World, pos: BlockPos, state: BlockState, _from: BlockPos) -> Result<()> {
+        if should_spread(world, pos, state)? {
+            schedule(world, pos, state);
+        }
+        Ok(())
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        _direction: Direction,
+        neighbor: BlockState,
+    ) -> BlockState {
+        let source = |state| FluidState::of(state).is_some_and(FluidState::is_source);
+        if source(state) || source(neighbor) {
+            schedule(world, pos, state);
+        }
+        state
+    }
+}
+
+/// Schedules the next tick of the fluid in the block.
+pub(crate) fn schedule(world: &World, pos: BlockPos, state: BlockState) {
+    if let Some(fluid) = FluidState::of(state) {
+        world.schedule_fluid_tick(pos, fluid.fluid, fluid.fluid.tick_delay());
+    }
+}
+
+/// Runs the tick of the fluid in the block: flowing fluid takes the amount the fluid around it
+/// gives it or dries up, and then the fluid flows down if it can and sideways otherwise.
+pub(crate) fn flow(world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+    let Some(mut fluid) = FluidState::of(state) else {
+        return Ok(());
+    };
+    let mut state = state;
+    if !fluid.is_source() {
+        match new_fluid(world, pos, state, fluid.fluid) {
+            None => {
+                world.set_block(pos.x, pos.y, pos.z, BlockState::AIR)?;
+                return Ok(());
+            }
+            Some(new) if new != fluid => {
+                fluid = new;
+                state = new.block();
+                world.set_block(pos.x, pos.y, pos.z, state)?;
+                world.schedule_fluid_tick(pos, new.fluid, new.fluid.tick_delay());
+            }
+            Some(_) => {}
+        }
+    }
+    spread(world, pos, state, fluid)
+}
+
+/// Turns lava touching water into obsidian or cobblestone, or into basalt if it's over soul soil
+/// and touches blue ice. Returns whether the fluid is still there to flow.
+fn should_spread(world: &World, pos: BlockPos, state: BlockState) -> Result<bool> {
+    let Some(fluid) = FluidState::of(state).filter(|fluid| fluid.fluid == Fluid::Lava) else {
+        return Ok(true);
+    };
+    let below = block_at(world, pos.relative(Direction::Down));
+    let soul_soil = below.is_some_and(|below| below.is("soul_soil"));
+    for direction in Direction::ALL.into_iter().filter(|&direction| direction != Direction::Down) {
+        let Some(neighbor) = block_at(world, pos.relative(direction)) else {
+            continue;
+        };
+        let block = if FluidState::of(neighbor).is_some_and(|neighbor| neighbor.fluid == Fluid::Water) {
+            if fluid.is_source() {
+                "obsidian"
+            } else {
+                "cobblestone"
+            }
+        } else if soul_soil && neighbor.is("blue_ice") {
+            "basalt"
+        } else {
+            continue;
+        };
+        world.set_block(pos.x, pos.y, pos.z, BlockState::default_of(block)?)?;
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Returns the fluid the block at `pos` should hold given the fluid around it: a source between
+/// two sources of water over something solid or another source, falling fluid under fluid, and
+/// otherwise the most fluid beside it minus the drop off.
+fn new_fluid(world: &World, pos: BlockPos, state: BlockState, kind: Fluid) -> Option<FluidState> {
+    let mut most = 0;
+    let mut sources = 0;
+    for direction in Direction::HORIZONTAL {
+        let Some(neighbor) = block_at(world, pos.relative(direction)) else {
+            continue;
+        };
+        let Some(fluid) = FluidState::of(neighbor).filter(|fluid| fluid.fluid == kind) else {
+            continue;
+        };
+        if can_pass_wall(world, state, direction, neighbor) {
+            if fluid.is_source() {
+                sources += 1;
+            }
+            most = most.max(fluid.amount);
+        }
+    }
+
+    if kind.converts_to_source() && sources >= 2 {
+        let below = block_at(world, pos.relative(Direction::Down));
+        if below.is_some_and(|below| world.collision_shape(below).is_solid() || is_source_of(below, kind)) {
+            return Some(FluidState::source(kind));
+        }
+    }
+    let above = block_at(world, pos.relative(Direction::Up));
+    if let Some(above) = above {
+        let same = FluidState::of(above).is_some_and(|fluid| fluid.fluid == kind);
+        if same && can_pass_wall(world, state, Direction::Up, above) {
+            return Some(FluidState::flowing(kind, 8, true));
+        }
+    }
+    let amount = most.saturating_sub(kind.drop_off());
+    (amount > 0).then(|| FluidState::flowing(kind, amount, false))
+}
+
+/// Flows down into the block below if the fluid can, and sideways if it can't or the block is
+/// surrounded by sources.
+fn spread(world: &World, pos: BlockPos, state: BlockState, fluid: FluidState) -> Result<()> {
+    let kind = fluid.fluid;
+    let below_pos = pos.relative(Direction::Down);
+    let below = block_at(world, below_pos);
+    if let Some(below) = below.filter(|&below| can_maybe_pass(world, state, Direction::Down, below, kind)) {
+        if let Some(new) = new_fluid(world, below_pos, below, kind) {
+            if can_be_replaced_with(below, kind, Direction::Down) && can_hold(world, below, new) {
+                spread_to(world, below_pos, below, Direction::Down, new)?;
+                if source_neighbors(world, pos, kind) >= 3 {
+                    spread_to_sides(world, pos, state, fluid)?;
+                }
+                return Ok(());
+            }
+        }
+    }
+    let hole = below.is_some_and(|below| is_hole(world, state, below, kind));
+    if fluid.is_source() || !hole {
+        spread_to_sides(world, pos, state, fluid)?;
+    }
+    Ok(())
+}
+
+/// Flows sideways towards the nearest places the fluid can flow down, or every way if there are
+/// none nearby.
+fn spread_to_sides(world: &World, pos: BlockPos, state: BlockState, fluid: FluidState) -> Result<()> {
+    let amount = if fluid.falling { 7 } else { fluid.amount.saturating_sub(fluid.fluid.drop_off()) };
+    if amount == 0 {
+        return Ok(());
+    }
+    for (direction, new) in spreads(world, pos, state, fluid.fluid) {
+        let neighbor_pos = pos.relative(direction);
+        let Some(neighbor) = block_at(world, neighbor_pos) else {
+            continue;
+        };
+        if can_hold(world, neighbor, new) {
+            spread_to(world, neighbor_pos, neighbor, direction, new)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the sides the fluid flows to and the fluid each gets: the sides closest to a hole
+/// within the fluid's slope find distance.
+fn spreads(world: &World, pos: BlockPos, state: BlockState, kind: Fluid) -> Vec<(Direction, FluidState)> {
+    let mut nearest = NO_SLOPE;
+    let mut spreads = Vec::new();
+    for direction in Direction::HORIZONTAL {
+        let neighbor_pos = pos.relative(direction);
+        let Some(neighbor) = block_at(world, neighbor_pos) else {
+            continue;
+        };
+        if !can_maybe_pass(world, state, direction, neighbor, kind) {
+            continue;
+        }
+        let Some(new) = new_fluid(world, neighbor_pos, neighbor, kind) else {
+            continue;
+        };
+        if !can_hold(world, neighbor, new) {
+            continue;
+        }
+        let below = block_at(world, neighbor_pos.relative(Direction::Down));
+        let distance = if below.is_some_and(|below| is_hole(world, neighbor, below, kind)) {
+            0
+        } else {
+            slope_distance(world, neighbor_pos, neighbor, 1, direction.opposite(), kind)
+        };
+        if distance < nearest {
+            spreads.clear();
+        }
+        if distance <= nearest {
+            if can_be_replaced_with(neighbor, kind, direction) {
+                spreads.push((direction, new));
+            }
+            nearest = distance;
+        }
+    }
+    spreads
+}
+
+/// Returns how many blocks sideways from the block at `pos` the nearest hole is, searching up to
+/// the fluid's slope find distance without going back the way it came.
+fn slope_distance(world: &World, pos: BlockPos, state: BlockState, depth: u32, from: Direction, kind: Fluid) -> u32 {
+    let mut nearest = NO_SLOPE;
+    for direction in Direction::HORIZONTAL.into_iter().filter(|&direction| direction != from) {
+        let neighbor_pos = pos.relative(direction);
+        let Some(neighbor) = block_at(world, neighbor_pos) else {
+            continue;
+        };
+        if !can_maybe_pass(world, state, direction, neighbor, kind)
+            || !can_hold(world, neighbor, FluidState::flowing(kind, 1, false))
+        {
+            continue;
+        }
+        let below = block_at(world, neighbor_pos.relative(Direction::Down));
+        if below.is_some_and(|below| is_hole(world, neighbor, below, kind)) {
+            return depth;
+        }
+        if depth < kind.slope_find_distance() {
+            let distance = slope_distance(world, neighbor_pos, neighbor, depth + 1, direction.opposite(), kind);
+            nearest = nearest.min(distance);
+        }
+    }
+    nearest
+}
+
+/// Puts the fluid in the block. Lava flowing down into water turns it into stone, and water
+/// sources waterlog the blocks that can be.
+fn spread_to(world: &World, pos: BlockPos, state: BlockState, direction: Direction, fluid: FluidState) -> Result<()> {
+    if fluid.fluid == Fluid::Lava && direction == Direction::Down && state.is("water") {
+        world.set_block(pos.x, pos.y, pos.z, BlockState::default_of("stone")?)?;
+        return Ok(());
+    }
+    let state =
+        if state.get("waterlogged").is_some() { with_value(state, "waterlogged", "true") } else { fluid.block() };
+    world.set_block(pos.x, pos.y, pos.z, state)?;
+    world.schedule_fluid_tick(pos, fluid.fluid, fluid.fluid.tick_delay());
+    Ok(())
+}
+
+/// How many sources of the fluid are beside the block.
+fn source_neighbors(world: &World, pos: BlockPos, kind: Fluid) -> usize {
+    Direction::HORIZONTAL
+        .into_iter()
+        .filter_map(|direction| block_at(world, pos.relative(direction)))
+        .filter(|&neighbor| is_source_of(neighbor, kind))
+        .count()
+}
+
+fn is_source_of(state: BlockState, kind: Fluid) -> bool {
+    FluidState::of(state).is_some_and(|fluid| fluid.fluid == kind && fluid.is_source())
+}
+
+/// Whether fluid can go from the block to its neighbor on the side, because neither covers the
+/// face between them.
+fn can_pass_wall(world: &World, state: BlockState, direction: Direction, neighbor: BlockState) -> bool {
+    !world.collision_shape(state).covers_face(direction)
+        && !world.collision_shape(neighbor).covers_face(direction.opposite())
+}
+
+/// Whether the fluid may flow into the neighbor on the side, which isn't a source of it already.
+fn can_maybe_pass(world: &World, state: BlockState, direction: Direction, neighbor: BlockState, kind: Fluid) -> bool {
+    !is_source_of(neighbor, kind) && can_hold_any(world, neighbor) && can_pass_wall(world, state, direction, neighbor)
+}
+
+/// Whether the block below the block holding `state` is somewhere the fluid flows down into.
+fn is_hole(world: &World, state: BlockState, below: BlockState, kind: Fluid) -> bool {
+    if !can_pass_wall(world, state, Direction::Down, below) {
+        return false;
+    }
+    FluidState::of(below).is_some_and(|fluid| fluid.fluid == kind)
+        || can_hold(world, below, FluidState::flowing(kind, 1, false))
+}
+
+/// Whether any fluid can go in the block: blocks that can be waterlogged, and the blocks that
+/// aren't solid, which the fluid washes away.
+fn can_hold_any(world: &World, state: BlockState) -> bool {
+    if state.get("waterlogged").is_some() {
+        return true;
+    }
+    if world.collision_shape(state).is_solid() {
+        return false;
+    }
+    match state.name() {
+        Some(name) => !name.ends_with("_door") && !name.ends_with("_sign") && !FLUID_BLOCKING_BLOCKS.contains(&name),
+        None => true,
+    }
+}
+
+/// Whether the fluid can go in the block. Only water sources waterlog blocks.
+fn can_hold(world: &World, state: BlockState, fluid: FluidState) -> bool {
+    match state.get("waterlogged") {
+        Some(waterlogged) => fluid.fluid == Fluid::Water && fluid.is_source() && waterlogged != "true",
+        None => can_hold_any(world, state),
+    }
+}
+
+/// Whether the fluid already in the block gives way to the fluid coming from the side: lava
+/// falling onto water, and water reaching lava at least half high. Blocks without fluid always do.
+fn can_be_replaced_with(state: BlockState, kind: Fluid, direction: Direction) -> bool {
+    match FluidState::of(state) {
+        None => true,
+        Some(fluid) if fluid.fluid == Fluid::Water => direction == Direction::Down && kind != Fluid::Water,
+        Some(fluid) => fluid.height() >= 4.0 / 9.0 && kind == Fluid::Water,
+    }
+}
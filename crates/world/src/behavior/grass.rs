@@ -0,0 +1,71 @@
+use super::{block_at, brightness, with_value, BlockBehavior};
+use crate::block::{light_properties, BlockState};
+use crate::error::Result;
+use crate::fluid::{Fluid, FluidState};
+use crate::pos::{BlockPos, Direction};
+use crate::world::World;
+
+/// How bright the block above grass must be for it to spread.
+const MIN_SPREAD_BRIGHTNESS: u8 = 9;
+/// How many blocks around it grass tries to spread to each time it ticks.
+const SPREAD_ATTEMPTS: usize = 4;
+
+/// Grass blocks and mycelium. They turn back into dirt under a block that lets no light
+/// through or under water, and otherwise spread to the dirt around them in the light.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpreadingBehavior;
+
+impl BlockBehavior for SpreadingBehavior {
+    fn on_random_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        let above = pos.relative(Direction::Up);
+        if !can_stay(world, pos) {
+            world.set_block(pos.x, pos.y, pos.z, BlockState::default_of("dirt")?)?;
+            return Ok(());
+        }
+        if brightness(world, above)? < MIN_SPREAD_BRIGHTNESS {
+            return Ok(());
+        }
+        let spread = state.default_state();
+        for _ in 0..SPREAD_ATTEMPTS {
+            let target = BlockPos::new(
+                pos.x + world.random_below(3) as i32 - 1,
+                pos.y + world.random_below(5) as i32 - 3,
+                pos.z + world.random_below(3) as i32 - 1,
+            );
+            if !block_at(world, target).is_some_and(|block| block.is("dirt")) || !can_spread_to(world, target) {
+                continue;
+            }
+            let snowy = block_at(world, target.relative(Direction::Up)).is_some_and(is_snowy);
+            let state = with_value(spread, "snowy", if snowy { "true" } else { "false" });
+            world.set_block(target.x, target.y, target.z, state)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether grass can be at the position: under a single layer of snow, or a block that lets
+/// some light through and isn't full of fluid.
+fn can_stay(world: &World, pos: BlockPos) -> bool {
+    let Some(above) = block_at(world, pos.relative(Direction::Up)) else {
+        return true;
+    };
+    if above.is("snow") && above.get("layers") == Some("1") {
+        return true;
+    }
+    if FluidState::of(above).is_some_and(|fluid| fluid.amount == 8) {
+        return false;
+    }
+    light_properties(above).opacity < 15
+}
+
+/// Whether grass can spread to the dirt at the position: it could stay there and isn't under
+/// water.
+fn can_spread_to(world: &World, pos: BlockPos) -> bool {
+    let above = block_at(world, pos.relative(Direction::Up));
+    can_stay(world, pos) && !above.and_then(FluidState::of).is_some_and(|fluid| fluid.fluid == Fluid::Water)
+}
+
+/// Whether the block makes the grass under it snowy.
+fn is_snowy(state: BlockState) -> bool {
+    state.is("snow") || state.is("snow_block") || state.is("powder_snow")
+}
@@ -0,0 +1,46 @@
+use super::{set_own_state, with_value, BlockBehavior};
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::pos::BlockPos;
+use crate::world::World;
+
+/// How many ticks a lamp stays lit after it stops being powered.
+const TURN_OFF_DELAY: u64 = 4;
+
+/// Redstone lamps. They light up as soon as they're powered, and go out a little after they
+/// stop being powered.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedstoneLampBehavior;
+
+impl BlockBehavior for RedstoneLampBehavior {
+    fn on_set(&self, world: &World, pos: BlockPos, state: BlockState, previous: BlockState) -> Result<()> {
+        if previous.default_state() != state.default_state() {
+            self.on_neighbor_update(world, pos, state, pos)?;
+        }
+        Ok(())
+    }
+
+    fn on_neighbor_update(&self, world: &World, pos: BlockPos, state: BlockState, _from: BlockPos) -> Result<()> {
+        let lit = is_lit(state);
+        if lit == world.has_neighbor_signal(pos) {
+            return Ok(());
+        }
+        if lit {
+            world.schedule_tick(pos, state, TURN_OFF_DELAY);
+        } else {
+            set_own_state(world, pos, with_value(state, "lit", "true"))?;
+        }
+        Ok(())
+    }
+
+    fn on_scheduled_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        if is_lit(state) && !world.has_neighbor_signal(pos) {
+            set_own_state(world, pos, with_value(state, "lit", "false"))?;
+        }
+        Ok(())
+    }
+}
+
+fn is_lit(state: BlockState) -> bool {
+    state.get("lit") == Some("true")
+}
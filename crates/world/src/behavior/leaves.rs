@@ -0,0 +1,94 @@
+use super::{block_at, waterlogged_for, with_value, BlockBehavior, PlaceContext};
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::pos::{BlockPos, Direction};
+use crate::world::World;
+
+/// The distance of leaves too far from a log to hold on.
+const MAX_DISTANCE: u8 = 7;
+
+/// Leaves. They keep track of how far the nearest log is through other leaves, and decay when
+/// they tick at random more than six blocks from one, unless a player placed them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LeavesBehavior;
+
+impl BlockBehavior for LeavesBehavior {
+    fn state_for_placement(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        _context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        let state = with_value(state, "persistent", "true");
+        let state = with_distance(world, pos, state);
+        Ok(Some(waterlogged_for(world, pos, state)?))
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        _direction: Direction,
+        neighbor: BlockState,
+    ) -> BlockState {
+        // The distance is worked out a tick later, so that a tree being cut down is only walked
+        // once.
+        let through_neighbor = distance_at(neighbor) + 1;
+        if through_neighbor != 1 || distance_at(state) != through_neighbor {
+            world.schedule_tick(pos, state, 1);
+        }
+        state
+    }
+
+    fn on_scheduled_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        world.set_block(pos.x, pos.y, pos.z, with_distance(world, pos, state))
+    }
+
+    fn on_random_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        if distance_at(state) == MAX_DISTANCE && state.get("persistent") == Some("false") {
+            world.set_block(pos.x, pos.y, pos.z, BlockState::AIR)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether the block holds up leaves.
+fn is_log(state: BlockState) -> bool {
+    let Some(name) = state.name() else {
+        return false;
+    };
+    name.ends_with("_log")
+        || name.ends_with("_wood")
+        || name.ends_with("_hyphae")
+        || name.ends_with("crimson_stem")
+        || name.ends_with("warped_stem")
+}
+
+/// How far the block is from a log for the leaves next to it: 0 for a log, the distance of
+/// leaves, and too far for anything else.
+fn distance_at(state: BlockState) -> u8 {
+    if is_log(state) {
+        return 0;
+    }
+    match state.get("distance") {
+        Some(distance) if state.name().is_some_and(|name| name.ends_with("_leaves")) => {
+            distance.parse().unwrap_or(MAX_DISTANCE)
+        }
+        _ => MAX_DISTANCE,
+    }
+}
+
+/// Returns the leaves with the distance of the nearest log through the blocks around them.
+fn with_distance(world: &World, pos: BlockPos, state: BlockState) -> BlockState {
+    let mut distance = MAX_DISTANCE;
+    for direction in Direction::ALL {
+        let neighbor = block_at(world, pos.relative(direction)).map_or(MAX_DISTANCE, distance_at);
+        distance = distance.min(neighbor + 1);
+        if distance == 1 {
+            break;
+        }
+    }
+    with_value(state, "distance", &distance.to_string())
+}
@@ -0,0 +1,39 @@
+use super::BlockBehavior;
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::fluid::Fluid;
+use crate::light::LightKind;
+use crate::pos::BlockPos;
+use crate::world::World;
+
+/// The block light above which ice melts.
+const ICE_MELT_LIGHT: u8 = 10;
+/// The block light above which snow melts.
+const SNOW_MELT_LIGHT: u8 = 11;
+
+/// Ice. It melts into water when it ticks at random near a light, though not from sunlight.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IceBehavior;
+
+/// Layers of snow. They melt away when they tick at random near a light.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SnowLayerBehavior;
+
+impl BlockBehavior for IceBehavior {
+    fn on_random_tick(&self, world: &World, pos: BlockPos, _state: BlockState) -> Result<()> {
+        if world.light_level(LightKind::Block, pos.x, pos.y, pos.z)? > ICE_MELT_LIGHT {
+            world.set_block(pos.x, pos.y, pos.z, Fluid::Water.source_block())?;
+            world.schedule_fluid_tick(pos, Fluid::Water, Fluid::Water.tick_delay());
+        }
+        Ok(())
+    }
+}
+
+impl BlockBehavior for SnowLayerBehavior {
+    fn on_random_tick(&self, world: &World, pos: BlockPos, _state: BlockState) -> Result<()> {
+        if world.light_level(LightKind::Block, pos.x, pos.y, pos.z)? > SNOW_MELT_LIGHT {
+            world.set_block(pos.x, pos.y, pos.z, BlockState::AIR)?;
+        }
+        Ok(())
+    }
+}
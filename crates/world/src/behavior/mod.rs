@@ -0,0 +1,548 @@
+mod crop;
+mod diode;
+mod door;
+mod fluid;
+mod grass;
+mod lamp;
+mod leaves;
+mod melt;
+mod observer;
+mod piston;
+mod redstone_block;
+mod slab;
+mod stairs;
+mod switch;
+mod torch;
+mod trapdoor;
+mod wire;
+
+pub use crop::{CropBehavior, FarmlandBehavior};
+pub use diode::{ComparatorBehavior, RepeaterBehavior};
+pub use door::DoorBehavior;
+pub use fluid::FluidBehavior;
+pub use grass::SpreadingBehavior;
+pub use lamp::RedstoneLampBehavior;
+pub use leaves::LeavesBehavior;
+pub use melt::{IceBehavior, SnowLayerBehavior};
+pub use observer::ObserverBehavior;
+pub use piston::{PistonBehavior, PistonHeadBehavior};
+pub use redstone_block::RedstoneBlockBehavior;
+pub use slab::SlabBehavior;
+pub use stairs::StairsBehavior;
+pub use switch::{ButtonBehavior, LeverBehavior};
+pub use torch::RedstoneTorchBehavior;
+pub use trapdoor::TrapdoorBehavior;
+pub use wire::RedstoneWireBehavior;
+
+use crate::block::{block_registry, light_properties, BlockState};
+use crate::error::Result;
+use crate::fluid::{Fluid, FluidState};
+use crate::light::LightKind;
+use crate::pos::{BlockPos, Direction};
+use crate::shape::{block_shape, Shape};
+use crate::update::{UpdateFlags, UpdateHandler};
+use crate::world::World;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Blocks that a placed block replaces instead of going next to.
+const REPLACEABLE_BLOCKS: [&str; 17] = [
+    "minecraft:air",
+    "minecraft:cave_air",
+    "minecraft:void_air",
+    "minecraft:water",
+    "minecraft:lava",
+    "minecraft:bubble_column",
+    "minecraft:short_grass",
+    "minecraft:tall_grass",
+    "minecraft:fern",
+    "minecraft:large_fern",
+    "minecraft:dead_bush",
+    "minecraft:vine",
+    "minecraft:glow_lichen",
+    "minecraft:seagrass",
+    "minecraft:tall_seagrass",
+    "minecraft:fire",
+    "minecraft:soul_fire",
+];
+
+/// Where a player clicked a block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockHit {
+    pub pos: BlockPos,
+    /// The side of the block that was clicked.
+    pub face: Direction,
+    /// Where on the block the click landed, from 0 to 1 along each axis.
+    pub cursor: (f32, f32, f32),
+}
+
+/// A player placing a block against the block it clicked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaceContext {
+    pub hit: BlockHit,
+    /// Where the player looks, in degrees, where a yaw of 0 faces south and 90 west.
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl PlaceContext {
+    /// The horizontal direction the player looks in.
+    pub fn horizontal_facing(&self) -> Direction {
+        Direction::from_yaw(self.yaw)
+    }
+
+    /// The direction the player looks in the most, up or down when they look more up or down
+    /// than along any horizontal axis.
+    pub fn nearest_looking_direction(&self) -> Direction {
+        let (yaw, pitch) = (self.yaw.to_radians(), self.pitch.to_radians());
+        let horizontal = pitch.cos() * yaw.sin().abs().max(yaw.cos().abs());
+        if pitch.sin().abs() <= horizontal {
+            self.horizontal_facing()
+        } else if pitch > 0.0 {
+            Direction::Down
+        } else {
+            Direction::Up
+        }
+    }
+
+    /// Returns where the click landed relative to the block at the position, which is where the
+    /// placed block goes.
+    pub fn click_relative_to(&self, pos: BlockPos) -> (f64, f64, f64) {
+        let (x, y, z) = self.hit.cursor;
+        (
+            x as f64 + (self.hit.pos.x - pos.x) as f64,
+            y as f64 + (self.hit.pos.y - pos.y) as f64,
+            z as f64 + (self.hit.pos.z - pos.z) as f64,
+        )
+    }
+
+    /// Whether the block goes in the upper half of its position, because the player clicked the
+    /// bottom of a block or the upper half of a side.
+    pub fn is_upper_half(&self, pos: BlockPos) -> bool {
+        match self.hit.face {
+            Direction::Down => true,
+            Direction::Up => false,
+            _ => self.click_relative_to(pos).1 > 0.5,
+        }
+    }
+}
+
+/// Whether using a block did something, so the item in the player's hand isn't used as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UseResult {
+    Pass,
+    Consumed,
+}
+
+/// What a kind of block does when it's placed, used, broken or updated, what shape it has and what
+/// redstone power it gives off. Every method does what a plain block does by default.
+///
+/// The methods are called without any lock of the world held, so they can read and set blocks.
+pub trait BlockBehavior: Send + Sync {
+    /// Whether placing `placing` at `pos`, which holds `state`, replaces the block there instead
+    /// of failing or, if `pos` is the block clicked, going next to it.
+    fn can_be_replaced(
+        &self,
+        _pos: BlockPos,
+        state: BlockState,
+        _context: &PlaceContext,
+        _placing: BlockState,
+    ) -> bool {
+        is_replaceable(state)
+    }
+
+    /// Returns the state `state` is placed as at `pos`, such as stairs facing the way the player
+    /// looks, or `None` if it can't be placed there.
+    fn state_for_placement(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        _context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        waterlogged_for(world, pos, state).map(Some)
+    }
+
+    /// Called after the block was placed where `previous` was.
+    fn on_place(&self, _world: &World, _pos: BlockPos, _state: BlockState, _previous: BlockState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a player uses the block, such as opening a door.
+    fn on_use(&self, _world: &World, _pos: BlockPos, _state: BlockState, _hit: &BlockHit) -> Result<UseResult> {
+        Ok(UseResult::Pass)
+    }
+
+    /// Called after a player broke the block, once it was replaced with air.
+    fn on_break(&self, _world: &World, _pos: BlockPos, _state: BlockState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after the block was set where `previous` was, whether a player placed it, something
+    /// else set it or its state changed. Blocks set without notifying their neighbors don't call
+    /// it.
+    fn on_set(&self, _world: &World, _pos: BlockPos, _state: BlockState, _previous: BlockState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after the block was replaced by `new`, a different block, before `new` is told it
+    /// was set. Blocks replaced without notifying their neighbors don't call it.
+    fn on_replaced(&self, _world: &World, _pos: BlockPos, _state: BlockState, _new: BlockState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when the block at `from`, next to the block, changed.
+    fn on_neighbor_update(&self, _world: &World, _pos: BlockPos, _state: BlockState, _from: BlockPos) -> Result<()> {
+        Ok(())
+    }
+
+    /// Returns the state the block should have now that the block on its `direction` side is
+    /// `neighbor`. Returning air breaks the block.
+    fn update_shape(
+        &self,
+        _world: &World,
+        _pos: BlockPos,
+        state: BlockState,
+        _direction: Direction,
+        _neighbor: BlockState,
+    ) -> BlockState {
+        state
+    }
+
+    /// Called when a tick scheduled for the block comes.
+    fn on_scheduled_tick(&self, _world: &World, _pos: BlockPos, _state: BlockState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when the block is picked to tick at random.
+    fn on_random_tick(&self, _world: &World, _pos: BlockPos, _state: BlockState) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_collision_shape(&self, state: BlockState) -> Shape {
+        block_shape(state)
+    }
+
+    /// Whether the block gives off redstone power of its own, which wire connects to.
+    fn is_signal_source(&self, _state: BlockState) -> bool {
+        false
+    }
+
+    /// Returns the power the block gives the block next to it, where `direction` points from that
+    /// block to this one.
+    fn get_signal(&self, _world: &World, _pos: BlockPos, _state: BlockState, _direction: Direction) -> u8 {
+        0
+    }
+
+    /// Returns the power the block gives the block next to it strongly, which a conductor passes
+    /// on to the blocks around it.
+    fn get_direct_signal(&self, _world: &World, _pos: BlockPos, _state: BlockState, _direction: Direction) -> u8 {
+        0
+    }
+
+    /// Returns the power a comparator reading the block gives, or `None` if it has nothing to
+    /// read.
+    fn get_analog_output(&self, _world: &World, _pos: BlockPos, _state: BlockState) -> Option<u8> {
+        None
+    }
+
+    /// Whether the block passes the power it's strongly given on to the blocks around it: full
+    /// opaque blocks that don't give off power of their own.
+    fn is_redstone_conductor(&self, state: BlockState) -> bool {
+        self.get_collision_shape(state).is_full()
+            && light_properties(state).opacity == 15
+            && !self.is_signal_source(state)
+    }
+}
+
+/// The behavior of blocks that don't do anything on their own.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultBehavior;
+
+impl BlockBehavior for DefaultBehavior {}
+
+/// The behaviors of the blocks, keyed by block. Blocks without one behave like
+/// [`DefaultBehavior`].
+///
+/// The registry is the [`UpdateHandler`] of the world it's used with, so the world's updates and
+/// scheduled ticks reach the behaviors.
+#[derive(Default)]
+pub struct BlockBehaviors {
+    /// The behaviors keyed by the default state of their block.
+    behaviors: HashMap<BlockState, Arc<dyn BlockBehavior>>,
+}
+
+impl BlockBehaviors {
+    /// Creates a registry in which every block behaves like [`DefaultBehavior`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry with the behaviors of the doors, trapdoors, buttons, levers, slabs,
+    /// stairs, fluids, crops, farmland, grass, leaves, ice, snow and redstone components of the
+    /// installed block registry.
+    pub fn vanilla() -> Self {
+        let mut behaviors = Self::new();
+        let Some(registry) = block_registry() else {
+            return behaviors;
+        };
+        for name in registry.block_names() {
+            if let Some(behavior) = vanilla_behavior(name) {
+                behaviors.behaviors.insert(BlockState::default_of(name).unwrap(), behavior);
+            }
+        }
+        behaviors
+    }
+
+    /// Sets the behavior of the block, replacing the one it had. `minecraft:` is assumed when
+    /// the name has no namespace.
+    pub fn register(&mut self, block: &str, behavior: Arc<dyn BlockBehavior>) -> Result<()> {
+        self.behaviors.insert(BlockState::default_of(block)?, behavior);
+        Ok(())
+    }
+
+    /// Returns the behavior of the state's block.
+    pub fn get(&self, state: BlockState) -> &dyn BlockBehavior {
+        match self.behaviors.get(&state.default_state()) {
+            Some(behavior) => behavior.as_ref(),
+            None => &DefaultBehavior,
+        }
+    }
+
+    /// Places the block for a player, replacing the block clicked if it can be replaced or else
+    /// going next to it. Returns where the block went, or `None` if it couldn't be placed.
+    pub fn place(&self, world: &World, context: &PlaceContext, block: BlockState) -> Result<Option<BlockPos>> {
+        let hit = context.hit;
+        let clicked = world.get_block(hit.pos.x, hit.pos.y, hit.pos.z)?;
+        let pos = if self.get(clicked).can_be_replaced(hit.pos, clicked, context, block) {
+            hit.pos
+        } else {
+            let pos = hit.pos.relative(hit.face);
+            if !world.height().contains(pos.y) {
+                return Ok(None);
+            }
+            let there = world.get_block(pos.x, pos.y, pos.z)?;
+            if !self.get(there).can_be_replaced(pos, there, context, block) {
+                return Ok(None);
+            }
+            pos
+        };
+
+        let behavior = self.get(block);
+        let Some(state) = behavior.state_for_placement(world, pos, context, block)? else {
+            return Ok(None);
+        };
+        let previous = world.get_block(pos.x, pos.y, pos.z)?;
+        world.set_block(pos.x, pos.y, pos.z, state)?;
+        behavior.on_place(world, pos, state, previous)?;
+        Ok(Some(pos))
+    }
+
+    /// Uses the block a player clicked.
+    pub fn use_block(&self, world: &World, hit: &BlockHit) -> Result<UseResult> {
+        let state = world.get_block(hit.pos.x, hit.pos.y, hit.pos.z)?;
+        self.get(state).on_use(world, hit.pos, state, hit)
+    }
+
+    /// Breaks the block for a player. Returns false if there was only air. Waterlogged blocks
+    /// leave their water behind.
+    pub fn break_block(&self, world: &World, pos: BlockPos) -> Result<bool> {
+        let state = world.get_block(pos.x, pos.y, pos.z)?;
+        if state.is_air() {
+            return Ok(false);
+        }
+        if state.get("waterlogged") == Some("true") {
+            world.set_block(pos.x, pos.y, pos.z, Fluid::Water.source_block())?;
+            world.schedule_fluid_tick(pos, Fluid::Water, Fluid::Water.tick_delay());
+        } else {
+            world.set_block(pos.x, pos.y, pos.z, BlockState::AIR)?;
+        }
+        self.get(state).on_break(world, pos, state)?;
+        Ok(true)
+    }
+}
+
+impl UpdateHandler for BlockBehaviors {
+    fn block_changed(&self, world: &World, pos: BlockPos, previous: BlockState, state: BlockState) -> Result<()> {
+        if previous.default_state() != state.default_state() {
+            self.get(previous).on_replaced(world, pos, previous, state)?;
+        }
+        self.get(state).on_set(world, pos, state, previous)
+    }
+
+    fn neighbor_changed(&self, world: &World, pos: BlockPos, state: BlockState, from: BlockPos) -> Result<()> {
+        self.get(state).on_neighbor_update(world, pos, state, from)
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        neighbor: BlockState,
+    ) -> BlockState {
+        // The water in waterlogged blocks flows into the space around them.
+        if state.get("waterlogged") == Some("true") {
+            fluid::schedule(world, pos, state);
+        }
+        self.get(state).update_shape(world, pos, state, direction, neighbor)
+    }
+
+    fn scheduled_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        self.get(state).on_scheduled_tick(world, pos, state)
+    }
+
+    fn fluid_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        fluid::flow(world, pos, state)
+    }
+
+    fn random_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        self.get(state).on_random_tick(world, pos, state)
+    }
+
+    fn collision_shape(&self, state: BlockState) -> Shape {
+        self.get(state).get_collision_shape(state)
+    }
+
+    fn is_signal_source(&self, state: BlockState) -> bool {
+        self.get(state).is_signal_source(state)
+    }
+
+    fn signal(&self, world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        self.get(state).get_signal(world, pos, state, direction)
+    }
+
+    fn direct_signal(&self, world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        self.get(state).get_direct_signal(world, pos, state, direction)
+    }
+
+    fn analog_output(&self, world: &World, pos: BlockPos, state: BlockState) -> Option<u8> {
+        self.get(state).get_analog_output(world, pos, state)
+    }
+
+    fn is_redstone_conductor(&self, state: BlockState) -> bool {
+        self.get(state).is_redstone_conductor(state)
+    }
+}
+
+fn vanilla_behavior(name: &str) -> Option<Arc<dyn BlockBehavior>> {
+    let behavior: Arc<dyn BlockBehavior> = if name.ends_with("_trapdoor") {
+        Arc::new(TrapdoorBehavior)
+    } else if name.ends_with("_door") {
+        Arc::new(DoorBehavior)
+    } else if name.ends_with("_button") {
+        Arc::new(ButtonBehavior)
+    } else if name == "minecraft:lever" {
+        Arc::new(LeverBehavior)
+    } else if name.ends_with("_slab") {
+        Arc::new(SlabBehavior)
+    } else if name.ends_with("_stairs") {
+        Arc::new(StairsBehavior)
+    } else if name == Fluid::Water.block_name() || name == Fluid::Lava.block_name() {
+        Arc::new(FluidBehavior)
+    } else if ["minecraft:wheat", "minecraft:carrots", "minecraft:potatoes", "minecraft:beetroots"].contains(&name) {
+        Arc::new(CropBehavior)
+    } else if name == "minecraft:farmland" {
+        Arc::new(FarmlandBehavior)
+    } else if name == "minecraft:grass_block" || name == "minecraft:mycelium" {
+        Arc::new(SpreadingBehavior)
+    } else if name.ends_with("_leaves") {
+        Arc::new(LeavesBehavior)
+    } else if name == "minecraft:ice" {
+        Arc::new(IceBehavior)
+    } else if name == "minecraft:snow" {
+        Arc::new(SnowLayerBehavior)
+    } else if name == "minecraft:redstone_wire" {
+        Arc::new(RedstoneWireBehavior)
+    } else if name == "minecraft:repeater" {
+        Arc::new(RepeaterBehavior)
+    } else if name == "minecraft:comparator" {
+        Arc::new(ComparatorBehavior)
+    } else if name == "minecraft:redstone_torch" || name == "minecraft:redstone_wall_torch" {
+        Arc::new(RedstoneTorchBehavior::default())
+    } else if name == "minecraft:redstone_block" {
+        Arc::new(RedstoneBlockBehavior)
+    } else if name == "minecraft:redstone_lamp" {
+        Arc::new(RedstoneLampBehavior)
+    } else if name == "minecraft:piston" || name == "minecraft:sticky_piston" {
+        Arc::new(PistonBehavior)
+    } else if name == "minecraft:piston_head" {
+        Arc::new(PistonHeadBehavior)
+    } else if name == "minecraft:observer" {
+        Arc::new(ObserverBehavior)
+    } else {
+        return None;
+    };
+    Some(behavior)
+}
+
+/// Whether a placed block replaces the block instead of going next to it, like it does air and
+/// grass.
+pub fn is_replaceable(state: BlockState) -> bool {
+    if state.get("waterlogged").is_some() {
+        return false;
+    }
+    match state.name() {
+        Some(name) => REPLACEABLE_BLOCKS.contains(&name),
+        None => state.is_air(),
+    }
+}
+
+/// Whether the block is a water source, which waterlogs blocks placed in it.
+pub fn is_water_source(state: BlockState) -> bool {
+    state.is("water") && FluidState::of(state).is_some_and(FluidState::is_source)
+}
+
+/// Returns the state waterlogged if it can be and is placed in a water source.
+pub fn waterlogged_for(world: &World, pos: BlockPos, state: BlockState) -> Result<BlockState> {
+    if state.get("waterlogged").is_none() {
+        return Ok(state);
+    }
+    let source = is_water_source(world.get_block(pos.x, pos.y, pos.z)?);
+    Ok(with_value(state, "waterlogged", if source { "true" } else { "false" }))
+}
+
+/// Returns the block at the position if its chunk is loaded. Blocks don't reach into chunks that
+/// aren't when they tick.
+pub(crate) fn block_at(world: &World, pos: BlockPos) -> Option<BlockState> {
+    if !world.height().contains(pos.y) {
+        return None;
+    }
+    world.loaded_block(pos.x, pos.y, pos.z)
+}
+
+/// Whether the block at the position covers its whole face on the side, like
+/// [`World::is_face_sturdy`] but without waiting for chunks that aren't loaded, whose blocks have
+/// no faces.
+pub(crate) fn is_sturdy(world: &World, pos: BlockPos, direction: Direction) -> bool {
+    block_at(world, pos).is_some_and(|state| world.collision_shape(state).covers_face(direction))
+}
+
+/// Returns how bright the block is in daylight: the brighter of its sky light and block light.
+pub(crate) fn brightness(world: &World, pos: BlockPos) -> Result<u8> {
+    let sky = world.light_level(LightKind::Sky, pos.x, pos.y, pos.z)?;
+    Ok(sky.max(world.light_level(LightKind::Block, pos.x, pos.y, pos.z)?))
+}
+
+/// Returns the state with the property set, or unchanged if the block doesn't have it.
+pub(crate) fn with_value(state: BlockState, property: &str, value: &str) -> BlockState {
+    state.with(property, value).unwrap_or(state)
+}
+
+/// Returns the state with a `true` or `false` property flipped.
+pub(crate) fn toggled(state: BlockState, property: &str) -> BlockState {
+    with_value(state, property, if state.get(property) == Some("true") { "false" } else { "true" })
+}
+
+/// Reads a `facing` property.
+pub(crate) fn facing(state: BlockState) -> Option<Direction> {
+    state.get("facing").and_then(Direction::from_name)
+}
+
+/// Sets the block without notifying its neighbors, which is how blocks change their own state
+/// when used.
+pub(crate) fn set_own_state(world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+    world.set_block_with(pos.x, pos.y, pos.z, state, UpdateFlags::SEND_TO_CLIENTS | UpdateFlags::UPDATE_SHAPES)?;
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use super::{facing, set_own_state, with_value, BlockBehavior, PlaceContext};
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::pos::{BlockPos, Direction};
+use crate::world::World;
+
+/// How many ticks an observer takes to send a pulse, and how long the pulse is.
+const PULSE_TICKS: u64 = 2;
+
+/// Observers. They send a short pulse out of their back when the block they face changes.
+///
+/// Their `facing` is the side they watch.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ObserverBehavior;
+
+impl BlockBehavior for ObserverBehavior {
+    fn state_for_placement(
+        &self,
+        _world: &World,
+        _pos: BlockPos,
+        context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        // The observer watches the block the player looks at past it.
+        Ok(Some(with_value(state, "facing", context.nearest_looking_direction().name())))
+    }
+
+    fn on_set(&self, world: &World, pos: BlockPos, state: BlockState, previous: BlockState) -> Result<()> {
+        if previous.default_state() == state.default_state() {
+            return Ok(());
+        }
+        // Observers moved while powered would stay on forever.
+        if is_powered(state) && !world.is_tick_scheduled(pos, state) {
+            let state = with_value(state, "powered", "false");
+            set_own_state(world, pos, state)?;
+            notify_back(world, pos, state)?;
+        }
+        Ok(())
+    }
+
+    fn on_replaced(&self, world: &World, pos: BlockPos, state: BlockState, _new: BlockState) -> Result<()> {
+        if is_powered(state) {
+            notify_back(world, pos, state)?;
+        }
+        Ok(())
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        _neighbor: BlockState,
+    ) -> BlockState {
+        if facing(state) == Some(direction) && !is_powered(state) && !world.is_tick_scheduled(pos, state) {
+            world.schedule_tick(pos, state, PULSE_TICKS);
+        }
+        state
+    }
+
+    fn on_scheduled_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        let state = if is_powered(state) {
+            with_value(state, "powered", "false")
+        } else {
+            world.schedule_tick(pos, state, PULSE_TICKS);
+            with_value(state, "powered", "true")
+        };
+        set_own_state(world, pos, state)?;
+        notify_back(world, pos, state)
+    }
+
+    fn is_signal_source(&self, _state: BlockState) -> bool {
+        true
+    }
+
+    fn get_signal(&self, _world: &World, _pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        if is_powered(state) && facing(state) == Some(direction) {
+            15
+        } else {
+            0
+        }
+    }
+
+    fn get_direct_signal(&self, world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        self.get_signal(world, pos, state, direction)
+    }
+}
+
+fn is_powered(state: BlockState) -> bool {
+    state.get("powered") == Some("true")
+}
+
+/// Tells the block behind the observer, and the blocks around it, that the observer changed.
+fn notify_back(world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+    let Some(watched) = facing(state) else {
+        return Ok(());
+    };
+    let back = pos.relative(watched.opposite());
+    world.notify_neighbor(back, pos)?;
+    world.notify_neighbors(back)
+}
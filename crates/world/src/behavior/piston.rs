@@ -0,0 +1,278 @@
+use super::{block_at, facing, with_value, BlockBehavior, PlaceContext};
+use crate::block::BlockState;
+use crate::block_entity::block_entity_kind;
+use crate::error::Result;
+use crate::pos::{BlockPos, Direction};
+use crate::shape::{Aabb, Shape};
+use crate::world::World;
+
+/// The most blocks a piston pushes.
+const PUSH_LIMIT: usize = 12;
+/// Blocks that pistons can't move.
+const IMMOVABLE_BLOCKS: [&str; 12] = [
+    "minecraft:barrier",
+    "minecraft:bedrock",
+    "minecraft:crying_obsidian",
+    "minecraft:end_gateway",
+    "minecraft:end_portal",
+    "minecraft:end_portal_frame",
+    "minecraft:moving_piston",
+    "minecraft:obsidian",
+    "minecraft:piston_head",
+    "minecraft:reinforced_deepslate",
+    "minecraft:respawn_anchor",
+    "minecraft:structure_void",
+];
+
+/// Pistons and sticky pistons. They push up to twelve blocks in front of them when powered,
+/// from any side but their front or from above the block above them, and sticky pistons pull
+/// the block in front of their head back when they retract.
+///
+/// The blocks move at once in the tick after the piston is powered, instead of sliding over two
+/// ticks. Their `facing` is the way they push.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PistonBehavior;
+
+/// The heads of extended pistons. A head breaks its piston when it's broken.
+///
+/// Their `facing` is the way their piston pushes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PistonHeadBehavior;
+
+/// The blocks a piston moves when it extends.
+#[derive(Debug)]
+struct Push {
+    /// The blocks pushed along, nearest first.
+    moved: Vec<(BlockPos, BlockState)>,
+    /// The block the pushed blocks break, if any.
+    destroyed: Option<BlockPos>,
+}
+
+/// What a block does when a piston pushes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PushReaction {
+    /// The block moves along.
+    Normal,
+    /// The block breaks, like plants and redstone wire.
+    Destroy,
+    /// The block stops the piston.
+    Block,
+}
+
+impl BlockBehavior for PistonBehavior {
+    fn state_for_placement(
+        &self,
+        _world: &World,
+        _pos: BlockPos,
+        context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        // The piston pushes towards the player.
+        Ok(Some(with_value(state, "facing", context.nearest_looking_direction().opposite().name())))
+    }
+
+    fn on_set(&self, world: &World, pos: BlockPos, state: BlockState, previous: BlockState) -> Result<()> {
+        if previous.default_state() != state.default_state() {
+            check_extend(world, pos, state);
+        }
+        Ok(())
+    }
+
+    fn on_neighbor_update(&self, world: &World, pos: BlockPos, state: BlockState, _from: BlockPos) -> Result<()> {
+        check_extend(world, pos, state);
+        Ok(())
+    }
+
+    fn on_scheduled_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        let Some(push) = facing(state) else {
+            return Ok(());
+        };
+        let powered = has_neighbor_signal(world, pos, push);
+        if powered && !is_extended(state) {
+            extend(world, pos, state, push)
+        } else if !powered && is_extended(state) {
+            retract(world, pos, state, push)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn get_collision_shape(&self, state: BlockState) -> Shape {
+        match facing(state) {
+            Some(push) if is_extended(state) => Shape::from_box(Aabb::side(push.opposite(), 0.75)),
+            _ => Shape::full(),
+        }
+    }
+
+    fn is_redstone_conductor(&self, _state: BlockState) -> bool {
+        false
+    }
+}
+
+impl BlockBehavior for PistonHeadBehavior {
+    fn on_replaced(&self, world: &World, pos: BlockPos, state: BlockState, _new: BlockState) -> Result<()> {
+        let Some(push) = facing(state) else {
+            return Ok(());
+        };
+        let base = pos.relative(push.opposite());
+        if block_at(world, base).is_some_and(|base| is_fitting_base(state, base)) {
+            world.set_block(base.x, base.y, base.z, BlockState::AIR)?;
+        }
+        Ok(())
+    }
+
+    fn update_shape(
+        &self,
+        _world: &World,
+        _pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        neighbor: BlockState,
+    ) -> BlockState {
+        if facing(state).map(Direction::opposite) == Some(direction) && !is_fitting_base(state, neighbor) {
+            BlockState::AIR
+        } else {
+            state
+        }
+    }
+
+    fn get_collision_shape(&self, state: BlockState) -> Shape {
+        let Some(push) = facing(state) else {
+            return Shape::full();
+        };
+        // A plate on the front and an arm down the middle of the rest.
+        let rest = Aabb::side(push.opposite(), 0.75);
+        let (low, high) = (0.375, 0.625);
+        let arm = match push {
+            Direction::Down | Direction::Up => Aabb::new(low, rest.min_y, low, high, rest.max_y, high),
+            Direction::North | Direction::South => Aabb::new(low, low, rest.min_z, high, high, rest.max_z),
+            Direction::West | Direction::East => Aabb::new(rest.min_x, low, low, rest.max_x, high, high),
+        };
+        Shape::from_boxes(vec![Aabb::side(push, 0.25), arm])
+    }
+}
+
+fn is_extended(state: BlockState) -> bool {
+    state.get("extended") == Some("true")
+}
+
+fn is_sticky(state: BlockState) -> bool {
+    state.is("sticky_piston")
+}
+
+/// Whether the block is the extended piston the head belongs to.
+fn is_fitting_base(head: BlockState, base: BlockState) -> bool {
+    let kind = if head.get("type") == Some("sticky") { "sticky_piston" } else { "piston" };
+    base.is(kind) && is_extended(base) && facing(base) == facing(head)
+}
+
+/// Whether the piston is powered: by any block around it but the one in front of it, or by a
+/// block around the block above it.
+fn has_neighbor_signal(world: &World, pos: BlockPos, push: Direction) -> bool {
+    let mut around = Direction::ALL.into_iter().filter(|&direction| direction != push);
+    if around.any(|direction| world.signal(pos.relative(direction), direction) > 0) {
+        return true;
+    }
+    let above = pos.relative(Direction::Up);
+    Direction::ALL
+        .into_iter()
+        .filter(|&direction| direction != Direction::Down)
+        .any(|direction| world.signal(above.relative(direction), direction) > 0)
+}
+
+/// Schedules the piston to move if whether it's powered doesn't match whether it's extended.
+fn check_extend(world: &World, pos: BlockPos, state: BlockState) {
+    let Some(push) = facing(state) else {
+        return;
+    };
+    if has_neighbor_signal(world, pos, push) != is_extended(state) && !world.is_tick_scheduled(pos, state) {
+        world.schedule_tick(pos, state, 1);
+    }
+}
+
+/// Returns what the block at the position does when a piston pushes or pulls it in `direction`.
+fn push_reaction(world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> PushReaction {
+    let height = world.height();
+    let at_edge = match direction {
+        Direction::Down => pos.y == height.min_y(),
+        Direction::Up => pos.y == height.max_y(),
+        _ => false,
+    };
+    let name = state.name().unwrap_or_default();
+    if at_edge
+        || IMMOVABLE_BLOCKS.contains(&name)
+        || block_entity_kind(state).is_some()
+        || ((state.is("piston") || state.is("sticky_piston")) && is_extended(state))
+    {
+        PushReaction::Block
+    } else if world.collision_shape(state).is_empty() || name.ends_with("_door") {
+        PushReaction::Destroy
+    } else {
+        PushReaction::Normal
+    }
+}
+
+/// Returns the blocks the piston would move, or `None` if something stops it.
+fn resolve_push(world: &World, pos: BlockPos, push: Direction) -> Option<Push> {
+    let mut moved = Vec::new();
+    let mut at = pos.relative(push);
+    loop {
+        if !world.height().contains(at.y) {
+            return None;
+        }
+        let state = block_at(world, at)?;
+        if state.is_air() {
+            return Some(Push { moved, destroyed: None });
+        }
+        match push_reaction(world, at, state, push) {
+            PushReaction::Block => return None,
+            PushReaction::Destroy => return Some(Push { moved, destroyed: Some(at) }),
+            PushReaction::Normal if moved.len() == PUSH_LIMIT => return None,
+            PushReaction::Normal => moved.push((at, state)),
+        }
+        at = at.relative(push);
+    }
+}
+
+/// Pushes the blocks in front of the piston one block along and puts its head in front of it.
+fn extend(world: &World, pos: BlockPos, state: BlockState, push: Direction) -> Result<()> {
+    let Some(Push { moved, destroyed }) = resolve_push(world, pos, push) else {
+        return Ok(());
+    };
+    if let Some(at) = destroyed {
+        world.set_block(at.x, at.y, at.z, BlockState::AIR)?;
+    }
+    // The farthest block moves first, so that no block lands on one that didn't move yet.
+    for &(at, block) in moved.iter().rev() {
+        let to = at.relative(push);
+        world.set_block(to.x, to.y, to.z, block)?;
+    }
+    world.set_block(pos.x, pos.y, pos.z, with_value(state, "extended", "true"))?;
+    let head = BlockState::default_of("piston_head")?;
+    let head = with_value(head, "facing", push.name());
+    let head = with_value(head, "type", if is_sticky(state) { "sticky" } else { "normal" });
+    let at = pos.relative(push);
+    world.set_block(at.x, at.y, at.z, head)
+}
+
+/// Pulls the piston's head back in, and the block in front of it if the piston is sticky.
+fn retract(world: &World, pos: BlockPos, state: BlockState, push: Direction) -> Result<()> {
+    world.set_block(pos.x, pos.y, pos.z, with_value(state, "extended", "false"))?;
+    let head = pos.relative(push);
+    let pulled = head.relative(push);
+    let pulled_state = block_at(world, pulled)
+        .filter(|state| !state.is_air())
+        .filter(|&block| push_reaction(world, pulled, block, push.opposite()) == PushReaction::Normal);
+    match pulled_state {
+        Some(block) if is_sticky(state) => {
+            world.set_block(pulled.x, pulled.y, pulled.z, BlockState::AIR)?;
+            world.set_block(head.x, head.y, head.z, block)
+        }
+        _ => {
+            if block_at(world, head).is_some_and(|block| block.is("piston_head")) {
+                world.set_block(head.x, head.y, head.z, BlockState::AIR)?;
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use super::BlockBehavior;
+use crate::block::BlockState;
+use crate::pos::{BlockPos, Direction};
+use crate::world::World;
+
+/// Blocks of redstone. They power the blocks around them fully, but only weakly, so the power
+/// doesn't pass through conductors.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedstoneBlockBehavior;
+
+impl BlockBehavior for RedstoneBlockBehavior {
+    fn is_signal_source(&self, _state: BlockState) -> bool {
+        true
+    }
+
+    fn get_signal(&self, _world: &World, _pos: BlockPos, _state: BlockState, _direction: Direction) -> u8 {
+        15
+    }
+}
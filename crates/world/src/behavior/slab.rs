@@ -0,0 +1,54 @@
+use super::{waterlogged_for, with_value, BlockBehavior, PlaceContext};
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::pos::{BlockPos, Direction};
+use crate::shape::{Aabb, Shape};
+use crate::world::World;
+
+/// Slabs go in the half of the block the player clicked, and a second slab of the same kind
+/// placed into the other half makes a double slab.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SlabBehavior;
+
+impl BlockBehavior for SlabBehavior {
+    fn can_be_replaced(&self, pos: BlockPos, state: BlockState, context: &PlaceContext, placing: BlockState) -> bool {
+        let kind = state.get("type");
+        if kind == Some("double") || placing.default_state() != state.default_state() {
+            return false;
+        }
+        if pos != context.hit.pos {
+            return true;
+        }
+        // Clicking a slab places the second one into it only from the side of its free half.
+        let face = context.hit.face;
+        let upper = context.click_relative_to(pos).1 > 0.5;
+        match kind {
+            Some("bottom") => face == Direction::Up || upper && face.is_horizontal(),
+            _ => face == Direction::Down || !upper && face.is_horizontal(),
+        }
+    }
+
+    fn state_for_placement(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        let existing = world.get_block(pos.x, pos.y, pos.z)?;
+        if existing.default_state() == state.default_state() {
+            let double = with_value(existing, "type", "double");
+            return Ok(Some(with_value(double, "waterlogged", "false")));
+        }
+        let half = if context.is_upper_half(pos) { "top" } else { "bottom" };
+        Ok(Some(waterlogged_for(world, pos, with_value(state, "type", half))?))
+    }
+
+    fn get_collision_shape(&self, state: BlockState) -> Shape {
+        match state.get("type") {
+            Some("top") => Shape::from_box(Aabb::side(Direction::Up, 0.5)),
+            Some("double") => Shape::full(),
+            _ => Shape::from_box(Aabb::side(Direction::Down, 0.5)),
+        }
+    }
+}
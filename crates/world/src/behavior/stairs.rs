@@ -0,0 +1,115 @@
+use super::{facing, waterlogged_for, with_value, BlockBehavior, PlaceContext};
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::pos::{BlockPos, Direction};
+use crate::shape::{Aabb, Shape};
+use crate::world::World;
+
+/// Stairs rise towards the way the player looks, sit upside down when placed against the upper
+/// half of a block, and turn into corners next to other stairs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StairsBehavior;
+
+impl BlockBehavior for StairsBehavior {
+    fn state_for_placement(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        let half = if context.is_upper_half(pos) { "top" } else { "bottom" };
+        let state = with_value(state, "facing", context.horizontal_facing().name());
+        let state = with_value(state, "half", half);
+        let state = with_value(state, "shape", stairs_shape(world, pos, state));
+        Ok(Some(waterlogged_for(world, pos, state)?))
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        _neighbor: BlockState,
+    ) -> BlockState {
+        if !direction.is_horizontal() {
+            return state;
+        }
+        with_value(state, "shape", stairs_shape(world, pos, state))
+    }
+
+    fn get_collision_shape(&self, state: BlockState) -> Shape {
+        let Some(facing) = facing(state) else {
+            return Shape::full();
+        };
+        let top = state.get("half") == Some("top");
+        let (base, step) = if top { (0.5, 0.0) } else { (0.0, 0.5) };
+        let mut boxes = vec![Aabb::new(0.0, base, 0.0, 1.0, base + 0.5, 1.0)];
+
+        // The step covers the quarters of the block on the side it faces, one more for an inner
+        // corner and one fewer for an outer corner.
+        let (left, right) = (facing.counter_clockwise(), facing.clockwise());
+        for x_side in [Direction::West, Direction::East] {
+            for z_side in [Direction::North, Direction::South] {
+                let on = |side: Direction| x_side == side || z_side == side;
+                let covered = match state.get("shape") {
+                    Some("inner_left") => on(facing) || on(left),
+                    Some("inner_right") => on(facing) || on(right),
+                    Some("outer_left") => on(facing) && on(left),
+                    Some("outer_right") => on(facing) && on(right),
+                    _ => on(facing),
+                };
+                if covered {
+                    let x = if x_side == Direction::West { 0.0 } else { 0.5 };
+                    let z = if z_side == Direction::North { 0.0 } else { 0.5 };
+                    boxes.push(Aabb::new(x, step, z, x + 0.5, step + 0.5, z + 0.5));
+                }
+            }
+        }
+        Shape::from_boxes(boxes)
+    }
+}
+
+fn is_stairs(state: BlockState) -> bool {
+    state.name().is_some_and(|name| name.ends_with("_stairs"))
+}
+
+/// Returns the loaded stairs on the side of the position, if there are any.
+fn stairs_at(world: &World, pos: BlockPos, direction: Direction) -> Option<BlockState> {
+    let pos = pos.relative(direction);
+    world.loaded_block(pos.x, pos.y, pos.z).filter(|state| is_stairs(*state))
+}
+
+/// Whether the direction is a quarter turn from the other one.
+fn is_sideways(direction: Direction, other: Direction) -> bool {
+    direction.is_horizontal() && direction != other && direction != other.opposite()
+}
+
+/// Returns the shape stairs take from the stairs in front of and behind them: stairs in front
+/// turned sideways make an outer corner, stairs behind turned sideways an inner one.
+fn stairs_shape(world: &World, pos: BlockPos, state: BlockState) -> &'static str {
+    let Some(facing) = facing(state) else {
+        return "straight";
+    };
+    let half = state.get("half");
+    let turned = |side: Direction| {
+        stairs_at(world, pos, side)
+            .filter(|stairs| stairs.get("half") == half)
+            .and_then(self::facing)
+            .filter(|turned| is_sideways(*turned, facing))
+    };
+    // Stairs can't turn towards a side where the same stairs continue them.
+    let can_turn = |side: Direction| {
+        stairs_at(world, pos, side)
+            .is_none_or(|stairs| stairs.get("facing") != Some(facing.name()) || stairs.get("half") != half)
+    };
+
+    if let Some(front) = turned(facing).filter(|front| can_turn(front.opposite())) {
+        return if front == facing.counter_clockwise() { "outer_left" } else { "outer_right" };
+    }
+    if let Some(back) = turned(facing.opposite()).filter(|back| can_turn(*back)) {
+        return if back == facing.counter_clockwise() { "inner_left" } else { "inner_right" };
+    }
+    "straight"
+}
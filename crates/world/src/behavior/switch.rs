@@ -0,0 +1,214 @@
+use super::{facing, toggled, with_value, BlockBehavior, BlockHit, PlaceContext, UseResult};
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::pos::{BlockPos, Direction};
+use crate::shape::Shape;
+use crate::world::World;
+
+/// How many ticks stone buttons stay pressed.
+const STONE_BUTTON_TICKS: u64 = 20;
+/// How many ticks the other buttons stay pressed.
+const WOODEN_BUTTON_TICKS: u64 = 30;
+
+/// Buttons hang on the side of the block the player clicked and stay pressed for a moment, during
+/// which they power the blocks around them and strongly the block they hang on. They break when
+/// that block goes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ButtonBehavior;
+
+/// Levers hang on the side of the block the player clicked and power blocks like buttons, and stay
+/// on until they are used again.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LeverBehavior;
+
+impl BlockBehavior for ButtonBehavior {
+    fn state_for_placement(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        attached_placement(world, pos, context, state)
+    }
+
+    fn on_use(&self, world: &World, pos: BlockPos, state: BlockState, _hit: &BlockHit) -> Result<UseResult> {
+        if state.get("powered") == Some("true") {
+            return Ok(UseResult::Consumed);
+        }
+        set_powered(world, pos, with_value(state, "powered", "true"))?;
+        let ticks = match state.name() {
+            Some("minecraft:stone_button" | "minecraft:polished_blackstone_button") => STONE_BUTTON_TICKS,
+            _ => WOODEN_BUTTON_TICKS,
+        };
+        world.schedule_tick(pos, state, ticks);
+        Ok(UseResult::Consumed)
+    }
+
+    fn on_break(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        if state.get("powered") != Some("true") {
+            return Ok(());
+        }
+        notify_support(world, pos, state)
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        _neighbor: BlockState,
+    ) -> BlockState {
+        attached_shape(world, pos, state, direction)
+    }
+
+    fn on_scheduled_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        if state.get("powered") == Some("true") {
+            set_powered(world, pos, with_value(state, "powered", "false"))?;
+        }
+        Ok(())
+    }
+
+    fn get_collision_shape(&self, _state: BlockState) -> Shape {
+        Shape::empty()
+    }
+
+    fn is_signal_source(&self, _state: BlockState) -> bool {
+        true
+    }
+
+    fn get_signal(&self, _world: &World, _pos: BlockPos, state: BlockState, _direction: Direction) -> u8 {
+        signal(state)
+    }
+
+    fn get_direct_signal(&self, _world: &World, _pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        direct_signal(state, direction)
+    }
+}
+
+impl BlockBehavior for LeverBehavior {
+    fn state_for_placement(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        attached_placement(world, pos, context, state)
+    }
+
+    fn on_use(&self, world: &World, pos: BlockPos, state: BlockState, _hit: &BlockHit) -> Result<UseResult> {
+        set_powered(world, pos, toggled(state, "powered"))?;
+        Ok(UseResult::Consumed)
+    }
+
+    fn on_break(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        if state.get("powered") != Some("true") {
+            return Ok(());
+        }
+        notify_support(world, pos, state)
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        _neighbor: BlockState,
+    ) -> BlockState {
+        attached_shape(world, pos, state, direction)
+    }
+
+    fn get_collision_shape(&self, _state: BlockState) -> Shape {
+        Shape::empty()
+    }
+
+    fn is_signal_source(&self, _state: BlockState) -> bool {
+        true
+    }
+
+    fn get_signal(&self, _world: &World, _pos: BlockPos, state: BlockState, _direction: Direction) -> u8 {
+        signal(state)
+    }
+
+    fn get_direct_signal(&self, _world: &World, _pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        direct_signal(state, direction)
+    }
+}
+
+/// Returns the side of a button or lever the block it hangs on is on.
+fn support_side(state: BlockState) -> Option<Direction> {
+    match state.get("face")? {
+        "floor" => Some(Direction::Down),
+        "ceiling" => Some(Direction::Up),
+        _ => facing(state).map(Direction::opposite),
+    }
+}
+
+/// Returns the power a button or lever gives the blocks around it: full power while it's on.
+fn signal(state: BlockState) -> u8 {
+    if state.get("powered") == Some("true") {
+        15
+    } else {
+        0
+    }
+}
+
+/// Returns the power a button or lever strongly gives the block next to it, which is only the
+/// block it hangs on.
+fn direct_signal(state: BlockState, direction: Direction) -> u8 {
+    match support_side(state) {
+        Some(side) if side.opposite() == direction => signal(state),
+        _ => 0,
+    }
+}
+
+/// Hangs the block on the side of the block the player clicked. On a floor or ceiling it turns
+/// the way the player looks.
+fn attached_placement(
+    world: &World,
+    pos: BlockPos,
+    context: &PlaceContext,
+    state: BlockState,
+) -> Result<Option<BlockState>> {
+    let face = context.hit.face;
+    let (attach, facing) = match face {
+        Direction::Up => ("floor", context.horizontal_facing()),
+        Direction::Down => ("ceiling", context.horizontal_facing()),
+        side => ("wall", side),
+    };
+    let state = with_value(with_value(state, "face", attach), "facing", facing.name());
+    let side = face.opposite();
+    if !world.is_face_sturdy(pos.relative(side), side.opposite())? {
+        return Ok(None);
+    }
+    Ok(Some(state))
+}
+
+/// Breaks the block when the block it hangs on can no longer hold it.
+fn attached_shape(world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> BlockState {
+    if support_side(state) != Some(direction) {
+        return state;
+    }
+    match world.is_face_sturdy(pos.relative(direction), direction.opposite()) {
+        Ok(false) => BlockState::AIR,
+        _ => state,
+    }
+}
+
+/// Sets a button or lever on or off, updating the blocks around it and around the block it hangs
+/// on, which it powers as well.
+fn set_powered(world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+    world.set_block(pos.x, pos.y, pos.z, state)?;
+    notify_support(world, pos, state)
+}
+
+/// Tells the blocks around the block a button or lever hangs on that its power changed.
+fn notify_support(world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+    match support_side(state) {
+        Some(side) => world.notify_neighbors(pos.relative(side)),
+        None => Ok(()),
+    }
+}
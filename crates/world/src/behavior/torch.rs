@@ -0,0 +1,156 @@
+use super::{facing, is_sturdy, with_value, BlockBehavior, PlaceContext};
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::pos::{BlockPos, Direction};
+use crate::world::World;
+use std::sync::Mutex;
+
+/// How many ticks a torch takes to turn on or off.
+const TORCH_DELAY: u64 = 2;
+/// How many times a torch can turn off within [`TOGGLE_WINDOW`] ticks before it burns out.
+const MAX_TOGGLES: usize = 8;
+/// The ticks the turns of a torch count towards burning it out.
+const TOGGLE_WINDOW: u64 = 60;
+/// How many ticks a burnt out torch waits before it checks whether it can turn on again.
+const BURNOUT_DELAY: u64 = 160;
+
+/// Redstone torches, standing or on a wall. They give off power unless the block they're on is
+/// powered, and strongly power the block above them.
+///
+/// A torch that turns off too often in a short time burns out, and stays off until it is
+/// updated once it cooled down.
+#[derive(Debug, Default)]
+pub struct RedstoneTorchBehavior {
+    /// Where torches turned off recently, with the tick they did.
+    toggles: Mutex<Vec<(BlockPos, u64)>>,
+}
+
+impl RedstoneTorchBehavior {
+    /// Counts a torch turning off at the position if `add`, and returns whether it turned off
+    /// too often to turn on again.
+    fn toggled_too_often(&self, world: &World, pos: BlockPos, add: bool) -> bool {
+        let mut toggles = self.toggles.lock().unwrap();
+        if add {
+            toggles.push((pos, world.current_tick()));
+        }
+        toggles.iter().filter(|(toggled, _)| *toggled == pos).count() >= MAX_TOGGLES
+    }
+}
+
+impl BlockBehavior for RedstoneTorchBehavior {
+    fn state_for_placement(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        // Torches go on the side of the block clicked, and stand on the floor otherwise.
+        let face = context.hit.face;
+        if face.is_horizontal() && is_sturdy(world, pos.relative(face.opposite()), face) {
+            let wall = BlockState::default_of("redstone_wall_torch")?;
+            return Ok(Some(with_value(wall, "facing", face.name())));
+        }
+        let standing = if state.is("redstone_wall_torch") { BlockState::default_of("redstone_torch")? } else { state };
+        Ok(can_survive(world, pos, standing).then_some(standing))
+    }
+
+    fn on_set(&self, world: &World, pos: BlockPos, _state: BlockState, _previous: BlockState) -> Result<()> {
+        notify_around(world, pos)
+    }
+
+    fn on_replaced(&self, world: &World, pos: BlockPos, _state: BlockState, _new: BlockState) -> Result<()> {
+        notify_around(world, pos)
+    }
+
+    fn on_neighbor_update(&self, world: &World, pos: BlockPos, state: BlockState, _from: BlockPos) -> Result<()> {
+        if is_lit(state) == has_input(world, pos, state) && !world.is_tick_scheduled(pos, state) {
+            world.schedule_tick(pos, state, TORCH_DELAY);
+        }
+        Ok(())
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        _neighbor: BlockState,
+    ) -> BlockState {
+        if Some(direction) == support_side(state) && !can_survive(world, pos, state) {
+            BlockState::AIR
+        } else {
+            state
+        }
+    }
+
+    fn on_scheduled_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        let input = has_input(world, pos, state);
+        let now = world.current_tick();
+        self.toggles.lock().unwrap().retain(|&(_, tick)| now.saturating_sub(tick) <= TOGGLE_WINDOW);
+        if is_lit(state) {
+            if input {
+                world.set_block(pos.x, pos.y, pos.z, with_value(state, "lit", "false"))?;
+                if self.toggled_too_often(world, pos, true) {
+                    world.schedule_tick(pos, state, BURNOUT_DELAY);
+                }
+            }
+        } else if !input && !self.toggled_too_often(world, pos, false) {
+            world.set_block(pos.x, pos.y, pos.z, with_value(state, "lit", "true"))?;
+        }
+        Ok(())
+    }
+
+    fn is_signal_source(&self, _state: BlockState) -> bool {
+        true
+    }
+
+    fn get_signal(&self, _world: &World, _pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        // Torches don't power the block they're on.
+        if is_lit(state) && support_side(state) != Some(direction.opposite()) {
+            15
+        } else {
+            0
+        }
+    }
+
+    fn get_direct_signal(&self, world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        if direction == Direction::Down {
+            self.get_signal(world, pos, state, direction)
+        } else {
+            0
+        }
+    }
+}
+
+fn is_lit(state: BlockState) -> bool {
+    state.get("lit") == Some("true")
+}
+
+/// Returns the side of the torch the block it's on is on.
+fn support_side(state: BlockState) -> Option<Direction> {
+    if state.is("redstone_wall_torch") {
+        facing(state).map(Direction::opposite)
+    } else {
+        Some(Direction::Down)
+    }
+}
+
+fn can_survive(world: &World, pos: BlockPos, state: BlockState) -> bool {
+    support_side(state).is_some_and(|side| is_sturdy(world, pos.relative(side), side.opposite()))
+}
+
+/// Whether the block the torch is on is powered.
+fn has_input(world: &World, pos: BlockPos, state: BlockState) -> bool {
+    support_side(state).is_some_and(|side| world.signal(pos.relative(side), side) > 0)
+}
+
+/// Tells the blocks around the torch, and the blocks around those, that it changed, since it
+/// powers the block above it through that block.
+fn notify_around(world: &World, pos: BlockPos) -> Result<()> {
+    for direction in Direction::ALL {
+        world.notify_neighbors(pos.relative(direction))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use super::door::PANEL_THICKNESS;
+use super::{
+    facing, set_own_state, toggled, waterlogged_for, with_value, BlockBehavior, BlockHit, PlaceContext, UseResult,
+};
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::pos::{BlockPos, Direction};
+use crate::shape::{Aabb, Shape};
+use crate::world::World;
+
+/// Trapdoors placed against the side of a block hinge on it, in the half that was clicked.
+/// Otherwise they hinge on the side away from the player, at the top of the block when placed
+/// against a ceiling. Iron trapdoors don't open by hand.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrapdoorBehavior;
+
+impl BlockBehavior for TrapdoorBehavior {
+    fn state_for_placement(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        let face = context.hit.face;
+        let (facing, top) = if pos != context.hit.pos && face.is_horizontal() {
+            (face, context.click_relative_to(pos).1 > 0.5)
+        } else {
+            (context.horizontal_facing().opposite(), face != Direction::Up)
+        };
+        let state = with_value(state, "facing", facing.name());
+        let state = with_value(state, "half", if top { "top" } else { "bottom" });
+        Ok(Some(waterlogged_for(world, pos, state)?))
+    }
+
+    fn on_use(&self, world: &World, pos: BlockPos, state: BlockState, _hit: &BlockHit) -> Result<UseResult> {
+        if state.is("iron_trapdoor") {
+            return Ok(UseResult::Pass);
+        }
+        set_own_state(world, pos, toggled(state, "open"))?;
+        Ok(UseResult::Consumed)
+    }
+
+    fn get_collision_shape(&self, state: BlockState) -> Shape {
+        let side = match (state.get("open"), facing(state)) {
+            (Some("true"), Some(facing)) => facing.opposite(),
+            _ if state.get("half") == Some("top") => Direction::Up,
+            _ => Direction::Down,
+        };
+        Shape::from_box(Aabb::side(side, PANEL_THICKNESS))
+    }
+}
@@ -0,0 +1,343 @@
+use super::{block_at, facing, is_sturdy, set_own_state, with_value, BlockBehavior, BlockHit, PlaceContext, UseResult};
+use crate::block::BlockState;
+use crate::error::Result;
+use crate::pos::{BlockPos, Direction};
+use crate::shape::Shape;
+use crate::world::World;
+use std::cell::Cell;
+
+thread_local! {
+    /// Whether wire gives off power. It doesn't while wire works out its own power, so that it
+    /// only counts the power of other wire once it decayed.
+    static WIRE_SIGNALS: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Redstone wire. It carries power, one level weaker for every block it goes, and connects to
+/// the wire and the power components around it, up and down a block as well.
+///
+/// Wire powers the blocks it points into, and strongly the block under it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedstoneWireBehavior;
+
+impl BlockBehavior for RedstoneWireBehavior {
+    fn state_for_placement(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        _context: &PlaceContext,
+        state: BlockState,
+    ) -> Result<Option<BlockState>> {
+        if !can_survive_on(world, pos.relative(Direction::Down)) {
+            return Ok(None);
+        }
+        Ok(Some(connection_state(world, pos, cross(state))))
+    }
+
+    fn on_use(&self, world: &World, pos: BlockPos, state: BlockState, _hit: &BlockHit) -> Result<UseResult> {
+        // A lone wire switches between pointing every way and being a dot.
+        if !is_cross(state) && !is_dot(state) {
+            return Ok(UseResult::Pass);
+        }
+        let flipped = if is_cross(state) {
+            with_power(BlockState::default_of("redstone_wire")?, power(state))
+        } else {
+            cross(state)
+        };
+        let flipped = connection_state(world, pos, flipped);
+        if flipped != state {
+            world.set_block(pos.x, pos.y, pos.z, flipped)?;
+            for direction in Direction::HORIZONTAL {
+                world.notify_neighbors(pos.relative(direction))?;
+            }
+        }
+        Ok(UseResult::Consumed)
+    }
+
+    fn on_set(&self, world: &World, pos: BlockPos, state: BlockState, previous: BlockState) -> Result<()> {
+        if is_wire(previous) {
+            return Ok(());
+        }
+        update_power(world, pos, state)?;
+        world.notify_neighbors(pos.relative(Direction::Up))?;
+        world.notify_neighbors(pos.relative(Direction::Down))?;
+        update_diagonal_shapes(world, pos, state)?;
+        notify_neighboring_wires(world, pos)
+    }
+
+    fn on_replaced(&self, world: &World, pos: BlockPos, state: BlockState, _new: BlockState) -> Result<()> {
+        for direction in Direction::ALL {
+            world.notify_neighbors(pos.relative(direction))?;
+        }
+        update_diagonal_shapes(world, pos, state)?;
+        notify_neighboring_wires(world, pos)
+    }
+
+    fn on_neighbor_update(&self, world: &World, pos: BlockPos, state: BlockState, _from: BlockPos) -> Result<()> {
+        update_power(world, pos, state)
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        _neighbor: BlockState,
+    ) -> BlockState {
+        wire_shape(world, pos, state, direction)
+    }
+
+    fn get_collision_shape(&self, _state: BlockState) -> Shape {
+        Shape::empty()
+    }
+
+    fn is_signal_source(&self, _state: BlockState) -> bool {
+        WIRE_SIGNALS.get()
+    }
+
+    fn get_signal(&self, world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        if !WIRE_SIGNALS.get() || direction == Direction::Down {
+            return 0;
+        }
+        let power = power(state);
+        if power == 0 || direction == Direction::Up {
+            return power;
+        }
+        // A dot doesn't power the blocks beside it, only wire pointing at them does.
+        let connected = connection_state(world, pos, state).get(direction.opposite().name());
+        if is_connected(connected) {
+            power
+        } else {
+            0
+        }
+    }
+
+    fn get_direct_signal(&self, world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        self.get_signal(world, pos, state, direction)
+    }
+}
+
+fn is_wire(state: BlockState) -> bool {
+    state.is("redstone_wire")
+}
+
+fn power(state: BlockState) -> u8 {
+    state.get("power").and_then(|power| power.parse().ok()).unwrap_or(0)
+}
+
+fn with_power(state: BlockState, power: u8) -> BlockState {
+    with_value(state, "power", &power.to_string())
+}
+
+/// Returns the power of the block if it's wire, and 0 for anything else.
+fn wire_power(state: Option<BlockState>) -> u8 {
+    state.filter(|&state| is_wire(state)).map_or(0, power)
+}
+
+fn is_connected(side: Option<&str>) -> bool {
+    matches!(side, Some("up" | "side"))
+}
+
+/// Returns the wire pointing every way, with the power of the state.
+fn cross(state: BlockState) -> BlockState {
+    Direction::HORIZONTAL.into_iter().fold(state, |state, direction| with_value(state, direction.name(), "side"))
+}
+
+fn is_cross(state: BlockState) -> bool {
+    Direction::HORIZONTAL.into_iter().all(|direction| state.get(direction.name()) == Some("side"))
+}
+
+fn is_dot(state: BlockState) -> bool {
+    Direction::HORIZONTAL.into_iter().all(|direction| !is_connected(state.get(direction.name())))
+}
+
+/// Whether wire can lie on the block at the position.
+fn can_survive_on(world: &World, pos: BlockPos) -> bool {
+    is_sturdy(world, pos, Direction::Up) || block_at(world, pos).is_some_and(|state| state.is("hopper"))
+}
+
+fn is_conductor(world: &World, pos: BlockPos) -> bool {
+    block_at(world, pos).is_some_and(|state| world.is_redstone_conductor(state))
+}
+
+/// Whether wire on the side of the block, or in any direction without one, connects to it.
+fn connects_to(world: &World, state: BlockState, direction: Option<Direction>) -> bool {
+    if is_wire(state) {
+        return true;
+    }
+    let Some(direction) = direction else {
+        return false;
+    };
+    if state.is("repeater") {
+        facing(state).is_some_and(|facing| facing == direction || facing == direction.opposite())
+    } else if state.is("observer") {
+        facing(state) == Some(direction)
+    } else {
+        world.is_signal_source(state)
+    }
+}
+
+/// Returns how the wire at the position connects on the side: `up` to wire on top of the block
+/// there, `side` to the block or to wire below it, or `none`. Wire only goes up when no conductor
+/// is above it.
+fn connecting_side(world: &World, pos: BlockPos, direction: Direction, can_go_up: bool) -> &'static str {
+    let neighbor_pos = pos.relative(direction);
+    let neighbor = block_at(world, neighbor_pos).unwrap_or(BlockState::AIR);
+    if can_go_up {
+        let holds_wire =
+            neighbor.name().is_some_and(|name| name.ends_with("_trapdoor")) || can_survive_on(world, neighbor_pos);
+        if holds_wire && block_at(world, neighbor_pos.relative(Direction::Up)).is_some_and(is_wire) {
+            return if is_sturdy(world, neighbor_pos, direction.opposite()) { "up" } else { "side" };
+        }
+    }
+    let wire_below = block_at(world, neighbor_pos.relative(Direction::Down)).is_some_and(is_wire);
+    if !connects_to(world, neighbor, Some(direction)) && (world.is_redstone_conductor(neighbor) || !wire_below) {
+        "none"
+    } else {
+        "side"
+    }
+}
+
+fn can_go_up(world: &World, pos: BlockPos) -> bool {
+    !is_conductor(world, pos.relative(Direction::Up))
+}
+
+/// Returns the wire connected on the sides it can connect on, with the power of the state. A
+/// wire connecting on one axis only points both ways along it, and one connecting nowhere every
+/// way, unless it was a dot already.
+fn connection_state(world: &World, pos: BlockPos, state: BlockState) -> BlockState {
+    let was_dot = is_dot(state);
+    let can_go_up = can_go_up(world, pos);
+    let mut connected = state;
+    for direction in Direction::HORIZONTAL {
+        connected = with_value(connected, direction.name(), connecting_side(world, pos, direction, can_go_up));
+    }
+    if was_dot && is_dot(connected) {
+        return connected;
+    }
+    let is = |direction: Direction| is_connected(connected.get(direction.name()));
+    let north_south = !is(Direction::North) && !is(Direction::South);
+    let east_west = !is(Direction::East) && !is(Direction::West);
+    let mut state = connected;
+    for (direction, empty) in [
+        (Direction::West, north_south),
+        (Direction::East, north_south),
+        (Direction::North, east_west),
+        (Direction::South, east_west),
+    ] {
+        if empty && !is(direction) {
+            state = with_value(state, direction.name(), "side");
+        }
+    }
+    state
+}
+
+/// Returns the state the wire should have now that the block on its `direction` side changed. It
+/// breaks without a block to lie on.
+fn wire_shape(world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> BlockState {
+    match direction {
+        Direction::Down if !can_survive_on(world, pos.relative(Direction::Down)) => BlockState::AIR,
+        Direction::Down => state,
+        Direction::Up => connection_state(world, pos, state),
+        _ => {
+            let side = connecting_side(world, pos, direction, can_go_up(world, pos));
+            if is_connected(Some(side)) == is_connected(state.get(direction.name())) && !is_cross(state) {
+                with_value(state, direction.name(), side)
+            } else {
+                connection_state(world, pos, with_value(cross(state), direction.name(), side))
+            }
+        }
+    }
+}
+
+/// Lets the wire a block up or down on the sides the wire at the position connects on connect to
+/// it or let go of it, since they aren't told when it changes.
+fn update_diagonal_shapes(world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+    for direction in Direction::HORIZONTAL {
+        let side = pos.relative(direction);
+        if !is_connected(state.get(direction.name())) || block_at(world, side).is_some_and(is_wire) {
+            continue;
+        }
+        for diagonal in [side.relative(Direction::Down), side.relative(Direction::Up)] {
+            let Some(wire) = block_at(world, diagonal).filter(|&state| is_wire(state)) else {
+                continue;
+            };
+            let shape = wire_shape(world, diagonal, wire, direction.opposite());
+            if shape != wire {
+                set_own_state(world, diagonal, shape)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Tells the wire around the position, and a block up or down from it, that the wire there came
+/// or went, along with the blocks around that wire.
+fn notify_neighboring_wires(world: &World, pos: BlockPos) -> Result<()> {
+    for direction in Direction::HORIZONTAL {
+        notify_wire_at(world, pos.relative(direction))?;
+    }
+    for direction in Direction::HORIZONTAL {
+        let side = pos.relative(direction);
+        if is_conductor(world, side) {
+            notify_wire_at(world, side.relative(Direction::Up))?;
+        } else {
+            notify_wire_at(world, side.relative(Direction::Down))?;
+        }
+    }
+    Ok(())
+}
+
+fn notify_wire_at(world: &World, pos: BlockPos) -> Result<()> {
+    if !block_at(world, pos).is_some_and(is_wire) {
+        return Ok(());
+    }
+    world.notify_neighbors(pos)?;
+    for direction in Direction::ALL {
+        world.notify_neighbors(pos.relative(direction))?;
+    }
+    Ok(())
+}
+
+/// Works out the power of the wire at the position and, if it changed, sets it and tells the
+/// blocks around it and around them.
+fn update_power(world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+    let target = target_power(world, pos);
+    if power(state) == target {
+        return Ok(());
+    }
+    if block_at(world, pos) == Some(state) {
+        set_own_state(world, pos, with_power(state, target))?;
+    }
+    world.notify_neighbors(pos)?;
+    for direction in Direction::ALL {
+        world.notify_neighbors(pos.relative(direction))?;
+    }
+    Ok(())
+}
+
+/// Returns the power the wire at the position should have: the strongest power the blocks
+/// around it give it, or that of the wire it connects to, one weaker.
+fn target_power(world: &World, pos: BlockPos) -> u8 {
+    WIRE_SIGNALS.set(false);
+    let given = world.best_neighbor_signal(pos);
+    WIRE_SIGNALS.set(true);
+    if given >= 15 {
+        return given;
+    }
+    let conductor_above = is_conductor(world, pos.relative(Direction::Up));
+    let mut wire = 0;
+    for direction in Direction::HORIZONTAL {
+        let side = pos.relative(direction);
+        let state = block_at(world, side);
+        wire = wire.max(wire_power(state));
+        if state.is_some_and(|state| world.is_redstone_conductor(state)) {
+            if !conductor_above {
+                wire = wire.max(wire_power(block_at(world, side.relative(Direction::Up))));
+            }
+        } else {
+            wire = wire.max(wire_power(block_at(world, side.relative(Direction::Down))));
+        }
+    }
+    given.max(wire.saturating_sub(1))
+}
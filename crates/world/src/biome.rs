@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// The biomes of the `minecraft:worldgen/biome` registry, with IDs matching the network IDs.
+#[derive(Debug, Default)]
+pub struct BiomeRegistry {
+    names: Vec<String>,
+    by_name: HashMap<String, u32>,
+}
+
+impl BiomeRegistry {
+    /// Takes the biome names in the order the registry is sent to the client.
+    pub fn new(names: Vec<String>) -> Self {
+        let by_name = names.iter().enumerate().map(|(id, name)| (name.clone(), id as u32)).collect();
+        Self { names, by_name }
+    }
+
+    pub fn id(&self, name: &str) -> Option<u32> {
+        self.by_name.get(name).copied()
+    }
+
+    pub fn name(&self, id: u32) -> Option<&str> {
+        self.names.get(id as usize).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+static BIOME_REGISTRY: OnceLock<BiomeRegistry> = OnceLock::new();
+
+/// Installs the registry biome IDs in chunks refer to. Returns false if a registry was already
+/// installed.
+pub fn install_biome_registry(registry: BiomeRegistry) -> bool {
+    BIOME_REGISTRY.set(registry).is_ok()
+}
+
+pub fn biome_registry() -> Option<&'static BiomeRegistry> {
+    BIOME_REGISTRY.get()
+}
@@ -0,0 +1,55 @@
+mod registry;
+mod state;
+
+pub use registry::{block_registry, install_block_registry, BlockRegistry};
+pub use state::BlockState;
+
+/// What chunk sections need to know about a block state to keep their counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockFlags {
+    /// Air, cave air and void air, which don't count as blocks.
+    pub air: bool,
+    /// Blocks holding water or lava, including waterlogged ones.
+    pub fluid: bool,
+    pub random_ticks: bool,
+    /// Blocks that stop movement or hold a fluid, which the `MOTION_BLOCKING` heightmap counts.
+    pub motion_blocking: bool,
+    /// Blocks that hold a block entity, such as chests and signs.
+    pub block_entity: bool,
+}
+
+/// How a block state gives off and blocks light.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LightProperties {
+    /// The light level the block gives off.
+    pub emission: u8,
+    /// How much light passing through the block is dimmed. Light always dims by at least one
+    /// per block, so 0 and 1 only differ for sky light going straight down.
+    pub opacity: u8,
+}
+
+/// Returns the flags of the block state. Without an installed registry only
+/// [`BlockState::AIR`] is air, every other block stops movement and nothing is a fluid or ticks
+/// randomly.
+pub fn block_flags(state: BlockState) -> BlockFlags {
+    match block_registry() {
+        Some(registry) => registry.flags(state.id()),
+        None => BlockFlags {
+            air: state == BlockState::AIR,
+            motion_blocking: state != BlockState::AIR,
+            ..BlockFlags::default()
+        },
+    }
+}
+
+/// Returns how the block state gives off and blocks light. Without an installed registry only
+/// [`BlockState::AIR`] lets light through and nothing gives off light.
+pub fn light_properties(state: BlockState) -> LightProperties {
+    match block_registry() {
+        Some(registry) => registry.light(state.id()),
+        None => LightProperties {
+            emission: 0,
+            opacity: if state == BlockState::AIR { 0 } else { 15 },
+        },
+    }
+}
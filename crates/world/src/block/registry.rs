@@ -0,0 +1,503 @@
+use crate::block::{BlockFlags, LightProperties};
+use crate::block_entity::kind_of_block;
+use crate::error::{Result, WorldError};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+/// Blocks that don't count as blocks in a section.
+const AIR_BLOCKS: [&str; 3] = ["minecraft:air", "minecraft:cave_air", "minecraft:void_air"];
+const FLUID_BLOCKS: [&str; 3] = ["minecraft:water", "minecraft:lava", "minecraft:bubble_column"];
+/// Blocks that vanilla ticks randomly in every state.
+const RANDOM_TICK_BLOCKS: [&str; 24] = [
+    "minecraft:grass_block",
+    "minecraft:mycelium",
+    "minecraft:farmland",
+    "minecraft:wheat",
+    "minecraft:carrots",
+    "minecraft:potatoes",
+    "minecraft:beetroots",
+    "minecraft:melon_stem",
+    "minecraft:pumpkin_stem",
+    "minecraft:sugar_cane",
+    "minecraft:cactus",
+    "minecraft:bamboo",
+    "minecraft:kelp",
+    "minecraft:vine",
+    "minecraft:ice",
+    "minecraft:snow",
+    "minecraft:fire",
+    "minecraft:lava",
+    "minecraft:oak_sapling",
+    "minecraft:spruce_sapling",
+    "minecraft:birch_sapling",
+    "minecraft:jungle_sapling",
+    "minecraft:acacia_sapling",
+    "minecraft:dark_oak_sapling",
+];
+
+/// Blocks that give off light in every state, with their light level.
+const LIGHT_SOURCES: [(&str, u8); 37] = [
+    ("minecraft:beacon", 15),
+    ("minecraft:conduit", 15),
+    ("minecraft:end_gateway", 15),
+    ("minecraft:end_portal", 15),
+    ("minecraft:fire", 15),
+    ("minecraft:glowstone", 15),
+    ("minecraft:jack_o_lantern", 15),
+    ("minecraft:lantern", 15),
+    ("minecraft:lava", 15),
+    ("minecraft:ochre_froglight", 15),
+    ("minecraft:pearlescent_froglight", 15),
+    ("minecraft:sea_lantern", 15),
+    ("minecraft:shroomlight", 15),
+    ("minecraft:verdant_froglight", 15),
+    ("minecraft:end_rod", 14),
+    ("minecraft:torch", 14),
+    ("minecraft:wall_torch", 14),
+    ("minecraft:nether_portal", 11),
+    ("minecraft:crying_obsidian", 10),
+    ("minecraft:soul_fire", 10),
+    ("minecraft:soul_lantern", 10),
+    ("minecraft:soul_torch", 10),
+    ("minecraft:soul_wall_torch", 10),
+    ("minecraft:enchanting_table", 7),
+    ("minecraft:ender_chest", 7),
+    ("minecraft:glow_lichen", 7),
+    ("minecraft:sculk_catalyst", 6),
+    ("minecraft:amethyst_cluster", 5),
+    ("minecraft:large_amethyst_bud", 4),
+    ("minecraft:magma_block", 3),
+    ("minecraft:medium_amethyst_bud", 2),
+    ("minecraft:brewing_stand", 1),
+    ("minecraft:brown_mushroom", 1),
+    ("minecraft:calibrated_sculk_sensor", 1),
+    ("minecraft:dragon_egg", 1),
+    ("minecraft:end_portal_frame", 1),
+    ("minecraft:small_amethyst_bud", 1),
+];
+/// Blocks that only give off light while their `lit` property is true.
+const LIT_LIGHT_SOURCES: [(&str, u8); 18] = [
+    ("minecraft:campfire", 15),
+    ("minecraft:copper_bulb", 15),
+    ("minecraft:redstone_lamp", 15),
+    ("minecraft:waxed_copper_bulb", 15),
+    ("minecraft:blast_furnace", 13),
+    ("minecraft:furnace", 13),
+    ("minecraft:smoker", 13),
+    ("minecraft:exposed_copper_bulb", 12),
+    ("minecraft:waxed_exposed_copper_bulb", 12),
+    ("minecraft:soul_campfire", 10),
+    ("minecraft:deepslate_redstone_ore", 9),
+    ("minecraft:redstone_ore", 9),
+    ("minecraft:weathered_copper_bulb", 8),
+    ("minecraft:waxed_weathered_copper_bulb", 8),
+    ("minecraft:redstone_torch", 7),
+    ("minecraft:redstone_wall_torch", 7),
+    ("minecraft:oxidized_copper_bulb", 4),
+    ("minecraft:waxed_oxidized_copper_bulb", 4),
+];
+/// Blocks that let light through only dimmed, like water does.
+const DIMMING_BLOCKS: [&str; 7] = [
+    "minecraft:cobweb",
+    "minecraft:frosted_ice",
+    "minecraft:honey_block",
+    "minecraft:ice",
+    "minecraft:powder_snow",
+    "minecraft:slime_block",
+    "minecraft:spawner",
+];
+/// Parts of the names of blocks that don't fill their whole space or can be seen through, and so
+/// let light through. The report has no block shapes, so every other block stops light.
+const TRANSPARENT_NAME_PARTS: [&str; 47] = [
+    "glass", "_slab", "_stairs", "_fence", "_wall", "door", "_pane", "iron_bars", "sign", "banner",
+    "_button", "pressure_plate", "torch", "carpet", "rail", "lantern", "chain", "ladder", "vine",
+    "grass", "fern", "sapling", "flower", "tulip", "mushroom", "bush", "roots", "fungus", "sprouts",
+    "coral", "kelp", "seagrass", "sugar_cane", "cactus", "_bed", "chest", "candle",
+    "potted_", "_head", "_skull", "_stem", "amethyst_", "rod", "lever", "redstone_wire", "repeater",
+    "comparator",
+];
+/// Blocks that let light through that the name parts don't catch.
+const TRANSPARENT_BLOCKS: [&str; 48] = [
+    "minecraft:allium",
+    "minecraft:anvil",
+    "minecraft:azure_bluet",
+    "minecraft:bamboo",
+    "minecraft:barrier",
+    "minecraft:beacon",
+    "minecraft:beetroots",
+    "minecraft:bell",
+    "minecraft:blue_orchid",
+    "minecraft:brewing_stand",
+    "minecraft:cake",
+    "minecraft:carrots",
+    "minecraft:cauldron",
+    "minecraft:chipped_anvil",
+    "minecraft:cocoa",
+    "minecraft:conduit",
+    "minecraft:cornflower",
+    "minecraft:damaged_anvil",
+    "minecraft:dandelion",
+    "minecraft:dragon_egg",
+    "minecraft:enchanting_table",
+    "minecraft:end_portal",
+    "minecraft:end_gateway",
+    "minecraft:fire",
+    "minecraft:flower_pot",
+    "minecraft:hopper",
+    "minecraft:lectern",
+    "minecraft:light",
+    "minecraft:lilac",
+    "minecraft:lily_of_the_valley",
+    "minecraft:lily_pad",
+    "minecraft:nether_portal",
+    "minecraft:nether_wart",
+    "minecraft:oxeye_daisy",
+    "minecraft:peony",
+    "minecraft:pointed_dripstone",
+    "minecraft:poppy",
+    "minecraft:potatoes",
+    "minecraft:scaffolding",
+    "minecraft:snow",
+    "minecraft:soul_fire",
+    "minecraft:stonecutter",
+    "minecraft:structure_void",
+    "minecraft:sunflower",
+    "minecraft:turtle_egg",
+    "minecraft:wheat",
+    "minecraft:wither_rose",
+    "minecraft:pitcher_plant",
+];
+/// Blocks that match a transparent name part but fill their space, besides the `_block` ones.
+const OPAQUE_BLOCKS: [&str; 9] = [
+    "minecraft:crimson_stem",
+    "minecraft:jack_o_lantern",
+    "minecraft:mushroom_stem",
+    "minecraft:muddy_mangrove_roots",
+    "minecraft:sea_lantern",
+    "minecraft:stripped_crimson_stem",
+    "minecraft:stripped_warped_stem",
+    "minecraft:tinted_glass",
+    "minecraft:warped_stem",
+];
+/// Parts of the names of blocks that can be walked through, or are too thin or low to stand in
+/// the way. The report has no collision shapes, so every other block stops movement.
+const NON_SOLID_NAME_PARTS: [&str; 33] = [
+    "torch", "sign", "banner", "_button", "pressure_plate", "rail", "carpet", "vine", "grass",
+    "fern", "sapling", "flower", "tulip", "mushroom", "bush", "roots", "fungus", "sprouts", "coral",
+    "kelp", "sugar_cane", "redstone_wire", "tripwire", "fire", "portal", "cobweb", "candle",
+    "lichen", "petals", "potted_", "lever", "repeater", "comparator",
+];
+/// Blocks that don't stop movement that the name parts don't catch.
+const NON_SOLID_BLOCKS: [&str; 27] = [
+    "minecraft:allium",
+    "minecraft:attached_melon_stem",
+    "minecraft:attached_pumpkin_stem",
+    "minecraft:azure_bluet",
+    "minecraft:bamboo_sapling",
+    "minecraft:beetroots",
+    "minecraft:blue_orchid",
+    "minecraft:carrots",
+    "minecraft:cornflower",
+    "minecraft:dandelion",
+    "minecraft:light",
+    "minecraft:lilac",
+    "minecraft:lily_of_the_valley",
+    "minecraft:melon_stem",
+    "minecraft:nether_wart",
+    "minecraft:oxeye_daisy",
+    "minecraft:peony",
+    "minecraft:pitcher_plant",
+    "minecraft:poppy",
+    "minecraft:potatoes",
+    "minecraft:pumpkin_stem",
+    "minecraft:small_dripleaf",
+    "minecraft:snow",
+    "minecraft:spore_blossom",
+    "minecraft:structure_void",
+    "minecraft:wheat",
+    "minecraft:wither_rose",
+];
+/// Blocks that match a non-solid name part but stop movement, besides the `_block` ones.
+const SOLID_BLOCKS: [&str; 7] = [
+    "minecraft:campfire",
+    "minecraft:end_portal_frame",
+    "minecraft:flowering_azalea",
+    "minecraft:mangrove_roots",
+    "minecraft:muddy_mangrove_roots",
+    "minecraft:mushroom_stem",
+    "minecraft:soul_campfire",
+];
+
+/// A block as listed in the data generator's `blocks.json` report.
+#[derive(Deserialize)]
+struct ReportBlock {
+    #[serde(default)]
+    properties: BTreeMap<String, Vec<String>>,
+    states: Vec<ReportState>,
+}
+
+#[derive(Deserialize)]
+struct ReportState {
+    id: u32,
+    #[serde(default)]
+    default: bool,
+    #[serde(default)]
+    properties: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct BlockType {
+    pub(crate) name: String,
+    /// The properties sorted by name with their values in report order.
+    pub(crate) properties: Vec<(String, Vec<String>)>,
+    pub(crate) first_state: u32,
+    pub(crate) default_state: u32,
+    /// How far apart in IDs two states differing by one value of each property are.
+    strides: Vec<u32>,
+}
+
+impl BlockType {
+    pub(crate) fn state_count(&self) -> u32 {
+        self.properties.iter().map(|(_, values)| values.len() as u32).product()
+    }
+
+    /// Returns the index of each property's value in the state.
+    pub(crate) fn value_indices(&self, state: u32) -> impl Iterator<Item = usize> + '_ {
+        let offset = state - self.first_state;
+        self.properties
+            .iter()
+            .zip(&self.strides)
+            .map(move |((_, values), stride)| (offset / stride) as usize % values.len())
+    }
+
+    pub(crate) fn property_index(&self, property: &str) -> Option<usize> {
+        self.properties.iter().position(|(name, _)| name == property)
+    }
+
+    /// Returns the state with one property set to another value.
+    pub(crate) fn with_value(&self, state: u32, property: usize, value: usize) -> u32 {
+        let current = self.value_indices(state).nth(property).unwrap();
+        let stride = self.strides[property];
+        state - current as u32 * stride + value as u32 * stride
+    }
+}
+
+/// Every block and block state of a game version, with state IDs matching the network IDs.
+#[derive(Debug)]
+pub struct BlockRegistry {
+    blocks: Vec<BlockType>,
+    by_name: HashMap<String, usize>,
+    /// The block of each state.
+    state_blocks: Vec<u32>,
+    flags: Vec<BlockFlags>,
+    light: Vec<LightProperties>,
+}
+
+impl BlockRegistry {
+    /// Reads the `blocks.json` report the vanilla server writes with `--reports`. Fails if a state
+    /// ID between those of the blocks is missing, as a report with blocks left out would send the
+    /// client the wrong ones.
+    pub fn from_report(json: &str) -> Result<Self> {
+        let report: BTreeMap<String, ReportBlock> =
+            serde_json::from_str(json).map_err(|e| WorldError::InvalidBlockReport(e.to_string()))?;
+
+        let mut blocks = Vec::with_capacity(report.len());
+        for (name, block) in report {
+            blocks.push(block_type(name, block)?);
+        }
+        blocks.sort_by_key(|block| block.first_state);
+
+        let mut by_name = HashMap::with_capacity(blocks.len());
+        let mut state_blocks = Vec::new();
+        let mut flags = Vec::new();
+        let mut light = Vec::new();
+        for (index, block) in blocks.iter().enumerate() {
+            by_name.insert(block.name.clone(), index);
+            let first = block.first_state as usize;
+            let end = first + block.state_count() as usize;
+            if state_blocks.len() < end {
+                state_blocks.resize(end, u32::MAX);
+                flags.resize(end, BlockFlags::default());
+                light.resize(end, LightProperties::default());
+            }
+            for state in first..end {
+                if state_blocks[state] != u32::MAX {
+                    return Err(WorldError::InvalidBlockReport(format!("State {} is used twice", state)));
+                }
+                state_blocks[state] = index as u32;
+                flags[state] = state_flags(block, state as u32);
+                light[state] = state_light(block, state as u32, flags[state]);
+            }
+        }
+
+        if let Some(state) = state_blocks.iter().position(|&block| block == u32::MAX) {
+            return Err(WorldError::InvalidBlockReport(format!("State {} has no block, blocks are missing", state)));
+        }
+
+        Ok(Self { blocks, by_name, state_blocks, flags, light })
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.state_blocks.len()
+    }
+
+    /// The names of the blocks, with their namespace.
+    pub fn block_names(&self) -> impl Iterator<Item = &str> {
+        self.blocks.iter().map(|block| block.name.as_str())
+    }
+
+    pub(crate) fn block(&self, name: &str) -> Option<&BlockType> {
+        self.by_name.get(name).map(|&index| &self.blocks[index])
+    }
+
+    pub(crate) fn block_of(&self, state: u32) -> Option<&BlockType> {
+        match self.state_blocks.get(state as usize) {
+            Some(&index) if index != u32::MAX => Some(&self.blocks[index as usize]),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn flags(&self, state: u32) -> BlockFlags {
+        self.flags.get(state as usize).copied().unwrap_or_default()
+    }
+
+    pub(crate) fn light(&self, state: u32) -> LightProperties {
+        self.light.get(state as usize).copied().unwrap_or_default()
+    }
+}
+
+fn block_type(name: String, block: ReportBlock) -> Result<BlockType> {
+    let invalid = |message: &str| WorldError::InvalidBlockReport(format!("{}: {}", name, message));
+
+    let properties: Vec<(String, Vec<String>)> = block.properties.into_iter().collect();
+    // Vanilla orders states with the last property, by name, changing fastest.
+    let mut strides = vec![1; properties.len()];
+    for index in (0..properties.len().saturating_sub(1)).rev() {
+        strides[index] = strides[index + 1] * properties[index + 1].1.len() as u32;
+    }
+
+    let first_state = block.states.iter().map(|state| state.id).min().ok_or_else(|| invalid("no states"))?;
+    let default_state = block
+        .states
+        .iter()
+        .find(|state| state.default)
+        .map_or(first_state, |state| state.id);
+    let block_type = BlockType { name: name.clone(), properties, first_state, default_state, strides };
+
+    if block.states.len() as u32 != block_type.state_count() {
+        return Err(invalid("states don't cover every combination of properties"));
+    }
+    for state in &block.states {
+        let mut id = first_state;
+        for ((property, values), stride) in block_type.properties.iter().zip(&block_type.strides) {
+            let value = state
+                .properties
+                .get(property)
+                .and_then(|value| values.iter().position(|v| v == value))
+                .ok_or_else(|| invalid("state has an unknown property value"))?;
+            id += value as u32 * stride;
+        }
+        if id != state.id {
+            return Err(invalid("states are not in vanilla order"));
+        }
+    }
+    Ok(block_type)
+}
+
+/// Returns the value of the property in the state, if the block has it.
+fn property_value<'a>(block: &'a BlockType, state: u32, property: &str) -> Option<&'a str> {
+    let index = block.property_index(property)?;
+    let value = block.value_indices(state).nth(index)?;
+    Some(block.properties[index].1[value].as_str())
+}
+
+fn state_flags(block: &BlockType, state: u32) -> BlockFlags {
+    let name = block.name.as_str();
+    let waterlogged = property_value(block, state, "waterlogged") == Some("true");
+    let air = AIR_BLOCKS.contains(&name);
+    let fluid = FLUID_BLOCKS.contains(&name) || waterlogged;
+    BlockFlags {
+        air,
+        fluid,
+        random_ticks: RANDOM_TICK_BLOCKS.contains(&name) || decays(block, state),
+        motion_blocking: fluid || (!air && blocks_motion(name)),
+        block_entity: kind_of_block(name).is_some(),
+    }
+}
+
+/// Whether the state is of leaves that are too far from a log and weren't placed by a player,
+/// which decay when they tick randomly.
+fn decays(block: &BlockType, state: u32) -> bool {
+    block.name.ends_with("_leaves")
+        && property_value(block, state, "distance") == Some("7")
+        && property_value(block, state, "persistent") == Some("false")
+}
+
+fn blocks_motion(name: &str) -> bool {
+    let short_name = name.strip_prefix("minecraft:").unwrap_or(name);
+    if SOLID_BLOCKS.contains(&name)
+        || name.ends_with("_block")
+        || name.ends_with("_leaves")
+        || name.ends_with("candle_cake")
+    {
+        return true;
+    }
+    !NON_SOLID_BLOCKS.contains(&name) && !NON_SOLID_NAME_PARTS.iter().any(|part| short_name.contains(part))
+}
+
+fn state_light(block: &BlockType, state: u32, flags: BlockFlags) -> LightProperties {
+    let name = block.name.as_str();
+    let value = |property| property_value(block, state, property);
+    let number = |property| value(property).and_then(|value| value.parse::<u8>().ok()).unwrap_or(0);
+    let lit = value("lit") == Some("true");
+
+    let emission = if let Some((_, level)) = LIGHT_SOURCES.iter().find(|(source, _)| *source == name) {
+        *level
+    } else if let Some((_, level)) = LIT_LIGHT_SOURCES.iter().find(|(source, _)| *source == name) {
+        if lit { *level } else { 0 }
+    } else if name == "minecraft:light" {
+        number("level")
+    } else if name.ends_with("candle") {
+        if lit { 3 * number("candles") } else { 0 }
+    } else if name == "minecraft:sea_pickle" {
+        if value("waterlogged") == Some("true") { 3 + 3 * number("pickles") } else { 0 }
+    } else if name == "minecraft:respawn_anchor" {
+        [0, 3, 7, 11, 15][number("charges").min(4) as usize]
+    } else if name == "minecraft:cave_vines" || name == "minecraft:cave_vines_plant" {
+        if value("berries") == Some("true") { 14 } else { 0 }
+    } else {
+        0
+    };
+
+    let short_name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let opacity = if flags.air {
+        0
+    } else if flags.fluid || DIMMING_BLOCKS.contains(&name) || name.ends_with("_leaves") {
+        1
+    } else if OPAQUE_BLOCKS.contains(&name) || name.ends_with("_block") {
+        15
+    } else if TRANSPARENT_BLOCKS.contains(&name) || TRANSPARENT_NAME_PARTS.iter().any(|part| short_name.contains(part)) {
+        0
+    } else {
+        15
+    };
+    LightProperties { emission, opacity }
+}
+
+static BLOCK_REGISTRY: OnceLock<BlockRegistry> = OnceLock::new();
+
+/// Installs the registry every [`BlockState`](crate::block::BlockState) refers to. Sections count
+/// blocks as they are set, so this has to happen before any chunk is built. Returns false if a
+/// registry was already installed.
+pub fn install_block_registry(registry: BlockRegistry) -> bool {
+    BLOCK_REGISTRY.set(registry).is_ok()
+}
+
+pub fn block_registry() -> Option<&'static BlockRegistry> {
+    BLOCK_REGISTRY.get()
+}
@@ -0,0 +1,169 @@
+use crate::block::registry::{block_registry, BlockRegistry, BlockType};
+use crate::block::{block_flags, BlockFlags};
+use crate::error::{Result, WorldError};
+use std::fmt;
+use std::str::FromStr;
+
+/// A block with all its properties set, identified by its state ID, which is also its network ID.
+///
+/// Everything but the ID is looked up in the installed [`BlockRegistry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockState(u32);
+
+impl BlockState {
+    pub const AIR: BlockState = BlockState(0);
+
+    pub const fn from_id(id: u32) -> Self {
+        Self(id)
+    }
+
+    pub const fn id(self) -> u32 {
+        self.0
+    }
+
+    /// Returns the default state of the block, `minecraft:` being assumed when the name has no
+    /// namespace.
+    pub fn default_of(name: &str) -> Result<Self> {
+        Ok(Self(block_type(name)?.default_state))
+    }
+
+    /// Parses a state written like `minecraft:oak_stairs[facing=north,half=top]`, where the
+    /// properties that aren't listed keep their default values.
+    pub fn parse(text: &str) -> Result<Self> {
+        let (name, properties) = match text.split_once('[') {
+            Some((name, rest)) => {
+                let properties = rest
+                    .strip_suffix(']')
+                    .ok_or_else(|| WorldError::InvalidBlockState(text.to_string()))?;
+                (name, properties)
+            }
+            None => (text, ""),
+        };
+
+        let mut state = Self::default_of(name.trim())?;
+        for property in properties.split(',').filter(|p| !p.trim().is_empty()) {
+            let (property, value) = property
+                .split_once('=')
+                .ok_or_else(|| WorldError::InvalidBlockState(text.to_string()))?;
+            state = state.with(property.trim(), value.trim())?;
+        }
+        Ok(state)
+    }
+
+    /// The name of the block, or `None` if the state isn't in the registry.
+    pub fn name(self) -> Option<&'static str> {
+        self.block().map(|block| block.name.as_str())
+    }
+
+    /// Whether the state belongs to the block, `minecraft:` being assumed when the name has no
+    /// namespace.
+    pub fn is(self, name: &str) -> bool {
+        self.name() == Some(namespaced(name).as_ref())
+    }
+
+    /// Returns the default state of this state's block.
+    pub fn default_state(self) -> Self {
+        self.block().map_or(self, |block| Self(block.default_state))
+    }
+
+    /// Returns the value of the property, or `None` if the block doesn't have it.
+    pub fn get(self, property: &str) -> Option<&'static str> {
+        let block = self.block()?;
+        let index = block.property_index(property)?;
+        let value = block.value_indices(self.0).nth(index)?;
+        Some(block.properties[index].1[value].as_str())
+    }
+
+    /// Returns the state with the property set to the value.
+    pub fn with(self, property: &str, value: &str) -> Result<Self> {
+        let block = self.block().ok_or(WorldError::UnknownBlockState(self.0))?;
+        let invalid = || WorldError::InvalidProperty {
+            block: block.name.clone(),
+            property: property.to_string(),
+            value: value.to_string(),
+        };
+        let index = block.property_index(property).ok_or_else(invalid)?;
+        let value = block.properties[index]
+            .1
+            .iter()
+            .position(|v| v == value)
+            .ok_or_else(invalid)?;
+        Ok(Self(block.with_value(self.0, index, value)))
+    }
+
+    /// Iterates over the property names and values of the state, sorted by name.
+    pub fn properties(self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        self.block().into_iter().flat_map(move |block| {
+            block
+                .properties
+                .iter()
+                .zip(block.value_indices(self.0))
+                .map(|((name, values), value)| (name.as_str(), values[value].as_str()))
+        })
+    }
+
+    pub fn flags(self) -> BlockFlags {
+        block_flags(self)
+    }
+
+    /// Whether the state is air, cave air or void air.
+    pub fn is_air(self) -> bool {
+        self.flags().air
+    }
+
+    fn block(self) -> Option<&'static BlockType> {
+        block_registry()?.block_of(self.0)
+    }
+}
+
+impl Default for BlockState {
+    fn default() -> Self {
+        Self::AIR
+    }
+}
+
+impl FromStr for BlockState {
+    type Err = WorldError;
+
+    fn from_str(text: &str) -> Result<Self> {
+        Self::parse(text)
+    }
+}
+
+/// Writes the state the way [`BlockState::parse`] reads it, or its ID if the registry doesn't know
+/// it.
+impl fmt::Display for BlockState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(name) = self.name() else {
+            return write!(f, "#{}", self.0);
+        };
+
+        write!(f, "{}", name)?;
+        let mut properties = self.properties().peekable();
+        if properties.peek().is_some() {
+            write!(f, "[")?;
+            for (index, (property, value)) in properties.enumerate() {
+                if index > 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "{}={}", property, value)?;
+            }
+            write!(f, "]")?;
+        }
+        Ok(())
+    }
+}
+
+fn block_type(name: &str) -> Result<&'static BlockType> {
+    block_registry()
+        .and_then(|registry: &'static BlockRegistry| registry.block(&namespaced(name)))
+        .ok_or_else(|| WorldError::UnknownBlock(name.to_string()))
+}
+
+fn namespaced(name: &str) -> std::borrow::Cow<'_, str> {
+    if name.contains(':') {
+        name.into()
+    } else {
+        format!("minecraft:{}", name).into()
+    }
+}
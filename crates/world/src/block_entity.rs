@@ -0,0 +1,134 @@
+use crate::block::BlockState;
+use fastnbt::Value;
+use std::collections::HashMap;
+
+/// Blocks with a block entity of the same name.
+const BLOCK_ENTITY_BLOCKS: [&str; 36] = [
+    "minecraft:barrel",
+    "minecraft:beacon",
+    "minecraft:beehive",
+    "minecraft:bell",
+    "minecraft:blast_furnace",
+    "minecraft:brewing_stand",
+    "minecraft:calibrated_sculk_sensor",
+    "minecraft:chest",
+    "minecraft:chiseled_bookshelf",
+    "minecraft:comparator",
+    "minecraft:conduit",
+    "minecraft:crafter",
+    "minecraft:creaking_heart",
+    "minecraft:daylight_detector",
+    "minecraft:decorated_pot",
+    "minecraft:dispenser",
+    "minecraft:dropper",
+    "minecraft:enchanting_table",
+    "minecraft:end_gateway",
+    "minecraft:end_portal",
+    "minecraft:ender_chest",
+    "minecraft:furnace",
+    "minecraft:hopper",
+    "minecraft:jigsaw",
+    "minecraft:jukebox",
+    "minecraft:lectern",
+    "minecraft:sculk_catalyst",
+    "minecraft:sculk_sensor",
+    "minecraft:sculk_shrieker",
+    "minecraft:smoker",
+    "minecraft:structure_block",
+    "minecraft:test_block",
+    "minecraft:test_instance_block",
+    "minecraft:trapped_chest",
+    "minecraft:trial_spawner",
+    "minecraft:vault",
+];
+/// Blocks whose block entity is named differently, with its name.
+const RENAMED_BLOCK_ENTITY_BLOCKS: [(&str, &str); 10] = [
+    ("minecraft:bee_nest", "minecraft:beehive"),
+    ("minecraft:campfire", "minecraft:campfire"),
+    ("minecraft:chain_command_block", "minecraft:command_block"),
+    ("minecraft:command_block", "minecraft:command_block"),
+    ("minecraft:moving_piston", "minecraft:piston"),
+    ("minecraft:repeating_command_block", "minecraft:command_block"),
+    ("minecraft:soul_campfire", "minecraft:campfire"),
+    ("minecraft:spawner", "minecraft:mob_spawner"),
+    ("minecraft:suspicious_gravel", "minecraft:brushable_block"),
+    ("minecraft:suspicious_sand", "minecraft:brushable_block"),
+];
+/// Endings of the names of blocks that come in many colors or woods, with their block entity.
+const BLOCK_ENTITY_SUFFIXES: [(&str, &str); 7] = [
+    ("_hanging_sign", "minecraft:hanging_sign"),
+    ("_sign", "minecraft:sign"),
+    ("_banner", "minecraft:banner"),
+    ("_bed", "minecraft:bed"),
+    ("shulker_box", "minecraft:shulker_box"),
+    ("_skull", "minecraft:skull"),
+    ("_head", "minecraft:skull"),
+];
+
+/// The extra data of blocks such as chests and signs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockEntity {
+    /// The block entity type, such as `minecraft:chest`.
+    pub kind: String,
+    /// Everything but the type and the position, as stored in the chunk.
+    pub data: HashMap<String, Value>,
+}
+
+impl BlockEntity {
+    pub fn new(kind: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            data: HashMap::new(),
+        }
+    }
+
+    /// Returns the string stored under the key, if there is one.
+    pub fn get_string(&self, key: &str) -> Option<&str> {
+        match self.data.get(key)? {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer stored under the key, whichever integer tag holds it.
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        match self.data.get(key)? {
+            Value::Byte(value) => Some(*value as i64),
+            Value::Short(value) => Some(*value as i64),
+            Value::Int(value) => Some(*value as i64),
+            Value::Long(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Sets the value stored under the key, returning the entity.
+    pub fn with(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.data.insert(key.into(), value);
+        self
+    }
+}
+
+/// Returns the type of the block entity the block has, such as `minecraft:sign` for every sign,
+/// or `None` if it has none.
+pub fn block_entity_kind(state: BlockState) -> Option<&'static str> {
+    if !state.flags().block_entity {
+        return None;
+    }
+    state.name().and_then(kind_of_block)
+}
+
+pub(crate) fn kind_of_block(name: &str) -> Option<&'static str> {
+    if let Some(kind) = BLOCK_ENTITY_BLOCKS.iter().find(|block| **block == name) {
+        return Some(kind);
+    }
+    if let Some((_, kind)) = RENAMED_BLOCK_ENTITY_BLOCKS.iter().find(|(block, _)| *block == name) {
+        return Some(kind);
+    }
+    if name == "minecraft:piston_head" {
+        return None;
+    }
+    BLOCK_ENTITY_SUFFIXES
+        .iter()
+        .find(|(suffix, _)| name.ends_with(suffix))
+        .map(|(_, kind)| *kind)
+}
@@ -0,0 +1,89 @@
+use crate::pos::BlockPos;
+use std::collections::VecDeque;
+
+/// How many ticks changes are kept for readers that haven't caught up.
+pub const CHANGE_LOG_TICKS: u64 = 100;
+
+/// What changed at a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    /// The block state, which may also have replaced the block entity.
+    Block,
+    /// The data of the block entity.
+    BlockEntity,
+}
+
+/// A change to a block of a loaded chunk, at world coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockChange {
+    pub pos: BlockPos,
+    pub kind: ChangeKind,
+}
+
+/// The changes made to the blocks of a world during the last ticks, numbered in the order they
+/// were made. Every player reads the changes since the last one it read, to send them for the
+/// chunks it was sent.
+#[derive(Debug, Default)]
+pub struct ChangeLog {
+    tick: u64,
+    /// The number of the oldest change kept.
+    first: u64,
+    /// The changes with the tick they were made in.
+    changes: VecDeque<(u64, BlockChange)>,
+}
+
+/// The changes made since a reader last read the log.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changes {
+    pub changes: Vec<BlockChange>,
+    /// The number to read from next time.
+    pub next: u64,
+    /// Whether changes the reader didn't read were already dropped, so it has to send the chunks
+    /// again instead.
+    pub missed: bool,
+}
+
+impl ChangeLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, change: BlockChange) {
+        self.changes.push_back((self.tick, change));
+    }
+
+    /// The number the next change will get, where a new reader starts.
+    pub fn end(&self) -> u64 {
+        self.first + self.changes.len() as u64
+    }
+
+    /// Returns the changes from number `from` on.
+    pub fn since(&self, from: u64) -> Changes {
+        let start = from.saturating_sub(self.first) as usize;
+        Changes {
+            changes: self.changes.iter().skip(start).map(|(_, change)| *change).collect(),
+            next: self.end(),
+            missed: from < self.first,
+        }
+    }
+
+    /// Moves on to the next tick, dropping the changes older than [`CHANGE_LOG_TICKS`].
+    pub fn tick(&mut self) {
+        self.tick += 1;
+        while let Some(&(tick, _)) = self.changes.front() {
+            if tick + CHANGE_LOG_TICKS > self.tick {
+                break;
+            }
+            self.changes.pop_front();
+            self.first += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
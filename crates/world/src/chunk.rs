@@ -1,4 +1,14 @@
+use crate::block::{block_flags, BlockFlags, BlockState};
+use crate::block_entity::{block_entity_kind, BlockEntity};
+use crate::error::WorldError;
+use crate::height::WorldHeight;
+use crate::heightmap::{Heightmap, HeightmapKind};
+use crate::light::{ChunkLight, LightKind};
+use crate::pos::BlockPos;
 use crate::palette::{PaletteType, PalettedContainer};
+use fastnbt::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 pub const SECTION_WIDTH: usize = 16;
 pub const SECTION_HEIGHT: usize = 16;
@@ -9,50 +19,528 @@ pub const BIOME_HEIGHT: usize = 4;
 pub const BIOME_VOLUME: usize = BIOME_WIDTH * BIOME_WIDTH * BIOME_HEIGHT;
 
 pub struct ChunkSection {
+    /// The number of non-air blocks.
     block_count: u16,
+    fluid_count: u16,
+    random_tick_count: u16,
     block_states: PalettedContainer,
     biomes: PalettedContainer,
+    dirty: bool,
 }
 
 impl ChunkSection {
     pub fn new() -> Self {
         Self {
             block_count: 0,
+            fluid_count: 0,
+            random_tick_count: 0,
             block_states: PalettedContainer::new(PaletteType::Block, SECTION_VOLUME),
             biomes: PalettedContainer::new(PaletteType::Biome, BIOME_VOLUME),
+            dirty: false,
         }
     }
 
-    pub fn get_block(&self, x: usize, y: usize, z: usize) -> u32 {
-        self.block_states.get(y * SECTION_WIDTH * SECTION_HEIGHT + z * SECTION_WIDTH + x)
+    /// Builds a section from its block states and biomes, counting the blocks.
+    pub fn from_containers(block_states: PalettedContainer, biomes: PalettedContainer) -> Self {
+        let mut section = Self {
+            block_states,
+            biomes,
+            ..Self::new()
+        };
+        for index in 0..SECTION_VOLUME {
+            let flags = block_flags(BlockState::from_id(section.block_states.get(index)));
+            section.count(flags, true);
+        }
+        section
+    }
+
+    pub fn block_count(&self) -> u16 {
+        self.block_count
+    }
+
+    /// The number of blocks holding a fluid.
+    pub fn fluid_count(&self) -> u16 {
+        self.fluid_count
+    }
+
+    /// The number of blocks that tick randomly.
+    pub fn random_tick_count(&self) -> u16 {
+        self.random_tick_count
+    }
+
+    /// Whether the section holds nothing but air.
+    pub fn is_empty(&self) -> bool {
+        self.block_count == 0
+    }
+
+    pub fn block_states(&self) -> &PalettedContainer {
+        &self.block_states
+    }
+
+    pub fn biomes(&self) -> &PalettedContainer {
+        &self.biomes
+    }
+
+    /// Coordinates are relative to the section and must be below 16.
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> Result<BlockState, WorldError> {
+        Ok(BlockState::from_id(self.block_states.get(block_index(x, y, z)?)))
+    }
+
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: BlockState) -> Result<(), WorldError> {
+        self.set_index(block_index(x, y, z)?, block);
+        Ok(())
     }
 
-    pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: u32) {
-        self.block_states.set(y * SECTION_WIDTH * SECTION_HEIGHT + z * SECTION_WIDTH + x, block);
+    /// Sets every block in the horizontal layer at `y` to the same block.
+    pub fn fill_layer(&mut self, y: usize, block: BlockState) -> Result<(), WorldError> {
+        let start = block_index(0, y, 0)?;
+        for index in start..start + SECTION_WIDTH * SECTION_WIDTH {
+            self.set_index(index, block);
+        }
+        Ok(())
+    }
+
+    /// Coordinates are in biome cells of 4 blocks, relative to the section, and must be below 4.
+    pub fn get_biome(&self, x: usize, y: usize, z: usize) -> Result<u32, WorldError> {
+        Ok(self.biomes.get(biome_index(x, y, z)?))
+    }
+
+    pub fn set_biome(&mut self, x: usize, y: usize, z: usize, biome: u32) -> Result<(), WorldError> {
+        self.biomes.set(biome_index(x, y, z)?, biome);
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn fill_biome(&mut self, biome: u32) {
+        self.biomes.fill(biome);
+        self.dirty = true;
+    }
+
+    fn set_index(&mut self, index: usize, block: BlockState) {
+        let previous = BlockState::from_id(self.block_states.set(index, block.id()));
+        if previous != block {
+            self.count(block_flags(previous), false);
+            self.count(block_flags(block), true);
+        }
+        self.dirty = true;
+    }
+
+    fn count(&mut self, flags: BlockFlags, added: bool) {
+        let change = |count: &mut u16, applies: bool| {
+            if applies {
+                *count = if added { *count + 1 } else { *count - 1 };
+            }
+        };
+        change(&mut self.block_count, !flags.air);
+        change(&mut self.fluid_count, flags.fluid);
+        change(&mut self.random_tick_count, flags.random_ticks);
+    }
+
+    /// Whether a block changed since the section was last saved.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the blocks of the horizontal layer at `y`, which must be below 16, in the order
+    /// z then x.
+    fn clone_layer(&self, y: usize) -> Vec<BlockState> {
+        let start = y * SECTION_WIDTH * SECTION_WIDTH;
+        (start..start + SECTION_WIDTH * SECTION_WIDTH)
+            .map(|index| BlockState::from_id(self.block_states.get(index)))
+            .collect()
+    }
+
+    /// Coordinates must be below 16.
+    fn block_at(&self, x: usize, y: usize, z: usize) -> BlockState {
+        BlockState::from_id(self.block_states.get(y * SECTION_WIDTH * SECTION_WIDTH + z * SECTION_WIDTH + x))
     }
 }
 
+impl Default for ChunkSection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn block_index(x: usize, y: usize, z: usize) -> Result<usize, WorldError> {
+    if x >= SECTION_WIDTH || y >= SECTION_HEIGHT || z >= SECTION_WIDTH {
+        return Err(WorldError::BlockOutOfBounds {
+            x: x as i32,
+            y: y as i32,
+            z: z as i32,
+        });
+    }
+    Ok(y * SECTION_WIDTH * SECTION_WIDTH + z * SECTION_WIDTH + x)
+}
+
+fn biome_index(x: usize, y: usize, z: usize) -> Result<usize, WorldError> {
+    if x >= BIOME_WIDTH || y >= BIOME_HEIGHT || z >= BIOME_WIDTH {
+        return Err(WorldError::BlockOutOfBounds {
+            x: (x * BIOME_WIDTH) as i32,
+            y: (y * BIOME_HEIGHT) as i32,
+            z: (z * BIOME_WIDTH) as i32,
+        });
+    }
+    Ok(y * BIOME_WIDTH * BIOME_WIDTH + z * BIOME_WIDTH + x)
+}
+
 pub struct ChunkColumn {
+    height: WorldHeight,
     sections: Vec<ChunkSection>,
+    /// The heightmaps of [`HeightmapKind::MAINTAINED`] in that order, so the world surface first.
+    heightmaps: [Heightmap; 2],
+    /// Keyed by position relative to the column, with the world y.
+    block_entities: HashMap<BlockPos, BlockEntity>,
+    /// Whether a block entity changed since the column was last saved.
+    block_entities_dirty: bool,
+    light: ChunkLight,
+    /// Whether the light was computed since the column was built.
+    lit: bool,
+    /// Whether the column was read with block states the registry doesn't know, which loaded as
+    /// something else. Saving it would overwrite them.
+    read_only: bool,
+    /// The top-level tags of the saved chunk that aren't read, such as scheduled ticks and
+    /// structures, which are saved again as they were.
+    extra_tags: HashMap<String, Value>,
+    /// Network encodings of the column by protocol version, dropped whenever a block changes.
+    encoded: Mutex<HashMap<i32, Arc<[u8]>>>,
 }
 
 impl ChunkColumn {
-    pub fn new() -> Self {
-        let mut sections = Vec::with_capacity(24);
-        for _ in 0..24 {
+    pub fn new(height: WorldHeight) -> Self {
+        let mut sections = Vec::with_capacity(height.section_count());
+        for _ in 0..height.section_count() {
+            sections.push(ChunkSection::new());
+        }
+        Self::with_sections(height, sections)
+    }
+
+    /// Builds a column from its sections, bottom first. Missing sections at the top are empty
+    /// and extra ones are dropped.
+    pub fn with_sections(height: WorldHeight, mut sections: Vec<ChunkSection>) -> Self {
+        sections.truncate(height.section_count());
+        while sections.len() < height.section_count() {
             sections.push(ChunkSection::new());
         }
 
-        Self { sections }
+        let mut column = Self {
+            height,
+            sections,
+            heightmaps: [Heightmap::new(), Heightmap::new()],
+            block_entities: HashMap::new(),
+            block_entities_dirty: false,
+            light: ChunkLight::new(height.section_count()),
+            lit: false,
+            read_only: false,
+            extra_tags: HashMap::new(),
+            encoded: Mutex::new(HashMap::new()),
+        };
+        column.rescan_heights(column.height.max_y(), u16::MAX);
+        column
+    }
+
+    /// `x` and `z` are relative to the column and `y` is the world y.
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> Result<BlockState, WorldError> {
+        let (section, x, y, z) = self.locate(x, y, z)?;
+        self.sections[section].get_block(x, y, z)
+    }
+
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockState) -> Result<(), WorldError> {
+        let (section, local_x, local_y, local_z) = self.locate(x, y, z)?;
+        let previous = self.sections[section].block_at(local_x, local_y, local_z);
+        self.sections[section].set_block(local_x, local_y, local_z, block)?;
+        self.update_heights(local_x, local_z, y, block);
+        self.replace_block_entity(BlockPos::new(x, y, z), previous, block);
+        self.changed();
+        Ok(())
+    }
+
+    /// Sets every block in the horizontal layer at `y` to the same block.
+    pub fn fill_layer(&mut self, y: i32, block: BlockState) -> Result<(), WorldError> {
+        let (section, _, local_y, _) = self.locate(0, y, 0)?;
+        let previous = self.sections[section].clone_layer(local_y);
+        self.sections[section].fill_layer(local_y, block)?;
+        for z in 0..SECTION_WIDTH {
+            for x in 0..SECTION_WIDTH {
+                self.update_heights(x, z, y, block);
+                let pos = BlockPos::new(x as i32, y, z as i32);
+                self.replace_block_entity(pos, previous[z * SECTION_WIDTH + x], block);
+            }
+        }
+        self.changed();
+        Ok(())
+    }
+
+    /// Replaces a whole section, the lowest being 0. Panics if there is no such section.
+    pub fn set_section(&mut self, index: usize, mut section: ChunkSection) {
+        section.dirty = true;
+        let previous = std::mem::replace(&mut self.sections[index], section);
+        let bottom = self.height.min_y() + (index * SECTION_HEIGHT) as i32;
+        for y in 0..SECTION_HEIGHT {
+            for z in 0..SECTION_WIDTH {
+                for x in 0..SECTION_WIDTH {
+                    let block = self.sections[index].block_at(x, y, z);
+                    let pos = BlockPos::new(x as i32, bottom + y as i32, z as i32);
+                    self.replace_block_entity(pos, previous.block_at(x, y, z), block);
+                }
+            }
+        }
+        // Heights above the section stay, since their blocks are still there.
+        let top_height = ((index + 1) * SECTION_HEIGHT) as u16;
+        self.rescan_heights(self.height.min_y() + top_height as i32 - 1, top_height);
+        self.changed();
+    }
+
+    /// Returns the biome of the cell of 4 blocks the block is in. `x` and `z` are relative to the
+    /// column and `y` is the world y.
+    pub fn get_biome(&self, x: i32, y: i32, z: i32) -> Result<u32, WorldError> {
+        let (section, x, y, z) = self.locate(x, y, z)?;
+        self.sections[section].get_biome(x / BIOME_WIDTH, y / BIOME_HEIGHT, z / BIOME_WIDTH)
+    }
+
+    /// Sets the biome of the cell of 4 blocks the block is in.
+    pub fn set_biome(&mut self, x: i32, y: i32, z: i32, biome: u32) -> Result<(), WorldError> {
+        let (section, x, y, z) = self.locate(x, y, z)?;
+        self.sections[section].set_biome(x / BIOME_WIDTH, y / BIOME_HEIGHT, z / BIOME_WIDTH, biome)?;
+        self.changed();
+        Ok(())
+    }
+
+    /// Sets the biome of the whole column.
+    pub fn fill_biome(&mut self, biome: u32) {
+        for section in &mut self.sections {
+            section.fill_biome(biome);
+        }
+        self.changed();
+    }
+
+    fn changed(&mut self) {
+        self.encoded.get_mut().unwrap().clear();
+    }
+
+    /// Returns the heightmap if it is one of [`HeightmapKind::MAINTAINED`].
+    pub fn heightmap(&self, kind: HeightmapKind) -> Option<&Heightmap> {
+        let index = HeightmapKind::MAINTAINED.iter().position(|maintained| *maintained == kind)?;
+        Some(&self.heightmaps[index])
+    }
+
+    /// Updates the heightmaps for a block set at the world `y`, only scanning down the column
+    /// when the highest block counting for a heightmap was replaced by one that doesn't.
+    fn update_heights(&mut self, x: usize, z: usize, y: i32, block: BlockState) {
+        let flags = block_flags(block);
+        let height = (y - self.height.min_y() + 1) as u16;
+        for (index, kind) in HeightmapKind::MAINTAINED.into_iter().enumerate() {
+            let current = self.heightmaps[index].get(x, z);
+            if kind.counts(flags) {
+                if height > current {
+                    self.heightmaps[index].set(x, z, height);
+                }
+            } else if height == current {
+                let below = self.find_height(kind, x, z, y - 1);
+                self.heightmaps[index].set(x, z, below);
+            }
+        }
+    }
+
+    /// Finds the heights again from the blocks at and below the world `top`, where heights above
+    /// `max_height` are kept.
+    fn rescan_heights(&mut self, top: i32, max_height: u16) {
+        for (index, kind) in HeightmapKind::MAINTAINED.into_iter().enumerate() {
+            for z in 0..SECTION_WIDTH {
+                for x in 0..SECTION_WIDTH {
+                    if self.heightmaps[index].get(x, z) <= max_height {
+                        let height = self.find_height(kind, x, z, top);
+                        self.heightmaps[index].set(x, z, height);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the height above the bottom of the column of the block above the highest block at
+    /// or below the world `top` that counts for the heightmap, 0 if there is none.
+    fn find_height(&self, kind: HeightmapKind, x: usize, z: usize, top: i32) -> u16 {
+        let min_y = self.height.min_y();
+        if top < min_y {
+            return 0;
+        }
+        let top = (top - min_y) as usize;
+        for index in (0..=top / SECTION_HEIGHT).rev() {
+            let section = &self.sections[index];
+            // Air counts for no heightmap.
+            if section.is_empty() {
+                continue;
+            }
+            let highest = if index == top / SECTION_HEIGHT { top % SECTION_HEIGHT } else { SECTION_HEIGHT - 1 };
+            for y in (0..=highest).rev() {
+                if kind.counts(block_flags(section.block_at(x, y, z))) {
+                    return (index * SECTION_HEIGHT + y + 1) as u16;
+                }
+            }
+        }
+        0
+    }
+
+    /// Keeps the block entity at `pos` in line with a block replacing `previous`. When the block
+    /// changes, the block entity of the replaced block is removed and a block that has one gets
+    /// a new, empty one.
+    fn replace_block_entity(&mut self, pos: BlockPos, previous: BlockState, block: BlockState) {
+        if previous == block || (self.block_entities.is_empty() && !block_flags(block).block_entity) {
+            return;
+        }
+        if previous.default_state() == block.default_state() {
+            return;
+        }
+        self.remove_block_entity(pos);
+        if let Some(kind) = block_entity_kind(block) {
+            self.block_entities.insert(pos, BlockEntity::new(kind));
+            self.block_entities_dirty = true;
+        }
+    }
+
+    /// `pos` is relative to the column, with the world y.
+    pub fn block_entity(&self, pos: BlockPos) -> Option<&BlockEntity> {
+        self.block_entities.get(&pos)
+    }
+
+    pub fn block_entities(&self) -> impl Iterator<Item = (BlockPos, &BlockEntity)> {
+        self.block_entities.iter().map(|(pos, entity)| (*pos, entity))
+    }
+
+    pub fn set_block_entity(&mut self, pos: BlockPos, entity: BlockEntity) -> Result<(), WorldError> {
+        self.locate(pos.x, pos.y, pos.z)?;
+        self.block_entities.insert(pos, entity);
+        self.block_entities_dirty = true;
+        self.encoded.get_mut().unwrap().clear();
+        Ok(())
+    }
+
+    pub fn remove_block_entity(&mut self, pos: BlockPos) -> Option<BlockEntity> {
+        let entity = self.block_entities.remove(&pos);
+        if entity.is_some() {
+            self.block_entities_dirty = true;
+            self.encoded.get_mut().unwrap().clear();
+        }
+        entity
+    }
+
+    /// The light of the column, dark until it is lit. Only blocks set through the
+    /// [`World`](crate::world::World) update it.
+    pub fn light(&self) -> &ChunkLight {
+        &self.light
+    }
+
+    pub(crate) fn light_mut(&mut self) -> &mut ChunkLight {
+        self.encoded.get_mut().unwrap().clear();
+        &mut self.light
+    }
+
+    /// Returns the light level of the block, `y` going from a section below the column to a
+    /// section above it.
+    pub fn light_level(&self, kind: LightKind, x: i32, y: i32, z: i32) -> Result<u8, WorldError> {
+        let light_y = y - self.height.min_y() + SECTION_HEIGHT as i32;
+        let light_height = (self.sections.len() + 2) * SECTION_HEIGHT;
+        let width = SECTION_WIDTH as i32;
+        if !(0..width).contains(&x) || !(0..width).contains(&z) || !(0..light_height as i32).contains(&light_y) {
+            return Err(WorldError::BlockOutOfBounds { x, y, z });
+        }
+        Ok(self.light.get(kind, x as usize, light_y as usize, z as usize))
     }
 
-    pub fn get_block(&self, x: usize, y: usize, z: usize) -> u32 {
-        let section = &self.sections[y / SECTION_HEIGHT];
-        section.get_block(x, y % SECTION_HEIGHT, z)
+    /// Whether the column's light was computed.
+    pub fn is_lit(&self) -> bool {
+        self.lit
     }
 
-    pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: u32) {
-        let section = &mut self.sections[y / SECTION_HEIGHT];
-        section.set_block(x, y % SECTION_HEIGHT, z, block);
+    pub(crate) fn mark_lit(&mut self) {
+        self.lit = true;
     }
-}
\ No newline at end of file
+
+    /// Returns the y of the highest non-air block in the column at `x`, `z`.
+    pub fn highest_block(&self, x: i32, z: i32) -> Result<Option<i32>, WorldError> {
+        let (_, local_x, _, local_z) = self.locate(x, self.height.min_y(), z)?;
+        let surface = &self.heightmaps[0];
+        let height = surface.get(local_x, local_z);
+        Ok((height > 0).then(|| self.height.min_y() + height as i32 - 1))
+    }
+
+    /// Splits column coordinates into the section index and the coordinates within the section.
+    fn locate(&self, x: i32, y: i32, z: i32) -> Result<(usize, usize, usize, usize), WorldError> {
+        let width = SECTION_WIDTH as i32;
+        match self.height.section_index(y) {
+            Some(section) if (0..width).contains(&x) && (0..width).contains(&z) => {
+                let local_y = (y - self.height.min_y()) as usize % SECTION_HEIGHT;
+                Ok((section, x as usize, local_y, z as usize))
+            }
+            _ => Err(WorldError::BlockOutOfBounds { x, y, z }),
+        }
+    }
+
+    /// Whether a block changed since the column was last saved.
+    pub fn is_dirty(&self) -> bool {
+        self.block_entities_dirty || self.sections.iter().any(ChunkSection::is_dirty)
+    }
+
+    /// Makes the column count as modified, so it is saved again.
+    pub fn mark_dirty(&mut self) {
+        for section in &mut self.sections {
+            section.dirty = true;
+        }
+    }
+
+    pub fn mark_saved(&mut self) {
+        for section in &mut self.sections {
+            section.dirty = false;
+        }
+        self.block_entities_dirty = false;
+    }
+
+    /// Whether the column holds blocks that didn't load as they were saved, so it must not be
+    /// saved over them.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub(crate) fn mark_read_only(&mut self) {
+        self.read_only = true;
+    }
+
+    pub fn extra_tags(&self) -> &HashMap<String, Value> {
+        &self.extra_tags
+    }
+
+    pub(crate) fn set_extra_tags(&mut self, tags: HashMap<String, Value>) {
+        self.extra_tags = tags;
+    }
+
+    /// Returns the column encoded for the protocol version, only calling `encode` if the column
+    /// changed since it was last encoded for that version. This lets every player viewing the
+    /// chunk share one encoding.
+    pub fn encoded<E>(
+        &self,
+        protocol_version: i32,
+        encode: impl FnOnce(&Self) -> Result<Vec<u8>, E>,
+    ) -> Result<Arc<[u8]>, E> {
+        if let Some(encoded) = self.encoded.lock().unwrap().get(&protocol_version) {
+            return Ok(encoded.clone());
+        }
+
+        let encoded: Arc<[u8]> = encode(self)?.into();
+        self.encoded.lock().unwrap().insert(protocol_version, encoded.clone());
+        Ok(encoded)
+    }
+
+    pub fn sections(&self) -> &[ChunkSection] {
+        &self.sections
+    }
+
+    pub fn world_height(&self) -> WorldHeight {
+        self.height
+    }
+}
+
+impl Default for ChunkColumn {
+    fn default() -> Self {
+        Self::new(WorldHeight::default())
+    }
+}
@@ -0,0 +1,203 @@
+//! A single-file world format for small, read-mostly maps like lobbies. A bounded set of chunks
+//! is stored in one zstd-compressed file, which is loaded into memory whole.
+
+use crate::anvil::{
+    read_block_entities, read_chunk_nbt, read_sections, region_coords, write_block_entities, write_sections,
+    RegionFile, SectionNbt, DATA_VERSION, REGION_WIDTH,
+};
+use crate::chunk::ChunkColumn;
+use crate::error::{Result, WorldError};
+use crate::height::WorldHeight;
+use fastnbt::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"IOXW";
+const VERSION: u8 = 1;
+/// Maps are written rarely and read on every start, so they are compressed hard.
+const COMPRESSION_LEVEL: i32 = 19;
+/// The most chunks a compact world holds, a square of 1024 by 1024 blocks.
+pub const MAX_CHUNKS: usize = 64 * 64;
+/// The most bytes a compact world file decompresses to.
+pub const MAX_DECOMPRESSED_LEN: usize = 256 * 1024 * 1024;
+
+/// The file after the magic and version, before compression. Chunks are stored like in region
+/// files, without heightmaps and light, which are computed when needed.
+#[derive(Serialize, Deserialize)]
+struct CompactNbt {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    #[serde(rename = "MinY")]
+    min_y: i32,
+    #[serde(rename = "Height")]
+    height: i32,
+    #[serde(rename = "Chunks")]
+    chunks: Vec<CompactChunkNbt>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CompactChunkNbt {
+    #[serde(rename = "X")]
+    x: i32,
+    #[serde(rename = "Z")]
+    z: i32,
+    sections: Vec<SectionNbt>,
+    #[serde(default)]
+    block_entities: Vec<HashMap<String, Value>>,
+}
+
+/// The chunks of a compact world file.
+pub struct CompactWorld {
+    height: WorldHeight,
+    chunks: HashMap<(i32, i32), ChunkColumn>,
+}
+
+impl CompactWorld {
+    pub fn new(height: WorldHeight) -> Self {
+        Self { height, chunks: HashMap::new() }
+    }
+
+    /// Reads every fully generated chunk of a world saved in region files, from the `region`
+    /// directory of the world. Fails if there are more than [`MAX_CHUNKS`].
+    pub fn from_regions(region_directory: impl AsRef<Path>, height: WorldHeight) -> Result<Self> {
+        let mut world = Self::new(height);
+        for entry in std::fs::read_dir(region_directory)? {
+            let path = entry?.path();
+            let Some((region_x, region_z)) = region_coords(&path) else {
+                continue;
+            };
+            let mut region = RegionFile::open(path)?;
+            for z in 0..REGION_WIDTH {
+                for x in 0..REGION_WIDTH {
+                    let Some(nbt) = region.read_chunk(x, z)? else {
+                        continue;
+                    };
+                    let (chunk_x, chunk_z) = (region_x * REGION_WIDTH + x, region_z * REGION_WIDTH + z);
+                    if let Some(chunk) = read_chunk_nbt(chunk_x, chunk_z, &nbt, height)? {
+                        world.insert_chunk(chunk_x, chunk_z, chunk)?;
+                    }
+                }
+            }
+        }
+        Ok(world)
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let invalid = |message: String| WorldError::InvalidCompactWorld(message);
+        let body = bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| invalid("not a compact world file".to_string()))?;
+        let (&version, body) = body.split_first().ok_or_else(|| invalid("missing version".to_string()))?;
+        if version != VERSION {
+            return Err(invalid(format!("unsupported version {}", version)));
+        }
+
+        // A file can decompress to far more than it holds, so it is only read up to the limit.
+        let mut nbt = Vec::new();
+        zstd::Decoder::new(body)?.take(MAX_DECOMPRESSED_LEN as u64 + 1).read_to_end(&mut nbt)?;
+        if nbt.len() > MAX_DECOMPRESSED_LEN {
+            return Err(invalid(format!("decompresses to more than {} bytes", MAX_DECOMPRESSED_LEN)));
+        }
+        let world: CompactNbt = fastnbt::from_bytes(&nbt).map_err(|e| invalid(e.to_string()))?;
+        if world.chunks.len() > MAX_CHUNKS {
+            return Err(invalid(format!("{} chunks, more than {}", world.chunks.len(), MAX_CHUNKS)));
+        }
+        let height = WorldHeight::new(world.min_y, world.height)?;
+        let mut chunks = HashMap::with_capacity(world.chunks.len());
+        for chunk in world.chunks {
+            let invalid = |message: String| WorldError::InvalidChunk { x: chunk.x, z: chunk.z, message };
+            let mut unknown = false;
+            let sections = read_sections(chunk.sections, height, &mut unknown, &invalid)?;
+            let mut column = ChunkColumn::with_sections(height, sections);
+            read_block_entities(&mut column, chunk.block_entities, &invalid)?;
+            column.mark_saved();
+            if unknown {
+                column.mark_read_only();
+            }
+            chunks.insert((chunk.x, chunk.z), column);
+        }
+        Ok(Self { height, chunks })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut keys: Vec<_> = self.chunks.keys().copied().collect();
+        keys.sort_unstable();
+        let chunks = keys
+            .into_iter()
+            .map(|(x, z)| {
+                let column = &self.chunks[&(x, z)];
+                if column.is_read_only() {
+                    return Err(WorldError::InvalidCompactWorld(format!(
+                        "chunk {}, {} has block states this version doesn't know",
+                        x, z
+                    )));
+                }
+                Ok(CompactChunkNbt {
+                    x,
+                    z,
+                    sections: write_sections(column)?,
+                    block_entities: write_block_entities(x, z, column),
+                })
+            })
+            .collect::<Result<_>>()?;
+        let world = CompactNbt {
+            data_version: DATA_VERSION,
+            min_y: self.height.min_y(),
+            height: self.height.height(),
+            chunks,
+        };
+        let nbt = fastnbt::to_bytes(&world).map_err(|e| WorldError::InvalidCompactWorld(e.to_string()))?;
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend(zstd::encode_all(nbt.as_slice(), COMPRESSION_LEVEL)?);
+        Ok(bytes)
+    }
+
+    pub fn height(&self) -> WorldHeight {
+        self.height
+    }
+
+    /// Adds a chunk, replacing any chunk at the same coordinates. Fails if the world already has
+    /// [`MAX_CHUNKS`].
+    pub fn insert_chunk(&mut self, x: i32, z: i32, chunk: ChunkColumn) -> Result<()> {
+        if chunk.world_height() != self.height {
+            return Err(WorldError::InvalidCompactWorld(format!(
+                "chunk {}, {} doesn't have the height of the world",
+                x, z
+            )));
+        }
+        if self.chunks.len() >= MAX_CHUNKS && !self.chunks.contains_key(&(x, z)) {
+            return Err(WorldError::InvalidCompactWorld(format!("more than {} chunks", MAX_CHUNKS)));
+        }
+        self.chunks.insert((x, z), chunk);
+        Ok(())
+    }
+
+    pub fn chunk(&self, x: i32, z: i32) -> Option<&ChunkColumn> {
+        self.chunks.get(&(x, z))
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn into_chunks(self) -> impl Iterator<Item = ((i32, i32), ChunkColumn)> {
+        self.chunks.into_iter()
+    }
+}
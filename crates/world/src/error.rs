@@ -0,0 +1,38 @@
+use std::io;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum WorldError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Block {x}, {y}, {z} is outside the world")]
+    BlockOutOfBounds { x: i32, y: i32, z: i32 },
+    #[error("Invalid world height: min_y {min_y} and height {height} must be multiples of 16 and the height positive")]
+    InvalidHeight { min_y: i32, height: i32 },
+    #[error("Unknown block: {0}")]
+    UnknownBlock(String),
+    #[error("Unknown block state: {0}")]
+    UnknownBlockState(u32),
+    #[error("Invalid block state: {0}")]
+    InvalidBlockState(String),
+    #[error("{block} has no property {property} with value {value}")]
+    InvalidProperty { block: String, property: String, value: String },
+    #[error("Invalid block report: {0}")]
+    InvalidBlockReport(String),
+    #[error("Unknown biome: {0}")]
+    UnknownBiome(String),
+    #[error("Invalid region file: {0}")]
+    InvalidRegion(String),
+    #[error("Invalid chunk {x}, {z}: {message}")]
+    InvalidChunk { x: i32, z: i32, message: String },
+    #[error("Failed to load chunk {x}, {z}: {message}")]
+    ChunkLoad { x: i32, z: i32, message: String },
+    #[error("Invalid generator preset: {0}")]
+    InvalidGeneratorPreset(String),
+    #[error("Invalid compact world: {0}")]
+    InvalidCompactWorld(String),
+    #[error("Invalid schematic: {0}")]
+    InvalidSchematic(String),
+}
+
+pub type Result<T> = std::result::Result<T, WorldError>;
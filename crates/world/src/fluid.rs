@@ -0,0 +1,121 @@
+use crate::block::BlockState;
+
+/// Blocks that are always full of water, though they have no `waterlogged` property.
+const WATER_FILLED_BLOCKS: [&str; 5] = [
+    "minecraft:bubble_column",
+    "minecraft:kelp",
+    "minecraft:kelp_plant",
+    "minecraft:seagrass",
+    "minecraft:tall_seagrass",
+];
+
+/// A fluid that flows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fluid {
+    Water,
+    Lava,
+}
+
+impl Fluid {
+    /// The name of the block of the fluid.
+    pub const fn block_name(self) -> &'static str {
+        match self {
+            Fluid::Water => "minecraft:water",
+            Fluid::Lava => "minecraft:lava",
+        }
+    }
+
+    /// The source block of the fluid, or air if the block registry doesn't have it.
+    pub fn source_block(self) -> BlockState {
+        BlockState::default_of(self.block_name()).unwrap_or(BlockState::AIR)
+    }
+
+    /// How many ticks the fluid waits before flowing on.
+    pub const fn tick_delay(self) -> u64 {
+        match self {
+            Fluid::Water => 5,
+            Fluid::Lava => 30,
+        }
+    }
+
+    /// How far the fluid looks for a hole to flow towards.
+    pub const fn slope_find_distance(self) -> u32 {
+        match self {
+            Fluid::Water => 4,
+            Fluid::Lava => 2,
+        }
+    }
+
+    /// How much the fluid's amount drops with each block it flows sideways.
+    pub const fn drop_off(self) -> u8 {
+        match self {
+            Fluid::Water => 1,
+            Fluid::Lava => 2,
+        }
+    }
+
+    /// Whether a block between two sources of the fluid becomes a source too.
+    pub const fn converts_to_source(self) -> bool {
+        matches!(self, Fluid::Water)
+    }
+}
+
+/// The fluid in a block: a source, which holds 8, or flowing with 1 to 8, 8 only when it falls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FluidState {
+    pub fluid: Fluid,
+    pub amount: u8,
+    pub falling: bool,
+}
+
+impl FluidState {
+    pub const fn source(fluid: Fluid) -> Self {
+        Self { fluid, amount: 8, falling: false }
+    }
+
+    pub const fn flowing(fluid: Fluid, amount: u8, falling: bool) -> Self {
+        Self { fluid, amount, falling }
+    }
+
+    pub const fn is_source(self) -> bool {
+        self.amount == 8 && !self.falling
+    }
+
+    /// Returns the fluid in the block, if any. Waterlogged blocks hold a water source.
+    pub fn of(state: BlockState) -> Option<Self> {
+        if state.get("waterlogged") == Some("true") {
+            return Some(Self::source(Fluid::Water));
+        }
+        let fluid = match state.name()? {
+            "minecraft:water" => Fluid::Water,
+            "minecraft:lava" => Fluid::Lava,
+            name if WATER_FILLED_BLOCKS.contains(&name) => return Some(Self::source(Fluid::Water)),
+            _ => return None,
+        };
+        let level: u8 = state.get("level")?.parse().ok()?;
+        Some(match level {
+            0 => Self::source(fluid),
+            1..=7 => Self::flowing(fluid, 8 - level, false),
+            _ => Self::flowing(fluid, 8, true),
+        })
+    }
+
+    /// Returns the block of the fluid on its own, which carries its amount as the `level`
+    /// property: 0 for a source, 1 to 7 the less there is, and 8 falling.
+    pub fn block(self) -> BlockState {
+        let level = if self.is_source() {
+            0
+        } else if self.falling {
+            8
+        } else {
+            8 - self.amount.min(7)
+        };
+        let source = self.fluid.source_block();
+        source.with("level", &level.to_string()).unwrap_or(source)
+    }
+
+    /// How high the fluid fills its block, from 0 to 1.
+    pub fn height(self) -> f32 {
+        self.amount as f32 / 9.0
+    }
+}
@@ -0,0 +1,98 @@
+use crate::block::BlockState;
+use crate::chunk::ChunkColumn;
+use crate::error::{Result, WorldError};
+use crate::generator::{biome_id, ChunkGenerator};
+
+/// The terrain the server generated before generators were configurable.
+pub const DEFAULT_FLAT_PRESET: &str = "minecraft:stone,3*minecraft:dirt,minecraft:grass_block;minecraft:plains";
+const DEFAULT_BIOME: &str = "minecraft:plains";
+
+/// Generates the same layers of blocks in every chunk, like vanilla's superflat worlds.
+#[derive(Debug)]
+pub struct FlatGenerator {
+    /// The layers from the bottom of the world up.
+    layers: Vec<BlockState>,
+    biome: u32,
+}
+
+impl FlatGenerator {
+    pub fn new(layers: Vec<BlockState>, biome: u32) -> Self {
+        Self { layers, biome }
+    }
+
+    /// Parses a vanilla superflat preset like `minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains`:
+    /// the layers from the bottom up, each optionally repeated, then optionally the biome.
+    /// Blocks may have properties, as in `minecraft:snow[layers=2]`.
+    pub fn from_preset(preset: &str) -> Result<Self> {
+        let invalid = |message: String| WorldError::InvalidGeneratorPreset(format!("{}: {}", preset, message));
+        let (layers_part, biome) = match split_top_level(preset, ';').as_slice() {
+            [layers] => (*layers, None),
+            [layers, biome] => (*layers, Some(biome.trim())),
+            _ => return Err(invalid("expected the layers and at most a biome".to_string())),
+        };
+
+        let mut layers = Vec::new();
+        for layer in split_top_level(layers_part, ',') {
+            let layer = layer.trim();
+            let (count, block) = match layer.split_once('*') {
+                Some((count, block)) if !count.contains('[') => {
+                    let count: usize = count
+                        .trim()
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid layer count in {}", layer)))?;
+                    (count, block.trim())
+                }
+                _ => (1, layer),
+            };
+            if count == 0 || block.is_empty() {
+                return Err(invalid(format!("empty layer {}", layer)));
+            }
+            let block = BlockState::parse(block)?;
+            layers.extend(std::iter::repeat_n(block, count));
+        }
+
+        let biome = match biome {
+            Some(biome) => biome_id(biome)?,
+            None => biome_id(DEFAULT_BIOME).unwrap_or_default(),
+        };
+        Ok(Self::new(layers, biome))
+    }
+
+    pub fn layers(&self) -> &[BlockState] {
+        &self.layers
+    }
+}
+
+impl ChunkGenerator for FlatGenerator {
+    fn generate(&self, _chunk_x: i32, _chunk_z: i32, column: &mut ChunkColumn) -> Result<()> {
+        column.fill_biome(self.biome);
+        let height = column.world_height();
+        // Layers above the top of the world are left out.
+        for (y, block) in (height.min_y()..=height.max_y()).zip(self.layers.iter().copied()) {
+            if !block.is_air() {
+                column.fill_layer(y, block)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits at separators outside of block properties.
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(&text[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
@@ -0,0 +1,30 @@
+//! Terrain generation for chunks that were never saved.
+
+mod flat;
+mod noise;
+mod perlin;
+mod void;
+
+pub use flat::{FlatGenerator, DEFAULT_FLAT_PRESET};
+pub use noise::{NoiseGenerator, SEA_LEVEL};
+pub use void::VoidGenerator;
+
+pub(crate) use perlin::Random;
+
+use crate::biome::biome_registry;
+use crate::chunk::ChunkColumn;
+use crate::error::{Result, WorldError};
+
+/// Generates the terrain of chunks. A world owns one generator, which may be asked for chunks from
+/// several threads at once.
+pub trait ChunkGenerator: Send + Sync {
+    /// Fills a new, empty column with the terrain of the chunk.
+    fn generate(&self, chunk_x: i32, chunk_z: i32, column: &mut ChunkColumn) -> Result<()>;
+}
+
+/// Returns the ID of the biome in the installed registry.
+fn biome_id(name: &str) -> Result<u32> {
+    biome_registry()
+        .and_then(|registry| registry.id(name))
+        .ok_or_else(|| WorldError::UnknownBiome(name.to_string()))
+}
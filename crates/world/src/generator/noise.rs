@@ -0,0 +1,281 @@
+use crate::block::BlockState;
+use crate::chunk::{
+    ChunkColumn, ChunkSection, BIOME_VOLUME, BIOME_WIDTH, SECTION_HEIGHT, SECTION_VOLUME, SECTION_WIDTH,
+};
+use crate::error::Result;
+use crate::generator::perlin::{lerp, OctaveNoise, Random};
+use crate::generator::{biome_id, ChunkGenerator};
+use crate::palette::{PaletteType, PalettedContainer};
+
+/// The y up to which oceans and lakes are filled with water.
+pub const SEA_LEVEL: i32 = 63;
+/// Density is sampled at the corners of cells of this size and interpolated in between.
+const CELL_WIDTH: usize = 4;
+const CELL_HEIGHT: usize = 8;
+/// How many blocks of height one unit of density stands for.
+const SQUASH: f64 = 10.0;
+/// Cave noise above this is carved out.
+const CAVE_THRESHOLD: f64 = 0.25;
+/// Climate noise below this is cold and above the negation hot.
+const COLD: f64 = -0.2;
+/// How many layers at the bottom of the world may be bedrock.
+const BEDROCK_LAYERS: i32 = 5;
+
+/// Generates hills, mountains, oceans and caves from noise, the same for the same seed.
+pub struct NoiseGenerator {
+    seed: u64,
+    /// Where the land is, low values are ocean.
+    continents: OctaveNoise,
+    hills: OctaveNoise,
+    /// Three dimensional variation of the surface, making overhangs.
+    detail: OctaveNoise,
+    caves: OctaveNoise,
+    temperature: OctaveNoise,
+    humidity: OctaveNoise,
+    blocks: Blocks,
+    biomes: Biomes,
+}
+
+struct Blocks {
+    stone: BlockState,
+    dirt: BlockState,
+    grass: BlockState,
+    sand: BlockState,
+    gravel: BlockState,
+    water: BlockState,
+    bedrock: BlockState,
+}
+
+struct Biomes {
+    ocean: u32,
+    frozen_ocean: u32,
+    warm_ocean: u32,
+    beach: u32,
+    snowy_beach: u32,
+    plains: u32,
+    forest: u32,
+    desert: u32,
+    savanna: u32,
+    snowy_plains: u32,
+    windswept_hills: u32,
+    snowy_slopes: u32,
+}
+
+/// Which blocks make up the top of the ground.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Surface {
+    Grass,
+    Sand,
+    SeaFloor,
+    Mountain,
+}
+
+impl NoiseGenerator {
+    /// Fails if the installed block or biome registry lacks the blocks or biomes of the terrain.
+    pub fn new(seed: i64) -> Result<Self> {
+        let blocks = Blocks {
+            stone: BlockState::default_of("minecraft:stone")?,
+            dirt: BlockState::default_of("minecraft:dirt")?,
+            grass: BlockState::default_of("minecraft:grass_block")?,
+            sand: BlockState::default_of("minecraft:sand")?,
+            gravel: BlockState::default_of("minecraft:gravel")?,
+            water: BlockState::default_of("minecraft:water")?,
+            bedrock: BlockState::default_of("minecraft:bedrock")?,
+        };
+        let biomes = Biomes {
+            ocean: biome_id("minecraft:ocean")?,
+            frozen_ocean: biome_id("minecraft:frozen_ocean")?,
+            warm_ocean: biome_id("minecraft:warm_ocean")?,
+            beach: biome_id("minecraft:beach")?,
+            snowy_beach: biome_id("minecraft:snowy_beach")?,
+            plains: biome_id("minecraft:plains")?,
+            forest: biome_id("minecraft:forest")?,
+            desert: biome_id("minecraft:desert")?,
+            savanna: biome_id("minecraft:savanna")?,
+            snowy_plains: biome_id("minecraft:snowy_plains")?,
+            windswept_hills: biome_id("minecraft:windswept_hills")?,
+            snowy_slopes: biome_id("minecraft:snowy_slopes")?,
+        };
+
+        let seed = seed as u64;
+        let mut random = Random::new(seed);
+        Ok(Self {
+            seed,
+            continents: OctaveNoise::new(&mut random, 4, 1.0 / 800.0),
+            hills: OctaveNoise::new(&mut random, 4, 1.0 / 200.0),
+            detail: OctaveNoise::new(&mut random, 3, 1.0 / 48.0),
+            caves: OctaveNoise::new(&mut random, 2, 1.0 / 40.0),
+            temperature: OctaveNoise::new(&mut random, 3, 1.0 / 1000.0),
+            humidity: OctaveNoise::new(&mut random, 3, 1.0 / 900.0),
+            blocks,
+            biomes,
+        })
+    }
+
+    /// The height the terrain tends towards at the column, before overhangs and caves.
+    fn surface_height(&self, x: f64, z: f64) -> f64 {
+        let continent = (self.continents.sample_2d(x, z) * 2.5 + 0.2).clamp(-1.0, 1.0);
+        let mountains = ((continent - 0.2) / 0.8).max(0.0);
+        let hills = self.hills.sample_2d(x, z);
+        let peaks = mountains * (hills + 0.5).max(0.0) * 80.0;
+        SEA_LEVEL as f64 + 4.0 + continent * 28.0 + hills * 8.0 + peaks
+    }
+
+    /// Greater than 0 where the ground is solid, given the surface height of the column.
+    fn density(&self, x: f64, y: f64, z: f64, surface_height: f64) -> f64 {
+        (surface_height - y) / SQUASH + self.detail.sample(x, y * 1.5, z) * 0.6
+    }
+
+    fn biome(&self, x: f64, z: f64) -> (u32, Surface) {
+        let height = self.surface_height(x, z);
+        let temperature = self.temperature.sample_2d(x, z);
+        let humidity = self.humidity.sample_2d(x, z);
+        let biomes = &self.biomes;
+        let sea_level = SEA_LEVEL as f64;
+        if height < sea_level - 3.0 {
+            let biome = if temperature < COLD {
+                biomes.frozen_ocean
+            } else if temperature > -COLD {
+                biomes.warm_ocean
+            } else {
+                biomes.ocean
+            };
+            (biome, Surface::SeaFloor)
+        } else if height < sea_level + 2.0 {
+            let biome = if temperature < COLD { biomes.snowy_beach } else { biomes.beach };
+            (biome, Surface::Sand)
+        } else if height > sea_level + 45.0 {
+            let biome = if temperature < 0.0 { biomes.snowy_slopes } else { biomes.windswept_hills };
+            (biome, Surface::Mountain)
+        } else if temperature < COLD {
+            (biomes.snowy_plains, Surface::Grass)
+        } else if temperature > -COLD && humidity < 0.0 {
+            (biomes.desert, Surface::Sand)
+        } else if temperature > -COLD {
+            (biomes.savanna, Surface::Grass)
+        } else if humidity > 0.1 {
+            (biomes.forest, Surface::Grass)
+        } else {
+            (biomes.plains, Surface::Grass)
+        }
+    }
+
+    /// The block `depth` blocks below the top of the ground.
+    fn surface_block(&self, surface: Surface, depth: i32, y: i32) -> BlockState {
+        let blocks = &self.blocks;
+        match surface {
+            Surface::Grass if depth == 0 && y >= SEA_LEVEL => blocks.grass,
+            Surface::Grass if depth < 4 => blocks.dirt,
+            Surface::Sand if depth < 4 => blocks.sand,
+            Surface::SeaFloor if depth < 3 && y < SEA_LEVEL - 10 => blocks.gravel,
+            Surface::SeaFloor if depth < 3 => blocks.sand,
+            Surface::Mountain if depth == 0 && y < SEA_LEVEL + 60 => blocks.grass,
+            Surface::Mountain if depth < 3 && y < SEA_LEVEL + 60 => blocks.dirt,
+            _ => blocks.stone,
+        }
+    }
+
+    /// Bedrock thins out over the bottom layers, differently in every column.
+    fn is_bedrock(&self, x: i32, y: i32, z: i32, min_y: i32) -> bool {
+        let layer = y - min_y;
+        if layer == 0 {
+            return true;
+        }
+        if layer >= BEDROCK_LAYERS {
+            return false;
+        }
+        let position = (x as u64).wrapping_mul(0x9E37_79B9) ^ (y as u64).wrapping_mul(0x85EB_CA6B) ^ (z as u64).wrapping_mul(0xC2B2_AE35);
+        Random::new(self.seed ^ position).next_f64() < (BEDROCK_LAYERS - layer) as f64 / BEDROCK_LAYERS as f64
+    }
+}
+
+impl ChunkGenerator for NoiseGenerator {
+    fn generate(&self, chunk_x: i32, chunk_z: i32, column: &mut ChunkColumn) -> Result<()> {
+        let height = column.world_height();
+        let min_y = height.min_y();
+        let (base_x, base_z) = (chunk_x * SECTION_WIDTH as i32, chunk_z * SECTION_WIDTH as i32);
+
+        // Density and caves at the corners of every cell of the chunk.
+        let corners = SECTION_WIDTH / CELL_WIDTH + 1;
+        let corners_y = height.height() as usize / CELL_HEIGHT + 1;
+        let corner = |x: usize, y: usize, z: usize| (x * corners + z) * corners_y + y;
+        let mut density = vec![0.0; corners * corners * corners_y];
+        let mut caves = vec![0.0; density.len()];
+        for x in 0..corners {
+            for z in 0..corners {
+                let world_x = (base_x + (x * CELL_WIDTH) as i32) as f64;
+                let world_z = (base_z + (z * CELL_WIDTH) as i32) as f64;
+                let surface_height = self.surface_height(world_x, world_z);
+                for y in 0..corners_y {
+                    let world_y = (min_y + (y * CELL_HEIGHT) as i32) as f64;
+                    density[corner(x, y, z)] = self.density(world_x, world_y, world_z, surface_height);
+                    caves[corner(x, y, z)] = self.caves.sample(world_x, world_y * 2.0, world_z);
+                }
+            }
+        }
+        let interpolate = |values: &[f64], x: usize, y: usize, z: usize| {
+            let (cell_x, cell_y, cell_z) = (x / CELL_WIDTH, y / CELL_HEIGHT, z / CELL_WIDTH);
+            let tx = (x % CELL_WIDTH) as f64 / CELL_WIDTH as f64;
+            let ty = (y % CELL_HEIGHT) as f64 / CELL_HEIGHT as f64;
+            let tz = (z % CELL_WIDTH) as f64 / CELL_WIDTH as f64;
+            let at = |dx: usize, dy: usize, dz: usize| values[corner(cell_x + dx, cell_y + dy, cell_z + dz)];
+            lerp(
+                ty,
+                lerp(tz, lerp(tx, at(0, 0, 0), at(1, 0, 0)), lerp(tx, at(0, 0, 1), at(1, 0, 1))),
+                lerp(tz, lerp(tx, at(0, 1, 0), at(1, 1, 0)), lerp(tx, at(0, 1, 1), at(1, 1, 1))),
+            )
+        };
+
+        let cells = SECTION_WIDTH / BIOME_WIDTH;
+        let mut biomes = Vec::with_capacity(cells * cells);
+        for z in 0..cells {
+            for x in 0..cells {
+                let center = |base: i32, cell: usize| (base + (cell * BIOME_WIDTH + BIOME_WIDTH / 2) as i32) as f64;
+                biomes.push(self.biome(center(base_x, x), center(base_z, z)));
+            }
+        }
+
+        let mut blocks = vec![vec![0u32; SECTION_VOLUME]; height.section_count()];
+        for z in 0..SECTION_WIDTH {
+            for x in 0..SECTION_WIDTH {
+                let (world_x, world_z) = (base_x + x as i32, base_z + z as i32);
+                let (_, surface) = biomes[z / BIOME_WIDTH * cells + x / BIOME_WIDTH];
+                // Caves stay below the ground so they don't cut into the surface.
+                let cave_ceiling = self.surface_height(world_x as f64, world_z as f64) as i32 - 8;
+                let mut depth = None;
+                for y in (0..height.height() as usize).rev() {
+                    let world_y = min_y + y as i32;
+                    let block = if self.is_bedrock(world_x, world_y, world_z, min_y) {
+                        self.blocks.bedrock
+                    } else if interpolate(&density, x, y, z) > 0.0 {
+                        let below_top = depth.map_or(0, |depth| depth + 1);
+                        depth = Some(below_top);
+                        let carved = world_y < cave_ceiling
+                            && world_y >= min_y + BEDROCK_LAYERS
+                            && interpolate(&caves, x, y, z) > CAVE_THRESHOLD;
+                        if carved {
+                            BlockState::AIR
+                        } else {
+                            self.surface_block(surface, below_top, world_y)
+                        }
+                    } else if depth.is_none() && world_y <= SEA_LEVEL {
+                        self.blocks.water
+                    } else {
+                        BlockState::AIR
+                    };
+                    let index = (y % SECTION_HEIGHT * SECTION_WIDTH + z) * SECTION_WIDTH + x;
+                    blocks[y / SECTION_HEIGHT][index] = block.id();
+                }
+            }
+        }
+
+        // Biomes only vary horizontally, the same cells repeat in every layer.
+        let section_biomes: Vec<u32> = (0..BIOME_VOLUME).map(|index| biomes[index % (cells * cells)].0).collect();
+        let biomes = PalettedContainer::from_values(PaletteType::Biome, &section_biomes);
+        for (index, blocks) in blocks.iter().enumerate() {
+            let block_states = PalettedContainer::from_values(PaletteType::Block, blocks);
+            column.set_section(index, ChunkSection::from_containers(block_states, biomes.clone()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+//! Seeded gradient noise for terrain generation.
+
+/// SplitMix64, a small generator that is enough to shuffle noise permutations.
+pub(crate) struct Random(u64);
+
+impl Random {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0.0..1.0`.
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Improved Perlin noise, returning values in about `-1.0..1.0`.
+pub(crate) struct Perlin {
+    /// A shuffled permutation of 0..256, repeated so lookups don't need to wrap.
+    permutation: [u8; 512],
+    /// Moves the lattice so that integer coordinates don't always sample 0.
+    offset: [f64; 3],
+}
+
+impl Perlin {
+    pub(crate) fn new(random: &mut Random) -> Self {
+        let offset = [random.next_f64() * 256.0, random.next_f64() * 256.0, random.next_f64() * 256.0];
+        let mut values: [u8; 256] = std::array::from_fn(|i| i as u8);
+        for i in (1..values.len()).rev() {
+            values.swap(i, (random.next_u64() % (i as u64 + 1)) as usize);
+        }
+        let permutation = std::array::from_fn(|i| values[i % 256]);
+        Self { permutation, offset }
+    }
+
+    pub(crate) fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x, y, z) = (x + self.offset[0], y + self.offset[1], z + self.offset[2]);
+        let (floor_x, floor_y, floor_z) = (x.floor(), y.floor(), z.floor());
+        let (x, y, z) = (x - floor_x, y - floor_y, z - floor_z);
+        let cell_x = (floor_x as i64 & 255) as usize;
+        let cell_y = (floor_y as i64 & 255) as usize;
+        let cell_z = (floor_z as i64 & 255) as usize;
+
+        let p = &self.permutation;
+        let a = p[cell_x] as usize + cell_y;
+        let b = p[cell_x + 1] as usize + cell_y;
+        let (aa, ab) = (p[a] as usize + cell_z, p[a + 1] as usize + cell_z);
+        let (ba, bb) = (p[b] as usize + cell_z, p[b + 1] as usize + cell_z);
+
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p[aa], x, y, z), grad(p[ba], x - 1.0, y, z)),
+                lerp(u, grad(p[ab], x, y - 1.0, z), grad(p[bb], x - 1.0, y - 1.0, z)),
+            ),
+            lerp(
+                v,
+                lerp(u, grad(p[aa + 1], x, y, z - 1.0), grad(p[ba + 1], x - 1.0, y, z - 1.0)),
+                lerp(u, grad(p[ab + 1], x, y - 1.0, z - 1.0), grad(p[bb + 1], x - 1.0, y - 1.0, z - 1.0)),
+            ),
+        )
+    }
+}
+
+/// Several layers of Perlin noise, each at twice the frequency and half the amplitude of the last.
+pub(crate) struct OctaveNoise {
+    octaves: Vec<Perlin>,
+    /// The frequency of the first octave, in cycles per block.
+    frequency: f64,
+}
+
+impl OctaveNoise {
+    pub(crate) fn new(random: &mut Random, octaves: usize, frequency: f64) -> Self {
+        Self {
+            octaves: (0..octaves).map(|_| Perlin::new(random)).collect(),
+            frequency,
+        }
+    }
+
+    /// Returns the sum of the octaves, scaled back to about `-1.0..1.0`.
+    pub(crate) fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let mut frequency = self.frequency;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut max = 0.0;
+        for octave in &self.octaves {
+            total += octave.sample(x * frequency, y * frequency, z * frequency) * amplitude;
+            max += amplitude;
+            frequency *= 2.0;
+            amplitude /= 2.0;
+        }
+        total / max
+    }
+
+    pub(crate) fn sample_2d(&self, x: f64, z: f64) -> f64 {
+        self.sample(x, 0.0, z)
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+pub(crate) fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// The dot product of the offset with one of 12 gradient directions picked by the hash.
+fn grad(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let hash = hash & 15;
+    let u = if hash < 8 { x } else { y };
+    let v = if hash < 4 {
+        y
+    } else if hash == 12 || hash == 14 {
+        x
+    } else {
+        z
+    };
+    (if hash & 1 == 0 { u } else { -u }) + (if hash & 2 == 0 { v } else { -v })
+}
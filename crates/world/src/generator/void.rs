@@ -0,0 +1,23 @@
+use crate::chunk::ChunkColumn;
+use crate::error::Result;
+use crate::generator::{biome_id, ChunkGenerator};
+
+/// Generates empty chunks in the void biome, for maps that are built rather than generated.
+#[derive(Debug, Default)]
+pub struct VoidGenerator {
+    biome: u32,
+}
+
+impl VoidGenerator {
+    /// Uses `minecraft:the_void` if the biome registry has it.
+    pub fn new() -> Self {
+        Self { biome: biome_id("minecraft:the_void").unwrap_or_default() }
+    }
+}
+
+impl ChunkGenerator for VoidGenerator {
+    fn generate(&self, _chunk_x: i32, _chunk_z: i32, column: &mut ChunkColumn) -> Result<()> {
+        column.fill_biome(self.biome);
+        Ok(())
+    }
+}
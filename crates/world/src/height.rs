@@ -0,0 +1,59 @@
+use crate::chunk::SECTION_HEIGHT;
+use crate::error::{Result, WorldError};
+
+/// The vertical extent of a dimension, which the client takes from its dimension type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorldHeight {
+    min_y: i32,
+    section_count: usize,
+}
+
+impl WorldHeight {
+    /// The height of the vanilla overworld, from -64 up to 319.
+    pub const OVERWORLD: Self = Self { min_y: -64, section_count: 24 };
+
+    /// Fails unless both the bottom and the height line up with sections, as the client requires.
+    pub fn new(min_y: i32, height: i32) -> Result<Self> {
+        let section_height = SECTION_HEIGHT as i32;
+        if height <= 0 || min_y % section_height != 0 || height % section_height != 0 {
+            return Err(WorldError::InvalidHeight { min_y, height });
+        }
+        Ok(Self {
+            min_y,
+            section_count: (height / section_height) as usize,
+        })
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.min_y
+    }
+
+    /// The highest y a block can be at.
+    pub fn max_y(&self) -> i32 {
+        self.min_y + self.height() - 1
+    }
+
+    pub fn height(&self) -> i32 {
+        (self.section_count * SECTION_HEIGHT) as i32
+    }
+
+    pub fn section_count(&self) -> usize {
+        self.section_count
+    }
+
+    pub fn contains(&self, y: i32) -> bool {
+        (self.min_y..=self.max_y()).contains(&y)
+    }
+
+    /// Returns the index of the section holding the y, counted from the bottom of the world.
+    pub fn section_index(&self, y: i32) -> Option<usize> {
+        self.contains(y)
+            .then(|| (y - self.min_y) as usize / SECTION_HEIGHT)
+    }
+}
+
+impl Default for WorldHeight {
+    fn default() -> Self {
+        Self::OVERWORLD
+    }
+}
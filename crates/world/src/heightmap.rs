@@ -0,0 +1,117 @@
+use crate::block::BlockFlags;
+use crate::chunk::SECTION_WIDTH;
+
+/// The heightmaps vanilla keeps for a chunk, named as in the chunk NBT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeightmapKind {
+    WorldSurfaceWg,
+    WorldSurface,
+    OceanFloorWg,
+    OceanFloor,
+    MotionBlocking,
+    MotionBlockingNoLeaves,
+}
+
+impl HeightmapKind {
+    pub const ALL: [HeightmapKind; 6] = [
+        HeightmapKind::WorldSurfaceWg,
+        HeightmapKind::WorldSurface,
+        HeightmapKind::OceanFloorWg,
+        HeightmapKind::OceanFloor,
+        HeightmapKind::MotionBlocking,
+        HeightmapKind::MotionBlockingNoLeaves,
+    ];
+
+    /// The heightmaps every column keeps up to date as its blocks change, the ones the client
+    /// needs.
+    pub const MAINTAINED: [HeightmapKind; 2] = [HeightmapKind::WorldSurface, HeightmapKind::MotionBlocking];
+
+    /// Whether a block counts for the heightmap. The world surface heightmaps count every block
+    /// but air and the motion blocking one counts blocks that stop movement or hold a fluid. The
+    /// flags don't tell leaves or waterlogged blocks apart, so the other heightmaps are only
+    /// approximated by the motion blocking one.
+    pub fn counts(&self, flags: BlockFlags) -> bool {
+        match self {
+            HeightmapKind::WorldSurfaceWg | HeightmapKind::WorldSurface => !flags.air,
+            _ => flags.motion_blocking,
+        }
+    }
+
+    pub fn nbt_name(&self) -> &'static str {
+        match self {
+            HeightmapKind::WorldSurfaceWg => "WORLD_SURFACE_WG",
+            HeightmapKind::WorldSurface => "WORLD_SURFACE",
+            HeightmapKind::OceanFloorWg => "OCEAN_FLOOR_WG",
+            HeightmapKind::OceanFloor => "OCEAN_FLOOR",
+            HeightmapKind::MotionBlocking => "MOTION_BLOCKING",
+            HeightmapKind::MotionBlockingNoLeaves => "MOTION_BLOCKING_NO_LEAVES",
+        }
+    }
+
+    pub fn from_nbt_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.nbt_name() == name)
+    }
+}
+
+/// For each column of a chunk, the height above the bottom of the world of the block above the
+/// highest block that counts for the heightmap, 0 if there is none.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heightmap {
+    heights: Vec<u16>,
+}
+
+impl Heightmap {
+    pub fn new() -> Self {
+        Self {
+            heights: vec![0; SECTION_WIDTH * SECTION_WIDTH],
+        }
+    }
+
+    /// `x` and `z` are relative to the chunk.
+    pub fn get(&self, x: usize, z: usize) -> u16 {
+        self.heights[z * SECTION_WIDTH + x]
+    }
+
+    pub fn set(&mut self, x: usize, z: usize, height: u16) {
+        self.heights[z * SECTION_WIDTH + x] = height;
+    }
+
+    /// Packs the heights into longs the way both the chunk NBT and the network do, with as many
+    /// bits per height as a world of the given height needs and no height spanning two longs.
+    pub fn pack(&self, world_height: i32) -> Vec<i64> {
+        let bits = bits_per_height(world_height);
+        let per_long = 64 / bits;
+        self.heights
+            .chunks(per_long)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u64, |long, (i, &height)| long | ((height as u64) << (i * bits))) as i64
+            })
+            .collect()
+    }
+
+    /// Reverses [`Heightmap::pack`], returning `None` if there are too few longs.
+    pub fn unpack(data: &[i64], world_height: i32) -> Option<Self> {
+        let bits = bits_per_height(world_height);
+        let per_long = 64 / bits;
+        let mask = (1u64 << bits) - 1;
+        let mut heights = Vec::with_capacity(SECTION_WIDTH * SECTION_WIDTH);
+        for index in 0..SECTION_WIDTH * SECTION_WIDTH {
+            let long = *data.get(index / per_long)? as u64;
+            heights.push(((long >> (index % per_long * bits)) & mask) as u16);
+        }
+        Some(Self { heights })
+    }
+}
+
+impl Default for Heightmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn bits_per_height(world_height: i32) -> usize {
+    (u32::BITS - (world_height as u32).leading_zeros()) as usize
+}
@@ -1,3 +1,23 @@
+pub mod anvil;
+pub mod behavior;
+pub mod biome;
+pub mod block;
+pub mod block_entity;
+pub mod changes;
 pub mod chunk;
+pub mod compact;
+pub mod error;
+pub mod fluid;
+pub mod generator;
+pub mod height;
+pub mod heightmap;
+pub mod light;
+pub mod loader;
 pub mod palette;
-pub mod world;
\ No newline at end of file
+pub mod pos;
+pub mod schematic;
+pub mod shape;
+pub mod ticket;
+pub mod update;
+pub mod world;
+pub mod view;
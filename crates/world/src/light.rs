@@ -0,0 +1,464 @@
+//! Sky light and block light. A column stores both kinds for each of its sections and for the
+//! section below and the one above it, like vanilla, as nibble arrays that go into packets as
+//! they are.
+//!
+//! Light spreads from block to block, losing one level per block, or the opacity of the block it
+//! enters if that is more. Sky light comes in from above the world and keeps its full strength
+//! going straight down through blocks that don't dim it.
+
+use crate::block::{light_properties, BlockState, LightProperties};
+use crate::chunk::{ChunkColumn, SECTION_HEIGHT, SECTION_VOLUME, SECTION_WIDTH};
+use crate::error::Result;
+use crate::palette::Palette;
+use std::collections::VecDeque;
+
+pub const MAX_LIGHT: u8 = 15;
+/// The size in bytes of the light of one kind in a section, two levels per byte.
+pub const LIGHT_ARRAY_SIZE: usize = SECTION_VOLUME / 2;
+
+const DIRECTIONS: [(i32, i32, i32); 6] = [(0, -1, 0), (0, 1, 0), (-1, 0, 0), (1, 0, 0), (0, 0, -1), (0, 0, 1)];
+const WIDTH: i32 = SECTION_WIDTH as i32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LightKind {
+    Sky,
+    Block,
+}
+
+impl LightKind {
+    pub const ALL: [LightKind; 2] = [LightKind::Sky, LightKind::Block];
+}
+
+/// The light levels of one kind in a section, indexed like its block states.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LightArray {
+    /// Every block has the same level.
+    Uniform(u8),
+    /// Two levels per byte, the lower nibble first.
+    Nibbles(Box<[u8; LIGHT_ARRAY_SIZE]>),
+}
+
+impl LightArray {
+    pub fn get(&self, index: usize) -> u8 {
+        match self {
+            LightArray::Uniform(level) => *level,
+            LightArray::Nibbles(nibbles) => (nibbles[index / 2] >> (index % 2 * 4)) & 0xF,
+        }
+    }
+
+    pub fn set(&mut self, index: usize, level: u8) {
+        if let LightArray::Uniform(uniform) = *self {
+            if uniform == level {
+                return;
+            }
+            *self = LightArray::Nibbles(Box::new([uniform * 0x11; LIGHT_ARRAY_SIZE]));
+        }
+        if let LightArray::Nibbles(nibbles) = self {
+            let shift = index % 2 * 4;
+            let byte = &mut nibbles[index / 2];
+            *byte = (*byte & !(0xF << shift)) | (level << shift);
+        }
+    }
+
+    /// Whether every level is 0, in which case clients are told the section is dark rather than
+    /// sent its levels.
+    pub fn is_dark(&self) -> bool {
+        match self {
+            LightArray::Uniform(level) => *level == 0,
+            LightArray::Nibbles(nibbles) => nibbles.iter().all(|&byte| byte == 0),
+        }
+    }
+
+    /// The levels as they are sent to clients.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            LightArray::Uniform(level) => vec![level * 0x11; LIGHT_ARRAY_SIZE],
+            LightArray::Nibbles(nibbles) => nibbles.to_vec(),
+        }
+    }
+}
+
+/// The light of a column: one array of each kind for every section, plus the section below and
+/// the one above the column, bottom first.
+#[derive(Debug, Clone)]
+pub struct ChunkLight {
+    sky: Vec<LightArray>,
+    block: Vec<LightArray>,
+}
+
+impl ChunkLight {
+    /// Dark light for a column of the given number of sections, except for the full sky light
+    /// above it.
+    pub fn new(section_count: usize) -> Self {
+        let count = section_count + 2;
+        let mut sky = vec![LightArray::Uniform(0); count];
+        sky[count - 1] = LightArray::Uniform(MAX_LIGHT);
+        Self {
+            sky,
+            block: vec![LightArray::Uniform(0); count],
+        }
+    }
+
+    pub fn arrays(&self, kind: LightKind) -> &[LightArray] {
+        match kind {
+            LightKind::Sky => &self.sky,
+            LightKind::Block => &self.block,
+        }
+    }
+
+    fn arrays_mut(&mut self, kind: LightKind) -> &mut [LightArray] {
+        match kind {
+            LightKind::Sky => &mut self.sky,
+            LightKind::Block => &mut self.block,
+        }
+    }
+
+    /// `y` counts blocks from the bottom of the section below the column.
+    pub fn get(&self, kind: LightKind, x: usize, y: usize, z: usize) -> u8 {
+        self.arrays(kind)[y / SECTION_HEIGHT].get(light_index(x, y, z))
+    }
+
+    fn set(&mut self, kind: LightKind, x: usize, y: usize, z: usize, level: u8) {
+        self.arrays_mut(kind)[y / SECTION_HEIGHT].set(light_index(x, y, z), level);
+    }
+}
+
+fn light_index(x: usize, y: usize, z: usize) -> usize {
+    (y % SECTION_HEIGHT) * SECTION_WIDTH * SECTION_WIDTH + z * SECTION_WIDTH + x
+}
+
+/// Lights a column on its own, as if it had no neighbors. Light from and to the neighbors is
+/// spread with [`LightArea::spread_across_edges`] once they are loaded.
+pub fn light_column(column: &mut ChunkColumn) {
+    let mut chunks: [Option<&mut ChunkColumn>; 9] = Default::default();
+    chunks[4] = Some(column);
+    let mut area = LightArea::new(0, 0, chunks);
+    area.light_center_block();
+    area.light_center_sky();
+    if let Some(column) = area.chunks[4].as_deref_mut() {
+        column.mark_lit();
+    }
+}
+
+/// The 3×3 chunks around one, as far as light from a block of the center chunk reaches. Missing
+/// chunks aren't loaded, and light stops at them.
+pub struct LightArea<'a> {
+    center_x: i32,
+    center_z: i32,
+    /// The y of the bottom of the light, a section below the world.
+    min_y: i32,
+    /// The number of blocks of light in a column.
+    light_height: i32,
+    chunks: [Option<&'a mut ChunkColumn>; 9],
+}
+
+impl<'a> LightArea<'a> {
+    /// Takes the chunks from the north west corner, x first. The center chunk must be present.
+    pub fn new(center_x: i32, center_z: i32, chunks: [Option<&'a mut ChunkColumn>; 9]) -> Self {
+        let height = chunks[4].as_ref().expect("the center of a light area must be loaded").world_height();
+        Self {
+            center_x,
+            center_z,
+            min_y: height.min_y() - SECTION_HEIGHT as i32,
+            light_height: (height.section_count() + 2) as i32 * SECTION_HEIGHT as i32,
+            chunks,
+        }
+    }
+
+    /// Sets a block of the center chunk, taking world coordinates, and updates the light
+    /// around it. Returns the block it replaced.
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockState) -> Result<BlockState> {
+        let center = self.chunks[4].as_deref_mut().unwrap();
+        let (local_x, local_z) = (x - self.center_x * WIDTH, z - self.center_z * WIDTH);
+        let previous = center.get_block(local_x, y, local_z)?;
+        center.set_block(local_x, y, local_z, block)?;
+        if light_properties(previous) != light_properties(block) {
+            for kind in LightKind::ALL {
+                self.relight(kind, x, y, z);
+            }
+        }
+        Ok(previous)
+    }
+
+    /// Spreads light between the center chunk and its neighbors, both ways, after one of them
+    /// was lit without the other.
+    pub fn spread_across_edges(&mut self) {
+        for kind in LightKind::ALL {
+            let mut queue = VecDeque::new();
+            let (min_x, min_z) = (self.center_x * WIDTH, self.center_z * WIDTH);
+            for (dx, dz) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                if self.chunk_at(self.center_x + dx, self.center_z + dz).is_none() {
+                    continue;
+                }
+                for offset in 0..WIDTH {
+                    // The blocks on each side of the edge.
+                    let (inside, outside) = match (dx, dz) {
+                        (-1, _) => ((min_x, min_z + offset), (min_x - 1, min_z + offset)),
+                        (1, _) => ((min_x + WIDTH - 1, min_z + offset), (min_x + WIDTH, min_z + offset)),
+                        (_, -1) => ((min_x + offset, min_z), (min_x + offset, min_z - 1)),
+                        _ => ((min_x + offset, min_z + WIDTH - 1), (min_x + offset, min_z + WIDTH)),
+                    };
+                    for (x, z) in [inside, outside] {
+                        for y in self.min_y..self.min_y + self.light_height {
+                            if self.light(kind, x, y, z).is_some_and(|level| level > 1) {
+                                queue.push_back((x, y, z));
+                            }
+                        }
+                    }
+                }
+            }
+            self.increase(kind, queue);
+        }
+    }
+
+    /// Recomputes the light around a block whose light properties changed.
+    fn relight(&mut self, kind: LightKind, x: i32, y: i32, z: i32) {
+        let mut increase = VecDeque::new();
+        if let Some(level) = self.light(kind, x, y, z).filter(|&level| level > 0) {
+            self.set_light(kind, x, y, z, 0);
+            self.decrease(kind, VecDeque::from([(x, y, z, level)]), &mut increase);
+        }
+        if kind == LightKind::Block {
+            let emission = self.properties(x, y, z).map_or(0, |properties| properties.emission);
+            if emission > 0 {
+                self.set_light(kind, x, y, z, emission);
+                increase.push_back((x, y, z));
+            }
+        }
+        // The neighbors light the block again if it lets light through.
+        for (dx, dy, dz) in DIRECTIONS {
+            increase.push_back((x + dx, y + dy, z + dz));
+        }
+        self.increase(kind, increase);
+    }
+
+    /// Spreads the light of the queued blocks to their neighbors, and theirs in turn, wherever
+    /// it brightens them.
+    fn increase(&mut self, kind: LightKind, mut queue: VecDeque<(i32, i32, i32)>) {
+        while let Some((x, y, z)) = queue.pop_front() {
+            let Some(level) = self.light(kind, x, y, z) else {
+                continue;
+            };
+            if level <= 1 {
+                continue;
+            }
+            for (dx, dy, dz) in DIRECTIONS {
+                let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                let Some(properties) = self.properties(nx, ny, nz) else {
+                    continue;
+                };
+                let spread = spread(kind, level, dy, properties.opacity);
+                if spread > self.light(kind, nx, ny, nz).unwrap_or(MAX_LIGHT) {
+                    self.set_light(kind, nx, ny, nz, spread);
+                    queue.push_back((nx, ny, nz));
+                }
+            }
+        }
+    }
+
+    /// Darkens the blocks lit by the queued blocks, which were darkened from the given levels.
+    /// Blocks lit some other way are queued to light the darkened blocks again.
+    fn decrease(
+        &mut self,
+        kind: LightKind,
+        mut queue: VecDeque<(i32, i32, i32, u8)>,
+        increase: &mut VecDeque<(i32, i32, i32)>,
+    ) {
+        while let Some((x, y, z, level)) = queue.pop_front() {
+            for (dx, dy, dz) in DIRECTIONS {
+                let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                let Some(neighbor) = self.light(kind, nx, ny, nz) else {
+                    continue;
+                };
+                if neighbor == 0 {
+                    continue;
+                }
+                let lit_by_block =
+                    neighbor < level || (kind == LightKind::Sky && dy == -1 && level == MAX_LIGHT && neighbor == MAX_LIGHT);
+                if !lit_by_block {
+                    increase.push_back((nx, ny, nz));
+                    continue;
+                }
+                self.set_light(kind, nx, ny, nz, 0);
+                queue.push_back((nx, ny, nz, neighbor));
+                if kind == LightKind::Block {
+                    let emission = self.properties(nx, ny, nz).map_or(0, |properties| properties.emission);
+                    if emission > 0 {
+                        self.set_light(kind, nx, ny, nz, emission);
+                        increase.push_back((nx, ny, nz));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Lights the center chunk with the light its own blocks give off.
+    fn light_center_block(&mut self) {
+        let center = self.chunks[4].as_deref_mut().unwrap();
+        let mut sources = Vec::new();
+        for (index, section) in center.sections().iter().enumerate() {
+            let may_emit = match section.block_states().palette() {
+                Palette::Single(state) => emits(*state),
+                Palette::Indirect(states) => states.iter().any(|&state| emits(state)),
+                Palette::Direct => true,
+            };
+            if section.is_empty() || !may_emit {
+                continue;
+            }
+            for y in 0..SECTION_HEIGHT {
+                for z in 0..SECTION_WIDTH {
+                    for x in 0..SECTION_WIDTH {
+                        let block = section.get_block(x, y, z).unwrap();
+                        let emission = light_properties(block).emission;
+                        if emission > 0 {
+                            sources.push((x, (index + 1) * SECTION_HEIGHT + y, z, emission));
+                        }
+                    }
+                }
+            }
+        }
+
+        let light = center.light_mut();
+        *light = ChunkLight::new(light.sky.len() - 2);
+        let (min_x, min_z) = (self.center_x * WIDTH, self.center_z * WIDTH);
+        let mut queue = VecDeque::with_capacity(sources.len());
+        for (x, y, z, emission) in sources {
+            light.set(LightKind::Block, x, y, z, emission);
+            queue.push_back((min_x + x as i32, self.min_y + y as i32, min_z + z as i32));
+        }
+        self.increase(LightKind::Block, queue);
+    }
+
+    /// Lights the center chunk with the sky light coming straight down, then spreads it.
+    fn light_center_sky(&mut self) {
+        let center = self.chunks[4].as_deref_mut().unwrap();
+        // The light y of the highest block of each x and z that stops sky light coming straight
+        // down, or -1.
+        let mut tops = [[-1i32; SECTION_WIDTH]; SECTION_WIDTH];
+        let mut remaining = SECTION_WIDTH * SECTION_WIDTH;
+        'sections: for (index, section) in center.sections().iter().enumerate().rev() {
+            if section.is_empty() {
+                continue;
+            }
+            for y in (0..SECTION_HEIGHT).rev() {
+                for (z, row) in tops.iter_mut().enumerate() {
+                    for (x, top) in row.iter_mut().enumerate() {
+                        if *top >= 0 {
+                            continue;
+                        }
+                        let block = section.get_block(x, y, z).unwrap();
+                        if light_properties(block).opacity > 0 {
+                            *top = ((index + 1) * SECTION_HEIGHT + y) as i32;
+                            remaining -= 1;
+                            if remaining == 0 {
+                                break 'sections;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let light = center.light_mut();
+        for (index, array) in light.sky.iter_mut().enumerate() {
+            let bottom = (index * SECTION_HEIGHT) as i32;
+            let top = bottom + SECTION_HEIGHT as i32 - 1;
+            *array = if tops.iter().flatten().all(|&column_top| column_top < bottom) {
+                LightArray::Uniform(MAX_LIGHT)
+            } else {
+                let mut array = LightArray::Uniform(0);
+                for (z, row) in tops.iter().enumerate() {
+                    for (x, &column_top) in row.iter().enumerate() {
+                        for y in (column_top + 1).max(bottom)..=top {
+                            array.set(light_index(x, y as usize, z), MAX_LIGHT);
+                        }
+                    }
+                }
+                array
+            };
+        }
+
+        // Only the lowest block of each column lit straight from above, and the ones next to
+        // lower columns, have anywhere to spread to.
+        let (min_x, min_z) = (self.center_x * WIDTH, self.center_z * WIDTH);
+        let mut queue = VecDeque::new();
+        for z in 0..SECTION_WIDTH {
+            for x in 0..SECTION_WIDTH {
+                let lowest = tops[z][x] + 1;
+                let mut highest = lowest;
+                for (dx, _, dz) in &DIRECTIONS[2..] {
+                    let (nx, nz) = (x as i32 + dx, z as i32 + dz);
+                    if (0..WIDTH).contains(&nx) && (0..WIDTH).contains(&nz) {
+                        highest = highest.max(tops[nz as usize][nx as usize]);
+                    }
+                }
+                for y in lowest..=highest {
+                    queue.push_back((min_x + x as i32, self.min_y + y, min_z + z as i32));
+                }
+            }
+        }
+        self.increase(LightKind::Sky, queue);
+    }
+
+    /// Returns the chunk of the area at the chunk coordinates.
+    fn chunk_at(&self, chunk_x: i32, chunk_z: i32) -> Option<&ChunkColumn> {
+        let index = chunk_index(chunk_x - self.center_x, chunk_z - self.center_z)?;
+        self.chunks[index].as_deref()
+    }
+
+    /// Returns the chunk holding the block at the world coordinates, with the block's coordinates
+    /// in the chunk and its light.
+    fn locate(&self, x: i32, y: i32, z: i32) -> Option<(usize, usize, usize, usize)> {
+        let light_y = y - self.min_y;
+        if !(0..self.light_height).contains(&light_y) {
+            return None;
+        }
+        let index = chunk_index(x.div_euclid(WIDTH) - self.center_x, z.div_euclid(WIDTH) - self.center_z)?;
+        self.chunks[index].as_ref()?;
+        Some((index, x.rem_euclid(WIDTH) as usize, light_y as usize, z.rem_euclid(WIDTH) as usize))
+    }
+
+    fn light(&self, kind: LightKind, x: i32, y: i32, z: i32) -> Option<u8> {
+        let (index, x, y, z) = self.locate(x, y, z)?;
+        Some(self.chunks[index].as_deref()?.light().get(kind, x, y, z))
+    }
+
+    fn set_light(&mut self, kind: LightKind, x: i32, y: i32, z: i32, level: u8) {
+        if let Some((index, x, y, z)) = self.locate(x, y, z) {
+            if let Some(column) = self.chunks[index].as_deref_mut() {
+                column.light_mut().set(kind, x, y, z, level);
+            }
+        }
+    }
+
+    /// Returns the light properties of the block, those of air above and below the world.
+    fn properties(&self, x: i32, y: i32, z: i32) -> Option<LightProperties> {
+        let (index, local_x, _, local_z) = self.locate(x, y, z)?;
+        let column = self.chunks[index].as_deref()?;
+        Some(match column.get_block(local_x as i32, y, local_z as i32) {
+            Ok(block) => light_properties(block),
+            Err(_) => LightProperties::default(),
+        })
+    }
+}
+
+fn chunk_index(dx: i32, dz: i32) -> Option<usize> {
+    if (-1..=1).contains(&dx) && (-1..=1).contains(&dz) {
+        Some(((dz + 1) * 3 + dx + 1) as usize)
+    } else {
+        None
+    }
+}
+
+fn emits(state: u32) -> bool {
+    light_properties(BlockState::from_id(state)).emission > 0
+}
+
+/// Returns the light a block gets from its neighbor with the given level, in the direction `dy`.
+fn spread(kind: LightKind, level: u8, dy: i32, opacity: u8) -> u8 {
+    if kind == LightKind::Sky && dy == -1 && level == MAX_LIGHT && opacity == 0 {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(opacity.max(1))
+    }
+}
@@ -0,0 +1,167 @@
+//! Loading and generating chunks on worker threads.
+
+use crate::chunk::ChunkColumn;
+use crate::error::{Result, WorldError};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, Condvar, Mutex, RwLock};
+use std::task::{Context, Poll, Waker};
+use std::thread::JoinHandle;
+
+/// A loaded chunk, shared by the world and everything using it.
+pub type SharedChunk = Arc<RwLock<ChunkColumn>>;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed number of threads running jobs in the order they were queued.
+pub(crate) struct WorkerPool {
+    sender: Option<mpsc::Sender<Job>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    pub(crate) fn new(threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let threads = (0..threads.max(1))
+            .map(|index| {
+                let receiver = Arc::clone(&receiver);
+                std::thread::Builder::new()
+                    .name(format!("chunk-worker-{}", index))
+                    .spawn(move || loop {
+                        let job = receiver.lock().unwrap().recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("failed to spawn a chunk worker")
+            })
+            .collect();
+        Self { sender: Some(sender), threads }
+    }
+
+    pub(crate) fn execute(&self, job: impl FnOnce() + Send + 'static) {
+        if let Some(sender) = &self.sender {
+            // The workers only stop once the sender is dropped.
+            let _ = sender.send(Box::new(job));
+        }
+    }
+
+    pub(crate) fn thread_count(&self) -> usize {
+        self.threads.len()
+    }
+}
+
+impl Drop for WorkerPool {
+    /// Lets the workers finish the queued jobs and waits for them.
+    fn drop(&mut self) {
+        self.sender.take();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A chunk being loaded, which every request for it waits on.
+#[derive(Default)]
+pub(crate) struct Pending {
+    state: Mutex<PendingState>,
+    done: Condvar,
+}
+
+#[derive(Default)]
+struct PendingState {
+    /// The error is kept as a message since every waiter gets a copy.
+    result: Option<std::result::Result<SharedChunk, String>>,
+    wakers: Vec<Waker>,
+}
+
+impl Pending {
+    pub(crate) fn complete(&self, result: std::result::Result<SharedChunk, String>) {
+        let mut state = self.state.lock().unwrap();
+        state.result = Some(result);
+        for waker in state.wakers.drain(..) {
+            waker.wake();
+        }
+        self.done.notify_all();
+    }
+}
+
+/// A request for a chunk, resolving once the chunk is loaded. Await it from async code or `wait`
+/// for it from a thread that may block.
+pub struct ChunkHandle {
+    x: i32,
+    z: i32,
+    inner: HandleInner,
+}
+
+enum HandleInner {
+    Ready(Option<SharedChunk>),
+    Pending(Arc<Pending>),
+}
+
+impl ChunkHandle {
+    pub(crate) fn ready(x: i32, z: i32, chunk: SharedChunk) -> Self {
+        Self { x, z, inner: HandleInner::Ready(Some(chunk)) }
+    }
+
+    pub(crate) fn pending(x: i32, z: i32, pending: Arc<Pending>) -> Self {
+        Self { x, z, inner: HandleInner::Pending(pending) }
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        (self.x, self.z)
+    }
+
+    /// Whether the chunk is loaded, or failed to.
+    pub fn is_done(&self) -> bool {
+        match &self.inner {
+            HandleInner::Ready(_) => true,
+            HandleInner::Pending(pending) => pending.state.lock().unwrap().result.is_some(),
+        }
+    }
+
+    /// Blocks the thread until the chunk is loaded.
+    pub fn wait(self) -> Result<SharedChunk> {
+        let (x, z) = (self.x, self.z);
+        match self.inner {
+            HandleInner::Ready(chunk) => Ok(chunk.expect("chunk handle used after completion")),
+            HandleInner::Pending(pending) => {
+                let state = pending.state.lock().unwrap();
+                let state = pending.done.wait_while(state, |state| state.result.is_none()).unwrap();
+                result(x, z, state.result.as_ref().unwrap())
+            }
+        }
+    }
+}
+
+impl Future for ChunkHandle {
+    type Output = Result<SharedChunk>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let (x, z) = (self.x, self.z);
+        match &mut self.get_mut().inner {
+            HandleInner::Ready(chunk) => Poll::Ready(Ok(chunk.take().expect("chunk handle polled after completion"))),
+            HandleInner::Pending(pending) => {
+                let mut state = pending.state.lock().unwrap();
+                match &state.result {
+                    Some(loaded) => Poll::Ready(result(x, z, loaded)),
+                    None => {
+                        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                            state.wakers.push(cx.waker().clone());
+                        }
+                        Poll::Pending
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn result(x: i32, z: i32, loaded: &std::result::Result<SharedChunk, String>) -> Result<SharedChunk> {
+    match loaded {
+        Ok(chunk) => Ok(Arc::clone(chunk)),
+        Err(message) => Err(WorldError::ChunkLoad { x, z, message: message.clone() }),
+    }
+}
@@ -1,6 +1,6 @@
-use std::mem::size_of;
+use std::ops::Range;
 
-const BITS_PER_U64: usize = size_of::<u64>() * 8;
+const BITS_PER_U64: usize = u64::BITS as usize;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PaletteType {
@@ -9,21 +9,21 @@ pub enum PaletteType {
 }
 
 impl PaletteType {
-    fn min_bits(&self) -> u8 {
+    pub fn min_bits(&self) -> u8 {
         match self {
             PaletteType::Block => 4,
             PaletteType::Biome => 1,
         }
     }
 
-    fn max_bits(&self) -> u8 {
+    pub fn max_bits(&self) -> u8 {
         match self {
             PaletteType::Block => 8,
             PaletteType::Biome => 3,
         }
     }
 
-    fn direct_bits(&self) -> u8 {
+    pub fn direct_bits(&self) -> u8 {
         match self {
             PaletteType::Block => 15,
             PaletteType::Biome => 6,
@@ -58,20 +58,76 @@ impl PalettedContainer {
         }
     }
 
-    pub fn get(&self, index: usize) -> u32 {
-        if self.bits_per_entry == 0 {
-            return match self.palette {
-                Palette::Single(id) => id,
-                _ => unreachable!(),
+    /// Builds a container holding the values, one per index, using the smallest palette that fits
+    /// them.
+    pub fn from_values(palette_type: PaletteType, values: &[u32]) -> Self {
+        let mut container = Self::new(palette_type, values.len());
+        let mut distinct = Vec::new();
+        for value in values {
+            if !distinct.contains(value) {
+                distinct.push(*value);
+                if distinct.len() > 1 << palette_type.max_bits() {
+                    break;
+                }
+            }
+        }
+
+        match distinct.len() {
+            0 => return container,
+            1 => {
+                container.fill(distinct[0]);
+                return container;
+            }
+            len if len > 1 << palette_type.max_bits() => {
+                container.palette = Palette::Direct;
+                container.bits_per_entry = palette_type.direct_bits();
+            }
+            len => {
+                container.palette = Palette::Indirect(distinct);
+                container.bits_per_entry = bits_for(len).max(palette_type.min_bits());
+            }
+        }
+
+        let bits = container.bits_per_entry;
+        let mut storage = vec![0; storage_len(values.len(), bits)];
+        for (index, value) in values.iter().enumerate() {
+            let entry = match &container.palette {
+                Palette::Indirect(palette) => palette.iter().position(|id| id == value).unwrap() as u64,
+                _ => *value as u64,
             };
+            set_packed(&mut storage, bits, index, entry);
         }
+        container.storage = storage;
+        container
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
 
-        let values_per_u64 = BITS_PER_U64 / self.bits_per_entry as usize;
-        let u64_index = index / values_per_u64;
-        let sub_index = index % values_per_u64;
-        let mask = (1 << self.bits_per_entry) - 1;
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
 
-        let value = (self.storage[u64_index] >> (sub_index * self.bits_per_entry as usize)) & mask;
+    pub fn bits_per_entry(&self) -> u8 {
+        self.bits_per_entry
+    }
+
+    /// The packed entries, each long holding `64 / bits_per_entry` entries with no entry spanning
+    /// two longs. Empty for a single valued container.
+    pub fn storage(&self) -> &[u64] {
+        &self.storage
+    }
+
+    pub fn get(&self, index: usize) -> u32 {
+        let value = match self.palette {
+            Palette::Single(id) => return id,
+            _ => get_packed(&self.storage, self.bits_per_entry, index),
+        };
 
         match &self.palette {
             Palette::Indirect(palette) => palette[value as usize],
@@ -80,24 +136,159 @@ impl PalettedContainer {
         }
     }
 
-    pub fn set(&mut self, index: usize, value: u32) {
-        // For now, we only support direct palette for simplicity
-        self.bits_per_entry = self.palette_type.direct_bits();
-        self.palette = Palette::Direct;
+    /// Sets the value at the index and returns the previous one. The container starts out single
+    /// valued, switches to an indirect palette when a second value is set and grows its entries up
+    /// to `PaletteType::max_bits` before falling back to storing values directly.
+    pub fn set(&mut self, index: usize, value: u32) -> u32 {
+        let entry = match &mut self.palette {
+            Palette::Single(id) if *id == value => return value,
+            Palette::Single(id) => {
+                let previous = *id;
+                self.palette = Palette::Indirect(vec![previous]);
+                self.bits_per_entry = self.palette_type.min_bits();
+                self.storage = vec![0; storage_len(self.size, self.bits_per_entry)];
+                return self.set(index, value);
+            }
+            Palette::Indirect(palette) => match palette.iter().position(|id| *id == value) {
+                Some(entry) => entry as u64,
+                None if palette.len() < 1 << self.bits_per_entry => {
+                    palette.push(value);
+                    (palette.len() - 1) as u64
+                }
+                None => {
+                    self.grow();
+                    return self.set(index, value);
+                }
+            },
+            Palette::Direct => value as u64,
+        };
 
-        let values_per_u64 = BITS_PER_U64 / self.bits_per_entry as usize;
-        let u64_count = (self.size + values_per_u64 - 1) / values_per_u64;
-        if self.storage.is_empty() {
-            self.storage.resize(u64_count, 0);
+        let previous = set_packed(&mut self.storage, self.bits_per_entry, index, entry);
+        match &self.palette {
+            Palette::Indirect(palette) => palette[previous as usize],
+            _ => previous as u32,
         }
+    }
 
-        let u64_index = index / values_per_u64;
-        let sub_index = index % values_per_u64;
-        let mask = (1 << self.bits_per_entry) - 1;
+    /// Sets every value to the same one.
+    pub fn fill(&mut self, value: u32) {
+        self.palette = Palette::Single(value);
+        self.bits_per_entry = 0;
+        self.storage = Vec::new();
+    }
 
-        let mut u64_value = self.storage[u64_index];
-        u64_value &= !(mask << (sub_index * self.bits_per_entry as usize));
-        u64_value |= (value as u64 & mask) << (sub_index * self.bits_per_entry as usize);
-        self.storage[u64_index] = u64_value;
+    /// Sets every value in the range to the same one.
+    pub fn fill_range(&mut self, range: Range<usize>, value: u32) {
+        if range.start == 0 && range.end >= self.size {
+            self.fill(value);
+            return;
+        }
+        for index in range {
+            self.set(index, value);
+        }
     }
-}
\ No newline at end of file
+
+    /// Drops palette entries no value refers to anymore and re-packs the storage with the fewest
+    /// bits that fit the remaining ones, possibly turning the container single valued again.
+    pub fn compact(&mut self) {
+        if let Palette::Single(_) = self.palette {
+            return;
+        }
+        if let Some(used) = self.used_values() {
+            self.compact_to(used);
+        }
+    }
+
+    /// Returns the distinct values in use, or `None` if there are too many for an indirect palette.
+    fn used_values(&self) -> Option<Vec<u32>> {
+        let mut used = Vec::new();
+        for index in 0..self.size {
+            let value = self.get(index);
+            if !used.contains(&value) {
+                used.push(value);
+                if used.len() > 1 << self.palette_type.max_bits() {
+                    return None;
+                }
+            }
+        }
+        Some(used)
+    }
+
+    fn compact_to(&mut self, used: Vec<u32>) {
+        if used.len() == 1 {
+            self.fill(used[0]);
+            return;
+        }
+
+        let bits = bits_for(used.len()).max(self.palette_type.min_bits());
+        self.repack(Palette::Indirect(used), bits);
+    }
+
+    /// Makes room for one more palette entry, by dropping unused entries if there are any and
+    /// otherwise by growing the entries, switching to direct storage past the maximum bits.
+    fn grow(&mut self) {
+        if let Palette::Indirect(palette) = &self.palette {
+            let palette_len = palette.len();
+            if let Some(used) = self.used_values().filter(|used| used.len() < palette_len) {
+                self.compact_to(used);
+                return;
+            }
+        }
+
+        let bits = self.bits_per_entry + 1;
+        if bits > self.palette_type.max_bits() {
+            self.repack(Palette::Direct, self.palette_type.direct_bits());
+        } else {
+            let palette = self.palette.clone();
+            self.repack(palette, bits);
+        }
+    }
+
+    fn repack(&mut self, palette: Palette, bits: u8) {
+        let mut storage = vec![0; storage_len(self.size, bits)];
+        for index in 0..self.size {
+            let value = self.get(index);
+            let entry = match &palette {
+                Palette::Indirect(palette) => palette.iter().position(|id| *id == value).unwrap() as u64,
+                Palette::Direct => value as u64,
+                Palette::Single(_) => unreachable!(),
+            };
+            set_packed(&mut storage, bits, index, entry);
+        }
+
+        self.palette = palette;
+        self.bits_per_entry = bits;
+        self.storage = storage;
+    }
+}
+
+/// The number of bits needed to index a palette of the given length.
+fn bits_for(len: usize) -> u8 {
+    (usize::BITS - (len - 1).leading_zeros()) as u8
+}
+
+fn storage_len(size: usize, bits: u8) -> usize {
+    let values_per_u64 = BITS_PER_U64 / bits as usize;
+    size.div_ceil(values_per_u64)
+}
+
+fn get_packed(storage: &[u64], bits: u8, index: usize) -> u64 {
+    let values_per_u64 = BITS_PER_U64 / bits as usize;
+    let u64_index = index / values_per_u64;
+    let sub_index = index % values_per_u64;
+    let mask = (1 << bits) - 1;
+
+    (storage[u64_index] >> (sub_index * bits as usize)) & mask
+}
+
+/// Stores the entry at the index and returns the entry that was there.
+fn set_packed(storage: &mut [u64], bits: u8, index: usize, entry: u64) -> u64 {
+    let values_per_u64 = BITS_PER_U64 / bits as usize;
+    let u64_index = index / values_per_u64;
+    let shift = (index % values_per_u64) * bits as usize;
+    let mask = (1 << bits) - 1;
+
+    let u64_value = storage[u64_index];
+    storage[u64_index] = (u64_value & !(mask << shift)) | ((entry & mask) << shift);
+    (u64_value >> shift) & mask
+}
@@ -0,0 +1,759 @@
+//! Sponge schematics, the `.schem` files WorldEdit and most other tools save builds as. Versions
+//! 2 and 3 are read and written, with their blocks, block entities and biomes. Entities are left
+//! out.
+
+use crate::anvil::DATA_VERSION;
+use crate::biome::biome_registry;
+use crate::block::BlockState;
+use crate::block_entity::BlockEntity;
+use crate::error::{Result, WorldError};
+use crate::pos::{BlockPos, Direction};
+use crate::update::UpdateFlags;
+use crate::world::World;
+use fastnbt::{ByteArray, IntArray, Value};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// The first bytes of gzip data, which schematics are usually compressed with.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// The name of the root tag of version 2 files, which version 3 files nest their content in.
+const ROOT_NAME: &str = "Schematic";
+/// The most blocks a schematic read from a file may have, 256 MiB of block states.
+const MAX_VOLUME: usize = 1 << 26;
+
+/// A version of the Sponge schematic format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchematicVersion {
+    /// Version 2, written by WorldEdit before 7.3. Biomes are stored per column.
+    V2,
+    /// Version 3, with the blocks and biomes in containers and biomes stored per block.
+    #[default]
+    V3,
+}
+
+/// Version 2 files, whose content is the root tag.
+#[derive(Serialize, Deserialize)]
+struct SchematicV2Nbt {
+    #[serde(rename = "Version")]
+    version: i32,
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    #[serde(rename = "Width")]
+    width: i16,
+    #[serde(rename = "Height")]
+    height: i16,
+    #[serde(rename = "Length")]
+    length: i16,
+    #[serde(rename = "Offset", default, skip_serializing_if = "Option::is_none")]
+    offset: Option<IntArray>,
+    #[serde(rename = "Metadata", default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<MetadataNbt>,
+    #[serde(rename = "PaletteMax", default)]
+    palette_max: i32,
+    #[serde(rename = "Palette")]
+    palette: HashMap<String, i32>,
+    #[serde(rename = "BlockData")]
+    block_data: ByteArray,
+    /// The block entities with their data next to their position and type.
+    #[serde(rename = "BlockEntities", default)]
+    block_entities: Vec<HashMap<String, Value>>,
+    #[serde(rename = "BiomePaletteMax", default, skip_serializing_if = "Option::is_none")]
+    biome_palette_max: Option<i32>,
+    #[serde(rename = "BiomePalette", default, skip_serializing_if = "Option::is_none")]
+    biome_palette: Option<HashMap<String, i32>>,
+    /// One biome for every column, ordered like the blocks.
+    #[serde(rename = "BiomeData", default, skip_serializing_if = "Option::is_none")]
+    biome_data: Option<ByteArray>,
+}
+
+/// Version 3 files, whose content is nested in the root tag.
+#[derive(Serialize, Deserialize)]
+struct SchematicV3FileNbt {
+    #[serde(rename = "Schematic")]
+    schematic: SchematicV3Nbt,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SchematicV3Nbt {
+    #[serde(rename = "Version")]
+    version: i32,
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    #[serde(rename = "Width")]
+    width: i16,
+    #[serde(rename = "Height")]
+    height: i16,
+    #[serde(rename = "Length")]
+    length: i16,
+    #[serde(rename = "Offset", default, skip_serializing_if = "Option::is_none")]
+    offset: Option<IntArray>,
+    #[serde(rename = "Metadata", default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<MetadataNbt>,
+    #[serde(rename = "Blocks", default, skip_serializing_if = "Option::is_none")]
+    blocks: Option<BlockContainerNbt>,
+    #[serde(rename = "Biomes", default, skip_serializing_if = "Option::is_none")]
+    biomes: Option<PaletteContainerNbt>,
+}
+
+/// The metadata WorldEdit adds. Its version 2 files have the corner the build was copied from as
+/// the offset, and the offset to paste with here.
+#[derive(Serialize, Deserialize)]
+struct MetadataNbt {
+    #[serde(rename = "WEOffsetX")]
+    we_offset_x: Option<i32>,
+    #[serde(rename = "WEOffsetY")]
+    we_offset_y: Option<i32>,
+    #[serde(rename = "WEOffsetZ")]
+    we_offset_z: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BlockContainerNbt {
+    #[serde(rename = "Palette")]
+    palette: HashMap<String, i32>,
+    #[serde(rename = "Data")]
+    data: ByteArray,
+    #[serde(rename = "BlockEntities", default)]
+    block_entities: Vec<BlockEntityV3Nbt>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BlockEntityV3Nbt {
+    #[serde(rename = "Pos")]
+    pos: IntArray,
+    #[serde(rename = "Id")]
+    id: String,
+    #[serde(rename = "Data", default, skip_serializing_if = "HashMap::is_empty")]
+    data: HashMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PaletteContainerNbt {
+    #[serde(rename = "Palette")]
+    palette: HashMap<String, i32>,
+    #[serde(rename = "Data")]
+    data: ByteArray,
+}
+
+/// Just enough of a file to tell its version.
+#[derive(Deserialize)]
+struct VersionNbt {
+    #[serde(rename = "Version")]
+    version: Option<i32>,
+    #[serde(rename = "Schematic")]
+    schematic: Option<NestedVersionNbt>,
+}
+
+#[derive(Deserialize)]
+struct NestedVersionNbt {
+    #[serde(rename = "Version")]
+    version: i32,
+}
+
+/// A quarter turn of a build around the vertical axis, seen from above.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise90,
+    Clockwise180,
+    CounterClockwise90,
+}
+
+/// A flip of a build, as in vanilla structure blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mirror {
+    #[default]
+    None,
+    /// Flips north and south.
+    LeftRight,
+    /// Flips east and west.
+    FrontBack,
+}
+
+/// How a build is turned when it's pasted: mirrored first, then rotated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Transform {
+    pub rotation: Rotation,
+    pub mirror: Mirror,
+}
+
+impl Transform {
+    pub fn new(rotation: Rotation, mirror: Mirror) -> Self {
+        Self { rotation, mirror }
+    }
+
+    /// Returns where the position goes, turning around the origin.
+    pub fn apply(&self, pos: BlockPos) -> BlockPos {
+        let (x, z) = match self.mirror {
+            Mirror::None => (pos.x, pos.z),
+            Mirror::LeftRight => (pos.x, -pos.z),
+            Mirror::FrontBack => (-pos.x, pos.z),
+        };
+        let (x, z) = match self.rotation {
+            Rotation::None => (x, z),
+            Rotation::Clockwise90 => (-z, x),
+            Rotation::Clockwise180 => (-x, -z),
+            Rotation::CounterClockwise90 => (z, -x),
+        };
+        BlockPos::new(x, pos.y, z)
+    }
+
+    /// Returns the direction turned. Up and down stay.
+    pub fn apply_direction(&self, direction: Direction) -> Direction {
+        let direction = match (self.mirror, direction) {
+            (Mirror::LeftRight, Direction::North | Direction::South) => direction.opposite(),
+            (Mirror::FrontBack, Direction::East | Direction::West) => direction.opposite(),
+            _ => direction,
+        };
+        match self.rotation {
+            Rotation::None => direction,
+            Rotation::Clockwise90 => direction.clockwise(),
+            Rotation::Clockwise180 => direction.clockwise().clockwise(),
+            Rotation::CounterClockwise90 => direction.counter_clockwise(),
+        }
+    }
+
+    /// Returns the state turned: the sides it faces or connects to, its axis, its rotation and
+    /// its handedness, like the hinge of a door.
+    pub fn apply_state(&self, state: BlockState) -> BlockState {
+        if *self == Transform::default() {
+            return state;
+        }
+        let mirrored = self.mirror != Mirror::None;
+        let quarter_turn = matches!(self.rotation, Rotation::Clockwise90 | Rotation::CounterClockwise90);
+        let mut turned = state;
+        for (property, value) in state.properties() {
+            let value = match property {
+                "facing" => {
+                    Direction::from_name(value).map(|direction| self.apply_direction(direction).name().to_string())
+                }
+                "axis" if quarter_turn => match value {
+                    "x" => Some("z".to_string()),
+                    "z" => Some("x".to_string()),
+                    _ => None,
+                },
+                "rotation" => value.parse().ok().map(|rotation: u8| self.apply_rotation(rotation).to_string()),
+                "north" | "east" | "south" | "west" => {
+                    // Connections move to the side their side turns into.
+                    let from = Direction::from_name(property).unwrap();
+                    let to = self.apply_direction(from);
+                    turned = turned.with(to.name(), value).unwrap_or(turned);
+                    continue;
+                }
+                "shape" | "orientation" => Some(self.apply_words(value, mirrored)),
+                "hinge" | "type" if mirrored => match value {
+                    "left" => Some("right".to_string()),
+                    "right" => Some("left".to_string()),
+                    _ => None,
+                },
+                _ => None,
+            };
+            let Some(value) = value else {
+                continue;
+            };
+            // Corners of rails name their sides in a fixed order, which turning can swap.
+            let swapped = value.split_once('_').map(|(first, second)| format!("{second}_{first}"));
+            turned = turned
+                .with(property, &value)
+                .or_else(|e| swapped.map_or(Err(e), |swapped| turned.with(property, &swapped)))
+                .unwrap_or(turned);
+        }
+        turned
+    }
+
+    /// Turns a rotation of 16 steps, where 0 faces south and 4 west.
+    fn apply_rotation(&self, rotation: u8) -> u8 {
+        let rotation = match self.mirror {
+            Mirror::None => rotation,
+            Mirror::LeftRight => (24 - rotation) % 16,
+            Mirror::FrontBack => (16 - rotation) % 16,
+        };
+        let steps = match self.rotation {
+            Rotation::None => 0,
+            Rotation::Clockwise90 => 4,
+            Rotation::Clockwise180 => 8,
+            Rotation::CounterClockwise90 => 12,
+        };
+        (rotation + steps) % 16
+    }
+
+    /// Turns the directions named in a value like `ascending_north` or `north_up`, and swaps
+    /// left and right if mirrored.
+    fn apply_words(&self, value: &str, mirrored: bool) -> String {
+        let words: Vec<String> = value
+            .split('_')
+            .map(|word| match (Direction::from_name(word), word) {
+                (Some(direction), _) => self.apply_direction(direction).name().to_string(),
+                (None, "left") if mirrored => "right".to_string(),
+                (None, "right") if mirrored => "left".to_string(),
+                _ => word.to_string(),
+            })
+            .collect();
+        words.join("_")
+    }
+}
+
+/// A cuboid of blocks, with their block entities and biomes, to paste into worlds.
+///
+/// Positions are relative to the lowest corner. The offset says where that corner is relative to
+/// the point the build was copied around, which it's pasted around again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schematic {
+    width: u16,
+    height: u16,
+    length: u16,
+    offset: BlockPos,
+    /// Ordered by x, then z, then y, like in the files.
+    blocks: Vec<BlockState>,
+    block_entities: HashMap<BlockPos, BlockEntity>,
+    /// One biome for every block, if the schematic has biomes.
+    biomes: Option<Vec<u32>>,
+}
+
+impl Schematic {
+    /// Creates a schematic of air of the size, without an offset or biomes.
+    pub fn new(width: u16, height: u16, length: u16) -> Self {
+        let volume = width as usize * height as usize * length as usize;
+        Self {
+            width,
+            height,
+            length,
+            offset: BlockPos::new(0, 0, 0),
+            blocks: vec![BlockState::AIR; volume],
+            block_entities: HashMap::new(),
+            biomes: None,
+        }
+    }
+
+    /// Copies the blocks, block entities and biomes of the world between the two corners, which
+    /// are both included, waiting for their chunks if needed.
+    pub fn copy(world: &World, from: BlockPos, to: BlockPos) -> Result<Self> {
+        let min = BlockPos::new(from.x.min(to.x), from.y.min(to.y), from.z.min(to.z));
+        let max = BlockPos::new(from.x.max(to.x), from.y.max(to.y), from.z.max(to.z));
+        if !world.height().contains(min.y) || !world.height().contains(max.y) {
+            let outside = if world.height().contains(min.y) { max } else { min };
+            return Err(WorldError::BlockOutOfBounds { x: outside.x, y: outside.y, z: outside.z });
+        }
+        let side = |min: i32, max: i32| {
+            u16::try_from(max as i64 - min as i64 + 1)
+                .map_err(|_| WorldError::InvalidSchematic("the region is too large".to_string()))
+        };
+        let mut schematic = Self::new(side(min.x, max.x)?, side(min.y, max.y)?, side(min.z, max.z)?);
+        let mut biomes = Vec::with_capacity(schematic.blocks.len());
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                for x in min.x..=max.x {
+                    let index = schematic.index(x - min.x, y - min.y, z - min.z).unwrap();
+                    schematic.blocks[index] = world.get_block(x, y, z)?;
+                    biomes.push(world.biome(x, y, z)?);
+                    if let Some(entity) = world.block_entity(x, y, z)? {
+                        let pos = BlockPos::new(x - min.x, y - min.y, z - min.z);
+                        schematic.block_entities.insert(pos, entity);
+                    }
+                }
+            }
+        }
+        schematic.biomes = Some(biomes);
+        Ok(schematic)
+    }
+
+    /// Reads a schematic file, compressed or not.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Reads a version 2 or 3 schematic, compressed with gzip or not.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let invalid = |message: String| WorldError::InvalidSchematic(message);
+        let nbt = if bytes.starts_with(&GZIP_MAGIC) {
+            let mut nbt = Vec::new();
+            GzDecoder::new(bytes).read_to_end(&mut nbt)?;
+            nbt
+        } else {
+            bytes.to_vec()
+        };
+        let version: VersionNbt = fastnbt::from_bytes(&nbt).map_err(|e| invalid(e.to_string()))?;
+        match (version.version, version.schematic) {
+            (_, Some(NestedVersionNbt { version: 3 })) => {
+                let file: SchematicV3FileNbt = fastnbt::from_bytes(&nbt).map_err(|e| invalid(e.to_string()))?;
+                Self::from_v3(file.schematic)
+            }
+            (Some(2), None) => Self::from_v2(fastnbt::from_bytes(&nbt).map_err(|e| invalid(e.to_string()))?),
+            (Some(version), None) | (_, Some(NestedVersionNbt { version })) => {
+                Err(invalid(format!("unsupported version {}", version)))
+            }
+            (None, None) => Err(invalid("not a schematic".to_string())),
+        }
+    }
+
+    /// Writes the schematic compressed with gzip, like WorldEdit does.
+    pub fn save(&self, path: impl AsRef<Path>, version: SchematicVersion) -> Result<()> {
+        std::fs::write(path, self.to_bytes(version)?)?;
+        Ok(())
+    }
+
+    /// Encodes the schematic in the version, compressed with gzip. Version 2 keeps the biomes of
+    /// the bottom layer only.
+    pub fn to_bytes(&self, version: SchematicVersion) -> Result<Vec<u8>> {
+        let invalid = |e: fastnbt::error::Error| WorldError::InvalidSchematic(e.to_string());
+        let nbt = match version {
+            SchematicVersion::V2 => {
+                let opts = fastnbt::SerOpts::new().root_name(ROOT_NAME);
+                fastnbt::to_bytes_with_opts(&self.to_v2()?, opts).map_err(invalid)?
+            }
+            SchematicVersion::V3 => {
+                fastnbt::to_bytes(&SchematicV3FileNbt { schematic: self.to_v3()? }).map_err(invalid)?
+            }
+        };
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&nbt)?;
+        Ok(encoder.finish()?)
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+
+    pub fn offset(&self) -> BlockPos {
+        self.offset
+    }
+
+    pub fn set_offset(&mut self, offset: BlockPos) {
+        self.offset = offset;
+    }
+
+    /// Returns the block at the position, or `None` if it's outside the schematic.
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> Option<BlockState> {
+        self.index(x, y, z).map(|index| self.blocks[index])
+    }
+
+    /// Sets the block at the position, removing the block entity there.
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockState) -> Result<()> {
+        let index = self.index(x, y, z).ok_or(WorldError::BlockOutOfBounds { x, y, z })?;
+        self.blocks[index] = block;
+        self.block_entities.remove(&BlockPos::new(x, y, z));
+        Ok(())
+    }
+
+    pub fn block_entity(&self, pos: BlockPos) -> Option<&BlockEntity> {
+        self.block_entities.get(&pos)
+    }
+
+    pub fn block_entities(&self) -> impl Iterator<Item = (BlockPos, &BlockEntity)> {
+        self.block_entities.iter().map(|(pos, entity)| (*pos, entity))
+    }
+
+    pub fn set_block_entity(&mut self, pos: BlockPos, entity: BlockEntity) -> Result<()> {
+        if self.index(pos.x, pos.y, pos.z).is_none() {
+            return Err(WorldError::BlockOutOfBounds { x: pos.x, y: pos.y, z: pos.z });
+        }
+        self.block_entities.insert(pos, entity);
+        Ok(())
+    }
+
+    /// Returns the biome at the position, or `None` if it's outside the schematic or the
+    /// schematic has no biomes.
+    pub fn biome(&self, x: i32, y: i32, z: i32) -> Option<u32> {
+        let index = self.index(x, y, z)?;
+        self.biomes.as_ref().map(|biomes| biomes[index])
+    }
+
+    /// Sets the biome at the position. A schematic without biomes gets the first biome of the
+    /// registry everywhere else.
+    pub fn set_biome(&mut self, x: i32, y: i32, z: i32, biome: u32) -> Result<()> {
+        let index = self.index(x, y, z).ok_or(WorldError::BlockOutOfBounds { x, y, z })?;
+        let volume = self.blocks.len();
+        self.biomes.get_or_insert_with(|| vec![0; volume])[index] = biome;
+        Ok(())
+    }
+
+    /// Pastes the schematic into the world, turned around `origin`, waiting for chunks if
+    /// needed. Every block of the cuboid is set, air included, and its biome if the schematic
+    /// has biomes.
+    ///
+    /// Blocks are set without updating the blocks around them, so that builds paste as they
+    /// were saved.
+    pub fn paste(&self, world: &World, origin: BlockPos, transform: Transform) -> Result<()> {
+        let place = |x: i32, y: i32, z: i32| {
+            let local = transform.apply(BlockPos::new(self.offset.x + x, self.offset.y + y, self.offset.z + z));
+            BlockPos::new(origin.x + local.x, origin.y + local.y, origin.z + local.z)
+        };
+        for (index, &block) in self.blocks.iter().enumerate() {
+            let (x, y, z) = self.position(index);
+            let pos = place(x, y, z);
+            if !world.height().contains(pos.y) {
+                continue;
+            }
+            world.set_block_with(pos.x, pos.y, pos.z, transform.apply_state(block), UpdateFlags::SEND_TO_CLIENTS)?;
+            if let Some(biomes) = &self.biomes {
+                world.set_biome(pos.x, pos.y, pos.z, biomes[index])?;
+            }
+        }
+        for (local, entity) in &self.block_entities {
+            let pos = place(local.x, local.y, local.z);
+            if world.height().contains(pos.y) {
+                world.set_block_entity(pos.x, pos.y, pos.z, entity.clone())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn index(&self, x: i32, y: i32, z: i32) -> Option<usize> {
+        let inside = |value: i32, size: u16| (0..size as i32).contains(&value);
+        if !inside(x, self.width) || !inside(y, self.height) || !inside(z, self.length) {
+            return None;
+        }
+        let (width, length) = (self.width as usize, self.length as usize);
+        Some((y as usize * length + z as usize) * width + x as usize)
+    }
+
+    fn position(&self, index: usize) -> (i32, i32, i32) {
+        let (width, length) = (self.width as usize, self.length as usize);
+        ((index % width) as i32, (index / (width * length)) as i32, (index / width % length) as i32)
+    }
+
+    /// Creates a schematic of air of the size read from a file, checking that the size is
+    /// allowed and that the data has a byte for every block before allocating it.
+    fn read_size(width: i16, height: i16, length: i16, data: Option<&[i8]>) -> Result<Self> {
+        // Sizes are unsigned, saved in signed tags.
+        let (width, height, length) = (width as u16, height as u16, length as u16);
+        let volume = width as usize * height as usize * length as usize;
+        if volume > MAX_VOLUME {
+            return Err(WorldError::InvalidSchematic(format!("{} blocks is more than {}", volume, MAX_VOLUME)));
+        }
+        // Every palette index takes at least a byte.
+        if let Some(data) = data.filter(|data| data.len() < volume) {
+            return Err(WorldError::InvalidSchematic(format!("{} bytes of data for {} blocks", data.len(), volume)));
+        }
+        Ok(Self::new(width, height, length))
+    }
+
+    fn from_v2(nbt: SchematicV2Nbt) -> Result<Self> {
+        let mut schematic = Self::read_size(nbt.width, nbt.height, nbt.length, Some(&nbt.block_data))?;
+        schematic.offset = read_offset(nbt.offset.as_deref(), nbt.metadata.as_ref())?;
+        schematic.blocks = read_blocks(&nbt.palette, &nbt.block_data, schematic.blocks.len())?;
+        for mut data in nbt.block_entities {
+            let (Some(Value::IntArray(pos)), Some(Value::String(kind))) = (data.remove("Pos"), data.remove("Id"))
+            else {
+                return Err(WorldError::InvalidSchematic("block entity without type or position".to_string()));
+            };
+            let pos = read_pos(&pos)?;
+            schematic.set_block_entity(pos, BlockEntity { kind, data })?;
+        }
+        if let (Some(palette), Some(data)) = (nbt.biome_palette, nbt.biome_data) {
+            // The biomes of the columns go to every block of them.
+            let columns = read_biomes(&palette, &data, schematic.width as usize * schematic.length as usize)?;
+            schematic.biomes = Some(columns.iter().copied().cycle().take(schematic.blocks.len()).collect());
+        }
+        Ok(schematic)
+    }
+
+    fn from_v3(nbt: SchematicV3Nbt) -> Result<Self> {
+        let data = nbt.blocks.as_ref().map(|blocks| &blocks.data[..]);
+        let mut schematic = Self::read_size(nbt.width, nbt.height, nbt.length, data)?;
+        schematic.offset = read_offset(nbt.offset.as_deref(), nbt.metadata.as_ref())?;
+        if let Some(blocks) = nbt.blocks {
+            schematic.blocks = read_blocks(&blocks.palette, &blocks.data, schematic.blocks.len())?;
+            for entity in blocks.block_entities {
+                let pos = read_pos(&entity.pos)?;
+                schematic.set_block_entity(pos, BlockEntity { kind: entity.id, data: entity.data })?;
+            }
+        }
+        if let Some(biomes) = nbt.biomes {
+            schematic.biomes = Some(read_biomes(&biomes.palette, &biomes.data, schematic.blocks.len())?);
+        }
+        Ok(schematic)
+    }
+
+    fn to_v2(&self) -> Result<SchematicV2Nbt> {
+        let (palette, block_data) = write_blocks(&self.blocks)?;
+        let block_entities = self
+            .sorted_block_entities()
+            .into_iter()
+            .map(|(pos, entity)| {
+                let mut data = entity.data.clone();
+                data.insert("Pos".to_string(), Value::IntArray(IntArray::new(vec![pos.x, pos.y, pos.z])));
+                data.insert("Id".to_string(), Value::String(entity.kind.clone()));
+                data
+            })
+            .collect();
+        let area = self.width as usize * self.length as usize;
+        let biomes = match &self.biomes {
+            Some(biomes) => Some(write_biomes(&biomes[..area])?),
+            None => None,
+        };
+        Ok(SchematicV2Nbt {
+            version: 2,
+            data_version: DATA_VERSION,
+            width: self.width as i16,
+            height: self.height as i16,
+            length: self.length as i16,
+            offset: Some(IntArray::new(vec![self.offset.x, self.offset.y, self.offset.z])),
+            metadata: None,
+            palette_max: palette.len() as i32,
+            palette,
+            block_data,
+            block_entities,
+            biome_palette_max: biomes.as_ref().map(|(palette, _)| palette.len() as i32),
+            biome_palette: biomes.as_ref().map(|(palette, _)| palette.clone()),
+            biome_data: biomes.map(|(_, data)| data),
+        })
+    }
+
+    fn to_v3(&self) -> Result<SchematicV3Nbt> {
+        let (palette, data) = write_blocks(&self.blocks)?;
+        let block_entities = self
+            .sorted_block_entities()
+            .into_iter()
+            .map(|(pos, entity)| BlockEntityV3Nbt {
+                pos: IntArray::new(vec![pos.x, pos.y, pos.z]),
+                id: entity.kind.clone(),
+                data: entity.data.clone(),
+            })
+            .collect();
+        let biomes = match &self.biomes {
+            Some(biomes) => {
+                let (palette, data) = write_biomes(biomes)?;
+                Some(PaletteContainerNbt { palette, data })
+            }
+            None => None,
+        };
+        Ok(SchematicV3Nbt {
+            version: 3,
+            data_version: DATA_VERSION,
+            width: self.width as i16,
+            height: self.height as i16,
+            length: self.length as i16,
+            offset: Some(IntArray::new(vec![self.offset.x, self.offset.y, self.offset.z])),
+            metadata: None,
+            blocks: Some(BlockContainerNbt { palette, data, block_entities }),
+            biomes,
+        })
+    }
+
+    /// Returns the block entities in the order of their blocks, so files come out the same.
+    fn sorted_block_entities(&self) -> Vec<(BlockPos, &BlockEntity)> {
+        let mut entities: Vec<_> = self.block_entities().collect();
+        entities.sort_unstable_by_key(|(pos, _)| (pos.y, pos.z, pos.x));
+        entities
+    }
+}
+
+/// Reads the offset, preferring WorldEdit's when there is one.
+fn read_offset(offset: Option<&[i32]>, metadata: Option<&MetadataNbt>) -> Result<BlockPos> {
+    if let Some(&MetadataNbt { we_offset_x: Some(x), we_offset_y: Some(y), we_offset_z: Some(z) }) = metadata {
+        return Ok(BlockPos::new(x, y, z));
+    }
+    offset.map_or(Ok(BlockPos::new(0, 0, 0)), read_pos)
+}
+
+fn read_pos(pos: &[i32]) -> Result<BlockPos> {
+    match pos {
+        [x, y, z] => Ok(BlockPos::new(*x, *y, *z)),
+        _ => Err(WorldError::InvalidSchematic(format!("position with {} coordinates", pos.len()))),
+    }
+}
+
+/// Decodes the palette indices, each a varint, into the values of the palette.
+fn read_palette<T: Copy>(palette: &HashMap<i32, T>, data: &[i8], len: usize) -> Result<Vec<T>> {
+    let invalid = |message: &str| WorldError::InvalidSchematic(message.to_string());
+    let mut values = Vec::with_capacity(len);
+    let mut bytes = data.iter().map(|&byte| byte as u8);
+    while values.len() < len {
+        let mut index = 0u32;
+        let mut shift = 0;
+        loop {
+            let byte = bytes.next().ok_or_else(|| invalid("palette data is too short"))?;
+            if shift >= 32 {
+                return Err(invalid("palette index is too long"));
+            }
+            index |= ((byte & 0x7f) as u32) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        values.push(*palette.get(&(index as i32)).ok_or_else(|| invalid("palette index out of range"))?);
+    }
+    Ok(values)
+}
+
+/// Encodes the values as varint indices into a palette of their distinct values, numbered in the
+/// order they first appear.
+fn write_palette<T: Copy + Eq + std::hash::Hash>(values: &[T]) -> (Vec<T>, ByteArray) {
+    let mut palette = Vec::new();
+    let mut indices = HashMap::new();
+    let mut data = Vec::with_capacity(values.len());
+    for &value in values {
+        let mut index = *indices.entry(value).or_insert_with(|| {
+            palette.push(value);
+            palette.len() as u32 - 1
+        });
+        while index >= 0x80 {
+            data.push((index as u8 & 0x7f | 0x80) as i8);
+            index >>= 7;
+        }
+        data.push(index as i8);
+    }
+    (palette, ByteArray::new(data))
+}
+
+fn read_blocks(palette: &HashMap<String, i32>, data: &[i8], len: usize) -> Result<Vec<BlockState>> {
+    let palette = palette
+        .iter()
+        .map(|(state, index)| Ok((*index, BlockState::parse(state)?)))
+        .collect::<Result<HashMap<_, _>>>()?;
+    read_palette(&palette, data, len)
+}
+
+fn write_blocks(blocks: &[BlockState]) -> Result<(HashMap<String, i32>, ByteArray)> {
+    let (palette, data) = write_palette(blocks);
+    let palette = palette
+        .into_iter()
+        .enumerate()
+        .map(|(index, state)| match state.name() {
+            Some(_) => Ok((state.to_string(), index as i32)),
+            None => Err(WorldError::UnknownBlockState(state.id())),
+        })
+        .collect::<Result<_>>()?;
+    Ok((palette, data))
+}
+
+fn read_biomes(palette: &HashMap<String, i32>, data: &[i8], len: usize) -> Result<Vec<u32>> {
+    let registry = biome_registry();
+    let palette = palette
+        .iter()
+        .map(|(name, index)| {
+            let biome = registry.and_then(|registry| registry.id(name));
+            Ok((*index, biome.ok_or_else(|| WorldError::UnknownBiome(name.clone()))?))
+        })
+        .collect::<Result<HashMap<_, _>>>()?;
+    read_palette(&palette, data, len)
+}
+
+fn write_biomes(biomes: &[u32]) -> Result<(HashMap<String, i32>, ByteArray)> {
+    let registry = biome_registry();
+    let (palette, data) = write_palette(biomes);
+    let palette = palette
+        .into_iter()
+        .enumerate()
+        .map(|(index, id)| {
+            let name = registry.and_then(|registry| registry.name(id));
+            Ok((name.ok_or_else(|| WorldError::UnknownBiome(format!("#{}", id)))?.to_string(), index as i32))
+        })
+        .collect::<Result<_>>()?;
+    Ok((palette, data))
+}
@@ -0,0 +1,135 @@
+use crate::block::BlockState;
+use crate::pos::Direction;
+
+/// A box within a block, from 0 to 1 along each axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub min_z: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+    pub max_z: f64,
+}
+
+impl Aabb {
+    pub const FULL: Aabb = Aabb::new(0.0, 0.0, 0.0, 1.0, 1.0, 1.0);
+
+    pub const fn new(min_x: f64, min_y: f64, min_z: f64, max_x: f64, max_y: f64, max_z: f64) -> Self {
+        Self { min_x, min_y, min_z, max_x, max_y, max_z }
+    }
+
+    /// Returns the part of the block `thickness` deep along its side, such as the bottom half for
+    /// down and 0.5.
+    pub fn side(direction: Direction, thickness: f64) -> Self {
+        let (min, max) = (0.0, 1.0);
+        match direction {
+            Direction::Down => Self::new(min, min, min, max, thickness, max),
+            Direction::Up => Self::new(min, max - thickness, min, max, max, max),
+            Direction::North => Self::new(min, min, min, max, max, thickness),
+            Direction::South => Self::new(min, min, max - thickness, max, max, max),
+            Direction::West => Self::new(min, min, min, thickness, max, max),
+            Direction::East => Self::new(max - thickness, min, min, max, max, max),
+        }
+    }
+
+    /// Returns the part of this box that is also in the other one, or `None` if they don't
+    /// overlap.
+    pub fn intersection(&self, other: &Aabb) -> Option<Aabb> {
+        let intersection = Aabb::new(
+            self.min_x.max(other.min_x),
+            self.min_y.max(other.min_y),
+            self.min_z.max(other.min_z),
+            self.max_x.min(other.max_x),
+            self.max_y.min(other.max_y),
+            self.max_z.min(other.max_z),
+        );
+        let empty = intersection.min_x >= intersection.max_x
+            || intersection.min_y >= intersection.max_y
+            || intersection.min_z >= intersection.max_z;
+        (!empty).then_some(intersection)
+    }
+
+    pub fn volume(&self) -> f64 {
+        (self.max_x - self.min_x) * (self.max_y - self.min_y) * (self.max_z - self.min_z)
+    }
+}
+
+/// The boxes an entity collides with in a block, which may be none. The boxes don't overlap.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Shape {
+    boxes: Vec<Aabb>,
+}
+
+impl Shape {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn full() -> Self {
+        Self::from_box(Aabb::FULL)
+    }
+
+    pub fn from_box(aabb: Aabb) -> Self {
+        Self { boxes: vec![aabb] }
+    }
+
+    pub fn from_boxes(boxes: Vec<Aabb>) -> Self {
+        Self { boxes }
+    }
+
+    pub fn boxes(&self) -> &[Aabb] {
+        &self.boxes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    /// Whether the shape fills the whole block.
+    pub fn is_full(&self) -> bool {
+        self.boxes.iter().map(|aabb| aabb.volume()).sum::<f64>() >= 1.0 - 1e-9
+    }
+
+    /// Whether the shape is big enough to count as solid, which stops fluids: its bounds are on
+    /// average at least 0.73 blocks long, or a block high.
+    pub fn is_solid(&self) -> bool {
+        let Some(first) = self.boxes.first() else {
+            return false;
+        };
+        let bounds = self.boxes.iter().fold(*first, |bounds, aabb| {
+            Aabb::new(
+                bounds.min_x.min(aabb.min_x),
+                bounds.min_y.min(aabb.min_y),
+                bounds.min_z.min(aabb.min_z),
+                bounds.max_x.max(aabb.max_x),
+                bounds.max_y.max(aabb.max_y),
+                bounds.max_z.max(aabb.max_z),
+            )
+        });
+        let height = bounds.max_y - bounds.min_y;
+        let size = (bounds.max_x - bounds.min_x + height + bounds.max_z - bounds.min_z) / 3.0;
+        size >= 0.7291666666666666 || height >= 1.0
+    }
+
+    /// Whether the shape covers the whole face of the block on the side, so that something can
+    /// hang on it.
+    pub fn covers_face(&self, direction: Direction) -> bool {
+        let face = Aabb::side(direction, 1.0 / 16.0);
+        let covered: f64 =
+            self.boxes.iter().filter_map(|aabb| aabb.intersection(&face)).map(|aabb| aabb.volume()).sum();
+        covered >= face.volume() - 1e-9
+    }
+}
+
+/// Returns the shape of a block that has no shape of its own: a full block if it stops movement,
+/// and nothing for air, plants and fluids.
+pub fn block_shape(state: BlockState) -> Shape {
+    let flags = state.flags();
+    let fluid = flags.fluid && state.get("waterlogged").is_none();
+    if flags.motion_blocking && !fluid {
+        Shape::full()
+    } else {
+        Shape::empty()
+    }
+}
@@ -0,0 +1,192 @@
+//! Tickets decide which chunks stay loaded. Like in vanilla, a ticket gives its chunk a load level
+//! that rises by one per chunk away from it, and every chunk with a level of at most
+//! [`FULL_CHUNK_LEVEL`] is kept loaded.
+
+use crate::view::ChunkPos;
+use std::collections::HashMap;
+
+/// The highest level at which a chunk is loaded.
+pub const FULL_CHUNK_LEVEL: u8 = 33;
+/// The highest level at which the blocks of a chunk are ticked.
+pub const BLOCK_TICKING_LEVEL: u8 = 32;
+/// The highest level at which the entities of a chunk are ticked.
+pub const ENTITY_TICKING_LEVEL: u8 = 31;
+
+/// Why a chunk is kept loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TicketKind {
+    /// The chunks a player can see.
+    Player,
+    /// The chunks around the world spawn.
+    Spawn,
+    /// A chunk forced to stay loaded until its ticket is removed.
+    Forced,
+    /// A chunk needed for a short while, whose ticket expires by itself.
+    Temporary,
+    /// The chunks whose blocks tick around a player. These tickets don't load chunks: the world
+    /// tracks them apart from the others, like vanilla's simulation distance.
+    Simulation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ticket {
+    pub kind: TicketKind,
+    /// The level of the ticket's chunk. Lower levels keep more chunks loaded.
+    pub level: u8,
+    /// How many ticks a temporary ticket lasts.
+    pub lifetime: Option<u64>,
+}
+
+impl Ticket {
+    /// Keeps the chunks within the view distance loaded.
+    pub fn player(view_distance: i32) -> Self {
+        Self::with_radius(TicketKind::Player, view_distance)
+    }
+
+    /// Keeps the chunks within the radius of the spawn chunk loaded.
+    pub fn spawn(radius: i32) -> Self {
+        Self::with_radius(TicketKind::Spawn, radius)
+    }
+
+    /// Ticks the blocks of the loaded chunks within the simulation distance.
+    pub fn simulation(simulation_distance: i32) -> Self {
+        Self {
+            kind: TicketKind::Simulation,
+            level: BLOCK_TICKING_LEVEL - simulation_distance.clamp(0, BLOCK_TICKING_LEVEL as i32) as u8,
+            lifetime: None,
+        }
+    }
+
+    /// Keeps the chunk loaded with its entities ticking, like the `/forceload` command.
+    pub fn forced() -> Self {
+        Self {
+            kind: TicketKind::Forced,
+            level: ENTITY_TICKING_LEVEL,
+            lifetime: None,
+        }
+    }
+
+    /// Keeps the chunks within the radius loaded for the given number of ticks.
+    pub fn temporary(radius: i32, lifetime: u64) -> Self {
+        Self {
+            lifetime: Some(lifetime),
+            ..Self::with_radius(TicketKind::Temporary, radius)
+        }
+    }
+
+    fn with_radius(kind: TicketKind, radius: i32) -> Self {
+        Self {
+            kind,
+            level: FULL_CHUNK_LEVEL - radius.clamp(0, FULL_CHUNK_LEVEL as i32) as u8,
+            lifetime: None,
+        }
+    }
+
+    /// How many chunks around its own the ticket keeps loaded.
+    pub fn radius(&self) -> i32 {
+        FULL_CHUNK_LEVEL.saturating_sub(self.level) as i32
+    }
+}
+
+/// The tickets of a dimension and the load levels they give to chunks. Levels are recomputed
+/// when they are next read after a ticket changed.
+#[derive(Debug, Default)]
+pub struct ChunkTickets {
+    tickets: HashMap<ChunkPos, Vec<(Ticket, Option<u64>)>>,
+    levels: HashMap<ChunkPos, u8>,
+    stale: bool,
+    tick: u64,
+}
+
+impl ChunkTickets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a ticket to the chunk. The same ticket can be added several times and is then
+    /// removed as many times.
+    pub fn add(&mut self, pos: ChunkPos, ticket: Ticket) {
+        let expires_at = ticket.lifetime.map(|lifetime| self.tick + lifetime);
+        self.tickets.entry(pos).or_default().push((ticket, expires_at));
+        self.stale = true;
+    }
+
+    /// Removes one copy of the ticket from the chunk. Returns false if the chunk didn't have it.
+    pub fn remove(&mut self, pos: ChunkPos, ticket: Ticket) -> bool {
+        let Some(tickets) = self.tickets.get_mut(&pos) else {
+            return false;
+        };
+        let Some(index) = tickets.iter().position(|(existing, _)| *existing == ticket) else {
+            return false;
+        };
+        tickets.swap_remove(index);
+        if tickets.is_empty() {
+            self.tickets.remove(&pos);
+        }
+        self.stale = true;
+        true
+    }
+
+    /// Advances the tickets by a tick, removing the temporary tickets that expired.
+    pub fn tick(&mut self) {
+        self.tick += 1;
+        let tick = self.tick;
+        let before = self.ticket_count();
+        self.tickets.retain(|_, tickets| {
+            tickets.retain(|(_, expires_at)| expires_at.is_none_or(|expires_at| expires_at > tick));
+            !tickets.is_empty()
+        });
+        if self.ticket_count() != before {
+            self.stale = true;
+        }
+    }
+
+    /// The number of ticks since the tickets were created.
+    pub fn current_tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns the load level of the chunk, or `None` if no ticket keeps it loaded.
+    pub fn level(&mut self, pos: ChunkPos) -> Option<u8> {
+        self.update_levels();
+        self.levels.get(&pos).copied()
+    }
+
+    /// Iterates over the chunks kept loaded and their levels.
+    pub fn levels(&mut self) -> impl Iterator<Item = (ChunkPos, u8)> + '_ {
+        self.update_levels();
+        self.levels.iter().map(|(&pos, &level)| (pos, level))
+    }
+
+    /// The number of chunks kept loaded.
+    pub fn ticketed_chunks(&mut self) -> usize {
+        self.update_levels();
+        self.levels.len()
+    }
+
+    pub fn ticket_count(&self) -> usize {
+        self.tickets.values().map(Vec::len).sum()
+    }
+
+    fn update_levels(&mut self) {
+        if !self.stale {
+            return;
+        }
+        self.levels.clear();
+        for (&pos, tickets) in &self.tickets {
+            let level = tickets.iter().map(|(ticket, _)| ticket.level).min().unwrap_or(u8::MAX);
+            let radius = FULL_CHUNK_LEVEL.saturating_sub(level) as i32;
+            for dz in -radius..=radius {
+                for dx in -radius..=radius {
+                    let distance = dx.abs().max(dz.abs()) as u8;
+                    let chunk_level = level + distance;
+                    self.levels
+                        .entry(ChunkPos::new(pos.x + dx, pos.z + dz))
+                        .and_modify(|existing| *existing = (*existing).min(chunk_level))
+                        .or_insert(chunk_level);
+                }
+            }
+        }
+        self.stale = false;
+    }
+}
@@ -0,0 +1,348 @@
+use crate::block::{light_properties, BlockState};
+use crate::error::Result;
+use crate::pos::{BlockPos, Direction};
+use crate::shape::{block_shape, Shape};
+use crate::world::World;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::ops::BitOr;
+use std::sync::Arc;
+use std::thread::{self, ThreadId};
+
+/// The most updates a single block change can set off, counting the updates of the blocks it
+/// changes in turn. The updates past it are dropped, so a contraption updating itself forever
+/// can't hang the server.
+pub const MAX_CHAINED_UPDATES: usize = 1_000_000;
+/// The most scheduled ticks run in a tick. The others wait for the next tick.
+pub const MAX_TICKS_PER_TICK: usize = 65_536;
+
+/// Which side effects setting a block has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UpdateFlags(u8);
+
+impl UpdateFlags {
+    pub const NONE: Self = Self(0);
+    /// Tells the six blocks around the block that it changed.
+    pub const NOTIFY_NEIGHBORS: Self = Self(1);
+    /// Lets the six blocks around the block change their state to fit it, such as a fence
+    /// connecting to it.
+    pub const UPDATE_SHAPES: Self = Self(1 << 1);
+    /// Sends the change to the players who were sent the chunk.
+    pub const SEND_TO_CLIENTS: Self = Self(1 << 2);
+    /// What placing or breaking a block does.
+    pub const DEFAULT: Self = Self(Self::NOTIFY_NEIGHBORS.0 | Self::UPDATE_SHAPES.0 | Self::SEND_TO_CLIENTS.0);
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn without(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl BitOr for UpdateFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl Default for UpdateFlags {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// What blocks do when the blocks around them change or their scheduled tick comes, what shape
+/// they have and what redstone power they give off. Every method does nothing by default, blocks
+/// have the shape of [`block_shape`] and give off no power.
+///
+/// The methods are called without any lock of the world held, so they can read and set blocks.
+pub trait UpdateHandler: Send + Sync {
+    /// Called after the block at `pos` was set to `state` where `previous` was, before the blocks
+    /// around it are told. Blocks set without notifying their neighbors don't call it.
+    fn block_changed(&self, _world: &World, _pos: BlockPos, _previous: BlockState, _state: BlockState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when the block at `from`, next to the block at `pos`, changed.
+    fn neighbor_changed(&self, _world: &World, _pos: BlockPos, _state: BlockState, _from: BlockPos) -> Result<()> {
+        Ok(())
+    }
+
+    /// Returns the state the block at `pos` should have now that the block on its `direction`
+    /// side is `neighbor`. Returning air breaks the block.
+    fn update_shape(
+        &self,
+        _world: &World,
+        _pos: BlockPos,
+        state: BlockState,
+        _direction: Direction,
+        _neighbor: BlockState,
+    ) -> BlockState {
+        state
+    }
+
+    /// Called when a tick scheduled for the block at `pos` comes, if the block is still there.
+    fn scheduled_tick(&self, _world: &World, _pos: BlockPos, _state: BlockState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a tick scheduled for the fluid in the block at `pos` comes, if the fluid is
+    /// still there.
+    fn fluid_tick(&self, _world: &World, _pos: BlockPos, _state: BlockState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when the block at `pos`, which ticks randomly, was picked to tick.
+    fn random_tick(&self, _world: &World, _pos: BlockPos, _state: BlockState) -> Result<()> {
+        Ok(())
+    }
+
+    /// Returns the boxes entities collide with in the block.
+    fn collision_shape(&self, state: BlockState) -> Shape {
+        block_shape(state)
+    }
+
+    /// Whether the block gives off redstone power of its own, which wire connects to.
+    fn is_signal_source(&self, _state: BlockState) -> bool {
+        false
+    }
+
+    /// Returns the power from 0 to 15 the block at `pos` gives the block next to it, where
+    /// `direction` points from that block to `pos`.
+    fn signal(&self, _world: &World, _pos: BlockPos, _state: BlockState, _direction: Direction) -> u8 {
+        0
+    }
+
+    /// Returns the power the block at `pos` gives the block next to it strongly, so that a
+    /// conductor passes it on to the blocks around it. `direction` points from that block to
+    /// `pos`.
+    fn direct_signal(&self, _world: &World, _pos: BlockPos, _state: BlockState, _direction: Direction) -> u8 {
+        0
+    }
+
+    /// Returns the power a comparator reading the block at `pos` gives, or `None` if the block
+    /// has nothing to read.
+    fn analog_output(&self, _world: &World, _pos: BlockPos, _state: BlockState) -> Option<u8> {
+        None
+    }
+
+    /// Whether the block passes the power it's strongly given on to the blocks around it, like
+    /// full opaque blocks do.
+    fn is_redstone_conductor(&self, state: BlockState) -> bool {
+        self.collision_shape(state).is_full() && light_properties(state).opacity == 15
+    }
+}
+
+impl<T: UpdateHandler + ?Sized> UpdateHandler for Arc<T> {
+    fn block_changed(&self, world: &World, pos: BlockPos, previous: BlockState, state: BlockState) -> Result<()> {
+        (**self).block_changed(world, pos, previous, state)
+    }
+
+    fn neighbor_changed(&self, world: &World, pos: BlockPos, state: BlockState, from: BlockPos) -> Result<()> {
+        (**self).neighbor_changed(world, pos, state, from)
+    }
+
+    fn update_shape(
+        &self,
+        world: &World,
+        pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        neighbor: BlockState,
+    ) -> BlockState {
+        (**self).update_shape(world, pos, state, direction, neighbor)
+    }
+
+    fn scheduled_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        (**self).scheduled_tick(world, pos, state)
+    }
+
+    fn fluid_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        (**self).fluid_tick(world, pos, state)
+    }
+
+    fn random_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        (**self).random_tick(world, pos, state)
+    }
+
+    fn collision_shape(&self, state: BlockState) -> Shape {
+        (**self).collision_shape(state)
+    }
+
+    fn is_signal_source(&self, state: BlockState) -> bool {
+        (**self).is_signal_source(state)
+    }
+
+    fn signal(&self, world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        (**self).signal(world, pos, state, direction)
+    }
+
+    fn direct_signal(&self, world: &World, pos: BlockPos, state: BlockState, direction: Direction) -> u8 {
+        (**self).direct_signal(world, pos, state, direction)
+    }
+
+    fn analog_output(&self, world: &World, pos: BlockPos, state: BlockState) -> Option<u8> {
+        (**self).analog_output(world, pos, state)
+    }
+
+    fn is_redstone_conductor(&self, state: BlockState) -> bool {
+        (**self).is_redstone_conductor(state)
+    }
+}
+
+/// The handler of worlds whose blocks do nothing on their own.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoUpdates;
+
+impl UpdateHandler for NoUpdates {}
+
+/// An update waiting to be given to the handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Update {
+    /// The block at `from`, next to the one at `pos`, changed.
+    Neighbor { pos: BlockPos, from: BlockPos },
+    /// The block on the `direction` side of the one at `pos` changed, and the block at `pos` is
+    /// set with the flags if its shape changes.
+    Shape { pos: BlockPos, direction: Direction, flags: UpdateFlags },
+    /// The block at `pos` was set where `previous` was.
+    Changed { pos: BlockPos, previous: BlockState },
+}
+
+/// The updates set off by block changes that weren't handled yet. The first thread to queue
+/// updates runs them, including the ones other threads and the handler queue while it does, so
+/// a chain of updates never deepens the stack and runs in the order it was queued.
+#[derive(Debug, Default)]
+pub(crate) struct UpdateQueue {
+    updates: VecDeque<Update>,
+    /// The thread running the updates, if any.
+    runner: Option<ThreadId>,
+    /// The updates run since the queue was last empty.
+    run: usize,
+}
+
+impl UpdateQueue {
+    /// Queues the updates of a block change. Returns true if the calling thread should run them.
+    pub(crate) fn push(&mut self, updates: impl IntoIterator<Item = Update>) -> bool {
+        self.updates.extend(updates);
+        if self.runner.is_some() {
+            return false;
+        }
+        self.runner = Some(thread::current().id());
+        true
+    }
+
+    /// Returns the next update to run, or `None` once the queue is empty or the chain ran too
+    /// long, after which the calling thread no longer runs the queue.
+    pub(crate) fn next(&mut self) -> Option<Update> {
+        if self.run >= MAX_CHAINED_UPDATES {
+            self.updates.clear();
+        }
+        let update = self.updates.pop_front();
+        match update {
+            Some(_) => self.run += 1,
+            None => {
+                self.runner = None;
+                self.run = 0;
+            }
+        }
+        update
+    }
+
+    /// Drops the queued updates after the thread running them panicked.
+    pub(crate) fn abandon(&mut self) {
+        self.updates.clear();
+        self.runner = None;
+        self.run = 0;
+    }
+}
+
+/// How soon a tick runs among the ticks due in the same tick.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TickPriority {
+    ExtremelyHigh,
+    VeryHigh,
+    High,
+    #[default]
+    Normal,
+    Low,
+    VeryLow,
+    ExtremelyLow,
+}
+
+/// A tick scheduled for a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScheduledTick {
+    pub pos: BlockPos,
+    /// The default state of the block the tick is for. The tick is skipped if a different block
+    /// is at the position by then.
+    pub block: BlockState,
+    /// The tick it runs in.
+    pub due: u64,
+    pub priority: TickPriority,
+}
+
+/// The ticks scheduled for blocks, run in the order of the tick they are due in, then of their
+/// priority, then of when they were scheduled.
+#[derive(Debug, Default)]
+pub struct TickQueue {
+    tick: u64,
+    /// How many ticks were scheduled, which orders ticks that are otherwise equal.
+    scheduled_count: u64,
+    queue: BTreeSet<(u64, TickPriority, u64, BlockPos, BlockState)>,
+    scheduled: HashSet<(BlockPos, BlockState)>,
+}
+
+impl TickQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of the tick being run, counting from 0.
+    pub fn current_tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Schedules a tick for the block at the position `delay` ticks from now, at least the next
+    /// tick. Returns false if a tick for the same block at the position was already scheduled.
+    pub fn schedule(&mut self, pos: BlockPos, block: BlockState, delay: u64, priority: TickPriority) -> bool {
+        let block = block.default_state();
+        if !self.scheduled.insert((pos, block)) {
+            return false;
+        }
+        self.queue.insert((self.tick + delay.max(1), priority, self.scheduled_count, pos, block));
+        self.scheduled_count += 1;
+        true
+    }
+
+    pub fn is_scheduled(&self, pos: BlockPos, block: BlockState) -> bool {
+        self.scheduled.contains(&(pos, block.default_state()))
+    }
+
+    /// Moves on to the next tick and returns the ticks due by then, in the order they run, at
+    /// most [`MAX_TICKS_PER_TICK`].
+    pub fn tick(&mut self) -> Vec<ScheduledTick> {
+        self.tick += 1;
+        let mut due = Vec::new();
+        while due.len() < MAX_TICKS_PER_TICK {
+            match self.queue.first() {
+                Some(&(at, ..)) if at <= self.tick => {}
+                _ => break,
+            }
+            let (at, priority, _, pos, block) = self.queue.pop_first().unwrap();
+            self.scheduled.remove(&(pos, block));
+            due.push(ScheduledTick { pos, block, due: at, priority });
+        }
+        due
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
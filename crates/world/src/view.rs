@@ -0,0 +1,67 @@
+use crate::chunk::SECTION_WIDTH;
+
+/// The position of a chunk column in chunk coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ChunkPos {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    /// Returns the chunk containing the given block coordinates.
+    pub fn from_block(x: i32, z: i32) -> Self {
+        Self {
+            x: x.div_euclid(SECTION_WIDTH as i32),
+            z: z.div_euclid(SECTION_WIDTH as i32),
+        }
+    }
+}
+
+/// The chunks a player can see, a circle of chunks around the chunk the player is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkView {
+    pub center: ChunkPos,
+    pub view_distance: i32,
+}
+
+impl ChunkView {
+    pub fn new(center: ChunkPos, view_distance: i32) -> Self {
+        Self { center, view_distance }
+    }
+
+    /// Uses the same rounded circle as vanilla, which measures the distance from the edges of the
+    /// center chunk rather than from its middle.
+    pub fn contains(&self, pos: ChunkPos) -> bool {
+        let dx = ((pos.x - self.center.x).abs() - 1).max(0) as i64;
+        let dz = ((pos.z - self.center.z).abs() - 1).max(0) as i64;
+        let distance = self.view_distance as i64;
+        dx * dx + dz * dz < distance * distance
+    }
+
+    /// Iterates over the chunks in view ring by ring, starting at the center.
+    pub fn spiral(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        (0..=self.view_distance)
+            .flat_map(move |radius| ring(self.center, radius))
+            .filter(move |pos| self.contains(*pos))
+    }
+}
+
+/// Returns the chunks on the edge of the square of the given radius around the center.
+fn ring(center: ChunkPos, radius: i32) -> Vec<ChunkPos> {
+    if radius == 0 {
+        return vec![center];
+    }
+
+    let mut positions = Vec::with_capacity(radius as usize * 8);
+    for offset in -radius..radius {
+        positions.push(ChunkPos::new(center.x + offset, center.z - radius));
+        positions.push(ChunkPos::new(center.x + radius, center.z + offset));
+        positions.push(ChunkPos::new(center.x - offset, center.z + radius));
+        positions.push(ChunkPos::new(center.x - radius, center.z - offset));
+    }
+    positions
+}
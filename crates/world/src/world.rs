@@ -1,38 +1,1038 @@
-use crate::chunk::ChunkColumn;
+use crate::anvil::{write_chunk_nbt, RegionStorage};
+use crate::block::BlockState;
+use crate::block_entity::BlockEntity;
+use crate::changes::{BlockChange, ChangeKind, ChangeLog, Changes};
+use crate::chunk::{ChunkColumn, SECTION_HEIGHT, SECTION_WIDTH};
+use crate::compact::CompactWorld;
+use crate::error::{Result, WorldError};
+use crate::fluid::{Fluid, FluidState};
+use crate::generator::{ChunkGenerator, Random};
+use crate::height::WorldHeight;
+use crate::light::{light_column, LightArea, LightKind};
+use crate::loader::{ChunkHandle, Pending, SharedChunk, WorkerPool};
+use crate::pos::{BlockPos, Direction};
+use crate::shape::Shape;
+use crate::ticket::{ChunkTickets, Ticket, TicketKind, BLOCK_TICKING_LEVEL};
+use crate::update::{NoUpdates, TickPriority, TickQueue, Update, UpdateFlags, UpdateHandler, UpdateQueue};
+use crate::view::ChunkPos;
 use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard};
+use tracing::warn;
 
+/// How many ticks a chunk without tickets stays loaded by default.
+pub const DEFAULT_UNLOAD_DELAY: u64 = 200;
+/// How many blocks of each section of the ticking chunks are picked to tick at random every tick
+/// by default, like vanilla's `randomTickSpeed` game rule.
+pub const DEFAULT_RANDOM_TICK_SPEED: u32 = 3;
+/// How many ticks to wait before loading a ticketed chunk that failed to load again. The wait
+/// doubles with every failure, up to `MAX_RETRY_DELAY`.
+const RETRY_DELAY: u64 = 20;
+const MAX_RETRY_DELAY: u64 = 1200;
+
+/// The chunks of a dimension. A world is shared between tasks: loaded chunks are read without
+/// waiting, and chunks that aren't loaded are read or generated on worker threads.
+///
+/// Chunks stay loaded while a [`Ticket`] keeps them loaded. [`tick`](Self::tick) loads the chunks
+/// that tickets need and unloads the others once they went unneeded for the unload delay. Worlds
+/// without region files keep every chunk they loaded, since they couldn't read them back.
+///
+/// Setting a block tells the blocks around it, and blocks can schedule ticks for later. The
+/// blocks of the chunks near a simulation ticket also tick at random. What blocks do about any of
+/// it is up to the world's [`UpdateHandler`].
 pub struct World {
-    chunks: HashMap<(i32, i32), ChunkColumn>,
+    shared: Arc<Shared>,
+    workers: WorkerPool,
+    lifetimes: Mutex<Lifetimes>,
+    changes: Mutex<ChangeLog>,
+    handler: Box<dyn UpdateHandler>,
+    updates: Mutex<UpdateQueue>,
+    ticks: Mutex<TickQueue>,
+    /// The scheduled ticks of fluids, keyed by the source block of the fluid.
+    fluid_ticks: Mutex<TickQueue>,
+    /// How many blocks of each section of the ticking chunks tick at random every tick.
+    random_tick_speed: u32,
+    random: Mutex<Random>,
+    /// How many ticks a chunk without tickets stays loaded.
+    unload_delay: u64,
+    /// The number of loaded chunks above which chunks without tickets are unloaded right away, or
+    /// 0 for no limit.
+    max_loaded_chunks: usize,
+}
+
+/// The tickets and the chunks waiting to be unloaded.
+#[derive(Default)]
+struct Lifetimes {
+    tickets: ChunkTickets,
+    /// The simulation tickets, which decide which chunks tick.
+    simulation: ChunkTickets,
+    /// The tick at which each chunk without tickets is unloaded.
+    unload_at: HashMap<(i32, i32), u64>,
+    unloaded: u64,
+    /// The loads started for tickets that haven't been checked since they finished.
+    loading: HashMap<(i32, i32), ChunkHandle>,
+    /// The ticketed chunks that failed to load, with the tick to try loading them again at.
+    failed: HashMap<(i32, i32), FailedLoad>,
+}
+
+struct FailedLoad {
+    retry_at: u64,
+    delay: u64,
+}
+
+impl Lifetimes {
+    /// Returns the tickets the ticket goes with.
+    fn tickets_of(&mut self, ticket: Ticket) -> &mut ChunkTickets {
+        match ticket.kind {
+            TicketKind::Simulation => &mut self.simulation,
+            _ => &mut self.tickets,
+        }
+    }
+}
+
+/// Counts of the chunks of a world, for monitoring.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChunkMetrics {
+    pub loaded: usize,
+    /// The chunks being read or generated.
+    pub pending: usize,
+    /// The chunks kept loaded by tickets, loaded or not.
+    pub ticketed: usize,
+    pub tickets: usize,
+    /// The loaded chunks without tickets, waiting for the unload delay.
+    pub awaiting_unload: usize,
+    /// The chunks unloaded since the world was opened.
+    pub unloaded: u64,
+}
+
+/// The part of the world the workers use.
+struct Shared {
+    height: WorldHeight,
+    /// Generates the chunks that were never saved.
+    generator: Box<dyn ChunkGenerator>,
+    /// Where chunks are read from and saved to. Worlds without one are never saved.
+    regions: Option<RegionStorage>,
+    chunks: RwLock<HashMap<(i32, i32), SharedChunk>>,
+    /// The chunks being loaded. A chunk is added to `chunks` and lit with its neighbors before
+    /// it is removed from here, so it is always in one of them while it is loading or loaded.
+    pending: Mutex<HashMap<(i32, i32), Arc<Pending>>>,
+    /// The unloaded chunks whose save wasn't written yet. Loading one of them takes it back
+    /// rather than reading what was saved before. Taken after `pending` and never with `chunks`.
+    unloading: Mutex<HashMap<(i32, i32), SharedChunk>>,
+    /// Held from encoding chunks until they are written, so an older copy of a chunk is never
+    /// written over a newer one and a failed save never marks an unloaded chunk dirty. Taken
+    /// before `pending`.
+    saving: Mutex<()>,
 }
 
 impl World {
-    pub fn new() -> Self {
+    /// Creates a world that only exists in memory, with a worker thread per CPU.
+    pub fn new(height: WorldHeight, generator: Box<dyn ChunkGenerator>) -> Self {
+        Self::with_storage(height, generator, None, HashMap::new())
+    }
+
+    /// Opens a world saved in the directory, which holds the `region` directory. Chunks that
+    /// weren't saved are generated.
+    pub fn open(height: WorldHeight, directory: impl Into<PathBuf>, generator: Box<dyn ChunkGenerator>) -> Self {
+        let regions = RegionStorage::new(directory.into().join("region"));
+        Self::with_storage(height, generator, Some(regions), HashMap::new())
+    }
+
+    /// Loads every chunk of a compact world. Chunks outside of it are generated, and nothing is
+    /// saved.
+    pub fn from_compact(
+        height: WorldHeight,
+        compact: CompactWorld,
+        generator: Box<dyn ChunkGenerator>,
+    ) -> Result<Self> {
+        if compact.height() != height {
+            return Err(WorldError::InvalidCompactWorld(format!(
+                "saved with {} blocks from y {}, but the dimension has {} from y {}",
+                compact.height().height(),
+                compact.height().min_y(),
+                height.height(),
+                height.min_y()
+            )));
+        }
+        let chunks: HashMap<_, _> = compact
+            .into_chunks()
+            .map(|(position, mut chunk)| {
+                light_column(&mut chunk);
+                (position, Arc::new(RwLock::new(chunk)))
+            })
+            .collect();
+        let positions: Vec<_> = chunks.iter().map(|(&position, chunk)| (position, Arc::clone(chunk))).collect();
+        let world = Self::with_storage(height, generator, None, chunks);
+        for ((x, z), chunk) in positions {
+            world.shared.with_light_area(x, z, &chunk, |area| area.spread_across_edges());
+        }
+        Ok(world)
+    }
+
+    fn with_storage(
+        height: WorldHeight,
+        generator: Box<dyn ChunkGenerator>,
+        regions: Option<RegionStorage>,
+        chunks: HashMap<(i32, i32), SharedChunk>,
+    ) -> Self {
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
         Self {
-            chunks: HashMap::new(),
+            shared: Arc::new(Shared {
+                height,
+                generator,
+                regions,
+                chunks: RwLock::new(chunks),
+                pending: Mutex::new(HashMap::new()),
+                unloading: Mutex::new(HashMap::new()),
+                saving: Mutex::new(()),
+            }),
+            workers: WorkerPool::new(threads),
+            lifetimes: Mutex::new(Lifetimes::default()),
+            changes: Mutex::new(ChangeLog::new()),
+            handler: Box::new(NoUpdates),
+            updates: Mutex::new(UpdateQueue::default()),
+            ticks: Mutex::new(TickQueue::new()),
+            fluid_ticks: Mutex::new(TickQueue::new()),
+            random_tick_speed: DEFAULT_RANDOM_TICK_SPEED,
+            random: Mutex::new(Random::new(0)),
+            unload_delay: DEFAULT_UNLOAD_DELAY,
+            max_loaded_chunks: 0,
         }
     }
 
-    pub fn get_chunk(&mut self, x: i32, z: i32) -> &mut ChunkColumn {
-        if !self.chunks.contains_key(&(x, z)) {
-            let chunk = self.generate_chunk(x, z);
-            self.chunks.insert((x, z), chunk);
+    /// Replaces the worker threads with the given number of threads, at least one.
+    pub fn with_worker_threads(mut self, threads: usize) -> Self {
+        self.workers = WorkerPool::new(threads);
+        self
+    }
+
+    /// Sets how many ticks a chunk without tickets stays loaded.
+    pub fn with_unload_delay(mut self, ticks: u64) -> Self {
+        self.unload_delay = ticks;
+        self
+    }
+
+    /// Sets the number of loaded chunks above which chunks without tickets are unloaded without
+    /// waiting for the unload delay. Chunks with tickets are never unloaded, so the limit can
+    /// still be exceeded. 0 removes the limit.
+    pub fn with_max_loaded_chunks(mut self, chunks: usize) -> Self {
+        self.max_loaded_chunks = chunks;
+        self
+    }
+
+    /// Sets how many blocks of each section of the ticking chunks tick at random every tick. 0
+    /// turns random ticks off.
+    pub fn with_random_tick_speed(mut self, speed: u32) -> Self {
+        self.random_tick_speed = speed;
+        self
+    }
+
+    /// Seeds the random source that picks the blocks that tick at random and that blocks use
+    /// when they do.
+    pub fn with_random_seed(self, seed: u64) -> Self {
+        *self.random.lock().unwrap() = Random::new(seed);
+        self
+    }
+
+    /// Sets what blocks do when the blocks around them change or their scheduled ticks come.
+    pub fn with_update_handler(mut self, handler: Box<dyn UpdateHandler>) -> Self {
+        self.handler = handler;
+        self
+    }
+
+    pub fn worker_threads(&self) -> usize {
+        self.workers.thread_count()
+    }
+
+    pub fn height(&self) -> WorldHeight {
+        self.shared.height
+    }
+
+    /// Returns the chunk if it is loaded, without waiting for anything but the chunk map.
+    pub fn loaded_chunk(&self, x: i32, z: i32) -> Option<SharedChunk> {
+        self.shared.chunks.read().unwrap().get(&(x, z)).cloned()
+    }
+
+    pub fn is_loaded(&self, x: i32, z: i32) -> bool {
+        self.shared.chunks.read().unwrap().contains_key(&(x, z))
+    }
+
+    /// Whether the chunk and the chunks around it are loaded, so light has spread into it from
+    /// every side and it can be sent to players.
+    pub fn is_ready(&self, x: i32, z: i32) -> bool {
+        let pending = self.shared.pending.lock().unwrap();
+        let chunks = self.shared.chunks.read().unwrap();
+        (-1..=1).all(|dz| {
+            (-1..=1).all(|dx| {
+                let position = (x + dx, z + dz);
+                chunks.contains_key(&position) && !pending.contains_key(&position)
+            })
+        })
+    }
+
+    /// The number of chunks being read or generated.
+    pub fn pending_chunks(&self) -> usize {
+        self.shared.pending.lock().unwrap().len()
+    }
+
+    /// Requests the chunk, reading it from the region files or generating it on a worker if it
+    /// isn't loaded. Requests for a chunk that is already being loaded wait for the same load.
+    pub fn load_chunk(&self, x: i32, z: i32) -> ChunkHandle {
+        if let Some(chunk) = self.loaded_chunk(x, z) {
+            return ChunkHandle::ready(x, z, chunk);
+        }
+
+        let mut pending = self.shared.pending.lock().unwrap();
+        // The chunk may have finished loading since it was looked up.
+        if let Some(chunk) = self.loaded_chunk(x, z) {
+            return ChunkHandle::ready(x, z, chunk);
         }
-        self.chunks.get_mut(&(x, z)).unwrap()
+        if let Some(load) = pending.get(&(x, z)) {
+            return ChunkHandle::pending(x, z, Arc::clone(load));
+        }
+        let unloading = self.shared.unloading.lock().unwrap().remove(&(x, z));
+        if let Some(chunk) = unloading {
+            self.shared.chunks.write().unwrap().insert((x, z), Arc::clone(&chunk));
+            return ChunkHandle::ready(x, z, chunk);
+        }
+
+        let load = Arc::new(Pending::default());
+        pending.insert((x, z), Arc::clone(&load));
+        drop(pending);
+
+        let shared = Arc::clone(&self.shared);
+        let handle = ChunkHandle::pending(x, z, Arc::clone(&load));
+        self.workers.execute(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| shared.read_or_generate(x, z)))
+                .unwrap_or_else(|_| Err(WorldError::ChunkLoad { x, z, message: "the generator panicked".to_string() }));
+            let result = result.map(|chunk| {
+                let chunk = Arc::new(RwLock::new(chunk));
+                shared.chunks.write().unwrap().insert((x, z), Arc::clone(&chunk));
+                shared.with_light_area(x, z, &chunk, |area| area.spread_across_edges());
+                chunk
+            });
+            shared.pending.lock().unwrap().remove(&(x, z));
+            load.complete(result.map_err(|e| e.to_string()));
+        });
+        handle
     }
 
-    fn generate_chunk(&self, _chunk_x: i32, _chunk_z: i32) -> ChunkColumn {
-        let mut chunk = ChunkColumn::new();
+    /// Returns the chunk, blocking the thread until it is loaded. Async code should await
+    /// [`load_chunk`](Self::load_chunk) instead.
+    pub fn get_chunk(&self, x: i32, z: i32) -> Result<SharedChunk> {
+        self.load_chunk(x, z).wait()
+    }
 
-        for x in 0..16 {
-            for z in 0..16 {
-                chunk.set_block(x, 0, z, 1); // Stone
-                chunk.set_block(x, 1, z, 2); // Dirt
-                chunk.set_block(x, 2, z, 2); // Dirt
-                chunk.set_block(x, 3, z, 2); // Dirt
-                chunk.set_block(x, 4, z, 3); // Grass
+    /// Returns the block at the world coordinates, waiting for its chunk if needed.
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> Result<BlockState> {
+        let (chunk_x, local_x, chunk_z, local_z) = split(x, z);
+        let chunk = self.get_chunk(chunk_x, chunk_z)?;
+        let block = chunk.read().unwrap().get_block(local_x, y, local_z);
+        block
+    }
+
+    /// Returns the block at the world coordinates if its chunk is loaded and the coordinates are
+    /// within the world's height, without waiting for anything.
+    pub fn loaded_block(&self, x: i32, y: i32, z: i32) -> Option<BlockState> {
+        let (chunk_x, local_x, chunk_z, local_z) = split(x, z);
+        let chunk = self.loaded_chunk(chunk_x, chunk_z)?;
+        let block = chunk.read().unwrap().get_block(local_x, y, local_z).ok();
+        block
+    }
+
+    /// Sets the block at the world coordinates with the [default](UpdateFlags::DEFAULT) side
+    /// effects, waiting for its chunk if needed.
+    pub fn set_block(&self, x: i32, y: i32, z: i32, block: BlockState) -> Result<()> {
+        self.set_block_with(x, y, z, block, UpdateFlags::DEFAULT).map(|_| ())
+    }
+
+    /// Sets the block at the world coordinates, waiting for its chunk if needed, and updates the
+    /// light around it. Replacing a block removes its block entity. Returns false if the block
+    /// was already there, in which case nothing else happens.
+    ///
+    /// The flags decide whether the change is sent to players and whether the block and the
+    /// blocks around it are updated. Blocks whose shape changes because of it are set with the
+    /// same flags, but don't notify their neighbors. The updates run before this returns, unless
+    /// another thread is running updates already, in which case it runs them.
+    pub fn set_block_with(&self, x: i32, y: i32, z: i32, block: BlockState, flags: UpdateFlags) -> Result<bool> {
+        let (chunk_x, _, chunk_z, _) = split(x, z);
+        let chunk = self.get_chunk(chunk_x, chunk_z)?;
+        let previous = self.shared.with_light_area(chunk_x, chunk_z, &chunk, |area| area.set_block(x, y, z, block))?;
+        if previous == block {
+            return Ok(false);
+        }
+
+        let pos = BlockPos::new(x, y, z);
+        if flags.contains(UpdateFlags::SEND_TO_CLIENTS) {
+            self.record(pos, ChangeKind::Block);
+        }
+        let mut updates = Vec::new();
+        if flags.contains(UpdateFlags::NOTIFY_NEIGHBORS) {
+            updates.push(Update::Changed { pos, previous });
+            updates.extend(neighbor_updates(pos));
+        }
+        if flags.contains(UpdateFlags::UPDATE_SHAPES) {
+            let flags = flags.without(UpdateFlags::NOTIFY_NEIGHBORS);
+            updates.extend(SHAPE_UPDATE_ORDER.map(|direction| Update::Shape {
+                pos: pos.relative(direction),
+                direction: direction.opposite(),
+                flags,
+            }));
+        }
+        self.queue_updates(updates)?;
+        Ok(true)
+    }
+
+    /// Tells the six blocks around the position that it changed, as if a block was set there.
+    /// Blocks such as buttons use it to update the blocks around the block they power.
+    pub fn notify_neighbors(&self, pos: BlockPos) -> Result<()> {
+        self.queue_updates(neighbor_updates(pos).to_vec())
+    }
+
+    /// Tells the block at `pos` that the block at `from` changed. Blocks such as repeaters use it
+    /// to update the block they power.
+    pub fn notify_neighbor(&self, pos: BlockPos, from: BlockPos) -> Result<()> {
+        self.queue_updates(vec![Update::Neighbor { pos, from }])
+    }
+
+    fn queue_updates(&self, updates: Vec<Update>) -> Result<()> {
+        if !updates.is_empty() && self.updates.lock().unwrap().push(updates) {
+            self.run_updates()?;
+        }
+        Ok(())
+    }
+
+    /// Runs the queued updates until none are left, including the ones they queue. Returns the
+    /// first error the handler returned, after running the rest.
+    fn run_updates(&self) -> Result<()> {
+        let mut result = Ok(());
+        loop {
+            // The queue isn't locked while the handler runs, so it can set blocks.
+            let next = self.updates.lock().unwrap().next();
+            let Some(update) = next else {
+                return result;
+            };
+            let ran = panic::catch_unwind(AssertUnwindSafe(|| self.run_update(update)));
+            match ran {
+                Ok(Err(e)) if result.is_ok() => result = Err(e),
+                Ok(_) => {}
+                Err(panic) => {
+                    // Let another thread run the next updates instead of none ever running.
+                    self.updates.lock().unwrap().abandon();
+                    panic::resume_unwind(panic);
+                }
+            }
+        }
+    }
+
+    fn run_update(&self, update: Update) -> Result<()> {
+        match update {
+            Update::Neighbor { pos, from } => match self.loaded_block(pos.x, pos.y, pos.z) {
+                Some(state) => self.handler.neighbor_changed(self, pos, state, from),
+                None => Ok(()),
+            },
+            Update::Shape { pos, direction, flags } => {
+                let neighbor_pos = pos.relative(direction);
+                let (Some(state), Some(neighbor)) = (
+                    self.loaded_block(pos.x, pos.y, pos.z),
+                    self.loaded_block(neighbor_pos.x, neighbor_pos.y, neighbor_pos.z),
+                ) else {
+                    return Ok(());
+                };
+                let shape = self.handler.update_shape(self, pos, state, direction, neighbor);
+                if shape != state {
+                    self.set_block_with(pos.x, pos.y, pos.z, shape, flags)?;
+                }
+                Ok(())
+            }
+            Update::Changed { pos, previous } => match self.loaded_block(pos.x, pos.y, pos.z) {
+                Some(state) => self.handler.block_changed(self, pos, previous, state),
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// Returns the boxes entities collide with in the block, as the update handler decides.
+    pub fn collision_shape(&self, state: BlockState) -> Shape {
+        self.handler.collision_shape(state)
+    }
+
+    /// Whether the block at the position covers its whole face on the side, so that blocks such
+    /// as buttons can hang on it. Positions outside of the world's height have no faces.
+    pub fn is_face_sturdy(&self, pos: BlockPos, direction: Direction) -> Result<bool> {
+        if !self.height().contains(pos.y) {
+            return Ok(false);
+        }
+        let state = self.get_block(pos.x, pos.y, pos.z)?;
+        Ok(self.collision_shape(state).covers_face(direction))
+    }
+
+    /// Whether the block gives off redstone power of its own, as the update handler decides.
+    pub fn is_signal_source(&self, state: BlockState) -> bool {
+        self.handler.is_signal_source(state)
+    }
+
+    /// Whether the block passes the power it's strongly given on to the blocks around it, as the
+    /// update handler decides.
+    pub fn is_redstone_conductor(&self, state: BlockState) -> bool {
+        self.handler.is_redstone_conductor(state)
+    }
+
+    /// Returns the power the block at `pos` gives the block next to it, where `direction` points
+    /// from that block to `pos`. A conductor gives the strongest power it's strongly given.
+    /// Blocks in chunks that aren't loaded give none.
+    pub fn signal(&self, pos: BlockPos, direction: Direction) -> u8 {
+        let Some(state) = self.loaded_block(pos.x, pos.y, pos.z) else {
+            return 0;
+        };
+        if self.is_redstone_conductor(state) {
+            self.direct_signal_to(pos)
+        } else {
+            self.handler.signal(self, pos, state, direction)
+        }
+    }
+
+    /// Returns the power the block at `pos` strongly gives the block next to it, where
+    /// `direction` points from that block to `pos`.
+    pub fn direct_signal(&self, pos: BlockPos, direction: Direction) -> u8 {
+        match self.loaded_block(pos.x, pos.y, pos.z) {
+            Some(state) => self.handler.direct_signal(self, pos, state, direction),
+            None => 0,
+        }
+    }
+
+    /// Returns the strongest power the blocks around the position strongly give it.
+    pub fn direct_signal_to(&self, pos: BlockPos) -> u8 {
+        let mut signal = 0;
+        for direction in Direction::ALL {
+            signal = signal.max(self.direct_signal(pos.relative(direction), direction));
+            if signal >= 15 {
+                break;
             }
         }
+        signal
+    }
 
-        chunk
+    /// Whether any block around the position powers it.
+    pub fn has_neighbor_signal(&self, pos: BlockPos) -> bool {
+        Direction::ALL.into_iter().any(|direction| self.signal(pos.relative(direction), direction) > 0)
     }
-}
\ No newline at end of file
+
+    /// Returns the strongest power the blocks around the position give it.
+    pub fn best_neighbor_signal(&self, pos: BlockPos) -> u8 {
+        let mut signal = 0;
+        for direction in Direction::ALL {
+            signal = signal.max(self.signal(pos.relative(direction), direction));
+            if signal >= 15 {
+                break;
+            }
+        }
+        signal
+    }
+
+    /// Returns the power a comparator reading the block at the position gives, or `None` if the
+    /// block has nothing to read.
+    pub fn analog_output(&self, pos: BlockPos) -> Option<u8> {
+        let state = self.loaded_block(pos.x, pos.y, pos.z)?;
+        self.handler.analog_output(self, pos, state)
+    }
+
+    /// Schedules a tick for the block at the position `delay` ticks from now, with the normal
+    /// priority. Returns false if one was already scheduled for the same block there.
+    pub fn schedule_tick(&self, pos: BlockPos, block: BlockState, delay: u64) -> bool {
+        self.schedule_tick_with_priority(pos, block, delay, TickPriority::Normal)
+    }
+
+    /// Schedules a tick for the block at the position `delay` ticks from now. Ticks due in the
+    /// same tick run by priority. Returns false if one was already scheduled for the same block
+    /// there.
+    pub fn schedule_tick_with_priority(
+        &self,
+        pos: BlockPos,
+        block: BlockState,
+        delay: u64,
+        priority: TickPriority,
+    ) -> bool {
+        self.ticks.lock().unwrap().schedule(pos, block, delay, priority)
+    }
+
+    /// Whether a tick is scheduled for the block at the position.
+    pub fn is_tick_scheduled(&self, pos: BlockPos, block: BlockState) -> bool {
+        self.ticks.lock().unwrap().is_scheduled(pos, block)
+    }
+
+    /// Schedules a tick for the fluid in the block at the position `delay` ticks from now. Fluid
+    /// ticks run after the block ticks of the same tick. Returns false if one was already
+    /// scheduled for the same fluid there.
+    pub fn schedule_fluid_tick(&self, pos: BlockPos, fluid: Fluid, delay: u64) -> bool {
+        self.fluid_ticks.lock().unwrap().schedule(pos, fluid.source_block(), delay, TickPriority::Normal)
+    }
+
+    /// Whether a tick is scheduled for the fluid in the block at the position.
+    pub fn is_fluid_tick_scheduled(&self, pos: BlockPos, fluid: Fluid) -> bool {
+        self.fluid_ticks.lock().unwrap().is_scheduled(pos, fluid.source_block())
+    }
+
+    /// The number of ticks the world ran.
+    pub fn current_tick(&self) -> u64 {
+        self.ticks.lock().unwrap().current_tick()
+    }
+
+    /// Runs the scheduled ticks that are due, those of blocks and then those of fluids. Ticks of
+    /// blocks whose chunk isn't loaded or that were replaced by another block or fluid are
+    /// dropped.
+    fn run_scheduled_ticks(&self) -> Result<()> {
+        let due = self.ticks.lock().unwrap().tick();
+        let mut result = Ok(());
+        for tick in due {
+            let Some(state) = self.loaded_block(tick.pos.x, tick.pos.y, tick.pos.z) else {
+                continue;
+            };
+            if state.default_state() != tick.block {
+                continue;
+            }
+            if let Err(e) = self.handler.scheduled_tick(self, tick.pos, state) {
+                result = result.and(Err(e));
+            }
+        }
+
+        let due = self.fluid_ticks.lock().unwrap().tick();
+        for tick in due {
+            let Some(state) = self.loaded_block(tick.pos.x, tick.pos.y, tick.pos.z) else {
+                continue;
+            };
+            if FluidState::of(state).map(|fluid| fluid.fluid.source_block()) != Some(tick.block) {
+                continue;
+            }
+            if let Err(e) = self.handler.fluid_tick(self, tick.pos, state) {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
+    /// Returns the block entity at the world coordinates, waiting for its chunk if needed.
+    pub fn block_entity(&self, x: i32, y: i32, z: i32) -> Result<Option<BlockEntity>> {
+        let (chunk_x, local_x, chunk_z, local_z) = split(x, z);
+        let chunk = self.get_chunk(chunk_x, chunk_z)?;
+        let entity = chunk.read().unwrap().block_entity(BlockPos::new(local_x, y, local_z)).cloned();
+        Ok(entity)
+    }
+
+    /// Sets the block entity at the world coordinates, waiting for its chunk if needed.
+    pub fn set_block_entity(&self, x: i32, y: i32, z: i32, entity: BlockEntity) -> Result<()> {
+        let (chunk_x, local_x, chunk_z, local_z) = split(x, z);
+        let chunk = self.get_chunk(chunk_x, chunk_z)?;
+        chunk.write().unwrap().set_block_entity(BlockPos::new(local_x, y, local_z), entity)?;
+        self.record(BlockPos::new(x, y, z), ChangeKind::BlockEntity);
+        Ok(())
+    }
+
+    /// The number of the next change to the blocks, from which a new reader of
+    /// [`changes_since`](Self::changes_since) starts.
+    pub fn change_cursor(&self) -> u64 {
+        self.changes.lock().unwrap().end()
+    }
+
+    /// Returns the changes to the blocks from the numbered one on.
+    pub fn changes_since(&self, cursor: u64) -> Changes {
+        self.changes.lock().unwrap().since(cursor)
+    }
+
+    fn record(&self, pos: BlockPos, kind: ChangeKind) {
+        self.changes.lock().unwrap().push(BlockChange { pos, kind });
+    }
+
+    /// Returns the light level of the kind at the world coordinates, waiting for its chunk if
+    /// needed.
+    pub fn light_level(&self, kind: LightKind, x: i32, y: i32, z: i32) -> Result<u8> {
+        let (chunk_x, local_x, chunk_z, local_z) = split(x, z);
+        let chunk = self.get_chunk(chunk_x, chunk_z)?;
+        let level = chunk.read().unwrap().light_level(kind, local_x, y, local_z);
+        level
+    }
+
+    /// Returns the biome at the world coordinates, waiting for its chunk if needed. Biomes are
+    /// stored for cells of 4 blocks.
+    pub fn biome(&self, x: i32, y: i32, z: i32) -> Result<u32> {
+        let (chunk_x, local_x, chunk_z, local_z) = split(x, z);
+        let chunk = self.get_chunk(chunk_x, chunk_z)?;
+        let biome = chunk.read().unwrap().get_biome(local_x, y, local_z);
+        biome
+    }
+
+    /// Sets the biome of the cell of 4 blocks the world coordinates are in, waiting for its chunk
+    /// if needed. Players see the change once the chunk is sent to them again.
+    pub fn set_biome(&self, x: i32, y: i32, z: i32, biome: u32) -> Result<()> {
+        let (chunk_x, local_x, chunk_z, local_z) = split(x, z);
+        let chunk = self.get_chunk(chunk_x, chunk_z)?;
+        chunk.write().unwrap().set_biome(local_x, y, local_z, biome)?;
+        Ok(())
+    }
+
+    /// Returns the y of the highest non-air block at the world coordinates.
+    pub fn highest_block(&self, x: i32, z: i32) -> Result<Option<i32>> {
+        let (chunk_x, local_x, chunk_z, local_z) = split(x, z);
+        let chunk = self.get_chunk(chunk_x, chunk_z)?;
+        let highest = chunk.read().unwrap().highest_block(local_x, local_z);
+        highest
+    }
+
+    /// Saves every modified chunk to the region files. Returns how many chunks were saved.
+    /// Read-only chunks are never saved, keeping their unknown blocks in the region files.
+    pub fn save_dirty(&self) -> Result<usize> {
+        let Some(regions) = &self.shared.regions else {
+            return Ok(0);
+        };
+        let _saving = self.shared.saving.lock().unwrap();
+        let loaded: Vec<_> = self
+            .shared
+            .chunks
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(&position, chunk)| (position, Arc::clone(chunk)))
+            .collect();
+
+        // Chunks are marked saved as they are encoded, so changes made while writing are saved
+        // next time.
+        let mut saved = Vec::new();
+        let mut encoded = Vec::new();
+        for ((x, z), chunk) in loaded {
+            let mut column = chunk.write().unwrap();
+            if column.is_dirty() && !column.is_read_only() {
+                encoded.push((x, z, write_chunk_nbt(x, z, &column)?));
+                column.mark_saved();
+                saved.push(Arc::clone(&chunk));
+            }
+        }
+        if encoded.is_empty() {
+            return Ok(0);
+        }
+
+        if let Err(e) = regions.write_chunks(encoded) {
+            for chunk in saved {
+                chunk.write().unwrap().mark_dirty();
+            }
+            return Err(e);
+        }
+        Ok(saved.len())
+    }
+
+    /// Saves the chunk if it was modified and drops it. Returns false if it wasn't loaded. Anyone
+    /// still holding the chunk keeps a copy the world no longer knows about. A world without
+    /// region files loses the chunk's changes, as does a read-only chunk.
+    pub fn unload_chunk(&self, x: i32, z: i32) -> Result<bool> {
+        Ok(self.unload_chunks(&[(x, z)])? == 1)
+    }
+
+    /// Unloads the chunks, saving the modified ones together. Returns how many were loaded.
+    fn unload_chunks(&self, positions: &[(i32, i32)]) -> Result<usize> {
+        let _saving = self.shared.saving.lock().unwrap();
+        let removed: Vec<_> = {
+            // Holding the pending loads keeps the chunks from being read before they are moved.
+            let _pending = self.shared.pending.lock().unwrap();
+            let removed: Vec<_> = {
+                let mut chunks = self.shared.chunks.write().unwrap();
+                positions
+                    .iter()
+                    .filter_map(|&position| chunks.remove(&position).map(|chunk| (position, chunk)))
+                    .collect()
+            };
+            if self.shared.regions.is_some() {
+                let mut unloading = self.shared.unloading.lock().unwrap();
+                unloading.extend(removed.iter().map(|(position, chunk)| (*position, Arc::clone(chunk))));
+            }
+            removed
+        };
+        let Some(regions) = &self.shared.regions else {
+            return Ok(removed.len());
+        };
+
+        // Loads don't wait for the save, they take the chunks back from `unloading`.
+        let saved = encode_dirty(&removed).and_then(|encoded| {
+            if encoded.is_empty() {
+                Ok(())
+            } else {
+                regions.write_chunks(encoded)
+            }
+        });
+        let _pending = self.shared.pending.lock().unwrap();
+        let kept: Vec<_> = {
+            let mut unloading = self.shared.unloading.lock().unwrap();
+            removed.iter().filter_map(|(position, _)| unloading.remove_entry(position)).collect()
+        };
+        if saved.is_err() {
+            // Keep the chunks that weren't loaded again rather than losing their changes.
+            self.shared.chunks.write().unwrap().extend(kept);
+        }
+        saved.map(|_| removed.len())
+    }
+
+    /// Adds a ticket to the chunk, keeping the chunks around it loaded from the next tick, or
+    /// ticking their blocks for a simulation ticket.
+    pub fn add_ticket(&self, pos: ChunkPos, ticket: Ticket) {
+        self.lifetimes.lock().unwrap().tickets_of(ticket).add(pos, ticket);
+    }
+
+    /// Removes a ticket added to the chunk. Returns false if the chunk didn't have it.
+    pub fn remove_ticket(&self, pos: ChunkPos, ticket: Ticket) -> bool {
+        self.lifetimes.lock().unwrap().tickets_of(ticket).remove(pos, ticket)
+    }
+
+    /// Whether the blocks of the chunk tick, because it's loaded and a simulation ticket is
+    /// close enough.
+    pub fn is_ticking(&self, pos: ChunkPos) -> bool {
+        let level = self.lifetimes.lock().unwrap().simulation.level(pos);
+        level.is_some_and(|level| level <= BLOCK_TICKING_LEVEL) && self.is_loaded(pos.x, pos.z)
+    }
+
+    /// Returns a random number below the bound from the random source of the world.
+    pub fn random_below(&self, bound: u32) -> u32 {
+        (self.random.lock().unwrap().next_u64() % bound.max(1) as u64) as u32
+    }
+
+    /// Ticks `random_tick_speed` blocks picked at random in every section of the ticking chunks
+    /// that has blocks that tick randomly. Returns the first error the handler returned, after
+    /// running the rest.
+    fn run_random_ticks(&self) -> Result<()> {
+        if self.random_tick_speed == 0 {
+            return Ok(());
+        }
+        let ticking: Vec<ChunkPos> = {
+            let mut lifetimes = self.lifetimes.lock().unwrap();
+            let levels = lifetimes.simulation.levels();
+            levels.filter(|&(_, level)| level <= BLOCK_TICKING_LEVEL).map(|(pos, _)| pos).collect()
+        };
+        let mut result = Ok(());
+        for chunk_pos in ticking {
+            let Some(chunk) = self.loaded_chunk(chunk_pos.x, chunk_pos.z) else {
+                continue;
+            };
+            let picked = {
+                let chunk = chunk.read().unwrap();
+                let mut random = self.random.lock().unwrap();
+                let mut picked = Vec::new();
+                for (index, section) in chunk.sections().iter().enumerate() {
+                    if section.random_tick_count() == 0 {
+                        continue;
+                    }
+                    for _ in 0..self.random_tick_speed {
+                        let bits = random.next_u64();
+                        let (x, y, z) = ((bits & 15) as usize, (bits >> 4 & 15) as usize, (bits >> 8 & 15) as usize);
+                        let Ok(state) = section.get_block(x, y, z) else {
+                            continue;
+                        };
+                        if state.flags().random_ticks {
+                            let section_y = self.height().min_y() + (index * SECTION_HEIGHT) as i32;
+                            let pos = BlockPos::new(
+                                chunk_pos.x * SECTION_WIDTH as i32 + x as i32,
+                                section_y + y as i32,
+                                chunk_pos.z * SECTION_WIDTH as i32 + z as i32,
+                            );
+                            picked.push((pos, state));
+                        }
+                    }
+                }
+                picked
+            };
+            for (pos, state) in picked {
+                // An earlier tick may have changed the block.
+                if self.loaded_block(pos.x, pos.y, pos.z) != Some(state) {
+                    continue;
+                }
+                if let Err(e) = self.handler.random_tick(self, pos, state) {
+                    result = result.and(Err(e));
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns the load level tickets give to the chunk, or `None` if no ticket keeps it loaded.
+    pub fn chunk_level(&self, pos: ChunkPos) -> Option<u8> {
+        self.lifetimes.lock().unwrap().tickets.level(pos)
+    }
+
+    /// Advances the tickets and the change log by a tick, runs the scheduled ticks that are due
+    /// and the random ticks, requests the chunks the tickets need and unloads the chunks no
+    /// ticket needed for the unload delay, saving them if they were modified. Returns how many
+    /// chunks were unloaded, or the first error a scheduled or random tick returned.
+    pub fn tick(&self) -> Result<usize> {
+        self.changes.lock().unwrap().tick();
+        let ticked = self.run_scheduled_ticks();
+        let ticked = ticked.and(self.run_random_ticks());
+        let unload = {
+            let mut lifetimes = self.lifetimes.lock().unwrap();
+            let lifetimes = &mut *lifetimes;
+            lifetimes.tickets.tick();
+            lifetimes.simulation.tick();
+            let tick = lifetimes.tickets.current_tick();
+            Self::check_loads(lifetimes, tick);
+            for (pos, _) in lifetimes.tickets.levels() {
+                let position = (pos.x, pos.z);
+                let waiting = lifetimes.failed.get(&position).is_some_and(|failed| failed.retry_at > tick);
+                if !waiting && !lifetimes.loading.contains_key(&position) && !self.is_loaded(pos.x, pos.z) {
+                    lifetimes.loading.insert(position, self.load_chunk(pos.x, pos.z));
+                }
+            }
+            lifetimes.failed.retain(|&(x, z), _| lifetimes.tickets.level(ChunkPos::new(x, z)).is_some());
+            if self.shared.regions.is_none() {
+                // Without region files an unloaded chunk couldn't be read back, only generated
+                // again without its changes or the compact world it came from.
+                return ticked.map(|_| 0);
+            }
+
+            let loaded: Vec<(i32, i32)> = self.shared.chunks.read().unwrap().keys().copied().collect();
+            let loaded_count = loaded.len();
+            let mut unload_at = HashMap::with_capacity(lifetimes.unload_at.len());
+            for position in loaded {
+                if lifetimes.tickets.level(ChunkPos::new(position.0, position.1)).is_none() {
+                    let at = lifetimes.unload_at.get(&position).copied().unwrap_or(tick + self.unload_delay);
+                    unload_at.insert(position, at);
+                }
+            }
+
+            let mut waiting: Vec<_> = unload_at.into_iter().collect();
+            waiting.sort_by_key(|&(_, at)| at);
+            let mut due = waiting.partition_point(|&(_, at)| at <= tick);
+            if self.max_loaded_chunks > 0 {
+                // Over the limit, the chunks that would be unloaded first go right away.
+                let over = loaded_count.saturating_sub(self.max_loaded_chunks);
+                due = due.max(over.min(waiting.len()));
+            }
+            let unload: Vec<_> = waiting.drain(..due).map(|(position, _)| position).collect();
+            lifetimes.unload_at = waiting.into_iter().collect();
+            unload
+        };
+        if unload.is_empty() {
+            return ticked.map(|_| 0);
+        }
+
+        let unloaded = self.unload_chunks(&unload)?;
+        self.lifetimes.lock().unwrap().unloaded += unloaded as u64;
+        ticked.map(|_| unloaded)
+    }
+
+    /// Forgets the finished ticket loads, logging the chunks that failed to load the first time
+    /// and waiting longer before loading them again every time.
+    fn check_loads(lifetimes: &mut Lifetimes, tick: u64) {
+        let done: Vec<_> =
+            lifetimes.loading.iter().filter(|(_, handle)| handle.is_done()).map(|(&position, _)| position).collect();
+        for position in done {
+            let handle = lifetimes.loading.remove(&position).unwrap();
+            let Err(e) = handle.wait() else {
+                lifetimes.failed.remove(&position);
+                continue;
+            };
+            let delay = match lifetimes.failed.get(&position) {
+                Some(failed) => (failed.delay * 2).min(MAX_RETRY_DELAY),
+                None => {
+                    warn!("{}, trying again later", e);
+                    RETRY_DELAY
+                }
+            };
+            lifetimes.failed.insert(position, FailedLoad { retry_at: tick + delay, delay });
+        }
+    }
+
+    pub fn chunk_metrics(&self) -> ChunkMetrics {
+        // Read before the lifetimes are locked, taking one lock at a time, since unloading takes
+        // `pending` before `chunks`.
+        let loaded = self.shared.chunks.read().unwrap().len();
+        let pending = self.pending_chunks();
+        let mut lifetimes = self.lifetimes.lock().unwrap();
+        ChunkMetrics {
+            loaded,
+            pending,
+            ticketed: lifetimes.tickets.ticketed_chunks(),
+            tickets: lifetimes.tickets.ticket_count(),
+            awaiting_unload: lifetimes.unload_at.len(),
+            unloaded: lifetimes.unloaded,
+        }
+    }
+}
+
+impl Shared {
+    /// Reads or generates the chunk and lights it on its own.
+    fn read_or_generate(&self, x: i32, z: i32) -> Result<ChunkColumn> {
+        let saved = match &self.regions {
+            Some(regions) => regions.read_chunk(x, z, self.height)?,
+            None => None,
+        };
+        let mut chunk = match saved {
+            Some(chunk) => chunk,
+            None => {
+                let mut chunk = ChunkColumn::new(self.height);
+                self.generator.generate(x, z, &mut chunk)?;
+                chunk
+            }
+        };
+        light_column(&mut chunk);
+        Ok(chunk)
+    }
+
+    /// Locks the chunk and its loaded neighbors for the light engine. Chunks are always locked
+    /// north to south and then west to east, so threads lighting next to each other can't
+    /// deadlock.
+    fn with_light_area<R>(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        center: &SharedChunk,
+        f: impl FnOnce(&mut LightArea) -> R,
+    ) -> R {
+        let mut area = Vec::with_capacity(9);
+        {
+            let chunks = self.chunks.read().unwrap();
+            for dz in -1..=1 {
+                for dx in -1..=1 {
+                    let chunk = match (dx, dz) {
+                        (0, 0) => Some(Arc::clone(center)),
+                        _ => chunks.get(&(chunk_x + dx, chunk_z + dz)).cloned(),
+                    };
+                    area.push(chunk);
+                }
+            }
+        }
+
+        let mut guards: Vec<Option<RwLockWriteGuard<ChunkColumn>>> =
+            area.iter().map(|chunk| chunk.as_ref().map(|chunk| chunk.write().unwrap())).collect();
+        let mut chunks: [Option<&mut ChunkColumn>; 9] = Default::default();
+        for (slot, guard) in chunks.iter_mut().zip(&mut guards) {
+            *slot = guard.as_deref_mut();
+        }
+        f(&mut LightArea::new(chunk_x, chunk_z, chunks))
+    }
+}
+
+/// The order in which the blocks around a changed block are told about it.
+const NEIGHBOR_UPDATE_ORDER: [Direction; 6] =
+    [Direction::West, Direction::East, Direction::Down, Direction::Up, Direction::North, Direction::South];
+/// The order in which the blocks around a changed block update their shape.
+const SHAPE_UPDATE_ORDER: [Direction; 6] =
+    [Direction::West, Direction::East, Direction::North, Direction::South, Direction::Down, Direction::Up];
+
+/// Encodes the modified chunks, leaving out read-only ones.
+fn encode_dirty(chunks: &[((i32, i32), SharedChunk)]) -> Result<Vec<(i32, i32, Vec<u8>)>> {
+    let mut encoded = Vec::new();
+    for ((x, z), chunk) in chunks {
+        let column = chunk.read().unwrap();
+        if column.is_dirty() && !column.is_read_only() {
+            encoded.push((*x, *z, write_chunk_nbt(*x, *z, &column)?));
+        }
+    }
+    Ok(encoded)
+}
+
+/// Returns the updates telling the blocks around the position that it changed.
+fn neighbor_updates(pos: BlockPos) -> [Update; 6] {
+    NEIGHBOR_UPDATE_ORDER.map(|direction| Update::Neighbor { pos: pos.relative(direction), from: pos })
+}
+
+/// Splits world x and z coordinates into chunk coordinates and coordinates within the chunk.
+pub fn split(x: i32, z: i32) -> (i32, i32, i32, i32) {
+    let width = SECTION_WIDTH as i32;
+    (x.div_euclid(width), x.rem_euclid(width), z.div_euclid(width), z.rem_euclid(width))
+}
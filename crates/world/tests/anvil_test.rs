@@ -0,0 +1,343 @@
+use fastnbt::{LongArray, Value};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use iron_oxide_world::anvil::{read_chunk_nbt, write_chunk_nbt, Compression, RegionFile, RegionStorage, SECTOR_SIZE};
+use iron_oxide_world::biome::{install_biome_registry, BiomeRegistry};
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::error::WorldError;
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::heightmap::HeightmapKind;
+use iron_oxide_world::pos::BlockPos;
+use iron_oxide_world::world::World;
+use std::io::Write;
+use std::path::PathBuf;
+
+mod common;
+use common::{flat, install_blocks};
+
+fn install_registries() {
+    install_blocks();
+    install_biome_registry(BiomeRegistry::new(vec![
+        "minecraft:plains".to_string(),
+        "minecraft:the_void".to_string(),
+    ]));
+}
+
+fn compound(entries: Vec<(&str, Value)>) -> Value {
+    Value::Compound(entries.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+}
+
+fn string(value: &str) -> Value {
+    Value::String(value.to_string())
+}
+
+/// A chunk with stone at the bottom of the world, east facing stairs in the first block of the
+/// second section and a chest above them.
+fn chunk_nbt(chunk_x: i32, chunk_z: i32, status: &str) -> Vec<u8> {
+    let mut data = vec![0i64; 256];
+    data[0] = 1;
+    let stairs_section = compound(vec![
+        ("Y", Value::Byte(-3)),
+        (
+            "block_states",
+            compound(vec![
+                (
+                    "palette",
+                    Value::List(vec![
+                        compound(vec![("Name", string("minecraft:air"))]),
+                        compound(vec![
+                            ("Name", string("minecraft:oak_stairs")),
+                            ("Properties", compound(vec![("facing", string("east"))])),
+                        ]),
+                    ]),
+                ),
+                ("data", Value::LongArray(LongArray::new(data))),
+            ]),
+        ),
+        (
+            "biomes",
+            compound(vec![
+                ("palette", Value::List(vec![string("minecraft:plains"), string("minecraft:the_void")])),
+                ("data", Value::LongArray(LongArray::new(vec![2]))),
+            ]),
+        ),
+    ]);
+    let stone_section = compound(vec![
+        ("Y", Value::Byte(-4)),
+        (
+            "block_states",
+            compound(vec![("palette", Value::List(vec![compound(vec![("Name", string("stone"))])]))]),
+        ),
+    ]);
+    // Light-only section below the world.
+    let light_section = compound(vec![("Y", Value::Byte(-5))]);
+
+    let mut heights = vec![0i64; 37];
+    heights[0] = 17;
+    let chest = compound(vec![
+        ("id", string("minecraft:chest")),
+        ("x", Value::Int(chunk_x * 16)),
+        ("y", Value::Int(-47)),
+        ("z", Value::Int(chunk_z * 16)),
+        ("keepPacked", Value::Byte(0)),
+        ("CustomName", string("\"Loot\"")),
+    ]);
+    let chunk = compound(vec![
+        ("DataVersion", Value::Int(4325)),
+        ("xPos", Value::Int(chunk_x)),
+        ("zPos", Value::Int(chunk_z)),
+        ("yPos", Value::Int(-5)),
+        ("Status", string(status)),
+        ("sections", Value::List(vec![light_section, stone_section, stairs_section])),
+        ("Heightmaps", compound(vec![("WORLD_SURFACE", Value::LongArray(LongArray::new(heights)))])),
+        ("block_entities", Value::List(vec![chest])),
+    ]);
+    fastnbt::to_bytes(&chunk).unwrap()
+}
+
+fn compress(compression: Compression, data: &[u8]) -> Vec<u8> {
+    match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        }
+        Compression::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        }
+        Compression::None => data.to_vec(),
+        Compression::Lz4 => {
+            let compressed = lz4_flex::block::compress(data);
+            let mut stream = Vec::new();
+            for (method, compressed, len) in [(0x20u8, &compressed[..], data.len()), (0x10, &[][..], 0)] {
+                stream.extend_from_slice(b"LZ4Block");
+                stream.push(method);
+                stream.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+                stream.extend_from_slice(&(len as u32).to_le_bytes());
+                stream.extend_from_slice(&0u32.to_le_bytes());
+                stream.extend_from_slice(compressed);
+            }
+            stream
+        }
+    }
+}
+
+/// Writes a region file holding the chunks, each with its local coordinates and payload.
+fn write_region(path: &PathBuf, chunks: &[(i32, i32, Compression, Vec<u8>)]) {
+    let mut header = vec![0u8; SECTOR_SIZE * 2];
+    let mut body = Vec::new();
+    for (x, z, compression, nbt) in chunks {
+        let payload = compress(*compression, nbt);
+        let sector = 2 + body.len() / SECTOR_SIZE;
+        body.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+        body.push(compression.id());
+        body.extend_from_slice(&payload);
+        body.resize(body.len().div_ceil(SECTOR_SIZE) * SECTOR_SIZE, 0);
+        let sectors = 2 + body.len() / SECTOR_SIZE - sector;
+
+        let index = (x + z * 32) as usize * 4;
+        header[index..index + 4].copy_from_slice(&((sector as u32) << 8 | sectors as u32).to_be_bytes());
+        header[SECTOR_SIZE + index..SECTOR_SIZE + index + 4].copy_from_slice(&1_700_000_000u32.to_be_bytes());
+    }
+    header.extend_from_slice(&body);
+    std::fs::write(path, header).unwrap();
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("iron-oxide-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(dir.join("region")).unwrap();
+    dir
+}
+
+#[test]
+fn test_read_chunk_nbt() {
+    install_registries();
+    let column = read_chunk_nbt(2, -1, &chunk_nbt(2, -1, "minecraft:full"), WorldHeight::OVERWORLD)
+        .unwrap()
+        .unwrap();
+
+    let stairs = column.get_block(0, -48, 0).unwrap();
+    assert_eq!(stairs, BlockState::parse("oak_stairs[facing=east]").unwrap());
+    assert!(column.get_block(1, -48, 0).unwrap().is_air());
+    assert_eq!(column.get_block(15, -64, 15).unwrap(), BlockState::parse("stone").unwrap());
+    assert_eq!(column.sections()[0].block_count(), 4096);
+    assert_eq!(column.sections()[1].block_count(), 1);
+
+    let biomes = &column.sections()[1];
+    assert_eq!(biomes.get_biome(0, 0, 0).unwrap(), 0);
+    assert_eq!(biomes.get_biome(1, 0, 0).unwrap(), 1);
+
+    // The heightmaps come from the blocks, the saved one agreeing with them.
+    let heightmap = column.heightmap(HeightmapKind::WorldSurface).unwrap();
+    assert_eq!(heightmap.get(0, 0), 17);
+    assert_eq!(heightmap.get(1, 0), 16);
+    assert_eq!(column.heightmap(HeightmapKind::MotionBlocking).unwrap().get(0, 0), 17);
+
+    let chest = column.block_entity(BlockPos::new(0, -47, 0)).unwrap();
+    assert_eq!(chest.kind, "minecraft:chest");
+    assert_eq!(chest.data.get("CustomName"), Some(&string("\"Loot\"")));
+    assert!(!chest.data.contains_key("x"));
+}
+
+#[test]
+fn test_unfinished_chunks_are_skipped() {
+    install_registries();
+    let nbt = chunk_nbt(0, 0, "minecraft:features");
+    assert!(read_chunk_nbt(0, 0, &nbt, WorldHeight::OVERWORLD).unwrap().is_none());
+}
+
+#[test]
+fn test_old_chunks_are_rejected() {
+    install_registries();
+    let chunk = compound(vec![("DataVersion", Value::Int(2586)), ("Status", string("full"))]);
+    let nbt = fastnbt::to_bytes(&chunk).unwrap();
+    assert!(read_chunk_nbt(0, 0, &nbt, WorldHeight::OVERWORLD).is_err());
+}
+
+/// A chunk whose first section has stone, a block of a mod and stairs with a property they don't
+/// have.
+fn unknown_blocks_chunk_nbt() -> Vec<u8> {
+    let mut data = vec![0i64; 256];
+    data[0] = 0x21;
+    let section = compound(vec![
+        ("Y", Value::Byte(0)),
+        (
+            "block_states",
+            compound(vec![
+                (
+                    "palette",
+                    Value::List(vec![
+                        compound(vec![("Name", string("minecraft:stone"))]),
+                        compound(vec![("Name", string("somemod:machine"))]),
+                        compound(vec![
+                            ("Name", string("minecraft:oak_stairs")),
+                            ("Properties", compound(vec![("facing", string("east")), ("age", string("2"))])),
+                        ]),
+                    ]),
+                ),
+                ("data", Value::LongArray(LongArray::new(data))),
+            ]),
+        ),
+    ]);
+    let chunk = compound(vec![
+        ("DataVersion", Value::Int(4325)),
+        ("Status", string("minecraft:full")),
+        ("sections", Value::List(vec![section])),
+    ]);
+    fastnbt::to_bytes(&chunk).unwrap()
+}
+
+#[test]
+fn test_unknown_block_states_load_as_air() {
+    install_registries();
+    let column = read_chunk_nbt(0, 0, &unknown_blocks_chunk_nbt(), WorldHeight::OVERWORLD).unwrap().unwrap();
+    assert!(column.get_block(0, 0, 0).unwrap().is_air());
+    assert_eq!(column.get_block(1, 0, 0).unwrap(), BlockState::parse("oak_stairs[facing=east]").unwrap());
+    assert_eq!(column.get_block(2, 0, 0).unwrap(), BlockState::parse("stone").unwrap());
+    assert!(column.is_read_only());
+    assert!(write_chunk_nbt(0, 0, &column).is_err());
+    let known = read_chunk_nbt(0, 0, &chunk_nbt(0, 0, "minecraft:full"), WorldHeight::OVERWORLD).unwrap().unwrap();
+    assert!(!known.is_read_only());
+}
+
+#[test]
+fn test_chunks_with_unknown_blocks_are_not_saved() {
+    install_registries();
+    let dir = temp_dir("unknown");
+    let path = dir.join("region").join("r.0.0.mca");
+    write_region(&path, &[(0, 0, Compression::Zlib, unknown_blocks_chunk_nbt())]);
+
+    let world = World::open(WorldHeight::OVERWORLD, &dir, flat());
+    world.set_block(5, 5, 5, BlockState::parse("dirt").unwrap()).unwrap();
+    world.set_block(20, 5, 5, BlockState::parse("dirt").unwrap()).unwrap();
+    // Only the generated chunk is saved. The other keeps the blocks of the mod in its region file.
+    assert_eq!(world.save_dirty().unwrap(), 1);
+    assert!(world.unload_chunk(0, 0).unwrap());
+    let saved = RegionFile::open(&path).unwrap().read_chunk(0, 0).unwrap().unwrap();
+    let saved: Value = fastnbt::from_bytes(&saved).unwrap();
+    assert!(saved == fastnbt::from_bytes::<Value>(&unknown_blocks_chunk_nbt()).unwrap());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_region_file_compressions() {
+    install_registries();
+    let dir = temp_dir("compressions");
+    let path = dir.join("region").join("r.0.0.mca");
+    let chunks: Vec<_> = [Compression::Gzip, Compression::Zlib, Compression::None, Compression::Lz4]
+        .into_iter()
+        .enumerate()
+        .map(|(x, compression)| (x as i32, 3, compression, chunk_nbt(x as i32, 3, "minecraft:full")))
+        .collect();
+    write_region(&path, &chunks);
+
+    let mut region = RegionFile::open(&path).unwrap();
+    for (x, z, _, nbt) in &chunks {
+        assert!(region.has_chunk(*x, *z));
+        assert_eq!(region.timestamp(*x, *z), 1_700_000_000);
+        assert_eq!(region.read_chunk(*x, *z).unwrap().as_ref(), Some(nbt));
+    }
+    assert!(region.read_chunk(5, 5).unwrap().is_none());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_lz4_block_lengths_are_checked() {
+    let mut stream = compress(Compression::Lz4, &[7; 100]);
+    assert_eq!(Compression::Lz4.decompress(&stream).unwrap(), vec![7; 100]);
+    // A block claiming to decompress to 4 GiB is rejected before anything is allocated.
+    stream[13..17].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(Compression::Lz4.decompress(&stream), Err(WorldError::InvalidRegion(_))));
+}
+
+#[test]
+fn test_world_reads_regions_before_generating() {
+    install_registries();
+    let dir = temp_dir("world");
+    write_region(
+        &dir.join("region").join("r.-1.0.mca"),
+        &[(31, 0, Compression::Zlib, chunk_nbt(-1, 0, "minecraft:full"))],
+    );
+
+    let storage = RegionStorage::new(dir.join("region"));
+    assert!(storage.read_chunk(-1, 0, WorldHeight::OVERWORLD).unwrap().is_some());
+    assert!(storage.read_chunk(-2, 0, WorldHeight::OVERWORLD).unwrap().is_none());
+
+    let world = World::open(WorldHeight::OVERWORLD, &dir, flat());
+    assert!(world.get_block(-16, -48, 0).unwrap().is("oak_stairs"));
+    // The neighbouring chunk isn't saved, so it is generated.
+    assert!(world.get_block(-32, -64, 0).unwrap().is("stone"));
+    assert!(world.get_block(-32, -60, 0).unwrap().is("grass_block"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_invalid_palette_data_is_an_error() {
+    install_registries();
+    let chunk = compound(vec![
+        ("DataVersion", Value::Int(4325)),
+        ("Status", string("minecraft:full")),
+        (
+            "sections",
+            Value::List(vec![compound(vec![
+                ("Y", Value::Byte(0)),
+                (
+                    "block_states",
+                    compound(vec![
+                        (
+                            "palette",
+                            Value::List(vec![
+                                compound(vec![("Name", string("air"))]),
+                                compound(vec![("Name", string("stone"))]),
+                            ]),
+                        ),
+                        ("data", Value::LongArray(LongArray::new(vec![0; 10]))),
+                    ]),
+                ),
+            ])]),
+        ),
+    ]);
+    let nbt = fastnbt::to_bytes(&chunk).unwrap();
+    assert!(read_chunk_nbt(0, 0, &nbt, WorldHeight::OVERWORLD).is_err());
+}
@@ -0,0 +1,224 @@
+use fastnbt::Value;
+use iron_oxide_world::anvil::{
+    read_chunk_nbt, write_chunk_nbt, write_region, Compression, RawChunk, RegionFile, RegionStorage, CHUNKS_PER_REGION,
+    SECTOR_SIZE,
+};
+use iron_oxide_world::biome::{install_biome_registry, BiomeRegistry};
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::block_entity::BlockEntity;
+use iron_oxide_world::chunk::{ChunkColumn, ChunkSection};
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::heightmap::HeightmapKind;
+use iron_oxide_world::pos::BlockPos;
+use iron_oxide_world::world::World;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+mod common;
+use common::{block, flat, install_blocks};
+
+fn install_registries() {
+    install_blocks();
+    install_biome_registry(BiomeRegistry::new(vec![
+        "minecraft:plains".to_string(),
+        "minecraft:the_void".to_string(),
+    ]));
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("iron-oxide-writer-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_chunk_nbt_round_trip() {
+    install_registries();
+    let mut section = ChunkSection::new();
+    section.set_biome(1, 2, 3, 1).unwrap();
+    let mut sections: Vec<ChunkSection> = (0..24).map(|_| ChunkSection::new()).collect();
+    sections[5] = section;
+    let mut column = ChunkColumn::with_sections(WorldHeight::OVERWORLD, sections);
+    column.fill_layer(-64, block("bedrock")).unwrap();
+    column.set_block(3, 10, 7, block("oak_stairs[facing=south,half=top]")).unwrap();
+    column.set_block(15, 10, 15, block("water")).unwrap();
+    let mut chest = BlockEntity::new("minecraft:chest");
+    chest.data.insert("CustomName".to_string(), Value::String("\"Loot\"".to_string()));
+    column.set_block_entity(BlockPos::new(3, 11, 7), chest.clone()).unwrap();
+
+    let nbt = write_chunk_nbt(-3, 5, &column).unwrap();
+    let read = read_chunk_nbt(-3, 5, &nbt, WorldHeight::OVERWORLD).unwrap().unwrap();
+
+    assert_eq!(read.get_block(0, -64, 0).unwrap(), block("bedrock"));
+    assert_eq!(read.get_block(3, 10, 7).unwrap(), block("oak_stairs[facing=south,half=top]"));
+    assert_eq!(read.get_block(15, 10, 15).unwrap(), block("water"));
+    assert_eq!(read.get_block(4, 10, 7).unwrap(), BlockState::AIR);
+    assert_eq!(read.sections()[5].get_biome(1, 2, 3).unwrap(), 1);
+    assert_eq!(read.sections()[5].get_biome(0, 0, 0).unwrap(), 0);
+    assert_eq!(read.block_entity(BlockPos::new(3, 11, 7)), Some(&chest));
+    let surface = read.heightmap(HeightmapKind::WorldSurface).unwrap();
+    assert_eq!(surface.get(3, 7), 75);
+    assert_eq!(surface.get(0, 0), 1);
+}
+
+#[test]
+fn test_empty_sections_keep_their_biome() {
+    install_registries();
+    let mut column = ChunkColumn::new(WorldHeight::OVERWORLD);
+    column.fill_biome(1);
+
+    let nbt = write_chunk_nbt(0, 0, &column).unwrap();
+    let read = read_chunk_nbt(0, 0, &nbt, WorldHeight::OVERWORLD).unwrap().unwrap();
+    assert_eq!(read.get_biome(0, 100, 0).unwrap(), 1);
+
+    let nbt = write_chunk_nbt(0, 0, &ChunkColumn::new(WorldHeight::OVERWORLD)).unwrap();
+    let read = read_chunk_nbt(0, 0, &nbt, WorldHeight::OVERWORLD).unwrap().unwrap();
+    assert_eq!(read.get_biome(0, 100, 0).unwrap(), 0);
+}
+
+#[test]
+fn test_unread_chunk_tags_are_kept() {
+    install_registries();
+    let tag = |entries: Vec<(&str, Value)>| {
+        Value::Compound(entries.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    };
+    let tick = tag(vec![
+        ("i", Value::String("minecraft:water".to_string())),
+        ("x", Value::Int(3)),
+        ("y", Value::Int(10)),
+        ("z", Value::Int(7)),
+        ("t", Value::Int(5)),
+        ("p", Value::Int(0)),
+    ]);
+    let extra = [
+        ("fluid_ticks", Value::List(vec![tick])),
+        ("block_ticks", Value::List(Vec::new())),
+        ("PostProcessing", Value::List(vec![Value::List(vec![Value::Short(18)]), Value::List(Vec::new())])),
+        ("structures", tag(vec![("References", tag(Vec::new())), ("starts", tag(Vec::new()))])),
+        ("InhabitedTime", Value::Long(1200)),
+    ];
+    let mut chunk = vec![("DataVersion", Value::Int(4325)), ("Status", Value::String("minecraft:full".to_string()))];
+    chunk.extend(extra.iter().cloned());
+    let nbt = fastnbt::to_bytes(&tag(chunk)).unwrap();
+
+    let mut column = read_chunk_nbt(0, 0, &nbt, WorldHeight::OVERWORLD).unwrap().unwrap();
+    assert_eq!(column.extra_tags().len(), extra.len());
+    column.set_block(3, 10, 7, block("water")).unwrap();
+    let saved: HashMap<String, Value> = fastnbt::from_bytes(&write_chunk_nbt(0, 0, &column).unwrap()).unwrap();
+    for (name, value) in &extra {
+        assert!(saved.get(*name) == Some(value), "{} wasn't kept", name);
+    }
+    // The tags that are read are written from the column.
+    assert!(saved.get("sections").is_some_and(|sections| matches!(sections, Value::List(list) if !list.is_empty())));
+}
+
+#[test]
+fn test_write_region_round_trip() {
+    let dir = temp_dir("region");
+    let path = dir.join("r.0.0.mca");
+    let mut chunks: Vec<Option<RawChunk>> = vec![None; CHUNKS_PER_REGION];
+    chunks[0] = Some(RawChunk { compression: Compression::Zlib, payload: vec![1, 2, 3], timestamp: 7 });
+    // Too many sectors for the region file, so it is saved next to it.
+    let large = vec![9; 256 * SECTOR_SIZE];
+    chunks[33] = Some(RawChunk { compression: Compression::None, payload: large.clone(), timestamp: 8 });
+    write_region(&path, &chunks).unwrap();
+
+    let mut region = RegionFile::open(&path).unwrap();
+    assert_eq!(region.read_raw(0, 0).unwrap().unwrap().payload, vec![1, 2, 3]);
+    assert_eq!(region.timestamp(0, 0), 7);
+    assert_eq!(region.read_chunk(1, 1).unwrap().unwrap(), large);
+    assert!(dir.join("c.1.1.mcc").exists());
+    assert!(!region.has_chunk(2, 0));
+
+    // Rewriting the chunk small enough removes the external file.
+    chunks[33] = Some(RawChunk { compression: Compression::None, payload: vec![4], timestamp: 9 });
+    write_region(&path, &chunks).unwrap();
+    let mut region = RegionFile::open(&path).unwrap();
+    assert_eq!(region.read_chunk(1, 1).unwrap().unwrap(), vec![4]);
+    assert!(!dir.join("c.1.1.mcc").exists());
+    let files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(files, vec!["r.0.0.mca"]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_region_chunks_are_written_in_free_sectors() {
+    let dir = temp_dir("sectors");
+    let path = dir.join("r.0.0.mca");
+    let raw = |payload: Vec<u8>, timestamp| RawChunk { compression: Compression::None, payload, timestamp };
+    let mut region = RegionFile::open_writable(&path).unwrap();
+    region.write_chunks(&[(0, 0, raw(vec![1; 100], 1)), (1, 0, raw(vec![2; 100], 1))]).unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 4 * SECTOR_SIZE as u64);
+
+    // A saved chunk goes to other sectors, and the ones it had are reused by the next save.
+    for timestamp in 2..10 {
+        region.write_chunks(&[(0, 0, raw(vec![timestamp as u8; 100], timestamp))]).unwrap();
+    }
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 5 * SECTOR_SIZE as u64);
+    // A chunk that grew past its sectors goes where it fits, here from the free last sector on.
+    region.write_chunks(&[(1, 0, raw(vec![3; 2 * SECTOR_SIZE], 10))]).unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 7 * SECTOR_SIZE as u64);
+
+    let mut reopened = RegionFile::open(&path).unwrap();
+    assert_eq!(reopened.read_chunk(0, 0).unwrap().unwrap(), vec![9; 100]);
+    assert_eq!(reopened.timestamp(0, 0), 9);
+    assert_eq!(reopened.read_chunk(1, 0).unwrap().unwrap(), vec![3; 2 * SECTOR_SIZE]);
+    assert!(!reopened.has_chunk(2, 0));
+    assert!(reopened.write_chunks(&[(2, 0, raw(vec![4], 11))]).is_err());
+
+    // Chunks too large for the region are saved next to it until they are small enough again.
+    let large = vec![5; 256 * SECTOR_SIZE];
+    region.write_chunks(&[(1, 1, raw(large.clone(), 12))]).unwrap();
+    assert!(dir.join("c.1.1.mcc").exists());
+    assert_eq!(RegionFile::open(&path).unwrap().read_chunk(1, 1).unwrap().unwrap(), large);
+    region.write_chunks(&[(1, 1, raw(vec![6], 13))]).unwrap();
+    assert!(!dir.join("c.1.1.mcc").exists());
+    assert_eq!(RegionFile::open(&path).unwrap().read_chunk(1, 1).unwrap().unwrap(), vec![6]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_large_chunks_are_saved_next_to_their_region() {
+    let dir = temp_dir("external");
+    let storage = RegionStorage::new(&dir);
+    // Noise, so it doesn't compress to fit in the region.
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let nbt: Vec<u8> = (0..256 * SECTOR_SIZE)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as u8
+        })
+        .collect();
+    storage.write_chunks(vec![(-1, 40, nbt.clone())]).unwrap();
+    assert!(dir.join("c.-1.40.mcc").exists());
+    let mut region = RegionFile::open(dir.join("r.-1.1.mca")).unwrap();
+    assert_eq!(region.read_chunk(31, 8).unwrap().unwrap(), nbt);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_world_saves_modified_chunks() {
+    install_registries();
+    let dir = temp_dir("world");
+    let world = World::open(WorldHeight::OVERWORLD, &dir, flat());
+    world.get_chunk(0, 0).unwrap();
+    world.set_block(-20, 1, 40, block("stone")).unwrap();
+    // Generated chunks are saved as well, once.
+    assert_eq!(world.save_dirty().unwrap(), 2);
+    assert_eq!(world.save_dirty().unwrap(), 0);
+    assert!(!world.get_chunk(-2, 2).unwrap().read().unwrap().is_dirty());
+
+    world.set_block(100, 5, 100, block("dirt")).unwrap();
+    assert!(world.unload_chunk(6, 6).unwrap());
+    assert!(!world.unload_chunk(6, 6).unwrap());
+
+    let reopened = World::open(WorldHeight::OVERWORLD, &dir, flat());
+    assert_eq!(reopened.get_block(-20, 1, 40).unwrap(), block("stone"));
+    assert_eq!(reopened.get_block(100, 5, 100).unwrap(), block("dirt"));
+    assert!(dir.join("region/r.-1.0.mca").exists());
+    assert!(dir.join("region/r.0.0.mca").exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,221 @@
+use iron_oxide_world::behavior::{BlockBehaviors, BlockHit, PlaceContext, UseResult};
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::pos::{BlockPos, Direction};
+use iron_oxide_world::shape::{Aabb, Shape};
+use iron_oxide_world::world::World;
+
+mod common;
+use common::{block, install_blocks, vanilla_world};
+
+const SOUTH: f32 = 0.0;
+const WEST: f32 = 90.0;
+const NORTH: f32 = 180.0;
+const EAST: f32 = 270.0;
+
+fn hit(pos: BlockPos, face: Direction, cursor: (f32, f32, f32)) -> BlockHit {
+    BlockHit { pos, face, cursor }
+}
+
+/// Places the block against the top of the block at the position, looking along the yaw.
+fn place_on(world: &World, behaviors: &BlockBehaviors, pos: BlockPos, yaw: f32, name: &str) -> Option<BlockPos> {
+    let context = PlaceContext { hit: hit(pos, Direction::Up, (0.5, 1.0, 0.5)), yaw, pitch: 0.0 };
+    behaviors.place(world, &context, block(name)).unwrap()
+}
+
+fn get(world: &World, pos: BlockPos) -> BlockState {
+    world.get_block(pos.x, pos.y, pos.z).unwrap()
+}
+
+#[test]
+fn test_horizontal_directions() {
+    assert_eq!(Direction::from_yaw(SOUTH), Direction::South);
+    assert_eq!(Direction::from_yaw(WEST), Direction::West);
+    assert_eq!(Direction::from_yaw(-180.0), Direction::North);
+    assert_eq!(Direction::from_yaw(EAST + 40.0), Direction::East);
+    assert_eq!(Direction::from_yaw(EAST + 50.0), Direction::South);
+    for direction in Direction::HORIZONTAL {
+        assert_eq!(direction.clockwise().counter_clockwise(), direction);
+        assert_eq!(direction.clockwise().clockwise(), direction.opposite());
+    }
+    assert_eq!(Direction::North.clockwise(), Direction::East);
+    assert_eq!(Direction::Up.clockwise(), Direction::Up);
+}
+
+#[test]
+fn test_shapes() {
+    install_blocks();
+    let behaviors = BlockBehaviors::vanilla();
+    let shape = |name: &str| behaviors.get(block(name)).get_collision_shape(block(name));
+    assert!(shape("stone").is_full());
+    assert!(shape("water").is_empty());
+    assert!(shape("wheat").is_empty());
+    assert!(shape("oak_button").is_empty());
+    assert_eq!(shape("oak_slab[type=top]"), Shape::from_box(Aabb::new(0.0, 0.5, 0.0, 1.0, 1.0, 1.0)));
+    assert!(shape("oak_slab[type=double]").is_full());
+    assert!(shape("oak_slab").covers_face(Direction::Down));
+    assert!(!shape("oak_slab").covers_face(Direction::Up));
+
+    let stairs = shape("oak_stairs[facing=north,shape=outer_left]");
+    assert_eq!(stairs.boxes()[1], Aabb::new(0.0, 0.5, 0.0, 0.5, 1.0, 0.5));
+    assert_eq!(stairs.boxes().len(), 2);
+    assert_eq!(shape("oak_stairs[shape=inner_right]").boxes().len(), 4);
+    assert!(shape("oak_stairs[facing=east]").covers_face(Direction::East));
+    assert!(!shape("oak_stairs[facing=east]").covers_face(Direction::West));
+    assert!(shape("oak_stairs[half=top]").covers_face(Direction::Up));
+
+    let thickness = 3.0 / 16.0;
+    assert_eq!(shape("oak_door[facing=east]").boxes()[0], Aabb::side(Direction::West, thickness));
+    assert_eq!(shape("oak_door[facing=east,open=true]").boxes()[0], Aabb::side(Direction::North, thickness));
+    assert_eq!(
+        shape("oak_door[facing=east,open=true,hinge=right]").boxes()[0],
+        Aabb::side(Direction::South, thickness)
+    );
+    assert_eq!(shape("oak_trapdoor[half=top]").boxes()[0], Aabb::side(Direction::Up, thickness));
+    assert_eq!(shape("oak_trapdoor[facing=north,open=true]").boxes()[0], Aabb::side(Direction::South, thickness));
+}
+
+#[test]
+fn test_doors() {
+    let (world, behaviors) = vanilla_world();
+    let grass = BlockPos::new(8, -60, 8);
+    let lower = place_on(&world, &behaviors, grass, SOUTH, "oak_door").unwrap();
+    let upper = lower.relative(Direction::Up);
+    assert_eq!(get(&world, lower), block("oak_door[facing=south,half=lower,hinge=left]"));
+    assert_eq!(get(&world, upper), block("oak_door[facing=south,half=upper,hinge=left]"));
+
+    // Opening either half opens both.
+    assert_eq!(
+        behaviors.use_block(&world, &hit(upper, Direction::North, (0.5, 0.5, 0.0))).unwrap(),
+        UseResult::Consumed
+    );
+    assert_eq!(get(&world, lower).get("open"), Some("true"));
+    behaviors.use_block(&world, &hit(lower, Direction::North, (0.5, 0.5, 0.0))).unwrap();
+    assert_eq!(get(&world, upper).get("open"), Some("false"));
+
+    // Breaking one half breaks the other, and so does breaking the block under the door.
+    assert!(behaviors.break_block(&world, upper).unwrap());
+    assert!(get(&world, lower).is_air());
+    place_on(&world, &behaviors, grass, SOUTH, "oak_door").unwrap();
+    world.set_block(grass.x, grass.y, grass.z, BlockState::AIR).unwrap();
+    assert!(get(&world, lower).is_air());
+    assert!(get(&world, upper).is_air());
+
+    // Doors can't be placed without room or on air, and iron doors don't open by hand.
+    world.set_block(grass.x, grass.y, grass.z, block("stone")).unwrap();
+    world.set_block(upper.x, upper.y, upper.z, block("stone")).unwrap();
+    assert_eq!(place_on(&world, &behaviors, grass, SOUTH, "oak_door"), None);
+    world.set_block(upper.x, upper.y, upper.z, BlockState::AIR).unwrap();
+    place_on(&world, &behaviors, grass, SOUTH, "iron_door").unwrap();
+    assert_eq!(behaviors.use_block(&world, &hit(lower, Direction::North, (0.5, 0.5, 0.0))).unwrap(), UseResult::Pass);
+}
+
+#[test]
+fn test_door_hinges() {
+    let (world, behaviors) = vanilla_world();
+    // A door looking south has its right side to the west.
+    world.set_block(-1, -59, 0, block("stone")).unwrap();
+    let door = place_on(&world, &behaviors, BlockPos::new(0, -60, 0), SOUTH, "oak_door").unwrap();
+    assert_eq!(get(&world, door).get("hinge"), Some("right"));
+
+    // A door next to another one makes a double door.
+    let pair = place_on(&world, &behaviors, BlockPos::new(1, -60, 0), SOUTH, "oak_door").unwrap();
+    assert_eq!(get(&world, pair).get("hinge"), Some("left"));
+    let pair = place_on(&world, &behaviors, BlockPos::new(5, -60, 0), SOUTH, "oak_door").unwrap();
+    let other = place_on(&world, &behaviors, BlockPos::new(4, -60, 0), SOUTH, "oak_door").unwrap();
+    assert_eq!(get(&world, pair).get("hinge"), Some("left"));
+    assert_eq!(get(&world, other).get("hinge"), Some("right"));
+}
+
+#[test]
+fn test_stairs_placement() {
+    let (world, behaviors) = vanilla_world();
+    let stairs = place_on(&world, &behaviors, BlockPos::new(0, -60, 0), NORTH, "oak_stairs").unwrap();
+    assert_eq!(get(&world, stairs), block("oak_stairs[facing=north,half=bottom,shape=straight]"));
+
+    // Stairs turned sideways behind make an inner corner.
+    let behind = place_on(&world, &behaviors, BlockPos::new(0, -60, 1), EAST, "oak_stairs").unwrap();
+    assert_eq!(get(&world, behind).get("shape"), Some("straight"));
+    assert_eq!(get(&world, stairs).get("shape"), Some("inner_right"));
+    world.set_block(behind.x, behind.y, behind.z, BlockState::AIR).unwrap();
+    assert_eq!(get(&world, stairs).get("shape"), Some("straight"));
+
+    // Stairs turned sideways in front make an outer corner.
+    place_on(&world, &behaviors, BlockPos::new(5, -60, -1), WEST, "oak_stairs").unwrap();
+    let corner = place_on(&world, &behaviors, BlockPos::new(5, -60, 0), NORTH, "oak_stairs").unwrap();
+    assert_eq!(get(&world, corner).get("shape"), Some("outer_left"));
+
+    // Clicking the upper half of a side turns stairs upside down.
+    world.set_block(10, -59, 0, block("stone")).unwrap();
+    let context =
+        PlaceContext { hit: hit(BlockPos::new(10, -59, 0), Direction::East, (1.0, 0.75, 0.5)), yaw: WEST, pitch: 0.0 };
+    let upside_down = behaviors.place(&world, &context, block("oak_stairs")).unwrap().unwrap();
+    assert_eq!(upside_down, BlockPos::new(11, -59, 0));
+    assert_eq!(get(&world, upside_down), block("oak_stairs[facing=west,half=top]"));
+}
+
+#[test]
+fn test_slabs() {
+    let (world, behaviors) = vanilla_world();
+    let slab = place_on(&world, &behaviors, BlockPos::new(3, -60, 3), NORTH, "oak_slab").unwrap();
+    assert_eq!(get(&world, slab), block("oak_slab[type=bottom]"));
+
+    // Clicking the top of a bottom slab fills it.
+    assert_eq!(place_on(&world, &behaviors, slab, NORTH, "oak_slab"), Some(slab));
+    assert_eq!(get(&world, slab), block("oak_slab[type=double]"));
+    assert_eq!(place_on(&world, &behaviors, slab, NORTH, "oak_slab"), Some(slab.relative(Direction::Up)));
+
+    world.set_block(3, -59, 6, block("stone")).unwrap();
+    let context =
+        PlaceContext { hit: hit(BlockPos::new(3, -59, 6), Direction::South, (0.5, 0.6, 1.0)), yaw: NORTH, pitch: 0.0 };
+    let top = behaviors.place(&world, &context, block("oak_slab")).unwrap().unwrap();
+    assert_eq!(get(&world, top), block("oak_slab[type=top]"));
+    let lower = PlaceContext { hit: hit(top, Direction::Up, (0.5, 1.0, 0.5)), ..context };
+    assert_eq!(behaviors.place(&world, &lower, block("oak_slab")).unwrap(), Some(top.relative(Direction::Up)));
+}
+
+#[test]
+fn test_trapdoors() {
+    let (world, behaviors) = vanilla_world();
+    world.set_block(0, -59, 0, block("stone")).unwrap();
+    let context =
+        PlaceContext { hit: hit(BlockPos::new(0, -59, 0), Direction::East, (1.0, 0.8, 0.5)), yaw: WEST, pitch: 0.0 };
+    let side = behaviors.place(&world, &context, block("oak_trapdoor")).unwrap().unwrap();
+    assert_eq!(get(&world, side), block("oak_trapdoor[facing=east,half=top]"));
+
+    let floor = place_on(&world, &behaviors, BlockPos::new(4, -60, 4), WEST, "oak_trapdoor").unwrap();
+    assert_eq!(get(&world, floor), block("oak_trapdoor[facing=east,half=bottom]"));
+    behaviors.use_block(&world, &hit(floor, Direction::Up, (0.5, 0.2, 0.5))).unwrap();
+    assert_eq!(get(&world, floor).get("open"), Some("true"));
+}
+
+#[test]
+fn test_buttons_and_levers() {
+    let (world, behaviors) = vanilla_world();
+    world.set_block(12, -59, 12, block("stone")).unwrap();
+    let context =
+        PlaceContext { hit: hit(BlockPos::new(12, -59, 12), Direction::East, (1.0, 0.5, 0.5)), yaw: WEST, pitch: 0.0 };
+    let button = behaviors.place(&world, &context, block("oak_button")).unwrap().unwrap();
+    assert_eq!(get(&world, button), block("oak_button[face=wall,facing=east]"));
+
+    behaviors.use_block(&world, &hit(button, Direction::East, (0.1, 0.5, 0.5))).unwrap();
+    assert_eq!(get(&world, button).get("powered"), Some("true"));
+    assert!(world.is_tick_scheduled(button, block("oak_button")));
+    for _ in 0..29 {
+        world.tick().unwrap();
+    }
+    assert_eq!(get(&world, button).get("powered"), Some("true"));
+    world.tick().unwrap();
+    assert_eq!(get(&world, button).get("powered"), Some("false"));
+
+    let lever = place_on(&world, &behaviors, BlockPos::new(12, -60, 14), NORTH, "lever").unwrap();
+    assert_eq!(get(&world, lever), block("lever[face=floor,facing=north]"));
+    behaviors.use_block(&world, &hit(lever, Direction::Up, (0.5, 0.2, 0.5))).unwrap();
+    assert_eq!(get(&world, lever).get("powered"), Some("true"));
+    behaviors.use_block(&world, &hit(lever, Direction::Up, (0.5, 0.2, 0.5))).unwrap();
+    assert_eq!(get(&world, lever).get("powered"), Some("false"));
+
+    // Switches need a block to hang on and break with it.
+    assert_eq!(place_on(&world, &behaviors, lever, NORTH, "lever"), None);
+    world.set_block(12, -59, 12, BlockState::AIR).unwrap();
+    assert!(get(&world, button).is_air());
+}
@@ -0,0 +1,53 @@
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::chunk::{ChunkColumn, ChunkSection};
+
+mod common;
+use common::{block, install_blocks};
+
+#[test]
+fn test_counts_follow_block_changes() {
+    install_blocks();
+    let mut section = ChunkSection::new();
+    assert!(section.is_empty());
+
+    section.set_block(0, 0, 0, block("stone")).unwrap();
+    section.set_block(1, 0, 0, block("water")).unwrap();
+    section.set_block(2, 0, 0, block("wheat")).unwrap();
+    assert_eq!(section.block_count(), 3);
+    assert_eq!(section.fluid_count(), 1);
+    assert_eq!(section.random_tick_count(), 1);
+
+    section.set_block(1, 0, 0, block("stone")).unwrap();
+    section.set_block(2, 0, 0, block("stone")).unwrap();
+    section.set_block(2, 0, 0, block("stone")).unwrap();
+    assert_eq!(section.block_count(), 3);
+    assert_eq!(section.fluid_count(), 0);
+    assert_eq!(section.random_tick_count(), 0);
+
+    section.set_block(0, 0, 0, block("cave_air")).unwrap();
+    section.set_block(1, 0, 0, BlockState::AIR).unwrap();
+    section.set_block(2, 0, 0, BlockState::AIR).unwrap();
+    assert!(section.is_empty());
+}
+
+#[test]
+fn test_fill_layer_counts_blocks() {
+    install_blocks();
+    let mut section = ChunkSection::new();
+    section.set_block(5, 3, 5, block("water")).unwrap();
+    section.fill_layer(3, block("wheat")).unwrap();
+    assert_eq!(section.block_count(), 256);
+    assert_eq!(section.fluid_count(), 0);
+    assert_eq!(section.random_tick_count(), 256);
+}
+
+#[test]
+fn test_highest_block_skips_air() {
+    install_blocks();
+    let mut column = ChunkColumn::default();
+    assert_eq!(column.highest_block(4, 4).unwrap(), None);
+    column.set_block(4, -44, 4, block("stone")).unwrap();
+    column.set_block(4, 36, 4, block("cave_air")).unwrap();
+    assert_eq!(column.highest_block(4, 4).unwrap(), Some(-44));
+    assert!(column.sections()[6].is_empty());
+}
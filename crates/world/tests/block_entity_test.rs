@@ -0,0 +1,99 @@
+use fastnbt::Value;
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::block_entity::{block_entity_kind, BlockEntity};
+use iron_oxide_world::changes::{BlockChange, ChangeKind, ChangeLog, CHANGE_LOG_TICKS};
+use iron_oxide_world::chunk::{ChunkColumn, ChunkSection};
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::pos::BlockPos;
+
+mod common;
+use common::{block, flat_world, install_blocks};
+
+fn named_chest(name: &str) -> BlockEntity {
+    BlockEntity::new("minecraft:chest").with("CustomName", Value::String(name.to_string()))
+}
+
+#[test]
+fn test_block_entity_kinds() {
+    install_blocks();
+    assert_eq!(block_entity_kind(block("chest[facing=east]")), Some("minecraft:chest"));
+    assert_eq!(block_entity_kind(block("stone")), None);
+    assert!(block("chest").flags().block_entity);
+    assert!(!block("oak_stairs").flags().block_entity);
+
+    let chest = named_chest("\"Loot\"").with("Lock", Value::Byte(1));
+    assert_eq!(chest.get_string("CustomName"), Some("\"Loot\""));
+    assert_eq!(chest.get_int("Lock"), Some(1));
+    assert_eq!(chest.get_int("CustomName"), None);
+}
+
+#[test]
+fn test_block_entities_follow_their_blocks() {
+    install_blocks();
+    let mut column = ChunkColumn::new(WorldHeight::OVERWORLD);
+    let pos = BlockPos::new(2, 5, 3);
+    column.set_block(2, 5, 3, block("chest")).unwrap();
+    assert_eq!(column.block_entity(pos), Some(&BlockEntity::new("minecraft:chest")));
+
+    // Turning the chest keeps its contents, replacing it drops them.
+    column.set_block_entity(pos, named_chest("\"Loot\"")).unwrap();
+    column.set_block(2, 5, 3, block("chest[facing=west]")).unwrap();
+    assert_eq!(column.block_entity(pos), Some(&named_chest("\"Loot\"")));
+    column.set_block(2, 5, 3, block("stone")).unwrap();
+    assert_eq!(column.block_entity(pos), None);
+
+    column.fill_layer(7, block("chest")).unwrap();
+    assert_eq!(column.block_entities().count(), 256);
+    column.set_block_entity(BlockPos::new(0, 7, 0), named_chest("\"Kept\"")).unwrap();
+    column.fill_layer(7, block("chest[facing=south]")).unwrap();
+    assert_eq!(column.block_entity(BlockPos::new(0, 7, 0)), Some(&named_chest("\"Kept\"")));
+    column.set_section(4, ChunkSection::new());
+    assert_eq!(column.block_entities().count(), 0);
+}
+
+#[test]
+fn test_world_records_changes() {
+    let world = flat_world();
+    let cursor = world.change_cursor();
+
+    world.set_block(-20, -59, 5, block("chest")).unwrap();
+    world.set_block_entity(-20, -59, 5, named_chest("\"Loot\"")).unwrap();
+    assert_eq!(world.block_entity(-20, -59, 5).unwrap(), Some(named_chest("\"Loot\"")));
+
+    let changes = world.changes_since(cursor);
+    let pos = BlockPos::new(-20, -59, 5);
+    assert_eq!(
+        changes.changes,
+        vec![
+            BlockChange { pos, kind: ChangeKind::Block },
+            BlockChange { pos, kind: ChangeKind::BlockEntity },
+        ]
+    );
+    assert!(!changes.missed);
+    assert!(world.changes_since(changes.next).changes.is_empty());
+
+    world.set_block(-20, -59, 5, BlockState::AIR).unwrap();
+    assert_eq!(world.block_entity(-20, -59, 5).unwrap(), None);
+}
+
+#[test]
+fn test_change_log_drops_old_changes() {
+    let mut log = ChangeLog::new();
+    let change = BlockChange { pos: BlockPos::new(1, 2, 3), kind: ChangeKind::Block };
+    log.push(change);
+    log.tick();
+    log.push(change);
+    assert_eq!(log.since(0).changes.len(), 2);
+    assert_eq!(log.since(1).changes.len(), 1);
+
+    for _ in 1..CHANGE_LOG_TICKS {
+        log.tick();
+    }
+    assert_eq!(log.len(), 1);
+    let changes = log.since(0);
+    assert!(changes.missed);
+    assert_eq!(changes.next, 2);
+    assert!(!log.since(1).missed);
+    log.tick();
+    assert!(log.is_empty());
+}
@@ -0,0 +1,99 @@
+use iron_oxide_world::block::{BlockRegistry, BlockState};
+use iron_oxide_world::error::WorldError;
+
+mod common;
+use common::install_blocks;
+
+#[test]
+fn test_registry_from_report() {
+    let registry = BlockRegistry::from_report(include_str!("fixtures/blocks.json")).unwrap();
+    assert_eq!(registry.block_count(), 39);
+    assert_eq!(registry.state_count(), 1901);
+}
+
+#[test]
+fn test_report_with_unordered_states_is_rejected() {
+    let report = r#"{"minecraft:lever": {
+        "properties": {"powered": ["true", "false"]},
+        "states": [
+            {"id": 0, "properties": {"powered": "false"}},
+            {"id": 1, "default": true, "properties": {"powered": "true"}}
+        ]
+    }}"#;
+    assert!(matches!(BlockRegistry::from_report(report), Err(WorldError::InvalidBlockReport(_))));
+}
+
+#[test]
+fn test_report_with_missing_blocks_is_rejected() {
+    let report = r#"{
+        "minecraft:air": {"states": [{"id": 0, "default": true}]},
+        "minecraft:stone": {"states": [{"id": 2, "default": true}]}
+    }"#;
+    match BlockRegistry::from_report(report) {
+        Err(WorldError::InvalidBlockReport(message)) => assert!(message.contains("State 1")),
+        other => panic!("unexpected result {:?}", other.map(|registry| registry.block_count())),
+    }
+}
+
+#[test]
+fn test_default_states() {
+    install_blocks();
+    assert_eq!(BlockState::default_of("minecraft:air").unwrap(), BlockState::AIR);
+    assert_eq!(BlockState::default_of("stone").unwrap().id(), 1);
+    assert_eq!(BlockState::default_of("minecraft:grass_block").unwrap().id(), 3);
+
+    let stairs = BlockState::default_of("oak_stairs").unwrap();
+    assert_eq!(stairs.name(), Some("minecraft:oak_stairs"));
+    assert_eq!(stairs.get("facing"), Some("north"));
+    assert_eq!(stairs.get("half"), Some("bottom"));
+    assert_eq!(stairs.get("power"), None);
+    assert!(matches!(BlockState::default_of("minecraft:nope"), Err(WorldError::UnknownBlock(_))));
+}
+
+#[test]
+fn test_parse_and_display() {
+    install_blocks();
+    let stairs = BlockState::parse("minecraft:oak_stairs[facing=east,waterlogged=true]").unwrap();
+    assert_eq!(stairs.get("facing"), Some("east"));
+    assert_eq!(stairs.get("half"), Some("bottom"));
+    assert_eq!(stairs.get("waterlogged"), Some("true"));
+    assert_eq!(
+        stairs.to_string(),
+        "minecraft:oak_stairs[facing=east,half=bottom,shape=straight,waterlogged=true]"
+    );
+    assert_eq!(stairs.to_string().parse::<BlockState>().unwrap(), stairs);
+    assert_eq!(BlockState::parse("stone").unwrap().to_string(), "minecraft:stone");
+
+    assert!(BlockState::parse("oak_stairs[facing=up]").is_err());
+    assert!(BlockState::parse("oak_stairs[facing=east").is_err());
+    assert!(BlockState::parse("oak_stairs[facing]").is_err());
+}
+
+#[test]
+fn test_property_mutation() {
+    install_blocks();
+    let stairs = BlockState::default_of("oak_stairs").unwrap();
+    let turned = stairs.with("facing", "west").unwrap().with("shape", "outer_right").unwrap();
+    assert_eq!(turned.get("facing"), Some("west"));
+    assert_eq!(turned.get("shape"), Some("outer_right"));
+    assert_eq!(turned.get("half"), Some("bottom"));
+    assert!(turned.is("oak_stairs"));
+    assert_eq!(turned.default_state(), stairs);
+    assert_eq!(turned.with("facing", "north").unwrap().with("shape", "straight").unwrap(), stairs);
+    assert!(matches!(stairs.with("age", "1"), Err(WorldError::InvalidProperty { .. })));
+}
+
+#[test]
+fn test_flags() {
+    install_blocks();
+    assert!(BlockState::AIR.is_air());
+    assert!(BlockState::parse("cave_air").unwrap().is_air());
+    assert!(!BlockState::parse("stone").unwrap().is_air());
+    assert!(BlockState::parse("water[level=3]").unwrap().flags().fluid);
+    assert!(BlockState::parse("oak_stairs[waterlogged=true]").unwrap().flags().fluid);
+    assert!(!BlockState::parse("oak_stairs").unwrap().flags().fluid);
+    assert!(BlockState::parse("wheat[age=7]").unwrap().flags().random_ticks);
+    assert!(BlockState::parse("oak_leaves[distance=7]").unwrap().flags().random_ticks);
+    assert!(!BlockState::parse("oak_leaves[distance=6]").unwrap().flags().random_ticks);
+    assert!(!BlockState::parse("oak_leaves[distance=7,persistent=true]").unwrap().flags().random_ticks);
+}
@@ -0,0 +1,52 @@
+use std::cell::Cell;
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::chunk::ChunkColumn;
+
+fn encode_counting(column: &ChunkColumn, calls: &Cell<u32>) -> Vec<u8> {
+    column
+        .encoded(770, |column| {
+            calls.set(calls.get() + 1);
+            Ok::<_, ()>(vec![column.get_block(0, 0, 0).unwrap().id() as u8])
+        })
+        .unwrap()
+        .to_vec()
+}
+
+#[test]
+fn test_encoding_is_cached() {
+    let column = ChunkColumn::default();
+    let calls = Cell::new(0);
+    assert_eq!(encode_counting(&column, &calls), vec![0]);
+    assert_eq!(encode_counting(&column, &calls), vec![0]);
+    assert_eq!(calls.get(), 1);
+}
+
+#[test]
+fn test_set_block_invalidates_encoding() {
+    let mut column = ChunkColumn::default();
+    let calls = Cell::new(0);
+    encode_counting(&column, &calls);
+    column.set_block(0, 0, 0, BlockState::from_id(7)).unwrap();
+    assert_eq!(encode_counting(&column, &calls), vec![7]);
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn test_encodings_are_per_protocol_version() {
+    let column = ChunkColumn::default();
+    column.encoded(770, |_| Ok::<_, ()>(vec![1])).unwrap();
+    let other = column.encoded(771, |_| Ok::<_, ()>(vec![2])).unwrap();
+    assert_eq!(&other[..], &[2]);
+}
+
+#[test]
+fn test_dirty_until_saved() {
+    let mut column = ChunkColumn::default();
+    assert!(!column.is_dirty());
+    column.set_block(3, -24, 3, BlockState::from_id(1)).unwrap();
+    assert!(column.is_dirty());
+    assert!(column.sections()[2].is_dirty());
+    assert!(!column.sections()[0].is_dirty());
+    column.mark_saved();
+    assert!(!column.is_dirty());
+}
@@ -0,0 +1,46 @@
+//! Helpers shared by the integration tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use iron_oxide_world::behavior::BlockBehaviors;
+use iron_oxide_world::block::{install_block_registry, BlockRegistry, BlockState};
+use iron_oxide_world::generator::FlatGenerator;
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::world::World;
+use std::sync::Arc;
+
+/// Installs the block registry of the test report. Every test installs it, so only the first
+/// install takes effect.
+pub fn install_blocks() {
+    let registry = BlockRegistry::from_report(include_str!("../fixtures/blocks.json")).unwrap();
+    install_block_registry(registry);
+}
+
+pub fn block(name: &str) -> BlockState {
+    BlockState::parse(name).unwrap()
+}
+
+/// The terrain the server generates by default, without a biome so no biome registry is needed.
+pub fn flat() -> Box<FlatGenerator> {
+    Box::new(FlatGenerator::from_preset("minecraft:stone,3*minecraft:dirt,minecraft:grass_block").unwrap())
+}
+
+/// A world of the default terrain, whose grass is at y -60.
+pub fn flat_world() -> World {
+    install_blocks();
+    World::new(WorldHeight::OVERWORLD, flat())
+}
+
+/// A flat world with the vanilla behaviors and the chunks around the origin loaded, since blocks
+/// don't update or tick in chunks that aren't.
+pub fn vanilla_world() -> (World, Arc<BlockBehaviors>) {
+    let world = flat_world();
+    // The behaviors are looked up by block, so the blocks must be installed first.
+    let behaviors = Arc::new(BlockBehaviors::vanilla());
+    let world = world.with_update_handler(Box::new(Arc::clone(&behaviors)));
+    for x in -1..=1 {
+        for z in -1..=1 {
+            world.get_chunk(x, z).unwrap();
+        }
+    }
+    (world, behaviors)
+}
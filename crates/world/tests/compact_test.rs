@@ -0,0 +1,139 @@
+use fastnbt::Value;
+use iron_oxide_world::biome::{install_biome_registry, BiomeRegistry};
+use iron_oxide_world::block_entity::BlockEntity;
+use iron_oxide_world::chunk::ChunkColumn;
+use iron_oxide_world::compact::{CompactWorld, MAX_CHUNKS};
+use iron_oxide_world::error::WorldError;
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::pos::BlockPos;
+use iron_oxide_world::world::World;
+use std::collections::HashMap;
+
+mod common;
+use common::{block, flat, install_blocks};
+
+fn install_registries() {
+    install_blocks();
+    install_biome_registry(BiomeRegistry::new(vec![
+        "minecraft:plains".to_string(),
+        "minecraft:the_void".to_string(),
+    ]));
+}
+
+#[test]
+fn test_compact_round_trip() {
+    install_registries();
+    let height = WorldHeight::new(0, 64).unwrap();
+    let mut world = CompactWorld::new(height);
+    let mut chunk = ChunkColumn::new(height);
+    chunk.fill_layer(0, block("bedrock")).unwrap();
+    chunk.set_block(2, 40, 3, block("oak_stairs[facing=west]")).unwrap();
+    let mut sign = BlockEntity::new("minecraft:sign");
+    sign.data.insert("is_waxed".to_string(), Value::Byte(1));
+    chunk.set_block_entity(BlockPos::new(2, 41, 3), sign.clone()).unwrap();
+    world.insert_chunk(-1, 4, chunk).unwrap();
+    world.insert_chunk(0, 0, ChunkColumn::new(height)).unwrap();
+
+    let bytes = world.to_bytes().unwrap();
+    assert!(bytes.starts_with(b"IOXW"));
+    let read = CompactWorld::from_bytes(&bytes).unwrap();
+    assert_eq!(read.height(), height);
+    assert_eq!(read.len(), 2);
+    let chunk = read.chunk(-1, 4).unwrap();
+    assert_eq!(chunk.get_block(15, 0, 15).unwrap(), block("bedrock"));
+    assert_eq!(chunk.get_block(2, 40, 3).unwrap(), block("oak_stairs[facing=west]"));
+    assert_eq!(chunk.block_entity(BlockPos::new(2, 41, 3)), Some(&sign));
+    assert!(!chunk.is_dirty());
+    assert!(read.chunk(0, 0).unwrap().sections().iter().all(|section| section.is_empty()));
+    assert!(read.chunk(1, 0).is_none());
+}
+
+#[test]
+fn test_compact_rejects_invalid_files() {
+    install_registries();
+    assert!(matches!(CompactWorld::from_bytes(b"PK\x03\x04"), Err(WorldError::InvalidCompactWorld(_))));
+    let mut bytes = CompactWorld::new(WorldHeight::OVERWORLD).to_bytes().unwrap();
+    bytes[4] = 99;
+    assert!(matches!(CompactWorld::from_bytes(&bytes), Err(WorldError::InvalidCompactWorld(_))));
+
+    let mut world = CompactWorld::new(WorldHeight::OVERWORLD);
+    let chunk = ChunkColumn::new(WorldHeight::new(0, 256).unwrap());
+    assert!(world.insert_chunk(0, 0, chunk).is_err());
+}
+
+#[test]
+fn test_compact_chunk_limit() {
+    install_registries();
+    let height = WorldHeight::new(0, 16).unwrap();
+    let mut world = CompactWorld::new(height);
+    for index in 0..MAX_CHUNKS as i32 {
+        world.insert_chunk(index % 64, index / 64, ChunkColumn::new(height)).unwrap();
+    }
+    world.insert_chunk(0, 0, ChunkColumn::new(height)).unwrap();
+    assert!(matches!(world.insert_chunk(64, 0, ChunkColumn::new(height)), Err(WorldError::InvalidCompactWorld(_))));
+
+    // Files holding more chunks than that are rejected too.
+    let chunk = |x: i32| {
+        Value::Compound(
+            [("X", Value::Int(x)), ("Z", Value::Int(0)), ("sections", Value::List(Vec::new()))]
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        )
+    };
+    let nbt: HashMap<String, Value> = [
+        ("DataVersion", Value::Int(4325)),
+        ("MinY", Value::Int(0)),
+        ("Height", Value::Int(16)),
+        ("Chunks", Value::List((0..=MAX_CHUNKS as i32).map(chunk).collect())),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value))
+    .collect();
+    let mut bytes = b"IOXW\x01".to_vec();
+    bytes.extend(zstd::encode_all(fastnbt::to_bytes(&nbt).unwrap().as_slice(), 1).unwrap());
+    match CompactWorld::from_bytes(&bytes) {
+        Err(WorldError::InvalidCompactWorld(message)) => assert!(message.contains("chunks")),
+        other => panic!("unexpected result {:?}", other.map(|world| world.len())),
+    }
+}
+
+#[test]
+fn test_compact_chunks_stay_loaded() {
+    install_registries();
+    let mut compact = CompactWorld::new(WorldHeight::OVERWORLD);
+    let mut chunk = ChunkColumn::new(WorldHeight::OVERWORLD);
+    chunk.set_block(1, 40, 1, block("sand")).unwrap();
+    compact.insert_chunk(5, 5, chunk).unwrap();
+    let lobby = World::from_compact(WorldHeight::OVERWORLD, compact, flat()).unwrap().with_unload_delay(1);
+
+    // Nothing could read the chunk back, so it stays loaded without tickets.
+    for _ in 0..5 {
+        assert_eq!(lobby.tick().unwrap(), 0);
+    }
+    assert!(lobby.is_loaded(5, 5));
+    assert_eq!(lobby.get_block(81, 40, 81).unwrap(), block("sand"));
+}
+
+#[test]
+fn test_convert_region_world() {
+    install_registries();
+    let dir = std::env::temp_dir().join(format!("iron-oxide-compact-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let world = World::open(WorldHeight::OVERWORLD, &dir, flat());
+    world.set_block(5, 70, -5, block("wheat[age=7]")).unwrap();
+    world.set_block(-600, 0, 0, block("dirt")).unwrap();
+    world.save_dirty().unwrap();
+
+    let compact = CompactWorld::from_regions(dir.join("region"), WorldHeight::OVERWORLD).unwrap();
+    assert_eq!(compact.len(), 2);
+    assert_eq!(compact.chunk(0, -1).unwrap().get_block(5, 70, 11).unwrap(), block("wheat[age=7]"));
+
+    let file = dir.join("lobby.ioxw");
+    compact.save(&file).unwrap();
+    let lobby = World::from_compact(WorldHeight::OVERWORLD, CompactWorld::open(&file).unwrap(), flat()).unwrap();
+    assert_eq!(lobby.get_block(5, 70, -5).unwrap(), block("wheat[age=7]"));
+    assert_eq!(lobby.get_block(-600, 0, 0).unwrap(), block("dirt"));
+    assert!(World::from_compact(WorldHeight::new(0, 256).unwrap(), CompactWorld::open(&file).unwrap(), flat()).is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+}
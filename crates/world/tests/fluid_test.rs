@@ -0,0 +1,145 @@
+use iron_oxide_world::behavior::{BlockBehaviors, BlockHit, PlaceContext};
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::fluid::{Fluid, FluidState};
+use iron_oxide_world::pos::{BlockPos, Direction};
+use iron_oxide_world::world::World;
+
+mod common;
+use common::{block, install_blocks, vanilla_world};
+
+/// Places the block on the grass at the column.
+fn place(world: &World, behaviors: &BlockBehaviors, x: i32, z: i32, name: &str) {
+    let hit = BlockHit { pos: BlockPos::new(x, -60, z), face: Direction::Up, cursor: (0.5, 1.0, 0.5) };
+    let context = PlaceContext { hit, yaw: 0.0, pitch: 0.0 };
+    assert!(behaviors.place(world, &context, block(name)).unwrap().is_some());
+}
+
+fn tick(world: &World, ticks: usize) {
+    for _ in 0..ticks {
+        world.tick().unwrap();
+    }
+}
+
+fn get(world: &World, x: i32, y: i32, z: i32) -> BlockState {
+    world.get_block(x, y, z).unwrap()
+}
+
+fn level(world: &World, x: i32, y: i32, z: i32) -> Option<u8> {
+    let state = get(world, x, y, z);
+    FluidState::of(state).map(|_| state.get("level").unwrap().parse().unwrap())
+}
+
+#[test]
+fn test_fluid_states() {
+    install_blocks();
+    assert_eq!(FluidState::of(block("water")), Some(FluidState::source(Fluid::Water)));
+    assert_eq!(FluidState::of(block("lava[level=3]")), Some(FluidState::flowing(Fluid::Lava, 5, false)));
+    assert_eq!(FluidState::of(block("water[level=9]")), Some(FluidState::flowing(Fluid::Water, 8, true)));
+    assert_eq!(FluidState::of(block("oak_stairs[waterlogged=true]")), Some(FluidState::source(Fluid::Water)));
+    assert_eq!(FluidState::of(block("oak_stairs")), None);
+    assert_eq!(FluidState::of(block("stone")), None);
+
+    assert_eq!(FluidState::flowing(Fluid::Water, 6, false).block(), block("water[level=2]"));
+    assert_eq!(FluidState::flowing(Fluid::Lava, 8, true).block(), block("lava[level=8]"));
+    assert_eq!(FluidState::source(Fluid::Lava).block(), block("lava"));
+}
+
+#[test]
+fn test_water_spreads() {
+    let (world, behaviors) = vanilla_world();
+    place(&world, &behaviors, 0, 0, "water");
+    assert!(world.is_fluid_tick_scheduled(BlockPos::new(0, -59, 0), Fluid::Water));
+    tick(&world, 60);
+
+    assert_eq!(level(&world, 0, -59, 0), Some(0));
+    assert_eq!(level(&world, 1, -59, 0), Some(1));
+    assert_eq!(level(&world, 0, -59, -3), Some(3));
+    assert_eq!(level(&world, 7, -59, 0), Some(7));
+    assert_eq!(level(&world, 4, -59, 3), Some(7));
+    assert_eq!(level(&world, 8, -59, 0), None);
+    assert_eq!(level(&world, 4, -59, 4), None);
+
+    // Taking the source away dries the water up.
+    behaviors.break_block(&world, BlockPos::new(0, -59, 0)).unwrap();
+    tick(&world, 60);
+    assert!(get(&world, 1, -59, 0).is_air());
+    assert!(get(&world, 7, -59, 0).is_air());
+}
+
+#[test]
+fn test_water_flows_to_holes() {
+    let (world, behaviors) = vanilla_world();
+    world.set_block(2, -60, 0, BlockState::AIR).unwrap();
+    place(&world, &behaviors, 0, 0, "water");
+    tick(&world, 60);
+
+    // The water only flows towards the hole, and falls into it.
+    assert_eq!(level(&world, 1, -59, 0), Some(1));
+    assert_eq!(level(&world, 2, -59, 0), Some(2));
+    assert_eq!(level(&world, 2, -60, 0), Some(8));
+    assert_eq!(level(&world, -1, -59, 0), None);
+    assert_eq!(level(&world, 0, -59, 1), None);
+    assert_eq!(level(&world, 3, -59, 0), None);
+}
+
+#[test]
+fn test_infinite_water() {
+    let (world, behaviors) = vanilla_world();
+    place(&world, &behaviors, 0, 0, "water");
+    place(&world, &behaviors, 2, 0, "water");
+    tick(&world, 20);
+    assert_eq!(level(&world, 1, -59, 0), Some(0));
+
+    // Lava doesn't make new sources.
+    place(&world, &behaviors, 20, 0, "lava");
+    place(&world, &behaviors, 22, 0, "lava");
+    tick(&world, 100);
+    assert_eq!(level(&world, 21, -59, 0), Some(2));
+    assert_eq!(level(&world, 23, -59, 0), Some(2));
+    assert_eq!(level(&world, 24, -59, 0), Some(4));
+    assert_eq!(level(&world, 25, -59, 0), Some(6));
+    assert_eq!(level(&world, 26, -59, 0), None);
+}
+
+#[test]
+fn test_lava_meets_water() {
+    let (world, behaviors) = vanilla_world();
+    place(&world, &behaviors, 0, 0, "lava");
+    place(&world, &behaviors, 1, 0, "water");
+    assert_eq!(get(&world, 0, -59, 0), block("obsidian"));
+
+    world.set_block(10, -59, 0, block("lava[level=2]")).unwrap();
+    place(&world, &behaviors, 11, 0, "water");
+    assert_eq!(get(&world, 10, -59, 0), block("cobblestone"));
+
+    // Lava falling onto water turns it into stone.
+    world.set_block(20, -59, 0, block("water")).unwrap();
+    world.set_block(20, -57, 0, block("lava")).unwrap();
+    world.schedule_fluid_tick(BlockPos::new(20, -57, 0), Fluid::Lava, 1);
+    tick(&world, 100);
+    assert_eq!(get(&world, 20, -59, 0), block("stone"));
+    assert_eq!(level(&world, 20, -58, 0), Some(8));
+}
+
+#[test]
+fn test_waterlogged_blocks() {
+    let (world, behaviors) = vanilla_world();
+    let stairs = block("oak_stairs[facing=north,waterlogged=true]");
+    world.set_block(0, -59, 0, stairs).unwrap();
+    world.schedule_fluid_tick(BlockPos::new(0, -59, 0), Fluid::Water, 1);
+    tick(&world, 20);
+
+    // The water flows out of the open sides of the stairs, and only reaches their back going
+    // around them.
+    assert_eq!(get(&world, 0, -59, 0), stairs);
+    assert_eq!(level(&world, 0, -59, 1), Some(1));
+    assert_eq!(level(&world, 1, -59, 0), Some(1));
+    assert_eq!(level(&world, 0, -59, -1), Some(3));
+
+    // Flowing water doesn't waterlog blocks, and waterlogged blocks leave their water behind.
+    world.set_block(2, -59, 0, block("oak_stairs")).unwrap();
+    tick(&world, 20);
+    assert_eq!(get(&world, 2, -59, 0), block("oak_stairs"));
+    behaviors.break_block(&world, BlockPos::new(0, -59, 0)).unwrap();
+    assert_eq!(level(&world, 0, -59, 0), Some(0));
+}
@@ -0,0 +1,70 @@
+use iron_oxide_world::biome::{install_biome_registry, BiomeRegistry};
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::chunk::ChunkColumn;
+use iron_oxide_world::error::WorldError;
+use iron_oxide_world::generator::{ChunkGenerator, FlatGenerator, VoidGenerator};
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::world::World;
+
+mod common;
+use common::{block, install_blocks};
+
+fn install_registries() {
+    install_blocks();
+    install_biome_registry(BiomeRegistry::new(vec![
+        "minecraft:badlands".to_string(),
+        "minecraft:plains".to_string(),
+        "minecraft:the_void".to_string(),
+    ]));
+}
+
+#[test]
+fn test_flat_preset_layers() {
+    install_registries();
+    let generator =
+        FlatGenerator::from_preset("minecraft:bedrock, 2*minecraft:dirt,oak_stairs[facing=east,half=top],water").unwrap();
+    let layers: Vec<String> = generator.layers().iter().map(ToString::to_string).collect();
+    assert_eq!(layers[..3], ["minecraft:bedrock", "minecraft:dirt", "minecraft:dirt"]);
+    assert!(layers[3].starts_with("minecraft:oak_stairs[facing=east,half=top"));
+    assert_eq!(layers.len(), 5);
+
+    let mut column = ChunkColumn::new(WorldHeight::new(0, 16).unwrap());
+    generator.generate(3, -8, &mut column).unwrap();
+    assert_eq!(column.get_block(7, 0, 9).unwrap(), block("bedrock"));
+    assert_eq!(column.get_block(7, 4, 9).unwrap(), block("water"));
+    assert_eq!(column.get_block(7, 5, 9).unwrap(), BlockState::AIR);
+    // Without a biome, flat worlds are plains.
+    assert_eq!(column.sections()[0].get_biome(3, 3, 3).unwrap(), 1);
+}
+
+#[test]
+fn test_flat_preset_errors() {
+    install_registries();
+    for preset in ["0*minecraft:dirt", "x*minecraft:dirt", "minecraft:dirt,,minecraft:stone", "stone;plains;plains"] {
+        assert!(matches!(FlatGenerator::from_preset(preset), Err(WorldError::InvalidGeneratorPreset(_))), "{}", preset);
+    }
+    assert!(matches!(FlatGenerator::from_preset("minecraft:marble"), Err(WorldError::UnknownBlock(_))));
+    assert!(matches!(FlatGenerator::from_preset("stone;minecraft:moon"), Err(WorldError::UnknownBiome(_))));
+}
+
+#[test]
+fn test_flat_layers_stop_at_the_top_of_the_world() {
+    install_registries();
+    let generator = FlatGenerator::from_preset("20*stone,grass_block;minecraft:badlands").unwrap();
+    let mut column = ChunkColumn::new(WorldHeight::new(-16, 16).unwrap());
+    generator.generate(0, 0, &mut column).unwrap();
+    assert_eq!(column.highest_block(0, 0).unwrap(), Some(-1));
+    assert_eq!(column.get_block(0, -1, 0).unwrap(), block("stone"));
+    assert_eq!(column.sections()[0].get_biome(0, 0, 0).unwrap(), 0);
+}
+
+#[test]
+fn test_void_generator() {
+    install_registries();
+    let world = World::new(WorldHeight::OVERWORLD, Box::new(VoidGenerator::new()));
+    assert_eq!(world.highest_block(100, -100).unwrap(), None);
+    let chunk = world.get_chunk(6, -7).unwrap();
+    let chunk = chunk.read().unwrap();
+    assert!(chunk.sections().iter().all(|section| section.is_empty()));
+    assert_eq!(chunk.sections()[10].get_biome(1, 1, 1).unwrap(), 2);
+}
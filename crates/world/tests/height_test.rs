@@ -0,0 +1,69 @@
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::chunk::{ChunkColumn, ChunkSection};
+use iron_oxide_world::error::WorldError;
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::world::World;
+
+mod common;
+use common::{flat, install_blocks};
+
+const STONE: BlockState = BlockState::from_id(1);
+
+#[test]
+fn test_world_height() {
+    let height = WorldHeight::new(-64, 384).unwrap();
+    assert_eq!(height, WorldHeight::OVERWORLD);
+    assert_eq!(height.max_y(), 319);
+    assert_eq!(height.section_count(), 24);
+    assert_eq!(height.section_index(-64), Some(0));
+    assert_eq!(height.section_index(-1), Some(3));
+    assert_eq!(height.section_index(0), Some(4));
+    assert_eq!(height.section_index(319), Some(23));
+    assert_eq!(height.section_index(320), None);
+    assert_eq!(height.section_index(-65), None);
+}
+
+#[test]
+fn test_invalid_world_height() {
+    assert!(WorldHeight::new(-60, 384).is_err());
+    assert!(WorldHeight::new(0, 250).is_err());
+    assert!(WorldHeight::new(0, 0).is_err());
+}
+
+#[test]
+fn test_column_uses_dimension_height() {
+    let mut column = ChunkColumn::new(WorldHeight::new(0, 256).unwrap());
+    assert_eq!(column.sections().len(), 16);
+    column.set_block(1, 255, 2, STONE).unwrap();
+    assert_eq!(column.get_block(1, 255, 2).unwrap(), STONE);
+    assert!(!column.sections()[15].is_empty());
+}
+
+#[test]
+fn test_out_of_bounds_blocks_are_errors() {
+    let mut column = ChunkColumn::default();
+    assert!(matches!(
+        column.get_block(0, 320, 0),
+        Err(WorldError::BlockOutOfBounds { x: 0, y: 320, z: 0 })
+    ));
+    assert!(column.set_block(0, -65, 0, STONE).is_err());
+    assert!(column.set_block(16, 0, 0, STONE).is_err());
+    assert!(column.get_block(0, 0, -1).is_err());
+    assert!(column.fill_layer(400, STONE).is_err());
+
+    let mut section = ChunkSection::new();
+    assert!(section.set_block(0, 16, 0, STONE).is_err());
+}
+
+#[test]
+fn test_world_uses_signed_coordinates() {
+    install_blocks();
+    let world = World::new(WorldHeight::OVERWORLD, flat());
+    world.set_block(-1, -30, -17, STONE).unwrap();
+    assert_eq!(world.get_block(-1, -30, -17).unwrap(), STONE);
+    assert_eq!(world.get_chunk(-1, -2).unwrap().read().unwrap().get_block(15, -30, 15).unwrap(), STONE);
+    // The generated terrain starts at the bottom of the world.
+    assert_eq!(world.get_block(5, -64, 5).unwrap(), STONE);
+    assert_eq!(world.get_block(5, -60, 5).unwrap().to_string(), "minecraft:grass_block[snowy=false]");
+    assert_eq!(world.highest_block(5, 5).unwrap(), Some(-60));
+}
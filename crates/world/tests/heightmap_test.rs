@@ -0,0 +1,96 @@
+use iron_oxide_world::block::{block_flags, BlockState};
+use iron_oxide_world::chunk::{ChunkColumn, ChunkSection};
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::heightmap::{Heightmap, HeightmapKind};
+
+mod common;
+use common::{block, install_blocks};
+
+fn heights(column: &ChunkColumn, x: usize, z: usize) -> (u16, u16) {
+    let surface = column.heightmap(HeightmapKind::WorldSurface).unwrap();
+    let motion = column.heightmap(HeightmapKind::MotionBlocking).unwrap();
+    (surface.get(x, z), motion.get(x, z))
+}
+
+#[test]
+fn test_motion_blocking_flags() {
+    install_blocks();
+    assert!(block_flags(block("stone")).motion_blocking);
+    assert!(block_flags(block("grass_block")).motion_blocking);
+    assert!(block_flags(block("oak_stairs")).motion_blocking);
+    assert!(block_flags(block("water")).motion_blocking);
+    assert!(!block_flags(block("wheat")).motion_blocking);
+    assert!(!block_flags(BlockState::AIR).motion_blocking);
+    assert!(!block_flags(block("cave_air")).motion_blocking);
+}
+
+#[test]
+fn test_heightmaps_follow_set_block() {
+    install_blocks();
+    let mut column = ChunkColumn::new(WorldHeight::OVERWORLD);
+    assert_eq!(heights(&column, 3, 4), (0, 0));
+    assert!(column.heightmap(HeightmapKind::OceanFloor).is_none());
+
+    column.set_block(3, 10, 4, block("stone")).unwrap();
+    column.set_block(3, 11, 4, block("wheat")).unwrap();
+    assert_eq!(heights(&column, 3, 4), (76, 75));
+    column.set_block(3, 20, 4, block("water")).unwrap();
+    assert_eq!(heights(&column, 3, 4), (85, 85));
+    assert_eq!(heights(&column, 4, 4), (0, 0));
+
+    // Removing the highest block scans down for the next one.
+    column.set_block(3, 20, 4, BlockState::AIR).unwrap();
+    assert_eq!(heights(&column, 3, 4), (76, 75));
+    column.set_block(3, 11, 4, block("cave_air")).unwrap();
+    assert_eq!(heights(&column, 3, 4), (75, 75));
+    column.set_block(3, 10, 4, block("wheat")).unwrap();
+    assert_eq!(heights(&column, 3, 4), (75, 0));
+    assert_eq!(column.highest_block(3, 4).unwrap(), Some(10));
+}
+
+#[test]
+fn test_heightmaps_follow_layers_and_sections() {
+    install_blocks();
+    let mut column = ChunkColumn::new(WorldHeight::OVERWORLD);
+    column.fill_layer(-64, block("bedrock")).unwrap();
+    column.set_block(0, 100, 0, block("stone")).unwrap();
+    assert_eq!(heights(&column, 15, 15), (1, 1));
+
+    let mut section = ChunkSection::new();
+    section.set_block(15, 3, 15, block("sand")).unwrap();
+    column.set_section(4, section);
+    assert_eq!(heights(&column, 15, 15), (68, 68));
+    assert_eq!(heights(&column, 0, 0), (165, 165));
+
+    column.set_section(4, ChunkSection::new());
+    assert_eq!(heights(&column, 15, 15), (1, 1));
+    assert_eq!(heights(&column, 0, 0), (165, 165));
+}
+
+#[test]
+fn test_columns_built_from_sections_have_heightmaps() {
+    install_blocks();
+    let mut section = ChunkSection::new();
+    section.fill_layer(15, block("dirt")).unwrap();
+    section.set_block(2, 14, 2, block("stone")).unwrap();
+    let column = ChunkColumn::with_sections(WorldHeight::new(0, 64).unwrap(), vec![ChunkSection::new(), section]);
+    assert_eq!(heights(&column, 2, 2), (32, 32));
+    assert_eq!(column.highest_block(2, 2).unwrap(), Some(31));
+}
+
+#[test]
+fn test_heightmap_packing() {
+    let mut heightmap = Heightmap::new();
+    heightmap.set(0, 0, 384);
+    heightmap.set(6, 0, 1);
+    heightmap.set(7, 0, 2);
+    heightmap.set(15, 15, 75);
+
+    // 384 blocks need 9 bits per height, so 7 heights fit in a long.
+    let packed = heightmap.pack(384);
+    assert_eq!(packed.len(), 37);
+    assert_eq!(packed[0], 384 | 1 << 54);
+    assert_eq!(packed[1], 2);
+    assert_eq!(Heightmap::unpack(&packed, 384), Some(heightmap));
+    assert_eq!(Heightmap::unpack(&packed[..36], 384), None);
+}
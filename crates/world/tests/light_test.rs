@@ -0,0 +1,133 @@
+use iron_oxide_world::block::{light_properties, BlockState};
+use iron_oxide_world::chunk::ChunkColumn;
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::light::{light_column, LightArray, LightKind, LIGHT_ARRAY_SIZE};
+
+mod common;
+use common::{block, flat_world, install_blocks};
+
+#[test]
+fn test_light_array_nibbles() {
+    let mut array = LightArray::Uniform(15);
+    array.set(7, 15);
+    assert_eq!(array, LightArray::Uniform(15));
+    array.set(1, 3);
+    assert_eq!(array.get(0), 15);
+    assert_eq!(array.get(1), 3);
+    let bytes = array.to_bytes();
+    assert_eq!(bytes.len(), LIGHT_ARRAY_SIZE);
+    assert_eq!(bytes[0], 0x3F);
+    assert!(!array.is_dark());
+    assert!(LightArray::Uniform(0).is_dark());
+}
+
+#[test]
+fn test_light_properties() {
+    install_blocks();
+    assert_eq!(light_properties(block("glowstone")).emission, 15);
+    assert_eq!(light_properties(block("glowstone")).opacity, 15);
+    assert_eq!(light_properties(block("stone")).opacity, 15);
+    assert_eq!(light_properties(block("grass_block")).opacity, 15);
+    assert_eq!(light_properties(block("water")).opacity, 1);
+    assert_eq!(light_properties(block("oak_stairs")).opacity, 0);
+    assert_eq!(light_properties(block("oak_stairs[waterlogged=true]")).opacity, 1);
+    assert_eq!(light_properties(BlockState::AIR).opacity, 0);
+}
+
+#[test]
+fn test_column_sky_light() {
+    install_blocks();
+    let mut column = ChunkColumn::new(WorldHeight::new(0, 64).unwrap());
+    column.fill_layer(0, block("stone")).unwrap();
+    // A roof over the west half and a pool in the east half.
+    for z in 0..16 {
+        for x in 0..8 {
+            column.set_block(x, 10, z, block("stone")).unwrap();
+        }
+        for x in 9..16 {
+            for y in 1..=5 {
+                column.set_block(x, y, z, block("water")).unwrap();
+            }
+        }
+    }
+    light_column(&mut column);
+    assert!(column.is_lit());
+
+    let sky = |x, y, z| column.light_level(LightKind::Sky, x, y, z).unwrap();
+    assert_eq!(sky(8, 5, 3), 15);
+    assert_eq!(sky(7, 5, 3), 14);
+    assert_eq!(sky(0, 5, 3), 7);
+    assert_eq!(sky(3, 0, 3), 0);
+    assert_eq!(sky(3, 11, 3), 15);
+    // Water dims sky light by one per block.
+    assert_eq!(sky(15, 5, 3), 14);
+    assert_eq!(sky(15, 1, 3), 10);
+    // Above the world is full sky light, below it is dark.
+    assert_eq!(sky(3, 70, 3), 15);
+    assert_eq!(sky(3, -5, 3), 0);
+    assert_eq!(column.light_level(LightKind::Block, 8, 5, 3).unwrap(), 0);
+}
+
+#[test]
+fn test_block_light_follows_set_block() {
+    install_blocks();
+    let world = flat_world();
+    world.get_chunk(-1, 0).unwrap();
+    world.set_block(1, -50, 1, block("glowstone")).unwrap();
+
+    let light = |x, y, z| world.light_level(LightKind::Block, x, y, z).unwrap();
+    assert_eq!(light(1, -50, 1), 15);
+    assert_eq!(light(1, -52, 1), 13);
+    // Into the neighboring chunk, around the corner.
+    assert_eq!(light(-3, -50, 2), 10);
+    // Opaque blocks stop it.
+    assert_eq!(light(1, -60, 1), 0);
+    assert_eq!(light(1, -59, 1), 6);
+
+    world.set_block(1, -50, 1, BlockState::AIR).unwrap();
+    assert_eq!(light(1, -50, 1), 0);
+    assert_eq!(light(-3, -50, 2), 0);
+}
+
+#[test]
+fn test_sky_light_follows_set_block() {
+    install_blocks();
+    let world = flat_world();
+    let sky = |x, y, z| world.light_level(LightKind::Sky, x, y, z).unwrap();
+    assert_eq!(sky(5, -59, 5), 15);
+    assert_eq!(sky(5, -60, 5), 0);
+
+    world.set_block(5, -56, 5, block("stone")).unwrap();
+    assert_eq!(sky(5, -57, 5), 14);
+    assert_eq!(sky(5, -59, 5), 14);
+
+    // Walled in, the block below gets no light at all.
+    for (x, z) in [(4, 5), (6, 5), (5, 4), (5, 6)] {
+        for y in -59..=-57 {
+            world.set_block(x, y, z, block("stone")).unwrap();
+        }
+    }
+    assert_eq!(sky(5, -58, 5), 0);
+
+    world.set_block(5, -56, 5, BlockState::AIR).unwrap();
+    assert_eq!(sky(5, -58, 5), 15);
+    assert_eq!(sky(5, -59, 5), 15);
+}
+
+#[test]
+fn test_light_spreads_into_loaded_chunks() {
+    install_blocks();
+    let world = flat_world();
+    world.set_block(15, -50, 8, block("glowstone")).unwrap();
+    assert!(!world.is_ready(0, 0));
+
+    for z in -1..=1 {
+        for x in -1..=1 {
+            world.get_chunk(x, z).unwrap();
+        }
+    }
+    assert!(world.is_ready(0, 0));
+    assert!(!world.is_ready(1, 0));
+    assert_eq!(world.light_level(LightKind::Block, 17, -50, 8).unwrap(), 13);
+    assert_eq!(world.light_level(LightKind::Block, 16, -50, 16).unwrap(), 6);
+}
@@ -0,0 +1,125 @@
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::chunk::ChunkColumn;
+use iron_oxide_world::error::{Result, WorldError};
+use iron_oxide_world::generator::{ChunkGenerator, FlatGenerator};
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::world::World;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+mod common;
+use common::{flat, install_blocks};
+
+/// Counts the chunks it generates, taking long enough that requests overlap.
+struct SlowGenerator {
+    inner: FlatGenerator,
+    generated: Arc<AtomicUsize>,
+}
+
+impl ChunkGenerator for SlowGenerator {
+    fn generate(&self, chunk_x: i32, chunk_z: i32, column: &mut ChunkColumn) -> Result<()> {
+        thread::sleep(Duration::from_millis(50));
+        self.generated.fetch_add(1, Ordering::SeqCst);
+        self.inner.generate(chunk_x, chunk_z, column)
+    }
+}
+
+struct FailingGenerator;
+
+impl ChunkGenerator for FailingGenerator {
+    fn generate(&self, chunk_x: i32, chunk_z: i32, _column: &mut ChunkColumn) -> Result<()> {
+        if chunk_x < 0 {
+            panic!("no terrain west of spawn");
+        }
+        Err(WorldError::InvalidChunk { x: chunk_x, z: chunk_z, message: "no terrain".to_string() })
+    }
+}
+
+fn slow_world() -> (World, Arc<AtomicUsize>) {
+    let generated = Arc::new(AtomicUsize::new(0));
+    let generator = SlowGenerator {
+        inner: *flat(),
+        generated: Arc::clone(&generated),
+    };
+    (World::new(WorldHeight::OVERWORLD, Box::new(generator)), generated)
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Polls the future on this thread until it completes.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+#[test]
+fn test_concurrent_requests_share_one_load() {
+    install_blocks();
+    let (world, generated) = slow_world();
+    let world = Arc::new(world);
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let world = Arc::clone(&world);
+            thread::spawn(move || world.get_chunk(3, -4).unwrap())
+        })
+        .collect();
+    let chunks: Vec<_> = threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+
+    assert_eq!(generated.load(Ordering::SeqCst), 1);
+    assert!(chunks.iter().all(|chunk| Arc::ptr_eq(chunk, &chunks[0])));
+    assert!(Arc::ptr_eq(&world.loaded_chunk(3, -4).unwrap(), &chunks[0]));
+    assert_eq!(world.pending_chunks(), 0);
+}
+
+#[test]
+fn test_chunk_handle_future() {
+    install_blocks();
+    let (world, generated) = slow_world();
+    let world = world.with_worker_threads(2);
+    assert_eq!(world.worker_threads(), 2);
+
+    let handles: Vec<_> = (0..4).map(|x| world.load_chunk(x, 0)).collect();
+    assert_eq!(handles[2].position(), (2, 0));
+    for handle in handles {
+        let chunk = block_on(handle).unwrap();
+        assert_eq!(chunk.read().unwrap().get_block(0, -60, 0).unwrap(), BlockState::parse("grass_block").unwrap());
+    }
+    assert_eq!(generated.load(Ordering::SeqCst), 4);
+    assert!(world.is_loaded(3, 0));
+
+    // Loaded chunks resolve without going through a worker.
+    let handle = world.load_chunk(1, 0);
+    assert!(handle.is_done());
+    block_on(handle).unwrap();
+    assert_eq!(generated.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn test_failed_loads_are_reported() {
+    install_blocks();
+    let world = World::new(WorldHeight::OVERWORLD, Box::new(FailingGenerator));
+    match world.get_chunk(2, 5) {
+        Err(WorldError::ChunkLoad { x: 2, z: 5, message }) => assert!(message.contains("no terrain")),
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+    assert!(matches!(block_on(world.load_chunk(-1, 0)), Err(WorldError::ChunkLoad { x: -1, z: 0, .. })));
+    assert!(!world.is_loaded(2, 5));
+    assert_eq!(world.pending_chunks(), 0);
+}
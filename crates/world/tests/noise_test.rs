@@ -0,0 +1,113 @@
+use iron_oxide_world::biome::{install_biome_registry, BiomeRegistry};
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::chunk::ChunkColumn;
+use iron_oxide_world::generator::{ChunkGenerator, NoiseGenerator, SEA_LEVEL};
+use iron_oxide_world::height::WorldHeight;
+
+mod common;
+use common::install_blocks;
+
+const BIOMES: [&str; 12] = [
+    "ocean",
+    "frozen_ocean",
+    "warm_ocean",
+    "beach",
+    "snowy_beach",
+    "plains",
+    "forest",
+    "desert",
+    "savanna",
+    "snowy_plains",
+    "windswept_hills",
+    "snowy_slopes",
+];
+
+fn install_registries() {
+    install_blocks();
+    install_biome_registry(BiomeRegistry::new(
+        BIOMES.iter().map(|name| format!("minecraft:{}", name)).collect(),
+    ));
+}
+
+fn generate(generator: &NoiseGenerator, chunk_x: i32, chunk_z: i32) -> ChunkColumn {
+    let mut column = ChunkColumn::new(WorldHeight::OVERWORLD);
+    generator.generate(chunk_x, chunk_z, &mut column).unwrap();
+    column
+}
+
+fn biome(column: &ChunkColumn, y: i32) -> &'static str {
+    let section = (y + 64) as usize / 16;
+    BIOMES[column.sections()[section].get_biome(0, 0, 0).unwrap() as usize]
+}
+
+#[test]
+fn test_noise_is_deterministic_per_seed() {
+    install_registries();
+    let first = generate(&NoiseGenerator::new(42).unwrap(), 3, -7);
+    let second = generate(&NoiseGenerator::new(42).unwrap(), 3, -7);
+    let other_seed = generate(&NoiseGenerator::new(43).unwrap(), 3, -7);
+
+    let blocks = |column: &ChunkColumn| {
+        let mut blocks = Vec::new();
+        for y in -64..320 {
+            for (x, z) in [(0, 0), (5, 11), (15, 15)] {
+                blocks.push(column.get_block(x, y, z).unwrap());
+            }
+        }
+        blocks
+    };
+    assert!(blocks(&first) == blocks(&second));
+    assert!(blocks(&first) != blocks(&other_seed));
+}
+
+#[test]
+fn test_noise_land_column() {
+    install_registries();
+    let column = generate(&NoiseGenerator::new(42).unwrap(), 0, 0);
+    assert_eq!(column.highest_block(0, 0).unwrap(), Some(115));
+    assert!(column.get_block(0, 115, 0).unwrap().is("grass_block"));
+    assert!(column.get_block(0, 114, 0).unwrap().is("dirt"));
+    assert!(column.get_block(0, 100, 0).unwrap().is("stone"));
+    assert!(column.get_block(0, -64, 0).unwrap().is("bedrock"));
+    assert_eq!(biome(&column, 115), "windswept_hills");
+    // Every block of the column is generated in a section that counts it.
+    assert!(column.sections()[0].block_count() > 0);
+    assert!(column.is_dirty());
+}
+
+#[test]
+fn test_noise_ocean_column() {
+    install_registries();
+    let column = generate(&NoiseGenerator::new(42).unwrap(), -184, 0);
+    assert_eq!(biome(&column, SEA_LEVEL), "ocean");
+    assert_eq!(column.highest_block(0, 0).unwrap(), Some(SEA_LEVEL));
+    let floor = (-64..SEA_LEVEL)
+        .rev()
+        .find(|&y| !column.get_block(0, y, 0).unwrap().is("water"))
+        .unwrap();
+    assert!(floor < SEA_LEVEL - 3);
+    let water = BlockState::default_of("water").unwrap();
+    assert_eq!(column.get_block(0, floor + 1, 0).unwrap(), water);
+    let floor_block = column.get_block(0, floor, 0).unwrap();
+    assert!(floor_block.is("sand") || floor_block.is("gravel"), "{}", floor_block);
+    assert_eq!(column.get_block(0, SEA_LEVEL + 1, 0).unwrap(), BlockState::AIR);
+}
+
+#[test]
+fn test_noise_carves_caves() {
+    install_registries();
+    let generator = NoiseGenerator::new(42).unwrap();
+    let mut cave_air = 0;
+    for chunk_x in 0..4 {
+        let column = generate(&generator, chunk_x, 0);
+        for y in -58..0 {
+            if column.get_block(8, y, 8).unwrap() == BlockState::AIR {
+                cave_air += 1;
+            }
+        }
+        for y in -64..-59 {
+            assert!(column.get_block(8, y, 8).unwrap() != BlockState::AIR);
+        }
+    }
+    assert!(cave_air > 0);
+}
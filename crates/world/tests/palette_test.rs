@@ -0,0 +1,146 @@
+use iron_oxide_world::chunk::{BIOME_VOLUME, SECTION_VOLUME};
+use iron_oxide_world::palette::{Palette, PaletteType, PalettedContainer};
+
+fn blocks() -> PalettedContainer {
+    PalettedContainer::new(PaletteType::Block, SECTION_VOLUME)
+}
+
+#[test]
+fn test_second_value_switches_to_indirect() {
+    let mut container = blocks();
+    assert_eq!(container.set(10, 0), 0);
+    assert!(matches!(container.palette(), Palette::Single(0)));
+
+    assert_eq!(container.set(10, 5), 0);
+    assert_eq!(container.bits_per_entry(), 4);
+    assert!(matches!(container.palette(), Palette::Indirect(palette) if *palette == vec![0, 5]));
+    assert_eq!(container.storage().len(), SECTION_VOLUME / 16);
+    assert_eq!(container.get(10), 5);
+    assert_eq!(container.get(11), 0);
+    assert_eq!(container.set(10, 6), 5);
+}
+
+#[test]
+fn test_palette_overflow_grows_entries() {
+    let mut container = blocks();
+    for value in 0..17 {
+        container.set(value as usize * 7, value);
+    }
+    assert_eq!(container.bits_per_entry(), 5);
+    for value in 0..200 {
+        container.set(value as usize * 7, value);
+    }
+    assert_eq!(container.bits_per_entry(), 8);
+    for value in 0..200 {
+        assert_eq!(container.get(value as usize * 7), value);
+    }
+    assert_eq!(container.get(1), 0);
+}
+
+#[test]
+fn test_too_many_values_switch_to_direct() {
+    let mut container = blocks();
+    for value in 0..300 {
+        container.set(value as usize, value + 1000);
+    }
+    assert_eq!(container.bits_per_entry(), 15);
+    assert!(matches!(container.palette(), Palette::Direct));
+    for value in 0..300 {
+        assert_eq!(container.get(value as usize), value + 1000);
+    }
+    assert_eq!(container.get(300), 0);
+}
+
+#[test]
+fn test_unused_entries_are_reused_before_growing() {
+    let mut container = blocks();
+    for value in 1..=15 {
+        container.set(0, value);
+    }
+    assert_eq!(container.bits_per_entry(), 4);
+    container.set(0, 16);
+    assert_eq!(container.bits_per_entry(), 4);
+    assert_eq!(container.get(0), 16);
+}
+
+#[test]
+fn test_compact() {
+    let mut container = blocks();
+    for value in 0..300 {
+        container.set(value as usize, value + 1);
+    }
+    for index in 0..300 {
+        container.set(index, if index < 3 { 9 } else { 0 });
+    }
+    container.compact();
+    assert_eq!(container.bits_per_entry(), 4);
+    assert!(matches!(container.palette(), Palette::Indirect(palette) if palette.len() == 2));
+    assert_eq!(container.get(2), 9);
+    assert_eq!(container.get(3), 0);
+
+    for index in 0..3 {
+        container.set(index, 0);
+    }
+    container.compact();
+    assert!(matches!(container.palette(), Palette::Single(0)));
+    assert!(container.storage().is_empty());
+}
+
+#[test]
+fn test_fill() {
+    let mut container = blocks();
+    container.set(3, 4);
+    container.fill(7);
+    assert!(matches!(container.palette(), Palette::Single(7)));
+    assert_eq!(container.get(3), 7);
+
+    container.fill_range(256..512, 2);
+    assert_eq!(container.get(255), 7);
+    assert_eq!(container.get(256), 2);
+    assert_eq!(container.get(511), 2);
+    assert_eq!(container.get(512), 7);
+
+    container.fill_range(0..SECTION_VOLUME, 1);
+    assert!(matches!(container.palette(), Palette::Single(1)));
+}
+
+#[test]
+fn test_biome_bits() {
+    let mut container = PalettedContainer::new(PaletteType::Biome, BIOME_VOLUME);
+    container.set(1, 1);
+    assert_eq!(container.bits_per_entry(), 1);
+    container.set(2, 2);
+    assert_eq!(container.bits_per_entry(), 2);
+    for value in 3..8 {
+        container.set(value as usize, value);
+    }
+    // Air and seven more biomes fill the 3 bit palette.
+    assert_eq!(container.bits_per_entry(), 3);
+    container.set(8, 8);
+    assert_eq!(container.bits_per_entry(), 6);
+    assert!(matches!(container.palette(), Palette::Direct));
+    for value in 1..9 {
+        assert_eq!(container.get(value as usize), value);
+    }
+}
+
+#[test]
+fn test_from_values() {
+    let single = PalettedContainer::from_values(PaletteType::Block, &[7; SECTION_VOLUME]);
+    assert!(matches!(single.palette(), Palette::Single(7)));
+
+    let mut values = vec![0; SECTION_VOLUME];
+    values[100] = 3;
+    values[200] = 4;
+    let indirect = PalettedContainer::from_values(PaletteType::Block, &values);
+    assert_eq!(indirect.bits_per_entry(), 4);
+    assert_eq!(indirect.get(100), 3);
+    assert_eq!(indirect.get(200), 4);
+    assert_eq!(indirect.get(0), 0);
+
+    let values: Vec<u32> = (0..SECTION_VOLUME as u32).map(|value| value % 300).collect();
+    let direct = PalettedContainer::from_values(PaletteType::Block, &values);
+    assert!(matches!(direct.palette(), Palette::Direct));
+    assert_eq!(direct.get(299), 299);
+    assert_eq!(direct.get(300), 0);
+}
@@ -0,0 +1,133 @@
+use iron_oxide_world::behavior::{BlockBehaviors, BlockHit, PlaceContext};
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::pos::{BlockPos, Direction};
+use iron_oxide_world::ticket::Ticket;
+use iron_oxide_world::view::ChunkPos;
+use iron_oxide_world::world::World;
+use std::sync::Arc;
+
+mod common;
+use common::{block, flat_world, vanilla_world};
+
+/// A quarter of the blocks of a section tick every tick, so that tests don't wait long.
+const RANDOM_TICK_SPEED: u32 = 1024;
+
+/// The vanilla world with the blocks of chunk 0, 0 ticking at random.
+fn ticking_world() -> (World, Arc<BlockBehaviors>) {
+    let (world, behaviors) = vanilla_world();
+    let world = world.with_random_tick_speed(RANDOM_TICK_SPEED);
+    world.add_ticket(ChunkPos::new(0, 0), Ticket::player(1));
+    world.add_ticket(ChunkPos::new(0, 0), Ticket::simulation(0));
+    (world, behaviors)
+}
+
+fn tick(world: &World, ticks: usize) {
+    for _ in 0..ticks {
+        world.tick().unwrap();
+    }
+}
+
+fn get(world: &World, x: i32, y: i32, z: i32) -> BlockState {
+    world.get_block(x, y, z).unwrap()
+}
+
+fn set(world: &World, x: i32, y: i32, z: i32, name: &str) {
+    world.set_block(x, y, z, block(name)).unwrap();
+}
+
+#[test]
+fn test_simulation_tickets() {
+    let world = flat_world();
+    let ticket = Ticket::simulation(2);
+    world.add_ticket(ChunkPos::new(0, 0), ticket);
+    world.tick().unwrap();
+
+    // Simulation tickets don't load chunks, they only tick the loaded ones.
+    assert!(!world.is_loaded(0, 0));
+    assert!(!world.is_ticking(ChunkPos::new(0, 0)));
+    world.get_chunk(0, 0).unwrap();
+    world.get_chunk(2, -2).unwrap();
+    world.get_chunk(3, 0).unwrap();
+    assert!(world.is_ticking(ChunkPos::new(0, 0)));
+    assert!(world.is_ticking(ChunkPos::new(2, -2)));
+    assert!(!world.is_ticking(ChunkPos::new(3, 0)));
+
+    assert!(world.remove_ticket(ChunkPos::new(0, 0), ticket));
+    assert!(!world.is_ticking(ChunkPos::new(0, 0)));
+    assert_eq!(world.chunk_metrics().tickets, 0);
+}
+
+#[test]
+fn test_crops_grow() {
+    let (world, _) = ticking_world();
+    set(&world, 3, -60, 3, "water");
+    set(&world, 4, -60, 4, "farmland");
+    set(&world, 4, -59, 4, "wheat");
+    set(&world, 12, -60, 12, "farmland[moisture=1]");
+    tick(&world, 600);
+
+    assert_eq!(get(&world, 4, -59, 4), block("wheat[age=7]"));
+    assert_eq!(get(&world, 4, -60, 4), block("farmland[moisture=7]"));
+    // Farmland away from water dries out, and turns into dirt with nothing planted on it, which
+    // the grass around may have spread to since.
+    assert!(!get(&world, 12, -60, 12).is("farmland"));
+
+    // Crops pop off without farmland under them.
+    set(&world, 4, -60, 4, "dirt");
+    assert!(get(&world, 4, -59, 4).is_air());
+}
+
+#[test]
+fn test_grass_spreads_and_dies() {
+    let (world, _) = ticking_world();
+    set(&world, 5, -60, 5, "dirt");
+    set(&world, 8, -60, 8, "dirt");
+    set(&world, 8, -59, 8, "stone");
+    set(&world, 10, -59, 10, "stone");
+    tick(&world, 200);
+
+    assert_eq!(get(&world, 5, -60, 5), block("grass_block"));
+    // Grass doesn't spread to dirt or stay under blocks that let no light through.
+    assert_eq!(get(&world, 8, -60, 8), block("dirt"));
+    assert_eq!(get(&world, 10, -60, 10), block("dirt"));
+}
+
+#[test]
+fn test_leaves_decay() {
+    let (world, behaviors) = ticking_world();
+    set(&world, 4, -59, 4, "oak_log");
+    set(&world, 4, -58, 4, "oak_leaves");
+    set(&world, 4, -57, 4, "oak_leaves");
+    let hit = BlockHit { pos: BlockPos::new(8, -60, 8), face: Direction::Up, cursor: (0.5, 1.0, 0.5) };
+    let context = PlaceContext { hit, yaw: 0.0, pitch: 0.0 };
+    behaviors.place(&world, &context, block("oak_leaves")).unwrap();
+    tick(&world, 100);
+
+    assert_eq!(get(&world, 4, -58, 4), block("oak_leaves[distance=1]"));
+    assert_eq!(get(&world, 4, -57, 4), block("oak_leaves[distance=2]"));
+    assert_eq!(get(&world, 8, -59, 8), block("oak_leaves[persistent=true]"));
+
+    // Without the log the leaves decay, but not the ones a player placed.
+    set(&world, 4, -59, 4, "air");
+    tick(&world, 100);
+    assert!(get(&world, 4, -58, 4).is_air());
+    assert!(get(&world, 4, -57, 4).is_air());
+    assert_eq!(get(&world, 8, -59, 8), block("oak_leaves[persistent=true]"));
+}
+
+#[test]
+fn test_ice_and_snow_melt() {
+    let (world, _) = ticking_world();
+    set(&world, 4, -59, 4, "glowstone");
+    set(&world, 5, -59, 4, "ice");
+    set(&world, 4, -58, 4, "snow");
+    set(&world, 12, -59, 12, "ice");
+    set(&world, 12, -59, 10, "snow");
+    tick(&world, 100);
+
+    assert_eq!(get(&world, 5, -59, 4), block("water"));
+    assert!(get(&world, 4, -58, 4).is_air());
+    // Sunlight doesn't melt them.
+    assert_eq!(get(&world, 12, -59, 12), block("ice"));
+    assert_eq!(get(&world, 12, -59, 10), block("snow"));
+}
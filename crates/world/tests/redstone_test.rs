@@ -0,0 +1,424 @@
+use iron_oxide_world::behavior::{BlockBehaviors, BlockHit, PlaceContext};
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::pos::{BlockPos, Direction};
+use iron_oxide_world::ticket::Ticket;
+use iron_oxide_world::view::ChunkPos;
+use iron_oxide_world::world::World;
+use std::sync::Arc;
+
+mod common;
+use common::{block, vanilla_world};
+
+/// The y of the blocks on the grass.
+const Y: i32 = -59;
+
+/// The vanilla world with a ticket keeping the chunks around the origin loaded as it ticks.
+fn test_world() -> (World, Arc<BlockBehaviors>) {
+    let (world, behaviors) = vanilla_world();
+    world.add_ticket(ChunkPos::new(0, 0), Ticket::player(1));
+    (world, behaviors)
+}
+
+/// Sets the block on the grass at the column.
+fn set(world: &World, x: i32, z: i32, name: &str) {
+    world.set_block(x, Y, z, block(name)).unwrap();
+}
+
+/// Places redstone wire on the grass at each column, the way a player does.
+fn wire(world: &World, behaviors: &BlockBehaviors, columns: &[(i32, i32)]) {
+    for &(x, z) in columns {
+        let hit = BlockHit { pos: BlockPos::new(x, Y - 1, z), face: Direction::Up, cursor: (0.5, 1.0, 0.5) };
+        let context = PlaceContext { hit, yaw: 0.0, pitch: 0.0 };
+        assert!(behaviors.place(world, &context, block("redstone_wire")).unwrap().is_some());
+    }
+}
+
+fn use_block(behaviors: &BlockBehaviors, world: &World, x: i32, z: i32) {
+    let hit = BlockHit { pos: BlockPos::new(x, Y, z), face: Direction::Up, cursor: (0.5, 0.5, 0.5) };
+    behaviors.use_block(world, &hit).unwrap();
+}
+
+fn tick(world: &World, ticks: usize) {
+    for _ in 0..ticks {
+        world.tick().unwrap();
+    }
+}
+
+fn get(world: &World, x: i32, z: i32) -> BlockState {
+    world.get_block(x, Y, z).unwrap()
+}
+
+fn power(world: &World, x: i32, z: i32) -> u8 {
+    get(world, x, z).get("power").unwrap().parse().unwrap()
+}
+
+fn is(world: &World, x: i32, z: i32, property: &str) -> bool {
+    get(world, x, z).get(property) == Some("true")
+}
+
+#[test]
+fn test_wire_decays() {
+    let (world, behaviors) = test_world();
+    let columns: Vec<_> = (1..=16).map(|x| (x, 0)).collect();
+    wire(&world, &behaviors, &columns);
+    set(&world, 0, 0, "redstone_block");
+    assert_eq!(power(&world, 1, 0), 15);
+    assert_eq!(power(&world, 8, 0), 8);
+    assert_eq!(power(&world, 15, 0), 1);
+    assert_eq!(power(&world, 16, 0), 0);
+
+    set(&world, 0, 0, "air");
+    assert!((1..=16).all(|x| power(&world, x, 0) == 0));
+}
+
+#[test]
+fn test_wire_connections() {
+    let (world, behaviors) = test_world();
+    wire(&world, &behaviors, &[(0, 0)]);
+    let cross = get(&world, 0, 0);
+    assert!(Direction::HORIZONTAL.iter().all(|direction| cross.get(direction.name()) == Some("side")));
+
+    wire(&world, &behaviors, &[(1, 0), (2, 0)]);
+    let line = get(&world, 1, 0);
+    assert_eq!((line.get("east"), line.get("west")), (Some("side"), Some("side")));
+    assert_eq!((line.get("north"), line.get("south")), (Some("none"), Some("none")));
+    assert_eq!(get(&world, 0, 0).get("east"), Some("side"));
+
+    // Wire climbs onto the block beside it.
+    set(&world, 3, 0, "stone");
+    let hit = BlockHit { pos: BlockPos::new(3, Y, 0), face: Direction::Up, cursor: (0.5, 1.0, 0.5) };
+    behaviors.place(&world, &PlaceContext { hit, yaw: 0.0, pitch: 0.0 }, block("redstone_wire")).unwrap();
+    assert_eq!(get(&world, 2, 0).get("east"), Some("up"));
+    set(&world, -1, 0, "redstone_block");
+    assert_eq!(power(&world, 2, 0), 13);
+    assert_eq!(world.get_block(3, Y + 1, 0).unwrap().get("power"), Some("12"));
+
+    // A lone wire switches to a dot and back.
+    wire(&world, &behaviors, &[(0, 5)]);
+    use_block(&behaviors, &world, 0, 5);
+    assert!(Direction::HORIZONTAL.iter().all(|direction| get(&world, 0, 5).get(direction.name()) == Some("none")));
+    use_block(&behaviors, &world, 0, 5);
+    assert_eq!(get(&world, 0, 5).get("north"), Some("side"));
+}
+
+#[test]
+fn test_lamp() {
+    let (world, behaviors) = test_world();
+    set(&world, 0, 0, "redstone_lamp");
+    wire(&world, &behaviors, &[(1, 0), (2, 0)]);
+    set(&world, 3, 0, "redstone_block");
+    assert!(is(&world, 0, 0, "lit"));
+
+    set(&world, 3, 0, "air");
+    tick(&world, 3);
+    assert!(is(&world, 0, 0, "lit"));
+    tick(&world, 1);
+    assert!(!is(&world, 0, 0, "lit"));
+}
+
+#[test]
+fn test_repeater_delay() {
+    let (world, _) = test_world();
+    set(&world, 1, 0, "repeater[facing=west,delay=2]");
+    set(&world, 2, 0, "redstone_lamp");
+    set(&world, 0, 0, "redstone_block");
+    tick(&world, 3);
+    assert!(!is(&world, 1, 0, "powered"));
+    tick(&world, 1);
+    assert!(is(&world, 1, 0, "powered"));
+    assert!(is(&world, 2, 0, "lit"));
+
+    set(&world, 0, 0, "air");
+    tick(&world, 3);
+    assert!(is(&world, 1, 0, "powered"));
+    tick(&world, 1);
+    assert!(!is(&world, 1, 0, "powered"));
+}
+
+#[test]
+fn test_repeater_lock() {
+    let (world, _) = test_world();
+    set(&world, 1, 0, "repeater[facing=west]");
+    set(&world, 1, 1, "repeater[facing=south]");
+    set(&world, 1, 2, "redstone_block");
+    tick(&world, 2);
+    assert!(is(&world, 1, 1, "powered"));
+    assert!(is(&world, 1, 0, "locked"));
+
+    // The locked repeater holds its output.
+    set(&world, 0, 0, "redstone_block");
+    tick(&world, 10);
+    assert!(!is(&world, 1, 0, "powered"));
+
+    set(&world, 1, 2, "air");
+    tick(&world, 2);
+    assert!(!is(&world, 1, 0, "locked"));
+    tick(&world, 2);
+    assert!(is(&world, 1, 0, "powered"));
+}
+
+#[test]
+fn test_comparator() {
+    let (world, behaviors) = test_world();
+    set(&world, -1, 0, "redstone_block");
+    set(&world, 0, 0, "comparator[facing=west]");
+    wire(&world, &behaviors, &[(1, 0), (0, -1), (0, -2), (0, -3), (0, -4)]);
+    tick(&world, 2);
+    assert!(is(&world, 0, 0, "powered"));
+    assert_eq!(power(&world, 1, 0), 15);
+
+    // The side is weaker than the rear, so a comparator passes the rear on or subtracts the side.
+    set(&world, 0, -5, "redstone_block");
+    assert_eq!(power(&world, 0, -1), 12);
+    tick(&world, 2);
+    assert_eq!(power(&world, 1, 0), 15);
+    use_block(&behaviors, &world, 0, 0);
+    assert_eq!(get(&world, 0, 0).get("mode"), Some("subtract"));
+    tick(&world, 2);
+    assert_eq!(power(&world, 1, 0), 3);
+
+    // A stronger side turns it off.
+    set(&world, 0, -1, "redstone_block");
+    tick(&world, 2);
+    assert!(!is(&world, 0, 0, "powered"));
+    assert_eq!(power(&world, 1, 0), 0);
+}
+
+#[test]
+fn test_torch_inverts() {
+    let (world, behaviors) = test_world();
+    set(&world, 0, 0, "stone");
+    set(&world, 1, 0, "redstone_wall_torch[facing=east]");
+    set(&world, 2, 0, "redstone_lamp");
+    assert!(is(&world, 2, 0, "lit"));
+
+    set(&world, -1, 0, "lever[face=wall,facing=west]");
+    use_block(&behaviors, &world, -1, 0);
+    tick(&world, 1);
+    assert!(is(&world, 1, 0, "lit"));
+    tick(&world, 1);
+    assert!(!is(&world, 1, 0, "lit"));
+    tick(&world, 4);
+    assert!(!is(&world, 2, 0, "lit"));
+
+    use_block(&behaviors, &world, -1, 0);
+    tick(&world, 2);
+    assert!(is(&world, 1, 0, "lit"));
+    assert!(is(&world, 2, 0, "lit"));
+
+    // The torch strongly powers the block above it, through which it lights the lamp beside.
+    world.set_block(4, Y + 1, 0, block("stone")).unwrap();
+    world.set_block(5, Y + 1, 0, block("redstone_lamp")).unwrap();
+    set(&world, 4, 0, "redstone_torch");
+    assert!(world.get_block(5, Y + 1, 0).unwrap().get("lit") == Some("true"));
+}
+
+/// A torch powering wire that loops back into the block it's on, which turns it off and on
+/// until it burns out.
+#[test]
+fn test_torch_burns_out() {
+    let (world, behaviors) = test_world();
+    set(&world, 0, 0, "stone");
+    wire(&world, &behaviors, &[(2, 0), (2, 1), (2, 2), (1, 2), (0, 2), (0, 1)]);
+    set(&world, 1, 0, "redstone_wall_torch[facing=east]");
+    let mut offs = 0;
+    let mut lit = true;
+    for _ in 0..100 {
+        tick(&world, 1);
+        let now = is(&world, 1, 0, "lit");
+        if lit && !now {
+            offs += 1;
+        }
+        lit = now;
+    }
+    assert_eq!(offs, 8);
+    assert!(!lit);
+
+    // It stays off until something updates it once it cooled down.
+    tick(&world, 160);
+    assert!(!is(&world, 1, 0, "lit"));
+    set(&world, 1, -1, "stone");
+    tick(&world, 2);
+    assert!(is(&world, 1, 0, "lit"));
+}
+
+/// The same loop slowed down by a repeater is a clock that doesn't burn the torch out.
+#[test]
+fn test_repeater_clock() {
+    let (world, behaviors) = test_world();
+    set(&world, 0, 0, "stone");
+    wire(&world, &behaviors, &[(2, 0), (2, 1), (2, 2), (0, 2), (0, 1)]);
+    set(&world, 1, 2, "repeater[facing=east,delay=4]");
+    set(&world, 1, 0, "redstone_wall_torch[facing=east]");
+    tick(&world, 40);
+
+    let mut changes = Vec::new();
+    let mut lit = is(&world, 1, 0, "lit");
+    for tick_count in 0..200 {
+        tick(&world, 1);
+        let now = is(&world, 1, 0, "lit");
+        if now != lit {
+            changes.push(tick_count);
+        }
+        lit = now;
+    }
+    assert_eq!(changes.len(), 20);
+    assert!(changes.windows(2).all(|pair| pair[1] - pair[0] == 10));
+}
+
+#[test]
+fn test_observer_clock() {
+    let (world, _) = test_world();
+    set(&world, 0, 0, "observer[facing=east,powered=false]");
+    set(&world, -1, 0, "redstone_lamp");
+    set(&world, 1, 0, "observer[facing=west,powered=false]");
+    let mut pulses = 0;
+    let mut powered = false;
+    for _ in 0..100 {
+        tick(&world, 1);
+        let now = is(&world, 0, 0, "powered");
+        if now && !powered {
+            pulses += 1;
+        }
+        powered = now;
+    }
+    assert!(pulses >= 10, "{pulses} pulses");
+    let lamp = (0..10).any(|_| {
+        tick(&world, 1);
+        is(&world, -1, 0, "lit")
+    });
+    assert!(lamp);
+}
+
+#[test]
+fn test_observer_pulse() {
+    let (world, _) = test_world();
+    set(&world, 0, 0, "observer[facing=east,powered=false]");
+    set(&world, -1, 0, "redstone_lamp");
+    set(&world, 1, 0, "stone");
+    tick(&world, 1);
+    assert!(!is(&world, 0, 0, "powered"));
+    tick(&world, 1);
+    assert!(is(&world, 0, 0, "powered"));
+    assert!(is(&world, -1, 0, "lit"));
+    tick(&world, 2);
+    assert!(!is(&world, 0, 0, "powered"));
+    tick(&world, 10);
+    assert!(!is(&world, 0, 0, "powered"));
+    assert!(!is(&world, -1, 0, "lit"));
+}
+
+#[test]
+fn test_piston_pushes() {
+    let (world, _) = test_world();
+    set(&world, 0, 0, "piston[facing=east]");
+    set(&world, 1, 0, "stone");
+    set(&world, 2, 0, "dirt");
+    set(&world, 0, -1, "redstone_block");
+    tick(&world, 1);
+    assert!(is(&world, 0, 0, "extended"));
+    assert!(get(&world, 1, 0).is("piston_head"));
+    assert!(get(&world, 2, 0).is("stone"));
+    assert!(get(&world, 3, 0).is("dirt"));
+
+    set(&world, 0, -1, "air");
+    tick(&world, 1);
+    assert!(!is(&world, 0, 0, "extended"));
+    assert!(get(&world, 1, 0).is_air());
+    assert!(get(&world, 2, 0).is("stone"));
+
+    // Breaking the head breaks the piston.
+    set(&world, 0, -1, "redstone_block");
+    tick(&world, 1);
+    set(&world, 1, 0, "air");
+    assert!(get(&world, 0, 0).is_air());
+}
+
+#[test]
+fn test_sticky_piston_pulls() {
+    let (world, behaviors) = test_world();
+    set(&world, 0, 0, "sticky_piston[facing=east]");
+    set(&world, 1, 0, "stone");
+    wire(&world, &behaviors, &[(2, 1)]);
+    set(&world, 0, -1, "redstone_block");
+    tick(&world, 1);
+    assert_eq!(get(&world, 1, 0).get("type"), Some("sticky"));
+    assert!(get(&world, 2, 0).is("stone"));
+
+    set(&world, 0, -1, "air");
+    tick(&world, 1);
+    assert!(get(&world, 1, 0).is("stone"));
+    assert!(get(&world, 2, 0).is_air());
+}
+
+#[test]
+fn test_piston_push_limit() {
+    let (world, _) = test_world();
+    set(&world, 0, 0, "piston[facing=east]");
+    for x in 1..=12 {
+        set(&world, x, 0, "stone");
+    }
+    set(&world, 13, 0, "redstone_wire");
+    set(&world, 0, -1, "redstone_block");
+    tick(&world, 1);
+    assert!(is(&world, 0, 0, "extended"));
+    assert!(get(&world, 13, 0).is("stone"));
+
+    // Thirteen blocks are too many, and obsidian doesn't move.
+    set(&world, 0, 2, "piston[facing=east]");
+    for x in 1..=13 {
+        set(&world, x, 2, "stone");
+    }
+    set(&world, 0, 4, "piston[facing=east]");
+    set(&world, 1, 4, "stone");
+    set(&world, 2, 4, "obsidian");
+    set(&world, 0, 3, "redstone_block");
+    tick(&world, 1);
+    assert!(!is(&world, 0, 2, "extended"));
+    assert!(!is(&world, 0, 4, "extended"));
+    assert!(get(&world, 1, 4).is("stone"));
+}
+
+/// A piston powered from above the block above it only notices when something updates it.
+#[test]
+fn test_piston_quasi_connectivity() {
+    let (world, _) = test_world();
+    set(&world, 0, 0, "piston[facing=east]");
+    world.set_block(0, Y + 2, 0, block("redstone_block")).unwrap();
+    tick(&world, 2);
+    assert!(!is(&world, 0, 0, "extended"));
+
+    set(&world, -1, 0, "stone");
+    tick(&world, 1);
+    assert!(is(&world, 0, 0, "extended"));
+}
+
+/// A T flip-flop from two locked repeaters: M takes the inverted output while the input is on,
+/// and S passes M on while it's off, so that S flips once for every pulse of the input.
+#[test]
+fn test_t_flip_flop() {
+    let (world, behaviors) = test_world();
+    set(&world, 0, 0, "repeater[facing=west]");
+    set(&world, 1, 0, "repeater[facing=west]");
+    set(&world, 2, 0, "stone");
+    set(&world, 3, 0, "redstone_wall_torch[facing=east]");
+    wire(&world, &behaviors, &[(3, -1), (3, -2), (2, -2), (1, -2), (0, -2), (-1, -2), (-1, -1), (-1, 0)]);
+    set(&world, 0, 1, "repeater[facing=south]");
+    set(&world, 1, 1, "repeater[facing=south]");
+    set(&world, 1, 2, "stone");
+    set(&world, 0, 2, "redstone_wall_torch[facing=west]");
+    set(&world, 2, 2, "lever[face=wall,facing=east]");
+    tick(&world, 40);
+
+    let mut output = is(&world, 1, 0, "powered");
+    for _ in 0..4 {
+        use_block(&behaviors, &world, 2, 2);
+        tick(&world, 20);
+        assert_eq!(is(&world, 1, 0, "powered"), output);
+        use_block(&behaviors, &world, 2, 2);
+        tick(&world, 20);
+        assert_eq!(is(&world, 1, 0, "powered"), !output);
+        assert_eq!(is(&world, 3, 0, "lit"), output);
+        output = !output;
+    }
+}
@@ -0,0 +1,281 @@
+use fastnbt::{ByteArray, IntArray, Value};
+use flate2::write::GzEncoder;
+use iron_oxide_world::biome::{install_biome_registry, BiomeRegistry};
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::block_entity::BlockEntity;
+use iron_oxide_world::error::WorldError;
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::pos::BlockPos;
+use iron_oxide_world::schematic::{Mirror, Rotation, Schematic, SchematicVersion, Transform};
+use iron_oxide_world::world::World;
+use std::collections::HashMap;
+use std::io::Write;
+
+mod common;
+use common::{block, flat, install_blocks};
+
+fn install_registries() {
+    install_blocks();
+    install_biome_registry(BiomeRegistry::new(vec![
+        "minecraft:plains".to_string(),
+        "minecraft:desert".to_string(),
+        "minecraft:the_void".to_string(),
+    ]));
+}
+
+fn gzip(nbt: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(nbt).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn compound(entries: Vec<(&str, Value)>) -> HashMap<String, Value> {
+    entries.into_iter().map(|(name, value)| (name.to_string(), value)).collect()
+}
+
+fn palette(entries: &[(&str, i32)]) -> Value {
+    Value::Compound(entries.iter().map(|(name, index)| (name.to_string(), Value::Int(*index))).collect())
+}
+
+/// A small build with a chest holding a custom name, and a desert column.
+fn sample() -> Schematic {
+    let mut schematic = Schematic::new(3, 2, 2);
+    schematic.set_offset(BlockPos::new(-1, 0, -1));
+    schematic.set_block(0, 0, 0, block("stone")).unwrap();
+    schematic.set_block(2, 1, 1, block("oak_stairs[facing=west]")).unwrap();
+    schematic.set_block(1, 0, 1, block("chest")).unwrap();
+    let mut chest = BlockEntity::new("minecraft:chest");
+    chest.data.insert("CustomName".to_string(), Value::String("Loot".to_string()));
+    schematic.set_block_entity(BlockPos::new(1, 0, 1), chest).unwrap();
+    for y in 0..2 {
+        schematic.set_biome(2, y, 0, 1).unwrap();
+    }
+    schematic
+}
+
+#[test]
+fn test_schematic_round_trip() {
+    install_registries();
+    let schematic = sample();
+    assert_eq!(schematic.biome(0, 0, 0), Some(0));
+    assert_eq!(schematic.biome(2, 1, 0), Some(1));
+
+    let bytes = schematic.to_bytes(SchematicVersion::V3).unwrap();
+    assert!(bytes.starts_with(&[0x1f, 0x8b]));
+    assert_eq!(Schematic::from_bytes(&bytes).unwrap(), schematic);
+
+    // Version 2 keeps one biome per column, which these biomes are.
+    let bytes = schematic.to_bytes(SchematicVersion::V2).unwrap();
+    assert_eq!(Schematic::from_bytes(&bytes).unwrap(), schematic);
+}
+
+#[test]
+fn test_schematic_large_palette() {
+    install_registries();
+    let mut schematic = Schematic::new(20, 1, 15);
+    for index in 0..300 {
+        let state = BlockState::from_id(index as u32);
+        schematic.set_block(index % 20, 0, index / 20, state).unwrap();
+    }
+    let read = Schematic::from_bytes(&schematic.to_bytes(SchematicVersion::V3).unwrap()).unwrap();
+    assert_eq!(read.get_block(19, 0, 14), Some(BlockState::from_id(299)));
+    assert_eq!(read, schematic);
+    assert_eq!(read.biome(0, 0, 0), None);
+}
+
+#[test]
+fn test_schematic_read_worldedit_v2() {
+    install_registries();
+    let chest = compound(vec![
+        ("Pos", Value::IntArray(IntArray::new(vec![1, 0, 0]))),
+        ("Id", Value::String("minecraft:chest".to_string())),
+        ("Lock", Value::String("key".to_string())),
+    ]);
+    let nbt = compound(vec![
+        ("Version", Value::Int(2)),
+        ("DataVersion", Value::Int(3465)),
+        ("Width", Value::Short(2)),
+        ("Height", Value::Short(1)),
+        ("Length", Value::Short(1)),
+        // WorldEdit saves the corner the build was copied from, and the offset in its metadata.
+        ("Offset", Value::IntArray(IntArray::new(vec![120, 64, -35]))),
+        (
+            "Metadata",
+            Value::Compound(compound(vec![
+                ("WEOffsetX", Value::Int(0)),
+                ("WEOffsetY", Value::Int(-1)),
+                ("WEOffsetZ", Value::Int(0)),
+            ])),
+        ),
+        ("PaletteMax", Value::Int(2)),
+        ("Palette", palette(&[("minecraft:oak_log[axis=x]", 0), ("minecraft:chest", 1)])),
+        ("BlockData", Value::ByteArray(ByteArray::new(vec![0, 1]))),
+        ("BlockEntities", Value::List(vec![Value::Compound(chest)])),
+        ("BiomePaletteMax", Value::Int(1)),
+        ("BiomePalette", palette(&[("minecraft:desert", 0)])),
+        ("BiomeData", Value::ByteArray(ByteArray::new(vec![0, 0]))),
+    ]);
+    let opts = fastnbt::SerOpts::new().root_name("Schematic");
+    let bytes = gzip(&fastnbt::to_bytes_with_opts(&nbt, opts).unwrap());
+
+    let schematic = Schematic::from_bytes(&bytes).unwrap();
+    assert_eq!((schematic.width(), schematic.height(), schematic.length()), (2, 1, 1));
+    assert_eq!(schematic.offset(), BlockPos::new(0, -1, 0));
+    assert_eq!(schematic.get_block(0, 0, 0), Some(block("oak_log[axis=x]")));
+    assert_eq!(schematic.get_block(1, 0, 0), Some(block("chest")));
+    let chest = schematic.block_entity(BlockPos::new(1, 0, 0)).unwrap();
+    assert_eq!(chest.kind, "minecraft:chest");
+    assert_eq!(chest.data, compound(vec![("Lock", Value::String("key".to_string()))]));
+    assert_eq!(schematic.biome(1, 0, 0), Some(1));
+}
+
+#[test]
+fn test_schematic_read_worldedit_v3() {
+    install_registries();
+    let chest = compound(vec![
+        ("Pos", Value::IntArray(IntArray::new(vec![0, 1, 0]))),
+        ("Id", Value::String("minecraft:chest".to_string())),
+        ("Data", Value::Compound(compound(vec![("Lock", Value::String("key".to_string()))]))),
+    ]);
+    let blocks = compound(vec![
+        ("Palette", palette(&[("minecraft:air", 0), ("minecraft:chest", 1), ("minecraft:dirt", 2)])),
+        ("Data", Value::ByteArray(ByteArray::new(vec![2, 1]))),
+        ("BlockEntities", Value::List(vec![Value::Compound(chest)])),
+    ]);
+    let biomes = compound(vec![
+        ("Palette", palette(&[("minecraft:plains", 0), ("minecraft:desert", 1)])),
+        ("Data", Value::ByteArray(ByteArray::new(vec![0, 1]))),
+    ]);
+    let schematic = compound(vec![
+        ("Version", Value::Int(3)),
+        ("DataVersion", Value::Int(3953)),
+        ("Width", Value::Short(1)),
+        ("Height", Value::Short(2)),
+        ("Length", Value::Short(1)),
+        ("Blocks", Value::Compound(blocks)),
+        ("Biomes", Value::Compound(biomes)),
+    ]);
+    let nbt = compound(vec![("Schematic", Value::Compound(schematic))]);
+    let bytes = fastnbt::to_bytes(&nbt).unwrap();
+
+    // Uncompressed files are read too.
+    let schematic = Schematic::from_bytes(&bytes).unwrap();
+    assert_eq!(schematic.offset(), BlockPos::new(0, 0, 0));
+    assert_eq!(schematic.get_block(0, 0, 0), Some(block("dirt")));
+    assert_eq!(schematic.get_block(0, 1, 0), Some(block("chest")));
+    let chest = schematic.block_entity(BlockPos::new(0, 1, 0)).unwrap();
+    assert_eq!(chest.data.get("Lock"), Some(&Value::String("key".to_string())));
+    assert_eq!((schematic.biome(0, 0, 0), schematic.biome(0, 1, 0)), (Some(0), Some(1)));
+}
+
+#[test]
+fn test_schematic_invalid() {
+    install_registries();
+    let invalid = |nbt: HashMap<String, Value>| Schematic::from_bytes(&fastnbt::to_bytes(&nbt).unwrap());
+    assert!(matches!(invalid(compound(vec![("Version", Value::Int(1))])), Err(WorldError::InvalidSchematic(_))));
+    assert!(matches!(invalid(compound(vec![("Name", Value::Int(1))])), Err(WorldError::InvalidSchematic(_))));
+
+    // Data that stops before the last block.
+    let mut schematic = Schematic::new(2, 1, 1).to_bytes(SchematicVersion::V2).unwrap();
+    schematic.truncate(schematic.len() / 2);
+    assert!(Schematic::from_bytes(&schematic).is_err());
+
+    // A size the data is far too short for is rejected before the blocks are allocated.
+    let blocks = compound(vec![
+        ("Palette", palette(&[("minecraft:air", 0)])),
+        ("Data", Value::ByteArray(ByteArray::new(vec![0; 8]))),
+    ]);
+    let huge = |blocks: Option<HashMap<String, Value>>| {
+        let mut schematic = compound(vec![
+            ("Version", Value::Int(3)),
+            ("DataVersion", Value::Int(3953)),
+            ("Width", Value::Short(-1)),
+            ("Height", Value::Short(-1)),
+            ("Length", Value::Short(-1)),
+        ]);
+        if let Some(blocks) = blocks {
+            schematic.insert("Blocks".to_string(), Value::Compound(blocks));
+        }
+        invalid(compound(vec![("Schematic", Value::Compound(schematic))]))
+    };
+    assert!(matches!(huge(Some(blocks)), Err(WorldError::InvalidSchematic(_))));
+    assert!(matches!(huge(None), Err(WorldError::InvalidSchematic(_))));
+
+    let mut schematic = Schematic::new(1, 1, 1);
+    assert!(matches!(
+        schematic.set_block(1, 0, 0, block("stone")),
+        Err(WorldError::BlockOutOfBounds { x: 1, y: 0, z: 0 })
+    ));
+    assert_eq!(schematic.get_block(0, -1, 0), None);
+}
+
+#[test]
+fn test_transform_states() {
+    install_registries();
+    let clockwise = Transform::new(Rotation::Clockwise90, Mirror::None);
+    assert_eq!(clockwise.apply(BlockPos::new(1, 5, 2)), BlockPos::new(-2, 5, 1));
+    assert_eq!(
+        clockwise.apply_state(block("oak_stairs[facing=north,shape=outer_left]")),
+        block("oak_stairs[facing=east,shape=outer_left]")
+    );
+    assert_eq!(clockwise.apply_state(block("oak_log[axis=x]")), block("oak_log[axis=z]"));
+    assert_eq!(clockwise.apply_state(block("oak_log[axis=y]")), block("oak_log[axis=y]"));
+    assert_eq!(
+        clockwise.apply_state(block("redstone_wire[north=side,east=up,south=none,west=none]")),
+        block("redstone_wire[north=none,east=side,south=up,west=none]")
+    );
+
+    let mirror = Transform::new(Rotation::None, Mirror::LeftRight);
+    assert_eq!(mirror.apply(BlockPos::new(1, 5, 2)), BlockPos::new(1, 5, -2));
+    assert_eq!(
+        mirror.apply_state(block("oak_stairs[facing=north,shape=inner_left]")),
+        block("oak_stairs[facing=south,shape=inner_right]")
+    );
+    assert_eq!(
+        mirror.apply_state(block("oak_door[facing=east,hinge=left]")),
+        block("oak_door[facing=east,hinge=right]")
+    );
+
+    let both = Transform::new(Rotation::Clockwise180, Mirror::FrontBack);
+    assert_eq!(both.apply(BlockPos::new(1, 0, 2)), BlockPos::new(1, 0, -2));
+    assert_eq!(both.apply_state(block("observer[facing=east]")), block("observer[facing=east]"));
+    assert_eq!(both.apply_state(block("observer[facing=north]")), block("observer[facing=south]"));
+}
+
+#[test]
+fn test_schematic_copy_and_paste() {
+    install_registries();
+    let world = World::new(WorldHeight::OVERWORLD, flat());
+    world.set_block(0, 10, 0, block("oak_stairs[facing=north]")).unwrap();
+    world.set_block(1, 10, 0, block("chest")).unwrap();
+    world.set_block(1, 11, 0, block("oak_log[axis=x]")).unwrap();
+    let mut lock = BlockEntity::new("minecraft:chest");
+    lock.data.insert("Lock".to_string(), Value::String("key".to_string()));
+    world.set_block_entity(1, 10, 0, lock.clone()).unwrap();
+    world.set_biome(1, 10, 0, 1).unwrap();
+
+    let schematic = Schematic::copy(&world, BlockPos::new(1, 11, 0), BlockPos::new(0, 10, 0)).unwrap();
+    assert_eq!((schematic.width(), schematic.height(), schematic.length()), (2, 2, 1));
+    assert_eq!(schematic.get_block(0, 0, 0), Some(block("oak_stairs[facing=north]")));
+    assert_eq!(schematic.block_entity(BlockPos::new(1, 0, 0)), Some(&lock));
+    assert_eq!(schematic.biome(1, 0, 0), Some(1));
+    assert!(Schematic::copy(&world, BlockPos::new(0, -100, 0), BlockPos::new(0, 0, 0)).is_err());
+
+    let dir = std::env::temp_dir().join(format!("iron-oxide-schematic-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("lobby.schem");
+    schematic.save(&path, SchematicVersion::V3).unwrap();
+    let schematic = Schematic::open(&path).unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+
+    // Turned a quarter clockwise around (20, 40, 20), the build runs south instead of east.
+    let transform = Transform::new(Rotation::Clockwise90, Mirror::None);
+    schematic.paste(&world, BlockPos::new(20, 40, 20), transform).unwrap();
+    assert_eq!(world.get_block(20, 40, 20).unwrap(), block("oak_stairs[facing=east]"));
+    assert_eq!(world.get_block(20, 40, 21).unwrap(), block("chest[facing=east]"));
+    assert_eq!(world.get_block(20, 41, 21).unwrap(), block("oak_log[axis=z]"));
+    assert_eq!(world.get_block(20, 41, 20).unwrap(), BlockState::AIR);
+    assert_eq!(world.block_entity(20, 40, 21).unwrap(), Some(lock));
+    assert_eq!(world.biome(20, 40, 21).unwrap(), 1);
+    assert_eq!(world.biome(20, 40, 20).unwrap(), world.biome(0, 10, 0).unwrap());
+}
@@ -0,0 +1,191 @@
+use iron_oxide_world::biome::{install_biome_registry, BiomeRegistry};
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::chunk::ChunkColumn;
+use iron_oxide_world::error::{Result, WorldError};
+use iron_oxide_world::generator::ChunkGenerator;
+use iron_oxide_world::height::WorldHeight;
+use iron_oxide_world::ticket::{ChunkTickets, Ticket, FULL_CHUNK_LEVEL};
+use iron_oxide_world::view::ChunkPos;
+use iron_oxide_world::world::{ChunkMetrics, World};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod common;
+use common::{flat, install_blocks};
+
+fn install_registries() {
+    install_blocks();
+    install_biome_registry(BiomeRegistry::new(vec!["minecraft:plains".to_string()]));
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("iron-oxide-ticket-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Fails to generate every chunk, counting the attempts.
+struct FailingGenerator(Arc<AtomicUsize>);
+
+impl ChunkGenerator for FailingGenerator {
+    fn generate(&self, chunk_x: i32, chunk_z: i32, _column: &mut ChunkColumn) -> Result<()> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Err(WorldError::InvalidChunk { x: chunk_x, z: chunk_z, message: "no terrain".to_string() })
+    }
+}
+
+/// Ticks the world until the chunks its tickets need are loaded.
+fn tick_until_loaded(world: &World) {
+    world.tick().unwrap();
+    while world.pending_chunks() > 0 {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn test_ticket_levels() {
+    let mut tickets = ChunkTickets::new();
+    let spawn = Ticket::spawn(2);
+    assert_eq!(spawn.level, 31);
+    assert_eq!(spawn.radius(), 2);
+    tickets.add(ChunkPos::new(0, 0), spawn);
+    tickets.add(ChunkPos::new(3, 0), Ticket::forced());
+
+    assert_eq!(tickets.level(ChunkPos::new(0, 0)), Some(31));
+    assert_eq!(tickets.level(ChunkPos::new(-2, 1)), Some(FULL_CHUNK_LEVEL));
+    assert_eq!(tickets.level(ChunkPos::new(-3, 0)), None);
+    // The closest ticket decides the level.
+    assert_eq!(tickets.level(ChunkPos::new(2, 0)), Some(32));
+    assert_eq!(tickets.ticketed_chunks(), 40);
+
+    tickets.add(ChunkPos::new(0, 0), spawn);
+    assert!(tickets.remove(ChunkPos::new(0, 0), spawn));
+    assert_eq!(tickets.level(ChunkPos::new(0, 0)), Some(31));
+    assert!(tickets.remove(ChunkPos::new(0, 0), spawn));
+    assert!(!tickets.remove(ChunkPos::new(0, 0), spawn));
+    assert_eq!(tickets.level(ChunkPos::new(0, 0)), None);
+    assert_eq!(tickets.ticket_count(), 1);
+}
+
+#[test]
+fn test_temporary_tickets_expire() {
+    let mut tickets = ChunkTickets::new();
+    tickets.add(ChunkPos::new(5, 5), Ticket::temporary(0, 2));
+    assert_eq!(tickets.level(ChunkPos::new(5, 5)), Some(FULL_CHUNK_LEVEL));
+    tickets.tick();
+    assert_eq!(tickets.level(ChunkPos::new(5, 5)), Some(FULL_CHUNK_LEVEL));
+    tickets.tick();
+    assert_eq!(tickets.level(ChunkPos::new(5, 5)), None);
+    assert_eq!(tickets.ticket_count(), 0);
+}
+
+#[test]
+fn test_world_loads_and_unloads_ticketed_chunks() {
+    install_registries();
+    let dir = temp_dir("unload");
+    let world = World::open(WorldHeight::OVERWORLD, &dir, flat()).with_unload_delay(3);
+    let player = Ticket::player(1);
+    world.add_ticket(ChunkPos::new(10, 10), player);
+    tick_until_loaded(&world);
+    assert!(world.is_loaded(9, 11));
+    assert!(!world.is_loaded(12, 10));
+    world.set_block(160, 1, 160, BlockState::parse("sand").unwrap()).unwrap();
+    world.save_dirty().unwrap();
+    world.set_block(176, 1, 176, BlockState::parse("gravel").unwrap()).unwrap();
+
+    assert!(world.remove_ticket(ChunkPos::new(10, 10), player));
+    world.tick().unwrap();
+    assert_eq!(
+        world.chunk_metrics(),
+        ChunkMetrics { loaded: 9, pending: 0, ticketed: 0, tickets: 0, awaiting_unload: 9, unloaded: 0 }
+    );
+    world.tick().unwrap();
+    world.tick().unwrap();
+    assert!(world.is_loaded(10, 10));
+    assert_eq!(world.tick().unwrap(), 9);
+    assert!(!world.is_loaded(10, 10));
+    assert_eq!(world.chunk_metrics().unloaded, 9);
+
+    // The chunk modified after the last save was saved when it was unloaded.
+    let reopened = World::open(WorldHeight::OVERWORLD, &dir, flat());
+    assert_eq!(reopened.get_block(176, 1, 176).unwrap(), BlockState::parse("gravel").unwrap());
+    assert_eq!(reopened.get_block(160, 1, 160).unwrap(), BlockState::parse("sand").unwrap());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_unloading_chunks_keep_their_changes() {
+    install_registries();
+    let dir = temp_dir("unloading");
+    let world = World::open(WorldHeight::OVERWORLD, &dir, flat()).with_unload_delay(1);
+    let sand = BlockState::parse("sand").unwrap();
+    for x in 0..4 {
+        for z in 0..4 {
+            world.set_block(x * 16, 1, z * 16, sand).unwrap();
+        }
+    }
+    // A chunk read while it is saved is either taken back before the save is written or read
+    // after, with its changes either way.
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            for _ in 0..5 {
+                world.tick().unwrap();
+            }
+        });
+        for _ in 0..200 {
+            assert_eq!(world.get_block(48, 1, 48).unwrap(), sand);
+        }
+    });
+    for _ in 0..3 {
+        world.tick().unwrap();
+    }
+    assert_eq!(world.chunk_metrics().loaded, 0);
+    let reopened = World::open(WorldHeight::OVERWORLD, &dir, flat());
+    assert_eq!(reopened.get_block(48, 1, 48).unwrap(), sand);
+    assert_eq!(reopened.get_block(16, 1, 32).unwrap(), sand);
+
+    // Chunks that couldn't be saved stay loaded.
+    std::fs::remove_dir_all(&dir).unwrap();
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("region"), b"not a directory").unwrap();
+    let world = World::open(WorldHeight::OVERWORLD, &dir, flat());
+    world.set_block(0, 1, 0, sand).unwrap();
+    assert!(world.unload_chunk(0, 0).is_err());
+    assert!(world.is_loaded(0, 0));
+    assert_eq!(world.get_block(0, 1, 0).unwrap(), sand);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_loaded_chunk_limit() {
+    install_registries();
+    let dir = temp_dir("limit");
+    let world = World::open(WorldHeight::OVERWORLD, &dir, flat()).with_max_loaded_chunks(4);
+    world.add_ticket(ChunkPos::new(0, 0), Ticket::forced());
+    tick_until_loaded(&world);
+    assert_eq!(world.chunk_metrics().loaded, 25);
+
+    // Chunks with tickets stay loaded above the limit, chunks without go right away.
+    world.get_chunk(20, 20).unwrap();
+    world.get_chunk(21, 20).unwrap();
+    assert_eq!(world.tick().unwrap(), 2);
+    assert_eq!(world.chunk_metrics().loaded, 25);
+    assert!(world.is_loaded(2, -2));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_failed_loads_wait_before_trying_again() {
+    install_registries();
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let world = World::new(WorldHeight::OVERWORLD, Box::new(FailingGenerator(Arc::clone(&attempts))));
+    world.add_ticket(ChunkPos::new(0, 0), Ticket::forced());
+    for _ in 0..30 {
+        tick_until_loaded(&world);
+    }
+    // Loaded on the first tick and once more 20 ticks after failing, rather than every tick.
+    assert_eq!(world.chunk_metrics().loaded, 0);
+    assert_eq!(attempts.load(Ordering::SeqCst), 2 * world.chunk_metrics().ticketed);
+}
@@ -0,0 +1,156 @@
+use iron_oxide_world::block::BlockState;
+use iron_oxide_world::error::Result;
+use iron_oxide_world::pos::{BlockPos, Direction};
+use iron_oxide_world::update::{TickPriority, TickQueue, UpdateFlags, UpdateHandler};
+use iron_oxide_world::world::World;
+
+mod common;
+use common::{block, flat_world};
+
+/// Wheat breaks without a block under it, sand falls a tick after it loses its support and grass
+/// is snowy under sand.
+struct TestBlocks;
+
+impl UpdateHandler for TestBlocks {
+    fn neighbor_changed(&self, world: &World, pos: BlockPos, state: BlockState, _from: BlockPos) -> Result<()> {
+        let below = world.get_block(pos.x, pos.y - 1, pos.z)?;
+        if state.is("wheat") && below.is_air() {
+            world.set_block(pos.x, pos.y, pos.z, BlockState::AIR)?;
+        } else if state.is("sand") && below.is_air() {
+            world.schedule_tick(pos, state, 2);
+        }
+        Ok(())
+    }
+
+    fn update_shape(
+        &self,
+        _world: &World,
+        _pos: BlockPos,
+        state: BlockState,
+        direction: Direction,
+        neighbor: BlockState,
+    ) -> BlockState {
+        if state.is("grass_block") && direction == Direction::Up {
+            let snowy = if neighbor.is("sand") { "true" } else { "false" };
+            return state.with("snowy", snowy).unwrap();
+        }
+        state
+    }
+
+    fn scheduled_tick(&self, world: &World, pos: BlockPos, state: BlockState) -> Result<()> {
+        if world.get_block(pos.x, pos.y - 1, pos.z)?.is_air() {
+            world.set_block(pos.x, pos.y, pos.z, BlockState::AIR)?;
+            world.set_block(pos.x, pos.y - 1, pos.z, state)?;
+            world.schedule_tick(pos.offset(0, -1, 0), state, 2);
+        }
+        Ok(())
+    }
+}
+
+fn test_world() -> World {
+    flat_world().with_update_handler(Box::new(TestBlocks))
+}
+
+#[test]
+fn test_directions() {
+    let pos = BlockPos::new(1, 2, 3);
+    assert_eq!(pos.relative(Direction::North), BlockPos::new(1, 2, 2));
+    assert_eq!(pos.relative(Direction::Down), BlockPos::new(1, 1, 3));
+    for direction in Direction::ALL {
+        assert_eq!(pos.relative(direction).relative(direction.opposite()), pos);
+        assert_eq!(Direction::from_name(direction.name()), Some(direction));
+    }
+}
+
+#[test]
+fn test_neighbors_are_notified() {
+    let world = test_world();
+    world.set_block(3, -59, 3, block("dirt")).unwrap();
+    world.set_block(3, -58, 3, block("wheat")).unwrap();
+
+    // Without neighbor updates the wheat floats.
+    world.set_block_with(3, -59, 3, BlockState::AIR, UpdateFlags::SEND_TO_CLIENTS).unwrap();
+    assert!(world.get_block(3, -58, 3).unwrap().is("wheat"));
+
+    world.set_block(3, -59, 3, block("dirt")).unwrap();
+    world.set_block(3, -59, 3, BlockState::AIR).unwrap();
+    assert!(world.get_block(3, -58, 3).unwrap().is_air());
+}
+
+#[test]
+fn test_setting_the_same_block_does_nothing() {
+    let world = test_world();
+    let cursor = world.change_cursor();
+    assert!(!world.set_block_with(0, -60, 0, block("grass_block"), UpdateFlags::DEFAULT).unwrap());
+    assert!(world.changes_since(cursor).changes.is_empty());
+
+    assert!(world.set_block_with(0, -60, 0, block("dirt"), UpdateFlags::NOTIFY_NEIGHBORS).unwrap());
+    assert!(world.changes_since(cursor).changes.is_empty());
+}
+
+#[test]
+fn test_shapes_follow_neighbors() {
+    let world = test_world();
+    world.set_block(-5, -59, 7, block("sand")).unwrap();
+    assert_eq!(world.get_block(-5, -60, 7).unwrap(), block("grass_block[snowy=true]"));
+    world.set_block(-5, -59, 7, block("glowstone")).unwrap();
+    assert_eq!(world.get_block(-5, -60, 7).unwrap(), block("grass_block[snowy=false]"));
+
+    let cursor = world.change_cursor();
+    world.set_block_with(-5, -59, 7, block("sand"), UpdateFlags::NOTIFY_NEIGHBORS).unwrap();
+    assert_eq!(world.get_block(-5, -60, 7).unwrap(), block("grass_block[snowy=false]"));
+    assert!(world.changes_since(cursor).changes.is_empty());
+}
+
+#[test]
+fn test_scheduled_ticks_make_sand_fall() {
+    let world = test_world();
+    for y in -59..=-57 {
+        world.set_block(8, y, 8, block("stone")).unwrap();
+    }
+    world.set_block(8, -56, 8, block("sand")).unwrap();
+    world.set_block(8, -57, 8, BlockState::AIR).unwrap();
+    world.set_block(8, -58, 8, BlockState::AIR).unwrap();
+    let sand = BlockPos::new(8, -56, 8);
+    assert!(world.is_tick_scheduled(sand, block("sand")));
+    assert!(!world.schedule_tick(sand, block("sand"), 5));
+
+    world.tick().unwrap();
+    assert!(world.get_block(8, -56, 8).unwrap().is("sand"));
+    for _ in 0..3 {
+        world.tick().unwrap();
+    }
+    assert!(world.get_block(8, -56, 8).unwrap().is_air());
+    assert!(world.get_block(8, -58, 8).unwrap().is("sand"));
+    world.tick().unwrap();
+    assert!(world.get_block(8, -58, 8).unwrap().is("sand"));
+    assert_eq!(world.current_tick(), 5);
+
+    // A tick for a block that was replaced is skipped.
+    world.schedule_tick(BlockPos::new(8, -58, 8), block("sand"), 1);
+    world.set_block(8, -59, 8, BlockState::AIR).unwrap();
+    world.set_block(8, -58, 8, block("dirt")).unwrap();
+    world.tick().unwrap();
+    assert!(world.get_block(8, -58, 8).unwrap().is("dirt"));
+}
+
+#[test]
+fn test_tick_queue_order() {
+    let mut queue = TickQueue::new();
+    let sand = block("sand");
+    let (a, b, c) = (BlockPos::new(0, 0, 0), BlockPos::new(1, 0, 0), BlockPos::new(2, 0, 0));
+    assert!(queue.schedule(a, sand, 2, TickPriority::Normal));
+    assert!(queue.schedule(b, sand, 2, TickPriority::Normal));
+    assert!(queue.schedule(c, sand, 2, TickPriority::High));
+    assert!(queue.schedule(a, block("gravel"), 0, TickPriority::Low));
+    assert!(!queue.schedule(a, sand, 1, TickPriority::High));
+    assert_eq!(queue.len(), 4);
+
+    let due = queue.tick();
+    assert_eq!(due.len(), 1);
+    assert_eq!((due[0].pos, due[0].block, due[0].due), (a, block("gravel"), 1));
+    let order: Vec<BlockPos> = queue.tick().into_iter().map(|tick| tick.pos).collect();
+    assert_eq!(order, vec![c, a, b]);
+    assert!(queue.is_empty());
+    assert!(queue.schedule(a, sand, 1, TickPriority::Normal));
+}
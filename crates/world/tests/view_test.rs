@@ -0,0 +1,33 @@
+use std::collections::HashSet;
+use iron_oxide_world::view::{ChunkPos, ChunkView};
+
+#[test]
+fn test_chunk_pos_from_negative_block() {
+    assert_eq!(ChunkPos::from_block(-1, 16), ChunkPos::new(-1, 1));
+    assert_eq!(ChunkPos::from_block(15, -16), ChunkPos::new(0, -1));
+}
+
+#[test]
+fn test_spiral_starts_at_center() {
+    let view = ChunkView::new(ChunkPos::new(3, -7), 8);
+    assert_eq!(view.spiral().next(), Some(ChunkPos::new(3, -7)));
+}
+
+#[test]
+fn test_spiral_covers_view_once() {
+    let view = ChunkView::new(ChunkPos::new(0, 0), 4);
+    let chunks: Vec<ChunkPos> = view.spiral().collect();
+    let unique: HashSet<ChunkPos> = chunks.iter().copied().collect();
+    assert_eq!(chunks.len(), unique.len());
+    assert_eq!(chunks.len(), 77);
+    assert!(chunks.iter().all(|pos| view.contains(*pos)));
+}
+
+#[test]
+fn test_view_is_rounded() {
+    let view = ChunkView::new(ChunkPos::new(0, 0), 4);
+    assert!(view.contains(ChunkPos::new(4, 0)));
+    assert!(view.contains(ChunkPos::new(3, 3)));
+    assert!(!view.contains(ChunkPos::new(4, 4)));
+    assert!(!view.contains(ChunkPos::new(5, 0)));
+}
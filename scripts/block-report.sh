@@ -0,0 +1,23 @@
+#!/bin/sh
+# Writes the blocks.json report of the vanilla data generator to config/v1_21_5/blocks.json.
+# Needs curl, python3 and Java 21, and downloads the 1.21.5 server jar from Mojang.
+set -eu
+
+version=1.21.5
+out="$(cd "$(dirname "$0")/.." && pwd)/config/v1_21_5/blocks.json"
+work=$(mktemp -d)
+trap 'rm -rf "$work"' EXIT
+
+json() {
+    python3 -c "import json, sys; data = json.load(sys.stdin); print($1)" "$version"
+}
+
+version_url=$(curl -fsSL https://piston-meta.mojang.com/mc/game/version_manifest_v2.json |
+    json 'next(v["url"] for v in data["versions"] if v["id"] == sys.argv[1])')
+server_url=$(curl -fsSL "$version_url" | json 'data["downloads"]["server"]["url"]')
+curl -fsSL -o "$work/server.jar" "$server_url"
+
+cd "$work"
+java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports --output generated
+cp generated/reports/blocks.json "$out"
+echo "Wrote $out"